@@ -0,0 +1,91 @@
+//! Compares BK-tree range queries with brute-force pairwise comparison.
+//!
+//! Builds ~5000 synthetic 16x16 perceptual hashes (clusters of near-duplicates
+//! around random bases) and times finding every pair within the default
+//! similarity threshold both ways.
+//!
+//! Run with `cargo bench --bench bktree`.
+
+use std::time::Instant;
+
+use image_hasher::ImageHash;
+use media_duplicate_eraser_rs::services::bktree::BkTree;
+use media_duplicate_eraser_rs::services::hasher;
+
+/// Number of distinct base images.
+const CLUSTERS: usize = 1000;
+/// Near-duplicate variants generated per base image.
+const VARIANTS_PER_CLUSTER: usize = 5;
+/// Bytes per hash (256 bits, like a 16x16 hash).
+const HASH_BYTES: usize = 32;
+/// Maximum number of bits flipped to create a variant.
+const MAX_FLIPPED_BITS: u64 = 6;
+
+/// Small deterministic xorshift generator so runs are comparable.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+fn synthetic_hashes() -> Vec<ImageHash> {
+    let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+    let mut hashes = Vec::with_capacity(CLUSTERS * VARIANTS_PER_CLUSTER);
+
+    for _ in 0..CLUSTERS {
+        let base: Vec<u8> = (0..HASH_BYTES).map(|_| rng.next_u64() as u8).collect();
+
+        for _ in 0..VARIANTS_PER_CLUSTER {
+            let mut variant = base.clone();
+            for _ in 0..(rng.next_u64() % (MAX_FLIPPED_BITS + 1)) {
+                let bit = (rng.next_u64() % (HASH_BYTES as u64 * 8)) as usize;
+                variant[bit / 8] ^= 1 << (bit % 8);
+            }
+            hashes.push(ImageHash::from_bytes(&variant).expect("valid hash bytes"));
+        }
+    }
+
+    hashes
+}
+
+fn main() {
+    let hashes = synthetic_hashes();
+    let threshold = hasher::DEFAULT_SIMILARITY_THRESHOLD;
+    println!("{} synthetic hashes, threshold {}", hashes.len(), threshold);
+
+    let start = Instant::now();
+    let mut brute_force_pairs = 0usize;
+    for (i, a) in hashes.iter().enumerate() {
+        for b in &hashes[i + 1..] {
+            if hasher::are_similar(a, b, threshold) {
+                brute_force_pairs += 1;
+            }
+        }
+    }
+    let brute_force_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut tree = BkTree::new();
+    for (index, hash) in hashes.iter().enumerate() {
+        tree.insert(hash.clone(), index);
+    }
+    let mut tree_pairs = 0usize;
+    for (index, hash) in hashes.iter().enumerate() {
+        tree_pairs += tree
+            .find_within(hash, threshold)
+            .into_iter()
+            .filter(|&(_, &other)| other > index)
+            .count();
+    }
+    let tree_time = start.elapsed();
+
+    assert_eq!(brute_force_pairs, tree_pairs, "BK-tree must find the same pairs");
+
+    println!("brute force: {:>10.2?} ({} pairs)", brute_force_time, brute_force_pairs);
+    println!("bk-tree:     {:>10.2?} ({} pairs)", tree_time, tree_pairs);
+}
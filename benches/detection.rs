@@ -0,0 +1,173 @@
+//! Times duplicate detection on generated fixtures, to catch regressions in
+//! the parallel hashing and grouping passes.
+//!
+//! Runs [`duplicate::find_duplicates_with_options`] without a progress
+//! callback, so nothing but the results below is printed:
+//!
+//! - the exact pass over ~1000 binary files, a fifth of them copies;
+//! - the perceptual pass over ~300 generated PNG images in clusters of a
+//!   picture, a brightened copy and a downscaled copy.
+//!
+//! Each pass runs a few times and the fastest run is reported. Detection is
+//! deterministic, so every run must find the same groups in the same order.
+//! Like the `bktree` benchmark, this is a plain `harness = false` program
+//! rather than a criterion suite, so benchmarking adds no dependency. Run
+//! with `cargo bench --bench detection`.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use image::{imageops, Rgb, RgbImage};
+use media_duplicate_eraser_rs::services::duplicate::{
+    self, DetectionOptions, DuplicateReport, MediaFilter,
+};
+
+/// Number of distinct binary files.
+const BINARY_FILES: usize = 800;
+/// Size of each binary file in bytes.
+const BINARY_FILE_SIZE: usize = 16 * 1024;
+/// Every this many binary files, one is also copied.
+const COPY_EVERY: usize = 4;
+/// Number of distinct generated pictures.
+const PICTURES: usize = 100;
+/// Width and height of the generated pictures.
+const PICTURE_SIZE: u32 = 64;
+/// Cells per side of the random block pattern of each picture.
+const PICTURE_CELLS: u32 = 4;
+/// Times each pass is run.
+const RUNS: usize = 3;
+
+/// Small deterministic xorshift generator so runs are comparable.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// Writes distinct same-sized binary files to `dir`, copying every
+/// [`COPY_EVERY`]th one, so most candidates only differ after hashing.
+fn binary_fixtures(dir: &Path, rng: &mut Rng) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+
+    for index in 0..BINARY_FILES {
+        let contents: Vec<u8> = (0..BINARY_FILE_SIZE)
+            .map(|_| rng.next_u64() as u8)
+            .collect();
+        let path = dir.join(format!("file_{:04}.bin", index));
+        std::fs::write(&path, &contents).expect("write fixture");
+        files.push(path);
+
+        if index % COPY_EVERY == 0 {
+            let copy = dir.join(format!("file_{:04}_copy.bin", index));
+            std::fs::write(&copy, &contents).expect("write fixture");
+            files.push(copy);
+        }
+    }
+
+    files
+}
+
+/// Writes clusters of a random block pattern, a brightened copy and a
+/// downscaled copy to `dir`, so each cluster only matches perceptually.
+fn picture_fixtures(dir: &Path, rng: &mut Rng) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let cell = PICTURE_SIZE / PICTURE_CELLS;
+
+    for index in 0..PICTURES {
+        let cells: Vec<u8> = (0..PICTURE_CELLS * PICTURE_CELLS)
+            .map(|_| rng.next_u64() as u8)
+            .collect();
+        let picture = RgbImage::from_fn(PICTURE_SIZE, PICTURE_SIZE, |x, y| {
+            let value = cells[((y / cell) * PICTURE_CELLS + x / cell) as usize];
+            Rgb([value, value, value.wrapping_add(x as u8)])
+        });
+        let brightened = RgbImage::from_fn(PICTURE_SIZE, PICTURE_SIZE, |x, y| {
+            let Rgb(pixel) = picture.get_pixel(x, y);
+            Rgb(pixel.map(|channel| channel.saturating_add(12)))
+        });
+        let downscaled = imageops::resize(
+            &picture,
+            PICTURE_SIZE * 3 / 4,
+            PICTURE_SIZE * 3 / 4,
+            imageops::FilterType::Triangle,
+        );
+
+        for (suffix, image) in [
+            ("", &picture),
+            ("_bright", &brightened),
+            ("_small", &downscaled),
+        ] {
+            let path = dir.join(format!("picture_{:03}{}.png", index, suffix));
+            image.save(&path).expect("write fixture");
+            files.push(path);
+        }
+    }
+
+    files
+}
+
+/// Runs detection [`RUNS`] times, checking every run finds the same groups in
+/// the same order, and returns the fastest time with the last report.
+fn time_detection(files: &[PathBuf], options: &DetectionOptions) -> (Duration, DuplicateReport) {
+    let mut fastest = Duration::MAX;
+    let mut previous: Option<DuplicateReport> = None;
+
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        let report = duplicate::find_duplicates_with_options(files, None, options)
+            .expect("detection should succeed");
+        fastest = fastest.min(start.elapsed());
+
+        if let Some(previous) = &previous {
+            let files_of = |report: &DuplicateReport| -> Vec<Vec<PathBuf>> {
+                report.groups.iter().map(|g| g.files.clone()).collect()
+            };
+            assert_eq!(
+                files_of(previous),
+                files_of(&report),
+                "Runs must find the same groups in the same order"
+            );
+        }
+        previous = Some(report);
+    }
+
+    (fastest, previous.expect("at least one run"))
+}
+
+fn main() {
+    let tmp = tempfile::tempdir().expect("create temporary directory");
+    let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+
+    let binary_dir = tmp.path().join("binary");
+    let picture_dir = tmp.path().join("pictures");
+    std::fs::create_dir(&binary_dir).expect("create fixture directory");
+    std::fs::create_dir(&picture_dir).expect("create fixture directory");
+    let binaries = binary_fixtures(&binary_dir, &mut rng);
+    let pictures = picture_fixtures(&picture_dir, &mut rng);
+
+    let exact = DetectionOptions::from(MediaFilter::AnyFile);
+    let (exact_time, exact_report) = time_detection(&binaries, &exact);
+    assert_eq!(exact_report.groups.len(), BINARY_FILES.div_ceil(COPY_EVERY));
+
+    let perceptual = DetectionOptions::from(MediaFilter::ImagesOnly);
+    let (perceptual_time, perceptual_report) = time_detection(&pictures, &perceptual);
+
+    println!("best of {} runs", RUNS);
+    println!(
+        "exact:      {:>10.2?} ({} files, {} groups)",
+        exact_time,
+        binaries.len(),
+        exact_report.groups.len()
+    );
+    println!(
+        "perceptual: {:>10.2?} ({} files, {} groups)",
+        perceptual_time,
+        pictures.len(),
+        perceptual_report.groups.len()
+    );
+}
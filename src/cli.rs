@@ -1,14 +1,45 @@
+use std::process::ExitCode;
+
+use chrono::{DateTime, Utc};
 use clap::{Parser, Subcommand, ValueEnum};
+use log::LevelFilter;
 
 use media_duplicate_eraser_rs::commands::clean::Cleaner;
-use media_duplicate_eraser_rs::commands::erase::Eraser;
-use media_duplicate_eraser_rs::commands::scan::Scanner;
+use media_duplicate_eraser_rs::commands::erase::{DeleteMode, Eraser};
+use media_duplicate_eraser_rs::commands::merge::Merger;
+use media_duplicate_eraser_rs::commands::regroup::Regrouper;
+use media_duplicate_eraser_rs::commands::scan::{ExtensionFilter, ScanOptions, Scanner};
+use media_duplicate_eraser_rs::commands::stats::Stats;
+use media_duplicate_eraser_rs::commands::undo::Undoer;
+use media_duplicate_eraser_rs::commands::verify::Verifier;
 use media_duplicate_eraser_rs::commands::Command;
 use media_duplicate_eraser_rs::error::Result;
-use media_duplicate_eraser_rs::services::duplicate::MediaFilter;
+use media_duplicate_eraser_rs::services::cancel::CancelToken;
+use media_duplicate_eraser_rs::services::duplicate::{
+    DetectionOptions, DetectionPreset, GroupOrder, Grouping, MediaFilter, MediaThresholds,
+    OriginalStrategy, Prescreen, DUPLICATES_FILENAME,
+};
+use media_duplicate_eraser_rs::services::hasher::{
+    self, HashAlgorithm, PerceptualAlgorithm, PerceptualConfig, HASH_SIZE, MAX_HASH_SIZE,
+    MAX_SIMILARITY_THRESHOLD, MIN_HASH_SIZE,
+};
+use media_duplicate_eraser_rs::services::media_registry::MediaRegistry;
+use media_duplicate_eraser_rs::services::report::ReportFormat;
+use media_duplicate_eraser_rs::services::soft_delete::DEFAULT_RETENTION_DAYS;
+use media_duplicate_eraser_rs::services::units::{parse_size, parse_time};
 
 use crate::logger;
 
+/// Exit code when a command fails.
+pub const EXIT_FAILURE: u8 = 1;
+
+/// Exit code of `mde scan --fail-on-duplicates` when duplicates were found.
+pub const EXIT_DUPLICATES_FOUND: u8 = 2;
+
+/// Exit code when a scan or erase was canceled with Ctrl-C, as for other
+/// programs interrupted by it.
+pub const EXIT_CANCELED: u8 = 130;
+
 /// Media type filter for scanning
 #[derive(Debug, Clone, Copy, ValueEnum, Default)]
 pub enum MediaType {
@@ -21,6 +52,8 @@ pub enum MediaType {
     Videos,
     /// Scan only audio files
     Audio,
+    /// Scan every file type for exact duplicates only, without perceptual analysis
+    Any,
 }
 
 impl From<MediaType> for MediaFilter {
@@ -30,6 +63,221 @@ impl From<MediaType> for MediaFilter {
             MediaType::Images => MediaFilter::ImagesOnly,
             MediaType::Videos => MediaFilter::VideosOnly,
             MediaType::Audio => MediaFilter::AudioOnly,
+            MediaType::Any => MediaFilter::AnyFile,
+        }
+    }
+}
+
+/// Named bundle of detection options
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Preset {
+    /// Find originals of images recompressed by messaging apps
+    /// (threshold 16, decode files with unrecognised extensions such as .jfif)
+    Social,
+}
+
+impl From<Preset> for DetectionPreset {
+    fn from(preset: Preset) -> Self {
+        match preset {
+            Preset::Social => DetectionPreset::Social,
+        }
+    }
+}
+
+/// Which file in a duplicate group is kept as the original
+#[derive(Debug, Clone, Copy, ValueEnum, Default)]
+pub enum Keep {
+    /// The file whose path sorts first
+    #[default]
+    FirstAlphabetical,
+    /// The file with the oldest modification time
+    Oldest,
+    /// The file with the newest modification time
+    Newest,
+    /// The file with the shortest path
+    ShortestPath,
+    /// The file with the longest path
+    LongestPath,
+    /// The image with the most pixels (other media: the file whose path sorts first)
+    HighestResolution,
+}
+
+impl From<Keep> for OriginalStrategy {
+    fn from(keep: Keep) -> Self {
+        match keep {
+            Keep::FirstAlphabetical => OriginalStrategy::FirstAlphabetical,
+            Keep::Oldest => OriginalStrategy::OldestMtime,
+            Keep::Newest => OriginalStrategy::NewestMtime,
+            Keep::ShortestPath => OriginalStrategy::ShortestPath,
+            Keep::LongestPath => OriginalStrategy::LongestPath,
+            Keep::HighestResolution => OriginalStrategy::HighestResolution,
+        }
+    }
+}
+
+/// Hash used to confirm exact duplicates
+#[derive(Debug, Clone, Copy, ValueEnum, Default)]
+pub enum HashAlgo {
+    /// SHA-256 (compatible with earlier manifests)
+    #[default]
+    Sha256,
+    /// BLAKE3 (much faster on large files)
+    Blake3,
+}
+
+impl From<HashAlgo> for HashAlgorithm {
+    fn from(algo: HashAlgo) -> Self {
+        match algo {
+            HashAlgo::Sha256 => HashAlgorithm::Sha256,
+            HashAlgo::Blake3 => HashAlgorithm::Blake3,
+        }
+    }
+}
+
+/// Algorithm used to compute perceptual hashes
+#[derive(Debug, Clone, Copy, ValueEnum, Default)]
+pub enum HashAlg {
+    /// Compare pixels to the mean brightness (fastest, least precise)
+    Mean,
+    /// Compare pixels to their horizontal neighbour
+    Gradient,
+    /// Compare pixels to their vertical neighbour
+    VertGradient,
+    /// Combine horizontal and vertical gradients
+    #[default]
+    DoubleGradient,
+    /// Compare blocks of pixels to the median (robust to small crops)
+    Blockhash,
+}
+
+impl From<HashAlg> for PerceptualAlgorithm {
+    fn from(alg: HashAlg) -> Self {
+        match alg {
+            HashAlg::Mean => PerceptualAlgorithm::Mean,
+            HashAlg::Gradient => PerceptualAlgorithm::Gradient,
+            HashAlg::VertGradient => PerceptualAlgorithm::VertGradient,
+            HashAlg::DoubleGradient => PerceptualAlgorithm::DoubleGradient,
+            HashAlg::Blockhash => PerceptualAlgorithm::Blockhash,
+        }
+    }
+}
+
+/// How similar files are joined into groups
+#[derive(Debug, Clone, Copy, ValueEnum, Default)]
+pub enum GroupingMode {
+    /// Group files similar to any file of the group, so A~B~C chains into one group
+    #[default]
+    Transitive,
+    /// Only group files that are all similar to each other
+    Pairwise,
+}
+
+impl From<GroupingMode> for Grouping {
+    fn from(mode: GroupingMode) -> Self {
+        match mode {
+            GroupingMode::Transitive => Grouping::Transitive,
+            GroupingMode::Pairwise => Grouping::Pairwise,
+        }
+    }
+}
+
+/// How files of the same size are split before being hashed in full
+#[derive(Debug, Clone, Copy, ValueEnum, Default)]
+pub enum PrescreenMode {
+    /// Hash the first 64 KiB of larger files first
+    #[default]
+    Partial,
+    /// Also compare a CRC32 of smaller files first, for many distinct files of one size such as thumbnails
+    Crc32,
+    /// Hash every file in full right away
+    Off,
+}
+
+impl From<PrescreenMode> for Prescreen {
+    fn from(mode: PrescreenMode) -> Self {
+        match mode {
+            PrescreenMode::Partial => Prescreen::Partial,
+            PrescreenMode::Crc32 => Prescreen::Crc32,
+            PrescreenMode::Off => Prescreen::Off,
+        }
+    }
+}
+
+/// Order of the groups in the scan report
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SortBy {
+    /// Largest reclaimable space first
+    Size,
+    /// By the path of the file to keep
+    Path,
+    /// Most files first
+    Count,
+}
+
+impl From<SortBy> for GroupOrder {
+    fn from(sort: SortBy) -> Self {
+        match sort {
+            SortBy::Size => GroupOrder::Size,
+            SortBy::Path => GroupOrder::Path,
+            SortBy::Count => GroupOrder::Count,
+        }
+    }
+}
+
+/// Output format of the scan report
+#[derive(Debug, Clone, Copy, ValueEnum, Default)]
+pub enum Format {
+    /// JSON manifest, used by `mde erase`
+    #[default]
+    Json,
+    /// YAML manifest, also readable by `mde erase`
+    Yaml,
+    /// TOML manifest, also readable by `mde erase`
+    Toml,
+    /// JSON Lines manifest with one group per line, also readable by `mde erase`
+    Jsonl,
+    /// CSV with one row per duplicate, for spreadsheets
+    Csv,
+}
+
+impl From<Format> for ReportFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Json => ReportFormat::Json,
+            Format::Yaml => ReportFormat::Yaml,
+            Format::Toml => ReportFormat::Toml,
+            Format::Jsonl => ReportFormat::Jsonl,
+            Format::Csv => ReportFormat::Csv,
+        }
+    }
+}
+
+/// Most detailed log messages to record
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum LogLevel {
+    /// No log messages
+    Off,
+    /// Only errors
+    Error,
+    /// Errors and warnings
+    Warn,
+    /// Progress of each step
+    Info,
+    /// Details useful when reporting a bug
+    Debug,
+    /// Everything
+    Trace,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Off => LevelFilter::Off,
+            LogLevel::Error => LevelFilter::Error,
+            LogLevel::Warn => LevelFilter::Warn,
+            LogLevel::Info => LevelFilter::Info,
+            LogLevel::Debug => LevelFilter::Debug,
+            LogLevel::Trace => LevelFilter::Trace,
         }
     }
 }
@@ -42,10 +290,30 @@ pub struct Cli {
     #[arg(short, long, action = clap::ArgAction::Count)]
     pub verbose: u8,
 
-    /// Suppress all output except errors
+    /// Suppress messages and progress bars; logs are still written
     #[arg(short, long)]
     pub quiet: bool,
 
+    /// Like --quiet, and only log errors
+    #[arg(short, long)]
+    pub silent: bool,
+
+    /// Log messages up to this level, overriding -v and --silent
+    #[arg(long, value_enum)]
+    pub log_level: Option<LogLevel>,
+
+    /// Append logs to this file, with timestamps, instead of printing them
+    #[arg(long)]
+    pub log_file: Option<std::path::PathBuf>,
+
+    /// JSON file registering more extensions as media, e.g. {"video": ["mts"], "image": ["jxl"]}
+    #[arg(long, value_name = "FILE")]
+    pub media_types: Option<std::path::PathBuf>,
+
+    /// Memory-map files at least this large for exact hashing, instead of reading them (default 64M)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub mmap_threshold: Option<u64>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -53,26 +321,215 @@ pub struct Cli {
 #[derive(Subcommand)]
 pub enum Commands {
     /// Scan a directory for duplicate media files
+    ///
+    /// Exit codes: 0 when the scan completes, 1 when it fails, 2 when
+    /// --fail-on-duplicates is set and duplicates were found (the report is
+    /// still written), and 130 when it is canceled with Ctrl-C (the partial
+    /// report is still written).
     Scan {
-        /// Directory to scan for duplicates
+        /// Directories to scan for duplicates; with several, --output is required
         #[arg(default_value = ".")]
-        path: std::path::PathBuf,
+        path: Vec<std::path::PathBuf>,
 
         /// Perform recursive scan
         #[arg(short, long, default_value_t = true)]
         recursive: bool,
 
+        /// Maximum directory depth to scan (1 = only the given directory). Overrides --recursive
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        max_depth: Option<u32>,
+
         /// Include hidden files (starting with '.')
         #[arg(long)]
         include_hidden: bool,
 
-        /// Output file for duplicates (JSON format). Defaults to duplicates.json in the first scanned directory.
+        /// Follow symbolic links to files and directories
+        #[arg(long)]
+        follow_symlinks: bool,
+
+        /// Scan the newline-separated paths listed in this file ('-' for stdin) instead of walking PATH; the report is still written to PATH
+        #[arg(long, value_name = "FILE")]
+        from_file: Option<std::path::PathBuf>,
+
+        /// Stop after the first N files found, for quick trial runs on large directories; the results are then partial
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..), conflicts_with = "from_file")]
+        limit: Option<u64>,
+
+        /// Output file for duplicates. Defaults to duplicates.json (or duplicates.yaml, duplicates.toml, duplicates.jsonl or duplicates.csv with --format) in the first scanned directory.
         #[arg(short, long)]
         output: Option<std::path::PathBuf>,
 
+        /// Name of the report written to PATH, so several scans can share a directory
+        #[arg(long, conflicts_with = "output")]
+        manifest_name: Option<String>,
+
+        /// Print a one-line JSON summary of the scan to stdout instead of the report
+        #[arg(long)]
+        json_summary: bool,
+
+        /// Print each duplicate path followed by a NUL byte to stdout instead of the report, for `xargs -0`
+        #[arg(long, conflicts_with = "json_summary")]
+        print0: bool,
+
+        /// Exit with code 2 if any duplicates are found, for failing CI builds
+        #[arg(long)]
+        fail_on_duplicates: bool,
+
+        /// Report format; CSV reports cannot be used by `mde erase` [default: from the --output extension, or json]
+        #[arg(long, value_enum)]
+        format: Option<Format>,
+
         /// Filter by media type (all, images, videos, or audio)
         #[arg(short, long, value_enum, default_value_t = MediaType::All)]
         media: MediaType,
+
+        /// Only scan files with these extensions (comma-separated, e.g. cr2,nef)
+        #[arg(long, value_delimiter = ',')]
+        include_ext: Vec<String>,
+
+        /// Skip files with these extensions (comma-separated, e.g. gif)
+        #[arg(long, value_delimiter = ',')]
+        exclude_ext: Vec<String>,
+
+        /// Skip files smaller than this size (e.g. 4K, 10M)
+        #[arg(long, value_parser = parse_size)]
+        min_size: Option<u64>,
+
+        /// Skip files larger than this size (e.g. 500M, 2G)
+        #[arg(long, value_parser = parse_size)]
+        max_size: Option<u64>,
+
+        /// Scan zero-byte files too; all of them are reported as one group of duplicates
+        #[arg(long)]
+        include_empty: bool,
+
+        /// Only scan files modified within this long (e.g. 30d, 12h) or since this date (e.g. 2024-05-01)
+        #[arg(long, value_name = "TIME", value_parser = parse_time)]
+        newer_than: Option<DateTime<Utc>>,
+
+        /// Only scan files modified longer ago than this (e.g. 2w) or before this date (e.g. 2024-05-01)
+        #[arg(long, value_name = "TIME", value_parser = parse_time)]
+        older_than: Option<DateTime<Utc>>,
+
+        /// Maximum perceptual hash distance (0-64) for files to count as similar [default: 10]
+        #[arg(short, long, value_parser = clap::value_parser!(u32).range(0..=MAX_SIMILARITY_THRESHOLD as i64))]
+        threshold: Option<u32>,
+
+        /// Threshold for images instead of --threshold, also used for files with unrecognised extensions decoded as images
+        #[arg(long, value_name = "THRESHOLD", value_parser = clap::value_parser!(u32).range(0..=MAX_SIMILARITY_THRESHOLD as i64))]
+        threshold_image: Option<u32>,
+
+        /// Threshold for videos instead of --threshold
+        #[arg(long, value_name = "THRESHOLD", value_parser = clap::value_parser!(u32).range(0..=MAX_SIMILARITY_THRESHOLD as i64))]
+        threshold_video: Option<u32>,
+
+        /// Threshold for audio instead of --threshold; spectrogram hashes often need a looser one
+        #[arg(long, value_name = "THRESHOLD", value_parser = clap::value_parser!(u32).range(0..=MAX_SIMILARITY_THRESHOLD as i64))]
+        threshold_audio: Option<u32>,
+
+        /// Which file in each group to keep as the original (ties fall back to alphabetical order)
+        #[arg(long, value_enum, default_value_t = Keep::FirstAlphabetical)]
+        keep: Keep,
+
+        /// Keep files under this directory over copies elsewhere, whatever --keep says (repeatable, highest priority first)
+        #[arg(long, value_name = "DIR")]
+        prefer: Vec<std::path::PathBuf>,
+
+        /// Hash used to confirm exact duplicates
+        #[arg(long, value_enum, default_value_t = HashAlgo::Sha256)]
+        hash_algo: HashAlgo,
+
+        /// Cheap check splitting files of the same size before they are hashed in full; matches are always confirmed by --hash-algo
+        #[arg(long, value_enum, default_value_t = PrescreenMode::Partial)]
+        prescreen: PrescreenMode,
+
+        /// Width and height of perceptual hashes: larger is more precise, smaller is faster
+        #[arg(long, default_value_t = HASH_SIZE, value_parser = clap::value_parser!(u32).range(MIN_HASH_SIZE as i64..=MAX_HASH_SIZE as i64))]
+        hash_size: u32,
+
+        /// Algorithm used to compute perceptual hashes
+        #[arg(long, value_enum, default_value_t = HashAlg::DoubleGradient)]
+        hash_alg: HashAlg,
+
+        /// How similar files are joined into groups: pairwise avoids chaining distant files together
+        #[arg(long, value_enum, default_value_t = GroupingMode::Transitive)]
+        grouping: GroupingMode,
+
+        /// Apply a bundle of detection options tuned for a common goal
+        #[arg(long, value_enum)]
+        preset: Option<Preset>,
+
+        /// Double-check perceptual matches with a hash twice as large to reduce false positives
+        #[arg(long)]
+        strict_perceptual: bool,
+
+        /// Skip perceptual analysis and report only exact duplicates, still scanning every media type
+        #[arg(long, conflicts_with = "strict_perceptual")]
+        exact_only: bool,
+
+        /// Only compare sizes and the first and last bytes of files: fast, but groups are probable duplicates that erase skips
+        #[arg(long, conflicts_with = "against")]
+        quick: bool,
+
+        /// Bytes hashed at each end of a file by --quick (default 64K)
+        #[arg(long, value_name = "SIZE", value_parser = parse_size, requires = "quick")]
+        quick_bytes: Option<u64>,
+
+        /// Keep Apple Live Photos (image + .mov with the same name) together, erasing a duplicate image's video with it
+        #[arg(long, visible_alias = "pair-live-photos")]
+        live_photos: bool,
+
+        /// Match videos by their scene-change frames so trimmed copies are found (slower)
+        #[arg(long)]
+        video_scenes: bool,
+
+        /// Also match videos by their soundtrack, so re-encodes that look different are found (slower)
+        #[arg(long, conflicts_with = "video_scenes")]
+        video_audio_match: bool,
+
+        /// Store file hashes in duplicates.json so `mde regroup` can change the threshold without rescanning
+        #[arg(long)]
+        store_hashes: bool,
+
+        /// Only report files that duplicate a file of this earlier report (written with --store-hashes), keeping those as originals
+        #[arg(long, value_name = "BASELINE")]
+        against: Option<std::path::PathBuf>,
+
+        /// Allow scanning a filesystem root or your home directory
+        #[arg(long = "i-know-what-im-doing")]
+        allow_dangerous_path: bool,
+
+        /// Skip directories matching this glob, by name or by path relative to PATH (repeatable)
+        #[arg(long = "exclude-dir", value_name = "GLOB")]
+        exclude_dir: Vec<String>,
+
+        /// Scan paths listed in .mdeignore too
+        #[arg(long)]
+        no_ignore: bool,
+
+        /// Hash every file instead of reusing hashes from earlier scans
+        #[arg(long)]
+        no_cache: bool,
+
+        /// Hash cache location [default: .mde_cache.json next to the output file]
+        #[arg(long)]
+        cache_path: Option<std::path::PathBuf>,
+
+        /// Number of files hashed in parallel [default: number of CPUs]; use fewer on spinning disks
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        workers: Option<u32>,
+
+        /// Order of the groups in the report and duplicates.json [default: the order they were found in]
+        #[arg(long, value_enum)]
+        sort: Option<SortBy>,
+
+        /// Update the existing duplicates.json instead of replacing it, keeping the originals chosen in it and its erased records
+        #[arg(long, conflicts_with_all = ["against", "no_cache"])]
+        update: bool,
+
+        /// Show why similar files were matched: their hash distance and a grid of the differing hash bits
+        #[arg(long)]
+        explain: bool,
     },
 
     /// Remove duplicates.json file from a directory
@@ -80,6 +537,113 @@ pub enum Commands {
         /// Directory containing duplicates.json to remove
         #[arg(default_value = ".")]
         path: std::path::PathBuf,
+
+        /// Name of the duplicates file, so several scans can share a directory
+        #[arg(long, default_value = DUPLICATES_FILENAME)]
+        manifest_name: String,
+    },
+
+    /// Summarize a duplicates.json without erasing anything
+    Stats {
+        /// duplicates.json, or the directory containing it
+        #[arg(default_value = ".")]
+        path: std::path::PathBuf,
+
+        /// Print the statistics as JSON for scripting
+        #[arg(long)]
+        json: bool,
+
+        /// Also list duplicates per directory, the most reclaimable space first
+        #[arg(long)]
+        by_dir: bool,
+
+        /// Name of the duplicates file, so several scans can share a directory
+        #[arg(long, default_value = DUPLICATES_FILENAME)]
+        manifest_name: String,
+    },
+
+    /// Rebuild the groups in duplicates.json with another threshold, from hashes stored by `scan --store-hashes`
+    Regroup {
+        /// Directory containing duplicates.json
+        #[arg(default_value = ".")]
+        path: std::path::PathBuf,
+
+        /// Maximum perceptual hash distance (0-64) for files to count as similar
+        #[arg(short, long, value_parser = clap::value_parser!(u32).range(0..=MAX_SIMILARITY_THRESHOLD as i64))]
+        threshold: u32,
+
+        /// Threshold for images instead of --threshold, also used for files with unrecognised extensions decoded as images; without any --threshold-* flag, the per-type thresholds of the scan are kept
+        #[arg(long, value_name = "THRESHOLD", value_parser = clap::value_parser!(u32).range(0..=MAX_SIMILARITY_THRESHOLD as i64))]
+        threshold_image: Option<u32>,
+
+        /// Threshold for videos instead of --threshold
+        #[arg(long, value_name = "THRESHOLD", value_parser = clap::value_parser!(u32).range(0..=MAX_SIMILARITY_THRESHOLD as i64))]
+        threshold_video: Option<u32>,
+
+        /// Threshold for audio instead of --threshold
+        #[arg(long, value_name = "THRESHOLD", value_parser = clap::value_parser!(u32).range(0..=MAX_SIMILARITY_THRESHOLD as i64))]
+        threshold_audio: Option<u32>,
+
+        /// Which file in each group to keep as the original (ties fall back to alphabetical order)
+        #[arg(long, value_enum, default_value_t = Keep::FirstAlphabetical)]
+        keep: Keep,
+
+        /// Keep files under this directory over copies elsewhere, whatever --keep says (repeatable, highest priority first)
+        #[arg(long, value_name = "DIR")]
+        prefer: Vec<std::path::PathBuf>,
+
+        /// Fail unless the stored hashes have this size [default: the size they were scanned with]
+        #[arg(long, value_parser = clap::value_parser!(u32).range(MIN_HASH_SIZE as i64..=MAX_HASH_SIZE as i64))]
+        hash_size: Option<u32>,
+
+        /// Fail unless the stored hashes were computed with this algorithm
+        #[arg(long, value_enum)]
+        hash_alg: Option<HashAlg>,
+
+        /// How similar files are joined into groups [default: the mode they were scanned with]
+        #[arg(long, value_enum)]
+        grouping: Option<GroupingMode>,
+
+        /// Name of the duplicates file, so several scans can share a directory
+        #[arg(long, default_value = DUPLICATES_FILENAME)]
+        manifest_name: String,
+    },
+
+    /// Combine several duplicates files into one, joining groups that share files
+    Merge {
+        /// Duplicates files to combine
+        #[arg(required = true)]
+        manifests: Vec<std::path::PathBuf>,
+
+        /// File to write the combined duplicates file to; .yaml, .yml, .toml and .jsonl files are written as YAML, TOML and JSON Lines
+        #[arg(short, long)]
+        output: std::path::PathBuf,
+
+        /// Which file to keep as the original in joined groups whose duplicates files disagree (ties fall back to alphabetical order)
+        #[arg(long, value_enum, default_value_t = Keep::FirstAlphabetical)]
+        keep: Keep,
+
+        /// Keep files under this directory over copies elsewhere, whatever --keep says (repeatable, highest priority first)
+        #[arg(long, value_name = "DIR")]
+        prefer: Vec<std::path::PathBuf>,
+    },
+
+    /// Re-hash the files in duplicates.json and fail if any no longer match
+    Verify {
+        /// Directory containing duplicates.json
+        #[arg(default_value = ".")]
+        path: std::path::PathBuf,
+
+        /// Name of the duplicates file, so several scans can share a directory
+        #[arg(long, default_value = DUPLICATES_FILENAME)]
+        manifest_name: String,
+    },
+
+    /// Restore the files removed by the most recent `erase --soft`
+    Undo {
+        /// Directory the soft erase was run in
+        #[arg(default_value = ".")]
+        path: std::path::PathBuf,
     },
 
     /// Delete duplicate files listed in duplicates.json (atomic operation)
@@ -87,32 +651,377 @@ pub enum Commands {
         /// Directory containing duplicates.json
         #[arg(default_value = ".")]
         path: std::path::PathBuf,
+
+        /// Name of the duplicates file, so several scans can share a directory
+        #[arg(long, default_value = DUPLICATES_FILENAME)]
+        manifest_name: String,
+
+        /// Erase the duplicates listed in this file, wherever it is, instead of PATH/duplicates.json.
+        /// Files are staged and soft-deleted in the directory it was scanned from
+        #[arg(long, value_name = "FILE", conflicts_with = "manifest_name")]
+        manifest: Option<std::path::PathBuf>,
+
+        /// Allow erasing from a filesystem root or your home directory
+        #[arg(long = "i-know-what-im-doing")]
+        allow_dangerous_path: bool,
+
+        /// Stop once this much space is freed (e.g. 500M, 50G), erasing the largest groups first
+        #[arg(long, value_parser = parse_size)]
+        reclaim_target: Option<u64>,
+
+        /// Move duplicates to the system trash instead of deleting them permanently
+        #[arg(long, conflicts_with_all = ["soft", "move_to"])]
+        trash: bool,
+
+        /// Keep erased files in a hidden folder so `mde undo` can restore them
+        #[arg(long, conflicts_with = "move_to")]
+        soft: bool,
+
+        /// Move duplicates into DIR for review instead of deleting them, keeping their
+        /// path relative to PATH
+        #[arg(long, value_name = "DIR")]
+        move_to: Option<std::path::PathBuf>,
+
+        /// Days to keep soft-deleted files before they are removed for good
+        #[arg(long, default_value_t = DEFAULT_RETENTION_DAYS, value_parser = clap::value_parser!(u32).range(1..=36500))]
+        retention_days: u32,
+
+        /// Show which files would be erased without changing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Choose which file to keep in each group, or skip the group, before erasing
+        #[arg(long)]
+        interactive: bool,
+
+        /// Only erase exact duplicates, keeping groups of similar files for later; with --interactive, only similar groups are reviewed
+        #[arg(long)]
+        only_exact: bool,
+
+        /// Never erase files under this directory, even if listed as duplicates; the rest of their groups is still erased (repeatable)
+        #[arg(long, value_name = "DIR")]
+        protect: Vec<std::path::PathBuf>,
+
+        /// Directory to stage files in before deleting them, e.g. on another disk.
+        /// Defaults to PATH
+        #[arg(long)]
+        staging_dir: Option<std::path::PathBuf>,
+
+        /// Keep duplicates.json after erasing, with each erased group marked and timestamped
+        #[arg(long)]
+        keep_manifest: bool,
+
+        /// Warn if duplicates.json was produced with a different hash algorithm
+        #[arg(long, value_enum)]
+        hash_algo: Option<HashAlgo>,
+
+        /// Skip re-hashing exact duplicates before erasing them to check they did not change since the scan
+        #[arg(long)]
+        no_verify: bool,
+
+        /// Erase without asking for confirmation, e.g. in scripts (implied by --quiet)
+        #[arg(short, long)]
+        yes: bool,
     },
 }
 
-pub fn run() -> Result<()> {
-    let cli = Cli::parse();
+pub fn run() -> Result<ExitCode> {
+    let mut cli = Cli::parse();
+    cli.quiet |= cli.silent;
+
+    logger::init(
+        cli.verbose,
+        cli.silent,
+        cli.log_level.map(Into::into),
+        cli.log_file.as_deref(),
+    )?;
 
-    logger::init(cli.verbose, cli.quiet);
+    if let Some(path) = &cli.media_types {
+        // No file has been classified yet, so no registry is in use
+        let _ = MediaRegistry::load(path)?.install();
+    }
+
+    if let Some(threshold) = cli.mmap_threshold {
+        hasher::set_mmap_threshold(Some(threshold));
+    }
 
-    let command: Box<dyn Command> = match cli.command {
+    match cli.command {
         Commands::Scan {
             path,
             recursive,
+            max_depth,
             include_hidden,
+            follow_symlinks,
+            from_file,
+            limit,
             output,
+            manifest_name,
+            json_summary,
+            print0,
+            fail_on_duplicates,
+            format,
+            include_ext,
+            exclude_ext,
+            min_size,
+            max_size,
+            include_empty,
+            newer_than,
+            older_than,
             media,
-        } => Box::new(Scanner::new(
+            threshold,
+            threshold_image,
+            threshold_video,
+            threshold_audio,
+            keep,
+            prefer,
+            hash_algo,
+            prescreen,
+            hash_size,
+            hash_alg,
+            grouping,
+            preset,
+            strict_perceptual,
+            exact_only,
+            quick,
+            quick_bytes,
+            live_photos,
+            video_scenes,
+            video_audio_match,
+            store_hashes,
+            against,
+            allow_dangerous_path,
+            exclude_dir,
+            no_ignore,
+            no_cache,
+            cache_path,
+            workers,
+            sort,
+            update,
+            explain,
+        } => {
+            let mut detection = DetectionOptions {
+                live_photos,
+                keep_hashes: store_hashes,
+                strict_perceptual,
+                exact_only,
+                media_thresholds: MediaThresholds {
+                    image: threshold_image,
+                    video: threshold_video,
+                    audio: threshold_audio,
+                },
+                video_scenes,
+                video_audio_match,
+                quick: quick.then(|| quick_bytes.unwrap_or(hasher::PARTIAL_HASH_LEN)),
+                original_strategy: keep.into(),
+                preferred_dirs: prefer,
+                hash_algorithm: hash_algo.into(),
+                prescreen: prescreen.into(),
+                perceptual: PerceptualConfig {
+                    hash_size,
+                    algorithm: hash_alg.into(),
+                },
+                grouping: grouping.into(),
+                min_size,
+                max_size,
+                ignore_empty: !include_empty,
+                cancel: cancel_on_ctrl_c(),
+                ..DetectionOptions::from(MediaFilter::from(media))
+            };
+            if let Some(preset) = preset {
+                DetectionPreset::from(preset).apply(&mut detection);
+            }
+            // An explicit threshold takes precedence over the preset
+            if let Some(threshold) = threshold {
+                detection.threshold = threshold;
+            }
+            // Files are compared across all directories; --manifest-name writes to the first
+            let mut paths = path.into_iter();
+            let path = paths.next().unwrap_or_else(|| std::path::PathBuf::from("."));
+            let output = output.or_else(|| manifest_name.map(|name| path.join(name)));
+            let format = match format {
+                Some(format) => format.into(),
+                None => output
+                    .as_deref()
+                    .map(ReportFormat::from_path)
+                    .unwrap_or_default(),
+            };
+            let scanner = Scanner::with_options(ScanOptions {
+                recursive,
+                include_hidden,
+                output,
+                quiet: cli.quiet,
+                detection,
+                max_depth: max_depth.map(|depth| depth as usize),
+                newer_than,
+                older_than,
+                format,
+                extensions: ExtensionFilter::new(include_ext, exclude_ext),
+                allow_dangerous_path,
+                use_ignore_file: !no_ignore,
+                exclude_dirs: exclude_dir,
+                use_cache: !no_cache,
+                cache_path,
+                follow_symlinks,
+                file_list: from_file,
+                json_summary,
+                print0,
+                limit: limit.map(|limit| limit as usize),
+                extra_paths: paths.collect(),
+                against,
+                workers: workers.map(|workers| workers as usize),
+                sort: sort.map(Into::into),
+                update,
+                explain,
+                ..ScanOptions::new(path)
+            });
+            let report = scanner.execute()?;
+            if report.canceled {
+                return Ok(ExitCode::from(EXIT_CANCELED));
+            }
+            if fail_on_duplicates && !report.groups.is_empty() {
+                return Ok(ExitCode::from(EXIT_DUPLICATES_FOUND));
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Commands::Clean {
             path,
-            recursive,
-            include_hidden,
+            manifest_name,
+        } => execute(Cleaner::new(path, cli.quiet).with_manifest_name(manifest_name)),
+        Commands::Stats {
+            path,
+            json,
+            by_dir,
+            manifest_name,
+        } => execute(
+            Stats::new(path, json, cli.quiet)
+                .with_by_directory(by_dir)
+                .with_manifest_name(manifest_name),
+        ),
+        Commands::Regroup {
+            path,
+            threshold,
+            threshold_image,
+            threshold_video,
+            threshold_audio,
+            keep,
+            prefer,
+            hash_size,
+            hash_alg,
+            grouping,
+            manifest_name,
+        } => {
+            // Settings left out on the command line default like they do for scan
+            let perceptual_config =
+                (hash_size.is_some() || hash_alg.is_some()).then(|| PerceptualConfig {
+                    hash_size: hash_size.unwrap_or(HASH_SIZE),
+                    algorithm: hash_alg.unwrap_or_default().into(),
+                });
+            let media_thresholds = MediaThresholds {
+                image: threshold_image,
+                video: threshold_video,
+                audio: threshold_audio,
+            };
+            execute(
+                Regrouper::new(path, threshold, cli.quiet)
+                    .with_original_strategy(keep.into())
+                    .with_preferred_dirs(prefer)
+                    .with_perceptual_config(perceptual_config)
+                    .with_grouping(grouping.map(Into::into))
+                    .with_media_thresholds(
+                        (!media_thresholds.is_empty()).then_some(media_thresholds),
+                    )
+                    .with_manifest_name(manifest_name),
+            )
+        }
+        Commands::Merge {
+            manifests,
             output,
-            cli.quiet,
-            media.into(),
-        )),
-        Commands::Clean { path } => Box::new(Cleaner::new(path, cli.quiet)),
-        Commands::Erase { path } => Box::new(Eraser::new(path, cli.quiet)),
-    };
-
-    command.execute()
+            keep,
+            prefer,
+        } => execute(
+            Merger::new(manifests, output, cli.quiet)
+                .with_original_strategy(keep.into())
+                .with_preferred_dirs(prefer),
+        ),
+        Commands::Verify {
+            path,
+            manifest_name,
+        } => execute(Verifier::new(path, cli.quiet).with_manifest_name(manifest_name)),
+        Commands::Undo { path } => execute(Undoer::new(path, cli.quiet)),
+        Commands::Erase {
+            path,
+            manifest_name,
+            manifest,
+            allow_dangerous_path,
+            reclaim_target,
+            trash,
+            soft,
+            move_to,
+            retention_days,
+            dry_run,
+            interactive,
+            only_exact,
+            protect,
+            staging_dir,
+            keep_manifest,
+            hash_algo,
+            no_verify,
+            yes,
+        } => {
+            let delete_mode = if trash {
+                DeleteMode::Trash
+            } else if soft {
+                DeleteMode::Soft
+            } else if let Some(dir) = move_to {
+                DeleteMode::Move(dir)
+            } else {
+                DeleteMode::Permanent
+            };
+            execute(
+                Eraser::new(path, cli.quiet)
+                    .allow_dangerous_path(allow_dangerous_path)
+                    .with_reclaim_target(reclaim_target)
+                    .with_delete_mode(delete_mode)
+                    .with_retention_days(retention_days)
+                    .with_dry_run(dry_run)
+                    .with_hash_algorithm(hash_algo.map(Into::into))
+                    .with_interactive(interactive)
+                    .with_only_exact(only_exact)
+                    .with_protected_dirs(protect)
+                    .with_staging_dir(staging_dir)
+                    .with_keep_manifest(keep_manifest)
+                    .with_verify_checksums(!no_verify)
+                    .with_assume_yes(yes)
+                    .with_cancel(cancel_on_ctrl_c())
+                    .with_manifest_name(manifest_name)
+                    .with_manifest_path(manifest),
+            )
+        }
+    }
+}
+
+/// Returns a token canceled by Ctrl-C, so a scan or erase stops cleanly
+/// instead of being killed. A second Ctrl-C exits right away.
+fn cancel_on_ctrl_c() -> Option<CancelToken> {
+    let token = CancelToken::new();
+    let handler_token = token.clone();
+    let installed = ctrlc::set_handler(move || {
+        if handler_token.is_canceled() {
+            std::process::exit(EXIT_CANCELED.into());
+        }
+        handler_token.cancel();
+    });
+    match installed {
+        Ok(()) => Some(token),
+        Err(e) => {
+            log::warn!("Could not handle Ctrl-C, it will stop mde at once: {}", e);
+            None
+        }
+    }
+}
+
+/// Runs a command for the CLI. Commands print their own results, so the
+/// typed output is only of use to library callers.
+fn execute<C: Command>(command: C) -> Result<ExitCode> {
+    command.execute().map(|_| ExitCode::SUCCESS)
 }
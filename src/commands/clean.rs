@@ -3,9 +3,8 @@ use std::path::PathBuf;
 use console::style;
 
 use super::Command;
-use crate::error::Result;
-
-const DUPLICATES_FILENAME: &str = "duplicates.json";
+use crate::error::{Error, FileAction, Result};
+use crate::services::duplicate::{DuplicatesFile, DUPLICATES_FILENAME};
 
 // Styled output prefixes (Classic ASCII)
 const SUCCESS_PREFIX: &str = "[OK]";
@@ -14,22 +13,49 @@ const INFO_PREFIX: &str = "[*]";
 pub struct Cleaner {
     path: PathBuf,
     quiet: bool,
+    manifest_name: String,
 }
 
 impl Cleaner {
     pub fn new(path: PathBuf, quiet: bool) -> Self {
-        Self { path, quiet }
+        Self {
+            path,
+            quiet,
+            manifest_name: DUPLICATES_FILENAME.to_string(),
+        }
+    }
+
+    /// Removes `name` instead of duplicates.json.
+    pub fn with_manifest_name(mut self, name: impl Into<String>) -> Self {
+        self.manifest_name = name.into();
+        self
     }
 }
 
 impl Command for Cleaner {
-    fn execute(&self) -> Result<()> {
-        let duplicates_file = self.path.join(DUPLICATES_FILENAME);
+    /// Whether a duplicates file was removed.
+    type Output = bool;
+
+    fn execute(&self) -> Result<bool> {
+        let duplicates_file = self.path.join(&self.manifest_name);
 
         log::debug!("Looking for duplicates file at: {:?}", duplicates_file);
 
         if duplicates_file.exists() {
-            std::fs::remove_file(&duplicates_file)?;
+            // An incompatible file is still removed, but say why erase rejected it
+            if let Err(Error::UnsupportedVersion { found, .. }) =
+                DuplicatesFile::load(&duplicates_file)
+                && !self.quiet
+            {
+                println!(
+                    "{} {} was written by an incompatible version ({}). Run 'mde scan' to create a new one.",
+                    style(INFO_PREFIX).blue().bold(),
+                    style(duplicates_file.display()).cyan(),
+                    found
+                );
+            }
+            std::fs::remove_file(&duplicates_file)
+                .map_err(Error::on_file(FileAction::Remove, &duplicates_file))?;
             if !self.quiet {
                 println!(
                     "{} Removed: {}",
@@ -38,17 +64,18 @@ impl Command for Cleaner {
                 );
             }
             log::info!("Duplicates file removed: {:?}", duplicates_file);
+            Ok(true)
         } else {
             if !self.quiet {
                 println!(
-                    "{} No duplicates.json found in: {}",
+                    "{} No {} found in: {}",
                     style(INFO_PREFIX).blue().bold(),
+                    self.manifest_name,
                     style(self.path.display()).cyan()
                 );
             }
             log::debug!("Duplicates file not found at: {:?}", duplicates_file);
+            Ok(false)
         }
-
-        Ok(())
     }
 }
@@ -1,14 +1,24 @@
+use std::collections::HashMap;
 use std::fs;
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 
+use chrono::Utc;
 use console::style;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{Confirm, Select};
 use indicatif::{ProgressBar, ProgressStyle};
 
 use super::Command;
-use crate::error::{Error, Result};
-use crate::services::duplicate::DuplicatesFile;
+use crate::error::{Error, FileAction, Result};
+use crate::services::cancel::CancelToken;
+use crate::services::duplicate::{DuplicateEntry, DuplicatesFile, DUPLICATES_FILENAME};
+use crate::services::hasher::{self, HashAlgorithm};
+use crate::services::relocate;
+use crate::services::safety;
+use crate::services::soft_delete::{self, SoftDeleteManifest, SoftDeletedFile};
+use crate::services::units::format_size;
 
-const DUPLICATES_FILENAME: &str = "duplicates.json";
 const STAGING_DIR_NAME: &str = ".mde_erase_staging";
 
 // Styled output prefixes (Classic ASCII)
@@ -17,62 +27,496 @@ const WARNING_PREFIX: &str = "[!]";
 const ERROR_PREFIX: &str = "[X]";
 const INFO_PREFIX: &str = "[*]";
 
+/// What happens to duplicate files once they have been staged.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum DeleteMode {
+    /// Delete the files for good (default).
+    #[default]
+    Permanent,
+    /// Move the files to the operating system's trash so they can be recovered.
+    Trash,
+    /// Keep the staged files in a timestamped batch that `mde undo` can restore.
+    Soft,
+    /// Move the files into this directory for review, keeping their path
+    /// relative to the erased directory.
+    Move(PathBuf),
+}
+
+/// What to do with a duplicate group during interactive review.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewChoice {
+    /// Keep the file at this position of the group and erase the others.
+    ///
+    /// Position 0 is the current original, followed by its duplicates.
+    Keep(usize),
+    /// Leave the group untouched and keep it in duplicates.json.
+    Skip,
+}
+
+/// Outcome of an erase run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EraseSummary {
+    /// Number of duplicate files deleted, or moved to the trash or the
+    /// review directory.
+    pub deleted: usize,
+    /// Number of listed duplicates that no longer existed and were skipped.
+    pub skipped_missing: usize,
+    /// Total size in bytes of the erased files.
+    pub reclaimed_bytes: u64,
+    /// Whether a failure forced every staged file to be restored.
+    pub rolled_back: bool,
+    /// Whether this was a dry run; `deleted` and `reclaimed_bytes` then describe
+    /// what would have been erased.
+    pub dry_run: bool,
+    /// Whether erasing was declined at the confirmation prompt, or refused
+    /// because there was no terminal to ask on, so nothing was erased.
+    pub declined: bool,
+    /// Whether erasing was canceled while files were being staged, so every
+    /// staged file was restored and nothing was erased.
+    pub canceled: bool,
+    /// Number of listed duplicates kept because they are under a protected
+    /// directory.
+    pub protected: usize,
+}
+
+/// What an erase run would do, worked out by [`Eraser::plan`] without
+/// touching the filesystem.
+#[derive(Debug, Default)]
+pub struct ErasePlan {
+    /// Groups whose duplicates are erased.
+    pub selected: Vec<DuplicateEntry>,
+    /// Pending groups kept for a later run: those not needed to meet the
+    /// reclaim target and those skipped during review.
+    pub remaining: Vec<DuplicateEntry>,
+    /// Existing duplicates that would be erased.
+    pub files: Vec<PathBuf>,
+    /// Listed duplicates that no longer exist and would be skipped.
+    pub missing: Vec<PathBuf>,
+    /// Combined size in bytes of `files`, the space erasing frees.
+    pub total_bytes: u64,
+    /// Listed duplicates under a protected directory, which are kept.
+    pub protected: Vec<PathBuf>,
+    /// Entries already marked erased, kept as a record.
+    erased: Vec<DuplicateEntry>,
+    /// Where the duplicates file was read from, and its contents without
+    /// the entries above; `None` if there is nothing to erase.
+    manifest: Option<(PathBuf, DuplicatesFile)>,
+}
+
 pub struct Eraser {
     path: PathBuf,
     quiet: bool,
+    allow_dangerous_path: bool,
+    reclaim_target: Option<u64>,
+    delete_mode: DeleteMode,
+    dry_run: bool,
+    hash_algorithm: Option<HashAlgorithm>,
+    retention_days: u32,
+    interactive: bool,
+    only_exact: bool,
+    protected_dirs: Vec<PathBuf>,
+    manifest_name: String,
+    manifest_path: Option<PathBuf>,
+    staging_dir: Option<PathBuf>,
+    keep_manifest: bool,
+    verify_checksums: bool,
+    assume_yes: bool,
+    cancel: Option<CancelToken>,
 }
 
 impl Eraser {
     pub fn new(path: PathBuf, quiet: bool) -> Self {
-        Self { path, quiet }
+        Self {
+            path,
+            quiet,
+            allow_dangerous_path: false,
+            reclaim_target: None,
+            delete_mode: DeleteMode::default(),
+            dry_run: false,
+            hash_algorithm: None,
+            retention_days: soft_delete::DEFAULT_RETENTION_DAYS,
+            interactive: false,
+            only_exact: false,
+            protected_dirs: Vec::new(),
+            manifest_name: DUPLICATES_FILENAME.to_string(),
+            manifest_path: None,
+            staging_dir: None,
+            keep_manifest: false,
+            verify_checksums: true,
+            assume_yes: false,
+            cancel: None,
+        }
+    }
+
+    /// Allows erasing from a filesystem root or the user's home directory.
+    pub fn allow_dangerous_path(mut self, allow: bool) -> Self {
+        self.allow_dangerous_path = allow;
+        self
+    }
+
+    /// Stops erasing once at least `target` bytes have been freed.
+    ///
+    /// Groups are processed largest-first; the groups left over are kept in
+    /// duplicates.json for a later run.
+    pub fn with_reclaim_target(mut self, target: Option<u64>) -> Self {
+        self.reclaim_target = target;
+        self
+    }
+
+    /// Chooses whether erased files are deleted permanently, moved to the
+    /// trash, soft-deleted or moved into a review directory.
+    pub fn with_delete_mode(mut self, mode: DeleteMode) -> Self {
+        self.delete_mode = mode;
+        self
+    }
+
+    /// Lists what would be erased without touching the filesystem.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Warns when duplicates.json was produced with a different exact-hash algorithm.
+    pub fn with_hash_algorithm(mut self, algorithm: Option<HashAlgorithm>) -> Self {
+        self.hash_algorithm = algorithm;
+        self
+    }
+
+    /// Sets how many days soft-deleted batches are kept before being pruned.
+    pub fn with_retention_days(mut self, days: u32) -> Self {
+        self.retention_days = days;
+        self
+    }
+
+    /// Asks which file to keep in each group before erasing anything.
+    ///
+    /// Skipped groups are left untouched and kept in duplicates.json.
+    pub fn with_interactive(mut self, interactive: bool) -> Self {
+        self.interactive = interactive;
+        self
     }
 
-    /// Returns the path to the duplicates.json file.
+    /// Only erases exact duplicates, keeping groups of similar files in
+    /// duplicates.json for a later run. With
+    /// [`with_interactive`](Self::with_interactive), exact groups are erased
+    /// without asking and only the similar ones are reviewed.
+    pub fn with_only_exact(mut self, only_exact: bool) -> Self {
+        self.only_exact = only_exact;
+        self
+    }
+
+    /// Never erases files under these directories, even when they are listed
+    /// as duplicates; the rest of their groups is erased as usual.
+    pub fn with_protected_dirs(mut self, dirs: Vec<PathBuf>) -> Self {
+        self.protected_dirs = dirs;
+        self
+    }
+
+    /// Reads the duplicates from `name` instead of duplicates.json.
+    pub fn with_manifest_name(mut self, name: impl Into<String>) -> Self {
+        self.manifest_name = name.into();
+        self
+    }
+
+    /// Reads the duplicates from the file at `path`, wherever it is, instead
+    /// of from the manifest inside the erased directory.
+    ///
+    /// Files are then staged and soft-deleted in the directory the manifest
+    /// records as scanned, or in the erased directory if it records none.
+    pub fn with_manifest_path(mut self, path: Option<PathBuf>) -> Self {
+        self.manifest_path = path;
+        self
+    }
+
+    /// Stages files in `.mde_erase_staging` inside `dir` instead of inside the
+    /// erased directory. `dir` may be on another filesystem; files are then
+    /// copied instead of renamed.
+    pub fn with_staging_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.staging_dir = dir;
+        self
+    }
+
+    /// Keeps duplicates.json after erasing, with the erased entries marked as
+    /// such and timestamped, instead of removing it.
+    pub fn with_keep_manifest(mut self, keep: bool) -> Self {
+        self.keep_manifest = keep;
+        self
+    }
+
+    /// Re-hashes each exact duplicate right before staging it, and rolls the
+    /// erase back if it no longer matches the hash recorded by scan (on by
+    /// default). Duplicates without a recorded hash are not checked.
+    pub fn with_verify_checksums(mut self, verify: bool) -> Self {
+        self.verify_checksums = verify;
+        self
+    }
+
+    /// Erases without asking for confirmation first.
+    ///
+    /// Otherwise, unless quiet, erase asks before touching any file and
+    /// refuses when stdin is not a terminal.
+    pub fn with_assume_yes(mut self, assume_yes: bool) -> Self {
+        self.assume_yes = assume_yes;
+        self
+    }
+
+    /// Stops erasing once `cancel` is canceled while files are being staged:
+    /// the files staged so far are restored and nothing is erased. Once every
+    /// file is staged, erasing completes, so no batch is left half-done.
+    pub fn with_cancel(mut self, cancel: Option<CancelToken>) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Returns the path to the duplicates file.
     fn duplicates_file_path(&self) -> PathBuf {
-        self.path.join(DUPLICATES_FILENAME)
+        match &self.manifest_path {
+            Some(path) => path.clone(),
+            None => self.path.join(&self.manifest_name),
+        }
+    }
+
+    /// Returns the directory files of `duplicates_file` are erased from:
+    /// its recorded root when it was given by path, the erased directory
+    /// otherwise.
+    fn erase_root<'a>(&'a self, duplicates_file: &'a DuplicatesFile) -> &'a Path {
+        match (&self.manifest_path, &duplicates_file.root) {
+            (Some(_), Some(root)) => root,
+            _ => &self.path,
+        }
     }
 
-    /// Returns the path to the staging directory.
-    fn staging_dir(&self) -> PathBuf {
-        self.path.join(STAGING_DIR_NAME)
+    /// Asks whether to erase `count` files freeing `bytes`, defaulting to no.
+    ///
+    /// Refuses without asking when stdin is not a terminal.
+    fn confirm(&self, count: usize, bytes: u64) -> Result<bool> {
+        if !std::io::stdin().is_terminal() {
+            println!(
+                "{} Not erasing: stdin is not a terminal. Pass --yes to erase without confirmation.",
+                style(WARNING_PREFIX).yellow().bold()
+            );
+            return Ok(false);
+        }
+
+        let action = match &self.delete_mode {
+            DeleteMode::Permanent => "Delete".to_string(),
+            DeleteMode::Trash => "Move to the trash".to_string(),
+            DeleteMode::Soft => "Soft-delete".to_string(),
+            DeleteMode::Move(dir) => format!("Move into {}", dir.display()),
+        };
+        let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!("{} {} files ({})?", action, count, format_size(bytes)))
+            .default(false)
+            .interact_opt()?;
+        Ok(confirmed == Some(true))
+    }
+
+    /// Returns the path to the staging directory for erasing from `root`.
+    fn staging_dir(&self, root: &Path) -> PathBuf {
+        self.staging_dir
+            .as_deref()
+            .unwrap_or(root)
+            .join(STAGING_DIR_NAME)
     }
 }
 
-impl Command for Eraser {
-    fn execute(&self) -> Result<()> {
+impl Eraser {
+    /// Works out what erasing would do, without changing anything on disk.
+    ///
+    /// Loads duplicates.json, selects the groups to erase (applying the
+    /// reclaim target and, if enabled, the interactive review) and checks
+    /// which listed duplicates still exist. The returned plan is empty if
+    /// there is no duplicates file or nothing to erase.
+    pub fn plan(&self) -> Result<ErasePlan> {
+        // A manifest given by path is checked against its root once loaded
+        if self.manifest_path.is_none()
+            && !self.allow_dangerous_path
+            && safety::is_dangerous_root(&self.path)
+        {
+            return Err(Error::DangerousPath(self.path.clone()));
+        }
+
         let duplicates_path = self.duplicates_file_path();
 
         log::info!("Looking for duplicates file at: {:?}", duplicates_path);
 
         if !duplicates_path.exists() {
+            if !self.quiet {
+                match &self.manifest_path {
+                    Some(path) => println!(
+                        "{} No duplicates file found at: {}",
+                        style(INFO_PREFIX).blue().bold(),
+                        style(path.display()).cyan()
+                    ),
+                    None => println!(
+                        "{} No {} found in: {}\n   Run 'mde scan' first to detect duplicates.",
+                        style(INFO_PREFIX).blue().bold(),
+                        self.manifest_name,
+                        style(self.path.display()).cyan()
+                    ),
+                }
+            }
+            return Ok(ErasePlan::default());
+        }
+
+        // Load the duplicates file
+        let mut duplicates_file = DuplicatesFile::load(&duplicates_path)?;
+
+        let root = self.erase_root(&duplicates_file);
+        if self.manifest_path.is_some()
+            && !self.allow_dangerous_path
+            && safety::is_dangerous_root(root)
+        {
+            return Err(Error::DangerousPath(root.to_path_buf()));
+        }
+
+        let outside_root = duplicates_file.paths_outside_root();
+        if let Some(root) = &duplicates_file.root
+            && !outside_root.is_empty()
+        {
+            log::warn!(
+                "{} listed files are outside the scanned directory {:?}: {:?}",
+                outside_root.len(),
+                root,
+                outside_root
+            );
             if !self.quiet {
                 println!(
-                    "{} No duplicates.json found in: {}\n   Run 'mde scan' first to detect duplicates.",
-                    style(INFO_PREFIX).blue().bold(),
-                    style(self.path.display()).cyan()
+                    "{} {} listed files are outside the scanned directory {}:",
+                    style(WARNING_PREFIX).yellow().bold(),
+                    style(outside_root.len()).yellow(),
+                    style(root.display()).cyan()
                 );
+                for path in &outside_root {
+                    println!("   {}", path.display());
+                }
             }
-            return Ok(());
         }
 
-        // Load the duplicates file
-        let duplicates_file = DuplicatesFile::load(&duplicates_path)?;
+        if let Some(expected) = self.hash_algorithm
+            && duplicates_file.hash_algorithm != expected
+        {
+            log::warn!(
+                "{:?} was produced with {}, expected {}",
+                duplicates_path,
+                duplicates_file.hash_algorithm,
+                expected
+            );
+            if !self.quiet {
+                println!(
+                    "{} {} was produced with {}, not {}.",
+                    style(WARNING_PREFIX).yellow().bold(),
+                    duplicates_path.display(),
+                    style(duplicates_file.hash_algorithm).yellow(),
+                    style(expected).yellow()
+                );
+            }
+        }
 
-        if duplicates_file.entries.is_empty() {
+        if duplicates_file.pending_entries().next().is_none() {
             if !self.quiet {
                 println!(
                     "{} No duplicates to erase.",
                     style(INFO_PREFIX).blue().bold()
                 );
             }
-            return Ok(());
+            return Ok(ErasePlan::default());
+        }
+
+        // Select the groups to erase in this run; erased entries are only a record
+        let (erased, entries): (Vec<_>, Vec<_>) = std::mem::take(&mut duplicates_file.entries)
+            .into_iter()
+            .partition(DuplicateEntry::is_erased);
+        // Quick scans are not trusted with deletion; their groups stay listed
+        let (probable, entries): (Vec<_>, Vec<_>) =
+            entries.into_iter().partition(DuplicateEntry::is_probable);
+        if !probable.is_empty() {
+            log::warn!(
+                "Skipping {} groups of probable duplicates from a quick scan",
+                probable.len()
+            );
+            if !self.quiet {
+                println!(
+                    "{} Skipping {} groups of probable duplicates (scan --quick); run a full scan to confirm them.",
+                    style(WARNING_PREFIX).yellow().bold(),
+                    style(probable.len()).yellow()
+                );
+            }
+        }
+        // Without a review, similar groups are kept for later instead
+        let (entries, similar): (Vec<_>, Vec<_>) = if self.only_exact && !self.interactive {
+            entries.into_iter().partition(DuplicateEntry::is_exact)
+        } else {
+            (entries, Vec::new())
+        };
+        if !similar.is_empty() && !self.quiet {
+            println!(
+                "{} Skipping {} groups of similar files (--only-exact).",
+                style(INFO_PREFIX).blue().bold(),
+                style(similar.len()).cyan()
+            );
+        }
+        let (mut selected, mut remaining) = match self.reclaim_target {
+            Some(target) => select_for_reclaim(entries, target),
+            None => (entries, Vec::new()),
+        };
+        remaining.extend(similar);
+        remaining.extend(probable);
+
+        if self.interactive {
+            // Exact groups need no review when only similar ones are doubted
+            let (trusted, doubted): (Vec<_>, Vec<_>) = if self.only_exact {
+                selected.into_iter().partition(DuplicateEntry::is_exact)
+            } else {
+                (Vec::new(), selected)
+            };
+            let total = doubted.len();
+            let mut index = 0;
+            let (reviewed, skipped) = review_entries(doubted, |entry| {
+                index += 1;
+                prompt_keep(entry, index, total)
+            })?;
+            selected = trusted;
+            selected.extend(reviewed);
+            remaining.extend(skipped);
+        }
+
+        // An original removed since the scan must not take all its copies with it
+        for entry in &mut selected {
+            let missing_original = entry.original.clone();
+            if entry.ensure_original_exists() && !self.quiet {
+                println!(
+                    "{} Original no longer exists: {}, keeping {} instead.",
+                    style(WARNING_PREFIX).yellow().bold(),
+                    style(missing_original.display()).yellow(),
+                    style(entry.original.display()).green()
+                );
+            }
+        }
+
+        // Protected copies stay on disk; groups left with nothing to erase are dropped
+        let mut protected = Vec::new();
+        if !self.protected_dirs.is_empty() {
+            for entry in &mut selected {
+                protected.extend(entry.keep_protected(&self.protected_dirs));
+            }
+            selected.retain(|entry| !entry.duplicates.is_empty());
+        }
+        if !protected.is_empty() {
+            log::info!("Keeping protected duplicates: {:?}", protected);
+            if !self.quiet {
+                println!(
+                    "{} Keeping {} duplicates under protected directories (--protect).",
+                    style(INFO_PREFIX).blue().bold(),
+                    style(protected.len()).cyan()
+                );
+            }
         }
 
         // Collect all files to delete
-        let files_to_delete: Vec<PathBuf> = duplicates_file
-            .entries
+        let files_to_delete: Vec<PathBuf> = selected
             .iter()
-            .flat_map(|entry| entry.duplicates.clone())
+            .flat_map(DuplicateEntry::files_to_erase)
             .collect();
 
         if files_to_delete.is_empty() {
@@ -82,7 +526,10 @@ impl Command for Eraser {
                     style(INFO_PREFIX).blue().bold()
                 );
             }
-            return Ok(());
+            return Ok(ErasePlan {
+                protected,
+                ..ErasePlan::default()
+            });
         }
 
         if !self.quiet {
@@ -90,7 +537,7 @@ impl Command for Eraser {
                 "{} Found {} duplicate files to erase from {} groups.",
                 style(INFO_PREFIX).blue().bold(),
                 style(files_to_delete.len()).cyan().bold(),
-                style(duplicates_file.entries.len()).cyan()
+                style(selected.len()).cyan()
             );
         }
 
@@ -109,88 +556,406 @@ impl Command for Eraser {
             None
         };
 
-        let missing_files: Vec<&PathBuf> = files_to_delete
-            .iter()
-            .filter(|f| !f.exists())
-            .collect();
+        let (files, missing): (Vec<PathBuf>, Vec<PathBuf>) =
+            files_to_delete.into_iter().partition(|f| f.exists());
 
         if let Some(sp) = spinner {
             sp.finish_and_clear();
         }
 
-        if !missing_files.is_empty() {
-            log::warn!("Some files no longer exist: {:?}", missing_files);
+        if !missing.is_empty() {
+            log::warn!("Some files no longer exist: {:?}", missing);
             if !self.quiet {
                 println!(
                     "{} {} files no longer exist and will be skipped.",
                     style(WARNING_PREFIX).yellow().bold(),
-                    style(missing_files.len()).yellow()
+                    style(missing.len()).yellow()
                 );
             }
         }
 
-        let existing_files: Vec<&PathBuf> = files_to_delete
+        let total_bytes = files
             .iter()
-            .filter(|f| f.exists())
-            .collect();
+            .map(|f| hasher::file_size(f).unwrap_or(0))
+            .sum();
 
-        if existing_files.is_empty() {
+        Ok(ErasePlan {
+            selected,
+            remaining,
+            files,
+            missing,
+            total_bytes,
+            protected,
+            erased,
+            manifest: Some((duplicates_path, duplicates_file)),
+        })
+    }
+
+    /// Deletes the duplicates listed in duplicates.json and reports what happened.
+    ///
+    /// The files to delete are those of [`plan`](Self::plan). If staging any
+    /// file fails, every staged file is restored and the returned summary has
+    /// `rolled_back` set.
+    pub fn erase_duplicates(&self) -> Result<EraseSummary> {
+        let plan = self.plan()?;
+        let mut summary = EraseSummary {
+            skipped_missing: plan.missing.len(),
+            protected: plan.protected.len(),
+            ..EraseSummary::default()
+        };
+
+        let Some((duplicates_path, mut duplicates_file)) = plan.manifest else {
+            return Ok(summary);
+        };
+
+        if plan.files.is_empty() {
             if !self.quiet {
                 println!(
                     "{} No existing files to erase.",
                     style(INFO_PREFIX).blue().bold()
                 );
             }
-            return Ok(());
+            return Ok(summary);
+        }
+
+        let freed_bytes = plan.total_bytes;
+        let selected = plan.selected;
+        let remaining = plan.remaining;
+
+        if self.dry_run {
+            if !self.quiet {
+                print_dry_run(&selected);
+            }
+            summary.deleted = plan.files.len();
+            summary.reclaimed_bytes = freed_bytes;
+            summary.dry_run = true;
+            return Ok(summary);
+        }
+
+        if !self.assume_yes && !self.quiet && !self.confirm(plan.files.len(), freed_bytes)? {
+            summary.declined = true;
+            return Ok(summary);
         }
 
+        let root = self.erase_root(&duplicates_file).to_path_buf();
+        let existing_files: Vec<&PathBuf> = plan.files.iter().collect();
+        let checksums: HashMap<&Path, &str> = if self.verify_checksums {
+            selected
+                .iter()
+                .flat_map(|entry| &entry.checksums)
+                .map(|(path, checksum)| (path.as_path(), checksum.as_str()))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
         // Perform atomic deletion
-        match atomic_delete(&existing_files, &self.staging_dir(), self.quiet) {
+        match atomic_delete(
+            &existing_files,
+            &checksums,
+            duplicates_file.hash_algorithm,
+            &self.staging_dir(&root),
+            &root,
+            &self.delete_mode,
+            self.cancel.as_ref(),
+            self.quiet,
+        ) {
             Ok(deleted_count) => {
-                if !self.quiet {
-                    println!(
-                        "{} Successfully erased {} duplicate files.",
-                        style(SUCCESS_PREFIX).green().bold(),
-                        style(deleted_count).green().bold()
-                    );
-                }
+                summary.deleted = deleted_count;
+                summary.reclaimed_bytes = freed_bytes;
 
-                // Remove the duplicates.json file after successful deletion
-                fs::remove_file(&duplicates_path)?;
-                if !self.quiet {
-                    println!(
-                        "{} Removed: {}",
-                        style(SUCCESS_PREFIX).green().bold(),
-                        style(duplicates_path.display()).cyan()
-                    );
+                if remaining.is_empty() && !self.keep_manifest {
+                    // Remove the duplicates.json file after successful deletion
+                    fs::remove_file(&duplicates_path)
+                        .map_err(Error::on_file(FileAction::Remove, &duplicates_path))?;
+                    if !self.quiet {
+                        println!(
+                            "{} Removed: {}",
+                            style(SUCCESS_PREFIX).green().bold(),
+                            style(duplicates_path.display()).cyan()
+                        );
+                    }
+                } else {
+                    // Keep the groups that were not needed to meet the reclaim
+                    // target, and a record of the erased ones if asked to
+                    let remaining_groups = remaining.len();
+                    let mut entries = plan.erased;
+                    if self.keep_manifest {
+                        let erased_at = Utc::now();
+                        entries.extend(selected.into_iter().map(|e| e.mark_erased(erased_at)));
+                    }
+                    entries.extend(remaining);
+                    duplicates_file.entries = entries;
+                    duplicates_file.refresh_totals();
+                    duplicates_file.save(&duplicates_path)?;
+                    if !self.quiet {
+                        println!(
+                            "{} Updated: {} ({} groups remaining)",
+                            style(SUCCESS_PREFIX).green().bold(),
+                            style(duplicates_path.display()).cyan(),
+                            style(remaining_groups).cyan()
+                        );
+                    }
                 }
 
                 log::info!(
-                    "Erase complete: {} files deleted, duplicates.json removed",
-                    deleted_count
+                    "Erase complete: {} files deleted, {} bytes freed",
+                    deleted_count,
+                    freed_bytes
                 );
+
+                match soft_delete::prune(&root, self.retention_days) {
+                    Ok(0) => {}
+                    Ok(pruned) => {
+                        if !self.quiet {
+                            println!(
+                                "{} Pruned {} soft-deleted batches older than {} days.",
+                                style(INFO_PREFIX).blue().bold(),
+                                style(pruned).cyan(),
+                                self.retention_days
+                            );
+                        }
+                    }
+                    Err(e) => log::warn!("Could not prune soft-deleted batches: {}", e),
+                }
+            }
+            Err(Error::Canceled) => {
+                log::info!("Erase canceled, all files restored");
+                summary.canceled = true;
             }
             Err(e) => {
                 log::error!("Erase failed, all files restored: {}", e);
+                summary.rolled_back = true;
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+impl Command for Eraser {
+    type Output = EraseSummary;
+
+    fn execute(&self) -> Result<EraseSummary> {
+        let summary = match self.erase_duplicates() {
+            Err(e @ Error::UnsupportedVersion { .. }) => {
+                if !self.quiet {
+                    println!(
+                        "{} {} was written by an incompatible version. Run 'mde scan' again to regenerate it.",
+                        style(ERROR_PREFIX).red().bold(),
+                        style(self.duplicates_file_path().display()).cyan()
+                    );
+                }
                 return Err(e);
             }
+            result => result?,
+        };
+
+        if summary.rolled_back {
+            return Err(Error::EraseRolledBack);
         }
 
-        Ok(())
+        if summary.canceled {
+            if !self.quiet {
+                println!(
+                    "{} Erase canceled: all staged files were restored and nothing was deleted.",
+                    style(WARNING_PREFIX).yellow().bold()
+                );
+            }
+            return Err(Error::Canceled);
+        }
+
+        if summary.declined {
+            println!(
+                "{} Nothing was erased.",
+                style(INFO_PREFIX).blue().bold()
+            );
+            return Ok(summary);
+        }
+
+        if summary.dry_run {
+            if !self.quiet {
+                println!(
+                    "{} Dry run: would erase {} duplicate files ({} freed). No files were changed.",
+                    style(INFO_PREFIX).blue().bold(),
+                    style(summary.deleted).cyan().bold(),
+                    style(format_size(summary.reclaimed_bytes)).cyan()
+                );
+            }
+            return Ok(summary);
+        }
+
+        if summary.deleted > 0 && !self.quiet {
+            match &self.delete_mode {
+                DeleteMode::Permanent => println!(
+                    "{} Successfully erased {} duplicate files ({} freed).",
+                    style(SUCCESS_PREFIX).green().bold(),
+                    style(summary.deleted).green().bold(),
+                    style(format_size(summary.reclaimed_bytes)).green()
+                ),
+                DeleteMode::Trash => println!(
+                    "{} Moved {} duplicate files to the trash ({} freed once emptied).",
+                    style(SUCCESS_PREFIX).green().bold(),
+                    style(summary.deleted).green().bold(),
+                    style(format_size(summary.reclaimed_bytes)).green()
+                ),
+                DeleteMode::Soft => println!(
+                    "{} Soft-deleted {} duplicate files ({} freed once pruned). Run 'mde undo' to restore them.",
+                    style(SUCCESS_PREFIX).green().bold(),
+                    style(summary.deleted).green().bold(),
+                    style(format_size(summary.reclaimed_bytes)).green()
+                ),
+                DeleteMode::Move(dir) => println!(
+                    "{} Moved {} duplicate files into {} for review ({} freed once deleted there).",
+                    style(SUCCESS_PREFIX).green().bold(),
+                    style(summary.deleted).green().bold(),
+                    style(dir.display()).cyan(),
+                    style(format_size(summary.reclaimed_bytes)).green()
+                ),
+            }
+
+            if let Some(target) = self.reclaim_target
+                && summary.reclaimed_bytes < target
+            {
+                println!(
+                    "{} Reclaim target of {} not reached.",
+                    style(WARNING_PREFIX).yellow().bold(),
+                    style(format_size(target)).yellow()
+                );
+            }
+        }
+
+        Ok(summary)
     }
 }
 
+/// Prints each group with the original that is kept and the files that would be erased.
+fn print_dry_run(entries: &[DuplicateEntry]) {
+    println!();
+    for (index, entry) in entries.iter().enumerate() {
+        println!(
+            "Group {} - keeping {}:",
+            index + 1,
+            style(entry.original.display()).green()
+        );
+        for file in entry.files_to_erase().iter().filter(|f| f.exists()) {
+            println!("  would delete {}", style(file.display()).red());
+        }
+    }
+    println!();
+}
+
+/// Lets `choose` pick the file to keep in each entry.
+///
+/// The chosen file becomes the entry's original and the other files its
+/// duplicates. Returns `(selected, skipped)`, where skipped entries are
+/// returned unchanged.
+pub fn review_entries(
+    entries: Vec<DuplicateEntry>,
+    mut choose: impl FnMut(&DuplicateEntry) -> Result<ReviewChoice>,
+) -> Result<(Vec<DuplicateEntry>, Vec<DuplicateEntry>)> {
+    let mut selected = Vec::new();
+    let mut skipped = Vec::new();
+
+    for entry in entries {
+        match choose(&entry)? {
+            ReviewChoice::Keep(0) => selected.push(entry),
+            ReviewChoice::Keep(position) if position <= entry.duplicates.len() => {
+                selected.push(keep_file(entry, position));
+            }
+            ReviewChoice::Keep(position) => {
+                log::warn!("Ignoring out-of-range choice {} for {:?}", position, entry.original);
+                skipped.push(entry);
+            }
+            ReviewChoice::Skip => skipped.push(entry),
+        }
+    }
+
+    Ok((selected, skipped))
+}
+
+/// Makes the file at `position` (1-based among the duplicates) the original of `entry`.
+fn keep_file(mut entry: DuplicateEntry, position: usize) -> DuplicateEntry {
+    let kept = entry.duplicates[position - 1].clone();
+    entry.set_original(&kept);
+    entry
+}
+
+/// Asks the user which file of `entry` to keep.
+fn prompt_keep(entry: &DuplicateEntry, index: usize, total: usize) -> Result<ReviewChoice> {
+    let mut items: Vec<String> = std::iter::once(&entry.original)
+        .chain(&entry.duplicates)
+        .map(|f| f.display().to_string())
+        .collect();
+    items.push("Skip this group".to_string());
+
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!("Group {}/{} - which file should be kept?", index, total))
+        .items(&items)
+        .default(0)
+        .interact_opt()?;
+
+    Ok(match selection {
+        Some(position) if position < items.len() - 1 => ReviewChoice::Keep(position),
+        _ => ReviewChoice::Skip,
+    })
+}
+
+/// Picks entries largest-first until their combined reclaimable size meets `target`.
+///
+/// Returns `(selected, remaining)`.
+fn select_for_reclaim(
+    mut entries: Vec<DuplicateEntry>,
+    target: u64,
+) -> (Vec<DuplicateEntry>, Vec<DuplicateEntry>) {
+    entries.sort_by(|a, b| b.reclaimable_bytes.cmp(&a.reclaimable_bytes));
+
+    let mut cumulative: u64 = 0;
+    let mut count = 0;
+    for entry in &entries {
+        if cumulative >= target {
+            break;
+        }
+        cumulative += entry.reclaimable_bytes;
+        count += 1;
+    }
+
+    let remaining = entries.split_off(count);
+    (entries, remaining)
+}
+
 /// Atomically deletes files by first moving them to a staging directory,
-/// then permanently deleting them. If any operation fails, all files are restored.
-fn atomic_delete(files: &[&PathBuf], staging_dir: &Path, quiet: bool) -> Result<usize> {
+/// then permanently deleting, trashing or relocating them. If any operation
+/// fails, all files are restored.
+///
+/// Files with an entry in `checksums` are re-hashed with `algorithm` just
+/// before being staged, and fail with [`Error::ChecksumMismatch`] if they
+/// changed. Soft-deleted batches are kept under `root`, the erased directory,
+/// and moved files keep their path relative to it.
+///
+/// Canceling `cancel` while files are staged restores them and fails with
+/// [`Error::Canceled`]; once all are staged, it has no effect.
+#[allow(clippy::too_many_arguments)]
+fn atomic_delete(
+    files: &[&PathBuf],
+    checksums: &HashMap<&Path, &str>,
+    algorithm: HashAlgorithm,
+    staging_dir: &Path,
+    root: &Path,
+    mode: &DeleteMode,
+    cancel: Option<&CancelToken>,
+    quiet: bool,
+) -> Result<usize> {
     // Clean up any leftover staging directory from previous failed runs
     if staging_dir.exists() {
         log::warn!("Found leftover staging directory, cleaning up...");
-        fs::remove_dir_all(staging_dir)?;
+        fs::remove_dir_all(staging_dir)
+            .map_err(Error::on_file(FileAction::Remove, staging_dir))?;
     }
 
     // Create staging directory
-    fs::create_dir_all(staging_dir)?;
+    fs::create_dir_all(staging_dir)
+        .map_err(Error::on_file(FileAction::CreateDir, staging_dir))?;
     log::debug!("Created staging directory: {:?}", staging_dir);
 
     // Track moved files for potential rollback
@@ -215,7 +980,20 @@ fn atomic_delete(files: &[&PathBuf], staging_dir: &Path, quiet: bool) -> Result<
     for (index, file) in files.iter().enumerate() {
         let staged_path = staging_dir.join(format!("{}", index));
 
-        match fs::rename(file, &staged_path) {
+        let result = if cancel.is_some_and(CancelToken::is_canceled) {
+            Err(Error::Canceled)
+        } else {
+            match checksums.get(file.as_path()) {
+                Some(expected) => check_unchanged(file, expected, algorithm),
+                None => Ok(()),
+            }
+        }
+        .and_then(|()| {
+            relocate::move_file(file, &staged_path)
+                .map_err(Error::on_file(FileAction::Move, file))
+        });
+
+        match result {
             Ok(()) => {
                 log::debug!("Staged: {:?} -> {:?}", file, staged_path);
                 moved_files.push(((*file).clone(), staged_path));
@@ -223,6 +1001,20 @@ fn atomic_delete(files: &[&PathBuf], staging_dir: &Path, quiet: bool) -> Result<
                     pb.set_position((index + 1) as u64);
                 }
             }
+            Err(Error::Canceled) => {
+                log::info!("Canceled before staging {:?}, restoring staged files", file);
+
+                if let Some(ref pb) = progress_bar {
+                    pb.finish_and_clear();
+                }
+
+                rollback(&moved_files, quiet)?;
+                if staging_dir.exists() {
+                    let _ = fs::remove_dir_all(staging_dir);
+                }
+
+                return Err(Error::Canceled);
+            }
             Err(e) => {
                 log::error!("Failed to stage {:?}: {}", file, e);
 
@@ -231,9 +1023,15 @@ fn atomic_delete(files: &[&PathBuf], staging_dir: &Path, quiet: bool) -> Result<
                 }
 
                 if !quiet {
+                    let reason = if matches!(e, Error::ChecksumMismatch(_)) {
+                        "Changed since it was scanned"
+                    } else {
+                        "Failed to stage"
+                    };
                     println!(
-                        "{} Failed to stage: {}",
+                        "{} {}: {}",
                         style(ERROR_PREFIX).red().bold(),
+                        reason,
                         style(file.display()).red()
                     );
                 }
@@ -246,7 +1044,7 @@ fn atomic_delete(files: &[&PathBuf], staging_dir: &Path, quiet: bool) -> Result<
                     let _ = fs::remove_dir_all(staging_dir);
                 }
 
-                return Err(Error::Io(e));
+                return Err(e);
             }
         }
     }
@@ -255,6 +1053,15 @@ fn atomic_delete(files: &[&PathBuf], staging_dir: &Path, quiet: bool) -> Result<
         pb.finish_and_clear();
     }
 
+    match mode {
+        DeleteMode::Trash => return trash_staged(&moved_files, staging_dir, quiet),
+        DeleteMode::Soft => return keep_staged(&moved_files, staging_dir, root, quiet),
+        DeleteMode::Move(target) => {
+            return move_staged(&moved_files, staging_dir, target, root, quiet);
+        }
+        DeleteMode::Permanent => {}
+    }
+
     // Phase 2: All files staged successfully, now permanently delete
     // Show spinner during final deletion
     let spinner = if !quiet {
@@ -297,7 +1104,335 @@ fn atomic_delete(files: &[&PathBuf], staging_dir: &Path, quiet: bool) -> Result<
             // Try to rollback
             rollback(&moved_files, quiet)?;
 
-            Err(Error::Io(e))
+            Err(Error::FileOperation {
+                action: FileAction::Remove,
+                path: staging_dir.to_path_buf(),
+                source: e,
+            })
+        }
+    }
+}
+
+/// Fails with [`Error::ChecksumMismatch`] if `file` no longer hashes to
+/// `expected` with `algorithm`.
+fn check_unchanged(file: &Path, expected: &str, algorithm: HashAlgorithm) -> Result<()> {
+    if hasher::exact_hash(file, algorithm)? != expected {
+        log::warn!("{:?} changed since it was scanned", file);
+        return Err(Error::ChecksumMismatch(file.to_path_buf()));
+    }
+    Ok(())
+}
+
+/// Keeps the staging directory as a soft-deleted batch, with a manifest
+/// recording where every staged file came from.
+///
+/// If the batch cannot be recorded, all files are restored.
+fn keep_staged(
+    moved_files: &[(PathBuf, PathBuf)],
+    staging_dir: &Path,
+    root: &Path,
+    quiet: bool,
+) -> Result<usize> {
+    let erased_at = Utc::now();
+    let manifest = SoftDeleteManifest {
+        erased_at,
+        files: moved_files
+            .iter()
+            .map(|(original, staged)| SoftDeletedFile {
+                original: original.clone(),
+                staged: staged
+                    .strip_prefix(staging_dir)
+                    .unwrap_or(staged)
+                    .to_path_buf(),
+            })
+            .collect(),
+    };
+    let batch_dir = soft_delete::new_batch_dir(root, erased_at);
+
+    let result = manifest.save(staging_dir).and_then(|()| {
+        let soft_delete_dir = soft_delete::soft_delete_dir(root);
+        fs::create_dir_all(&soft_delete_dir)
+            .map_err(Error::on_file(FileAction::CreateDir, &soft_delete_dir))?;
+        relocate::move_dir(staging_dir, &batch_dir)
+            .map_err(Error::on_file(FileAction::Move, staging_dir))?;
+        Ok(())
+    });
+
+    match result {
+        Ok(()) => {
+            log::info!(
+                "Soft-deleted {} files into {:?}",
+                moved_files.len(),
+                batch_dir
+            );
+            Ok(moved_files.len())
+        }
+        Err(e) => {
+            log::error!("Failed to record soft-deleted batch: {}", e);
+
+            if !quiet {
+                println!(
+                    "{} Failed to keep erased files for undo, rolling back...",
+                    style(ERROR_PREFIX).red().bold()
+                );
+            }
+
+            rollback(moved_files, quiet)?;
+
+            if staging_dir.exists() {
+                let _ = fs::remove_dir_all(staging_dir);
+            }
+
+            Err(e)
+        }
+    }
+}
+
+/// Moves staged files into `target`, at their path relative to `root`.
+///
+/// A file whose destination is already taken gets a counter appended to its
+/// name. If moving any file fails, the files already moved and the rest of
+/// the staged files are put back where they came from.
+fn move_staged(
+    moved_files: &[(PathBuf, PathBuf)],
+    staging_dir: &Path,
+    target: &Path,
+    root: &Path,
+    quiet: bool,
+) -> Result<usize> {
+    let mut relocated: Vec<(PathBuf, PathBuf)> = Vec::new();
+
+    for (index, (original_path, staged_path)) in moved_files.iter().enumerate() {
+        let destination = free_destination(&target.join(relative_to(original_path, root)));
+        let result = destination
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| relocate::move_file(staged_path, &destination));
+
+        match result {
+            Ok(()) => {
+                log::debug!("Moved: {:?} -> {:?}", original_path, destination);
+                relocated.push((original_path.clone(), destination));
+            }
+            Err(e) => {
+                log::error!("Failed to move {:?} to {:?}: {}", original_path, destination, e);
+
+                if !quiet {
+                    println!(
+                        "{} Failed to move: {}, rolling back...",
+                        style(ERROR_PREFIX).red().bold(),
+                        style(original_path.display()).red()
+                    );
+                }
+
+                relocated.extend_from_slice(&moved_files[index..]);
+                rollback(&relocated, quiet)?;
+
+                if staging_dir.exists() {
+                    let _ = fs::remove_dir_all(staging_dir);
+                }
+
+                return Err(Error::FileOperation {
+                    action: FileAction::Move,
+                    path: original_path.clone(),
+                    source: e,
+                });
+            }
+        }
+    }
+
+    // Every staged file has been moved out, so only the empty directory is left
+    fs::remove_dir_all(staging_dir)
+        .map_err(Error::on_file(FileAction::Remove, staging_dir))?;
+
+    log::info!("Moved {} files into {:?}", relocated.len(), target);
+    Ok(relocated.len())
+}
+
+/// Returns `file` relative to `root`, or, for a file outside `root`, its
+/// path without the root or drive prefix.
+fn relative_to(file: &Path, root: &Path) -> PathBuf {
+    match file.strip_prefix(root) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => file
+            .components()
+            .filter(|c| matches!(c, std::path::Component::Normal(_)))
+            .collect(),
+    }
+}
+
+/// Returns `path`, or if it is taken, the first free `name-N.ext` beside it.
+fn free_destination(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().map(|e| e.to_string_lossy());
+    let mut destination = path.to_path_buf();
+    let mut suffix = 1;
+    while destination.exists() {
+        let name = match &extension {
+            Some(extension) => format!("{}-{}.{}", stem, suffix, extension),
+            None => format!("{}-{}", stem, suffix),
+        };
+        destination = path.with_file_name(name);
+        suffix += 1;
+    }
+    destination
+}
+
+/// Sends staged files to the system trash, one at a time, from their original
+/// locations so the trash remembers where they came from.
+///
+/// If trashing any file fails, the files already trashed are recovered (where
+/// the platform supports it) and the rest are restored from staging.
+fn trash_staged(
+    moved_files: &[(PathBuf, PathBuf)],
+    staging_dir: &Path,
+    quiet: bool,
+) -> Result<usize> {
+    let spinner = if !quiet {
+        let sp = ProgressBar::new_spinner();
+        sp.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner:.green} {msg}")
+                .unwrap(),
+        );
+        sp.set_message("Moving files to trash...");
+        sp.enable_steady_tick(std::time::Duration::from_millis(100));
+        Some(sp)
+    } else {
+        None
+    };
+
+    let mut trashed: Vec<PathBuf> = Vec::new();
+
+    for (index, (original_path, staged_path)) in moved_files.iter().enumerate() {
+        let result = relocate::move_file(staged_path, original_path)
+            .and_then(|()| fs::canonicalize(original_path))
+            .map_err(Error::on_file(FileAction::Move, original_path))
+            .and_then(|canonical| {
+                trash::delete(&canonical)?;
+                Ok(canonical)
+            });
+
+        match result {
+            Ok(canonical) => {
+                log::debug!("Trashed: {:?}", original_path);
+                trashed.push(canonical);
+            }
+            Err(e) => {
+                if let Some(ref sp) = spinner {
+                    sp.finish_and_clear();
+                }
+                log::error!("Failed to move {:?} to trash: {}", original_path, e);
+
+                if !quiet {
+                    println!(
+                        "{} Failed to move to trash: {}, rolling back...",
+                        style(ERROR_PREFIX).red().bold(),
+                        style(original_path.display()).red()
+                    );
+                }
+
+                restore_from_trash(&trashed, quiet);
+                rollback(&moved_files[index..], quiet)?;
+
+                if staging_dir.exists() {
+                    let _ = fs::remove_dir_all(staging_dir);
+                }
+
+                return Err(e);
+            }
+        }
+    }
+
+    if let Some(sp) = spinner {
+        sp.finish_and_clear();
+    }
+
+    // Every staged file has been moved out, so only the empty directory is left
+    fs::remove_dir_all(staging_dir)
+        .map_err(Error::on_file(FileAction::Remove, staging_dir))?;
+
+    log::info!("Moved {} files to trash", trashed.len());
+    Ok(trashed.len())
+}
+
+/// Puts files that were already trashed back where they came from.
+#[cfg(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+))]
+fn restore_from_trash(trashed: &[PathBuf], quiet: bool) {
+    if trashed.is_empty() {
+        return;
+    }
+
+    let mut items = match trash::os_limited::list() {
+        Ok(items) => items,
+        Err(e) => {
+            log::error!("Failed to list trash contents: {}", e);
+            report_unrestored(trashed, quiet);
+            return;
+        }
+    };
+
+    // Only restore the most recent trash entry for each path we trashed
+    items.sort_by(|a, b| b.time_deleted.cmp(&a.time_deleted));
+    let mut restored: Vec<PathBuf> = Vec::new();
+    items.retain(|item| {
+        let path = item.original_path();
+        if trashed.contains(&path) && !restored.contains(&path) {
+            restored.push(path);
+            true
+        } else {
+            false
+        }
+    });
+
+    match trash::os_limited::restore_all(items) {
+        Ok(()) => log::debug!("Restored {} files from trash", restored.len()),
+        Err(e) => {
+            log::error!("Failed to restore files from trash: {}", e);
+            report_unrestored(trashed, quiet);
+        }
+    }
+}
+
+/// Puts files that were already trashed back where they came from.
+///
+/// This platform offers no way to restore trash items, so the user is told
+/// which files to recover by hand.
+#[cfg(not(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+)))]
+fn restore_from_trash(trashed: &[PathBuf], quiet: bool) {
+    if !trashed.is_empty() {
+        report_unrestored(trashed, quiet);
+    }
+}
+
+/// Tells the user which trashed files could not be restored automatically.
+fn report_unrestored(trashed: &[PathBuf], quiet: bool) {
+    log::warn!("Files left in trash: {:?}", trashed);
+
+    if !quiet {
+        println!(
+            "{} {} files are still in the trash and must be restored manually:",
+            style(WARNING_PREFIX).yellow().bold(),
+            style(trashed.len()).yellow()
+        );
+        for path in trashed {
+            println!("   {}", path.display());
         }
     }
 }
@@ -316,7 +1451,7 @@ fn rollback(moved_files: &[(PathBuf, PathBuf)], quiet: bool) -> Result<()> {
 
     for (original_path, staged_path) in moved_files {
         if staged_path.exists() {
-            match fs::rename(staged_path, original_path) {
+            match relocate::move_file(staged_path, original_path) {
                 Ok(()) => {
                     log::debug!("Restored: {:?}", original_path);
                 }
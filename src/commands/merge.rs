@@ -0,0 +1,135 @@
+use std::path::PathBuf;
+
+use console::style;
+
+use super::Command;
+use crate::error::{Error, Result};
+use crate::services::duplicate::{DuplicatesFile, OriginalStrategy};
+
+// Styled output prefixes (Classic ASCII)
+const SUCCESS_PREFIX: &str = "[OK]";
+
+/// Outcome of a merge run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeSummary {
+    /// Number of duplicates files merged.
+    pub manifests: usize,
+    /// Number of groups in the merged duplicates file.
+    pub groups: usize,
+    /// Number of duplicates in the merged duplicates file.
+    pub duplicates: usize,
+    /// Groups folded into another group because they shared a file with it.
+    pub joined: usize,
+    /// Joined groups whose duplicates files kept different originals, so the
+    /// original was chosen again.
+    pub conflicts: usize,
+}
+
+pub struct Merger {
+    manifests: Vec<PathBuf>,
+    output: PathBuf,
+    quiet: bool,
+    original_strategy: OriginalStrategy,
+    preferred_dirs: Vec<PathBuf>,
+}
+
+impl Merger {
+    /// Creates a merge command combining the duplicates files `manifests`
+    /// into one written to `output`.
+    pub fn new(manifests: Vec<PathBuf>, output: PathBuf, quiet: bool) -> Self {
+        Self {
+            manifests,
+            output,
+            quiet,
+            original_strategy: OriginalStrategy::default(),
+            preferred_dirs: Vec::new(),
+        }
+    }
+
+    /// Sets how the file to keep is chosen in groups whose duplicates files
+    /// kept different originals.
+    pub fn with_original_strategy(mut self, strategy: OriginalStrategy) -> Self {
+        self.original_strategy = strategy;
+        self
+    }
+
+    /// Keeps files under `dirs`, highest priority first, over files
+    /// elsewhere when an original is chosen again, whatever the original
+    /// strategy.
+    pub fn with_preferred_dirs(mut self, dirs: Vec<PathBuf>) -> Self {
+        self.preferred_dirs = dirs;
+        self
+    }
+
+    /// Loads every duplicates file, merges them as
+    /// [`DuplicatesFile::merge`] does and saves the result to the output
+    /// file, in the format its extension selects.
+    ///
+    /// Only the duplicates files are read: the listed media files are left
+    /// alone, apart from reading the size of those whose group changed.
+    pub fn merge(&self) -> Result<MergeSummary> {
+        let mut manifests = Vec::with_capacity(self.manifests.len());
+        for path in &self.manifests {
+            if !path.exists() {
+                return Err(Error::PathNotFound(path.clone()));
+            }
+            log::debug!("Loading duplicates file: {:?}", path);
+            manifests.push(DuplicatesFile::load(path)?);
+        }
+
+        let mut manifests = manifests.into_iter();
+        let Some(mut merged) = manifests.next() else {
+            return Ok(MergeSummary::default());
+        };
+        let outcome = merged.merge(
+            manifests.collect(),
+            self.original_strategy,
+            &self.preferred_dirs,
+        )?;
+        merged.save(&self.output)?;
+
+        log::info!(
+            "Merged {} duplicates files into {:?}: {} groups, {} joined, {} conflicts",
+            self.manifests.len(),
+            self.output,
+            merged.duplicate_groups,
+            outcome.joined,
+            outcome.conflicts
+        );
+        Ok(MergeSummary {
+            manifests: self.manifests.len(),
+            groups: merged.duplicate_groups,
+            duplicates: merged.total_duplicates,
+            joined: outcome.joined,
+            conflicts: outcome.conflicts,
+        })
+    }
+}
+
+impl Command for Merger {
+    type Output = MergeSummary;
+
+    fn execute(&self) -> Result<MergeSummary> {
+        let summary = self.merge()?;
+
+        if !self.quiet {
+            println!(
+                "{} Merged {} duplicates files into {}: {} groups, {} duplicate files.",
+                style(SUCCESS_PREFIX).green().bold(),
+                style(summary.manifests).cyan(),
+                style(self.output.display()).cyan(),
+                style(summary.groups).cyan().bold(),
+                style(summary.duplicates).cyan()
+            );
+            if summary.joined > 0 {
+                println!(
+                    "   {} groups were joined into others they shared files with; {} joined groups had kept different originals, chosen again with --keep.",
+                    summary.joined,
+                    summary.conflicts
+                );
+            }
+        }
+
+        Ok(summary)
+    }
+}
@@ -1,9 +1,18 @@
 pub mod clean;
 pub mod erase;
+pub mod merge;
+pub mod regroup;
 pub mod scan;
+pub mod stats;
+pub mod undo;
+pub mod verify;
 
 use crate::error::Result;
 
 pub trait Command {
-    fn execute(&self) -> Result<()>;
+    /// What the command produces, for callers using it as a library.
+    type Output;
+
+    /// Runs the command, printing its progress and results unless quiet.
+    fn execute(&self) -> Result<Self::Output>;
 }
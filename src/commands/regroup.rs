@@ -0,0 +1,264 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use console::style;
+
+use super::Command;
+use crate::error::{Error, Result};
+use crate::services::duplicate::{
+    self, DuplicateGroup, DuplicateReport, DuplicatesFile, FileHashes, Grouping, MediaThresholds,
+    OriginalStrategy, RegroupOptions, DUPLICATES_FILENAME,
+};
+use crate::services::hasher::{self, MediaType, PerceptualConfig};
+
+// Styled output prefixes (Classic ASCII)
+const SUCCESS_PREFIX: &str = "[OK]";
+const WARNING_PREFIX: &str = "[!]";
+const INFO_PREFIX: &str = "[*]";
+
+/// Outcome of a regroup run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegroupSummary {
+    /// Number of groups before regrouping.
+    pub groups_before: usize,
+    /// Number of groups after regrouping.
+    pub groups_after: usize,
+    /// Number of duplicates after regrouping.
+    pub duplicates: usize,
+    /// How similar files were grouped.
+    pub grouping: Grouping,
+}
+
+pub struct Regrouper {
+    path: PathBuf,
+    threshold: u32,
+    quiet: bool,
+    original_strategy: OriginalStrategy,
+    preferred_dirs: Vec<PathBuf>,
+    manifest_name: String,
+    perceptual_config: Option<PerceptualConfig>,
+    grouping: Option<Grouping>,
+    media_thresholds: Option<MediaThresholds>,
+}
+
+impl Regrouper {
+    /// Creates a regroup command rebuilding the groups of the duplicates file
+    /// in `path` with a perceptual `threshold`.
+    pub fn new(path: PathBuf, threshold: u32, quiet: bool) -> Self {
+        Self {
+            path,
+            threshold,
+            quiet,
+            original_strategy: OriginalStrategy::default(),
+            preferred_dirs: Vec::new(),
+            manifest_name: DUPLICATES_FILENAME.to_string(),
+            perceptual_config: None,
+            grouping: None,
+            media_thresholds: None,
+        }
+    }
+
+    /// Sets how the file to keep is chosen within each rebuilt group.
+    pub fn with_original_strategy(mut self, strategy: OriginalStrategy) -> Self {
+        self.original_strategy = strategy;
+        self
+    }
+
+    /// Keeps files under `dirs`, highest priority first, over files
+    /// elsewhere, whatever the original strategy.
+    pub fn with_preferred_dirs(mut self, dirs: Vec<PathBuf>) -> Self {
+        self.preferred_dirs = dirs;
+        self
+    }
+
+    /// Reads and rewrites `name` instead of duplicates.json.
+    pub fn with_manifest_name(mut self, name: impl Into<String>) -> Self {
+        self.manifest_name = name.into();
+        self
+    }
+
+    /// Requires the stored perceptual hashes to have been computed with
+    /// `config`; regrouping a file hashed with other settings then fails.
+    pub fn with_perceptual_config(mut self, config: Option<PerceptualConfig>) -> Self {
+        self.perceptual_config = config;
+        self
+    }
+
+    /// Sets how similar files are joined into groups, instead of the mode
+    /// the duplicates file was scanned with.
+    pub fn with_grouping(mut self, grouping: Option<Grouping>) -> Self {
+        self.grouping = grouping;
+        self
+    }
+
+    /// Sets the thresholds of single media types, instead of those the
+    /// duplicates file was scanned with.
+    pub fn with_media_thresholds(mut self, thresholds: Option<MediaThresholds>) -> Self {
+        self.media_thresholds = thresholds;
+        self
+    }
+
+    /// Returns the path to the duplicates file.
+    fn duplicates_file_path(&self) -> PathBuf {
+        self.path.join(&self.manifest_name)
+    }
+
+    /// Rebuilds the groups of the duplicates file from its stored hashes and
+    /// saves the result in place.
+    ///
+    /// Videos of a file scanned with `--video-scenes` or `--video-audio-match`
+    /// keep the groups they were scanned into, since their scene signatures
+    /// and soundtrack hashes are not stored.
+    ///
+    /// Returns `None` if the duplicates file does not exist or holds no
+    /// stored hashes; it is then left untouched. Fails if the hashes were
+    /// computed with other settings than set by
+    /// [`with_perceptual_config`](Self::with_perceptual_config).
+    pub fn regroup(&self) -> Result<Option<RegroupSummary>> {
+        let duplicates_path = self.duplicates_file_path();
+        log::debug!("Looking for duplicates file at: {:?}", duplicates_path);
+
+        if !duplicates_path.exists() {
+            return Ok(None);
+        }
+
+        let file = DuplicatesFile::load(&duplicates_path)?;
+        if file.hashes.is_empty() {
+            return Ok(None);
+        }
+
+        if let Some(expected) = self.perceptual_config
+            && file.perceptual_config != expected
+        {
+            return Err(Error::PerceptualConfigMismatch {
+                found: file.perceptual_config,
+                expected,
+            });
+        }
+
+        let grouping = self.grouping.unwrap_or(file.grouping);
+        let media_thresholds = self.media_thresholds.unwrap_or(file.media_thresholds);
+        let live_photo_pairs: HashMap<PathBuf, PathBuf> = file
+            .entries
+            .iter()
+            .flat_map(|entry| entry.paired.clone())
+            .collect();
+        let options = RegroupOptions {
+            threshold: self.threshold,
+            media_thresholds,
+            perceptual: file.perceptual_config,
+            grouping,
+            original_strategy: self.original_strategy,
+            preferred_dirs: self.preferred_dirs.clone(),
+        };
+        // Scene signatures and soundtrack hashes are not stored, so videos
+        // matched by them keep the groups they were scanned into
+        let (video_scenes, video_audio_match) = (file.video_scenes, file.video_audio_match);
+        let is_kept = |path: &Path| {
+            (video_scenes || video_audio_match)
+                && hasher::get_media_type(path) == MediaType::Video
+        };
+        let hashes: BTreeMap<PathBuf, FileHashes> = file
+            .hashes
+            .iter()
+            .filter(|(path, _)| !is_kept(path))
+            .map(|(path, hashes)| (path.clone(), hashes.clone()))
+            .collect();
+        let (mut groups, mut distances) = duplicate::regroup(&hashes, &options, &live_photo_pairs)?;
+        for entry in file.pending_entries().filter(|entry| is_kept(&entry.original)) {
+            groups.push(DuplicateGroup {
+                files: std::iter::once(&entry.original)
+                    .chain(&entry.duplicates)
+                    .cloned()
+                    .collect(),
+                duplicate_type: entry.duplicate_type,
+            });
+            distances.extend(entry.distances.clone());
+        }
+        let checksums = file
+            .hashes
+            .iter()
+            .filter_map(|(path, hashes)| Some((path.clone(), hashes.exact.clone()?)))
+            .collect();
+        let report = DuplicateReport {
+            groups,
+            total_files: file.total_files_scanned,
+            errors: 0,
+            threshold: self.threshold,
+            media_thresholds,
+            hash_algorithm: file.hash_algorithm,
+            perceptual_config: file.perceptual_config,
+            grouping,
+            video_scenes,
+            video_audio_match,
+            file_sizes: Default::default(),
+            perceptual_skipped: false,
+            quick: None,
+            hashes: file.hashes,
+            distances,
+            checksums,
+            live_photo_pairs,
+            root: file.root,
+            inaccessible_paths: 0,
+            canceled: false,
+        };
+
+        let mut regrouped = DuplicatesFile::from_report(&report);
+        // The files were not looked at again, so the scan time still applies
+        regrouped.scanned_at = file.scanned_at;
+        regrouped.save(&duplicates_path)?;
+
+        log::info!(
+            "Regrouped {:?} with threshold {}: {} groups",
+            duplicates_path,
+            self.threshold,
+            regrouped.duplicate_groups
+        );
+        Ok(Some(RegroupSummary {
+            groups_before: file.duplicate_groups,
+            groups_after: regrouped.duplicate_groups,
+            duplicates: regrouped.total_duplicates,
+            grouping,
+        }))
+    }
+}
+
+impl Command for Regrouper {
+    /// The regroup outcome, or `None` if there was nothing to regroup.
+    type Output = Option<RegroupSummary>;
+
+    fn execute(&self) -> Result<Option<RegroupSummary>> {
+        let Some(summary) = self.regroup()? else {
+            if !self.quiet {
+                if self.duplicates_file_path().exists() {
+                    println!(
+                        "{} {} has no stored hashes.\n   Run 'mde scan --store-hashes' to be able to regroup it.",
+                        style(WARNING_PREFIX).yellow().bold(),
+                        style(self.duplicates_file_path().display()).cyan()
+                    );
+                } else {
+                    println!(
+                        "{} No duplicates file found at: {}\n   Run 'mde scan --store-hashes' first to detect duplicates.",
+                        style(INFO_PREFIX).blue().bold(),
+                        style(self.duplicates_file_path().display()).cyan()
+                    );
+                }
+            }
+            return Ok(None);
+        };
+
+        if !self.quiet {
+            println!(
+                "{} Regrouped with threshold {} ({} grouping): {} groups ({} before), {} duplicate files.",
+                style(SUCCESS_PREFIX).green().bold(),
+                style(self.threshold).cyan(),
+                summary.grouping,
+                style(summary.groups_after).cyan().bold(),
+                summary.groups_before,
+                style(summary.duplicates).cyan()
+            );
+        }
+
+        Ok(Some(summary))
+    }
+}
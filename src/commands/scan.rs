@@ -1,32 +1,308 @@
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
 
+use chrono::{DateTime, Utc};
 use console::style;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use image_hasher::ImageHash;
 use indicatif::{ProgressBar, ProgressStyle};
+use rayon::ThreadPoolBuilder;
+use serde::Serialize;
 use walkdir::WalkDir;
 
 use super::Command;
-use crate::error::{Error, Result};
-use crate::services::duplicate::{self, DuplicateType, DuplicatesFile, MediaFilter, ProgressCallback};
+use crate::error::{Error, FileAction, Result};
+use crate::services::duplicate::{
+    self, DetectionOptions, DetectionPreset, DuplicateType, DuplicatesFile, GroupOrder, MediaFilter,
+    ProgressCallback, ProgressEvent, ProgressLog, DUPLICATES_FILENAME,
+};
+use crate::services::cache::{HashCache, DEFAULT_CACHE_FILENAME};
+use crate::services::report::{save_report, NulListWriter, ReportFormat, ReportWriter};
 use crate::services::hasher;
+use crate::services::safety;
+use crate::services::units::format_size;
 
-const DEFAULT_OUTPUT_FILENAME: &str = "duplicates.json";
+/// Ignore file read from the scan root, using gitignore syntax.
+const IGNORE_FILENAME: &str = ".mdeignore";
 
 // Styled output prefixes (Classic ASCII)
 const SUCCESS_PREFIX: &str = "[OK]";
 const INFO_PREFIX: &str = "[*]";
 const WARNING_PREFIX: &str = "[!]";
 
+/// Include/exclude lists of file extensions applied while listing files.
+///
+/// Extensions are compared case-insensitively and without the leading dot.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtensionFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl ExtensionFilter {
+    /// Creates a filter. An empty `include` list accepts every extension.
+    pub fn new<I, E>(include: I, exclude: E) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+        E: IntoIterator,
+        E::Item: AsRef<str>,
+    {
+        Self {
+            include: include.into_iter().map(normalize_extension).collect(),
+            exclude: exclude.into_iter().map(normalize_extension).collect(),
+        }
+    }
+
+    /// Checks if a file passes the filter.
+    pub fn matches(&self, path: &Path) -> bool {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+
+        (self.include.is_empty() || self.include.contains(&extension))
+            && !self.exclude.contains(&extension)
+    }
+}
+
+fn normalize_extension(extension: impl AsRef<str>) -> String {
+    extension
+        .as_ref()
+        .trim()
+        .trim_start_matches('.')
+        .to_lowercase()
+}
+
+/// Settings for a [`Scanner`], with named fields instead of positional flags.
+///
+/// Start from [`ScanOptions::new`] and override only what differs:
+///
+/// ```no_run
+/// # use media_duplicate_eraser_rs::commands::scan::{ScanOptions, Scanner};
+/// let options = ScanOptions {
+///     include_hidden: true,
+///     quiet: true,
+///     ..ScanOptions::new("/path/to/photos".into())
+/// };
+/// let scanner = Scanner::with_options(options);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// Directory to scan.
+    pub path: PathBuf,
+    /// More directories to scan along with `path`, so duplicates across them
+    /// are found. Each is walked with its own `.mdeignore`, and files reached
+    /// through several of them are scanned once. `output` must be set when
+    /// this is not empty.
+    pub extra_paths: Vec<PathBuf>,
+    /// Descend into subdirectories. Defaults to `true`.
+    pub recursive: bool,
+    /// Include hidden files and directories. Defaults to `false`.
+    pub include_hidden: bool,
+    /// Where to write the report. Defaults to `duplicates.json` (or
+    /// `duplicates.csv`) in `path`.
+    pub output: Option<PathBuf>,
+    /// Suppress progress bars and the printed report.
+    pub quiet: bool,
+    /// What counts as a duplicate and which files are considered.
+    pub detection: DetectionOptions,
+    /// Maximum directory depth to visit; overrides `recursive` when set.
+    pub max_depth: Option<usize>,
+    /// Only scan files last modified at or after this time. Files left out
+    /// do not count toward the files scanned.
+    pub newer_than: Option<DateTime<Utc>>,
+    /// Only scan files last modified at or before this time.
+    pub older_than: Option<DateTime<Utc>>,
+    /// Output format of the report.
+    pub format: ReportFormat,
+    /// Allow scanning a filesystem root or the user's home directory.
+    pub allow_dangerous_path: bool,
+    /// Only scan files with these extensions, minus excluded ones. Combines
+    /// with the media filter in `detection`: a file must pass both.
+    pub extensions: ExtensionFilter,
+    /// Skip paths matched by a `.mdeignore` file (gitignore syntax) at the
+    /// scan root. Defaults to `true`.
+    pub use_ignore_file: bool,
+    /// Skip directories matching any of these gitignore-style globs, without
+    /// visiting their contents. A pattern without a slash matches a directory
+    /// name at any depth; one with a slash matches a path relative to `path`.
+    pub exclude_dirs: Vec<String>,
+    /// Reuse hashes of unchanged files from earlier scans. Defaults to `true`.
+    pub use_cache: bool,
+    /// Where the hash cache is stored. Defaults to `.mde_cache.json` next to the report.
+    pub cache_path: Option<PathBuf>,
+    /// Follow symbolic links to files and directories. Defaults to `false`.
+    pub follow_symlinks: bool,
+    /// Read the files to scan, one path per line, from this file (`-` for
+    /// stdin) instead of walking `path` and `extra_paths`. The report is still written to `path`.
+    pub file_list: Option<PathBuf>,
+    /// Print a [`ScanSummary`] as compact JSON to stdout. Implies `quiet`, so
+    /// stdout holds nothing but the JSON.
+    pub json_summary: bool,
+    /// Print the path of every duplicate, leaving out the originals, to
+    /// stdout with a NUL byte after each, for `xargs -0`. Implies `quiet`, so
+    /// stdout holds nothing but the paths. The report is still saved.
+    pub print0: bool,
+    /// Stop walking `path` after this many files, leaving the rest unscanned.
+    /// The report then notes that its results are partial.
+    pub limit: Option<usize>,
+    /// Only report files duplicating a file of this earlier report, which
+    /// must have been written with stored hashes. The baseline files are
+    /// kept as the originals and are not read again.
+    pub against: Option<PathBuf>,
+    /// Number of threads hashing files in parallel. Defaults to one per CPU.
+    /// Scans bound by disk reads, such as of spinning disks, may be faster
+    /// with fewer; `Some(1)` hashes one file at a time.
+    pub workers: Option<usize>,
+    /// Order of the groups in the printed and saved report. Defaults to the
+    /// order they were found in.
+    pub sort: Option<GroupOrder>,
+    /// Update the existing report at the output path instead of replacing
+    /// it: groups keep the original chosen in it, erased entries are kept as
+    /// a record, and groups that are gone are dropped. Only new or changed
+    /// files are hashed again when `use_cache` is set.
+    pub update: bool,
+    /// After the report, print for each file of the similar and mixed
+    /// groups its perceptual hash distance to the original and a grid of the
+    /// hash bits that differ. The hashes are only saved if
+    /// [`DetectionOptions::keep_hashes`] is set.
+    pub explain: bool,
+}
+
+impl ScanOptions {
+    /// Creates options for scanning `path` with the default settings.
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            extra_paths: Vec::new(),
+            recursive: true,
+            include_hidden: false,
+            output: None,
+            quiet: false,
+            detection: DetectionOptions::default(),
+            max_depth: None,
+            newer_than: None,
+            older_than: None,
+            format: ReportFormat::default(),
+            allow_dangerous_path: false,
+            extensions: ExtensionFilter::default(),
+            use_ignore_file: true,
+            exclude_dirs: Vec::new(),
+            use_cache: true,
+            cache_path: None,
+            follow_symlinks: false,
+            file_list: None,
+            json_summary: false,
+            print0: false,
+            limit: None,
+            against: None,
+            workers: None,
+            sort: None,
+            update: false,
+            explain: false,
+        }
+    }
+}
+
+/// Totals of a scan, printed as JSON by `scan --json-summary` for scripts.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ScanSummary {
+    /// Number of files compared.
+    pub files_scanned: usize,
+    /// Number of duplicate groups found.
+    pub duplicate_groups: usize,
+    /// Number of duplicates, excluding one original per group.
+    pub duplicates: usize,
+    /// Duplicates in exact groups.
+    pub exact_duplicates: usize,
+    /// Duplicates in perceptual groups.
+    pub perceptual_duplicates: usize,
+    /// Duplicates in groups mixing exact and perceptual matches.
+    pub mixed_duplicates: usize,
+    /// Probable duplicates found by a quick scan, not confirmed byte for byte.
+    pub probable_duplicates: usize,
+    /// Space freed by erasing every duplicate, in bytes.
+    pub reclaimable_bytes: u64,
+    /// Number of files that could not be processed.
+    pub errors: usize,
+    /// Number of those files on which FFmpeg failed to run or crashed.
+    pub ffmpeg_errors: usize,
+    /// Number of paths skipped while walking the scan roots because they
+    /// could not be read, e.g. directories without read permission.
+    pub inaccessible_paths: usize,
+    /// Whether perceptual analysis was skipped.
+    pub perceptual_skipped: bool,
+    /// Whether the scan stopped at its file limit or was canceled, so files
+    /// were left out.
+    pub partial: bool,
+    /// Where the report was saved, or `None` if there was nothing to save.
+    pub report_path: Option<PathBuf>,
+}
+
+impl ScanSummary {
+    /// Summarizes a duplicate report. `ffmpeg_errors`, `partial` and
+    /// `report_path` are left unset.
+    pub fn from_report(report: &duplicate::DuplicateReport) -> Self {
+        Self {
+            files_scanned: report.total_files,
+            duplicate_groups: report.groups.len(),
+            duplicates: report.duplicate_count(),
+            exact_duplicates: report.exact_duplicate_count(),
+            perceptual_duplicates: report.perceptual_duplicate_count(),
+            mixed_duplicates: report.mixed_duplicate_count(),
+            probable_duplicates: report.probable_duplicate_count(),
+            reclaimable_bytes: report.reclaimable_bytes(),
+            errors: report.errors,
+            ffmpeg_errors: 0,
+            inaccessible_paths: report.inaccessible_paths,
+            perceptual_skipped: report.perceptual_skipped,
+            partial: false,
+            report_path: None,
+        }
+    }
+}
+
 pub struct Scanner {
     path: PathBuf,
+    extra_paths: Vec<PathBuf>,
     recursive: bool,
     include_hidden: bool,
     output: Option<PathBuf>,
     quiet: bool,
-    media_filter: MediaFilter,
+    detection: DetectionOptions,
+    max_depth: Option<usize>,
+    newer_than: Option<DateTime<Utc>>,
+    older_than: Option<DateTime<Utc>>,
+    format: ReportFormat,
+    allow_dangerous_path: bool,
+    extensions: ExtensionFilter,
+    use_ignore_file: bool,
+    exclude_dirs: Vec<String>,
+    use_cache: bool,
+    cache_path: Option<PathBuf>,
+    follow_symlinks: bool,
+    file_list: Option<PathBuf>,
+    json_summary: bool,
+    print0: bool,
+    limit: Option<usize>,
+    against: Option<PathBuf>,
+    workers: Option<usize>,
+    sort: Option<GroupOrder>,
+    update: bool,
+    explain: bool,
 }
 
 impl Scanner {
+    #[deprecated(note = "use `Scanner::with_options` with a `ScanOptions` instead")]
     pub fn new(
         path: PathBuf,
         recursive: bool,
@@ -35,42 +311,352 @@ impl Scanner {
         quiet: bool,
         media_filter: MediaFilter,
     ) -> Self {
-        Self {
+        Self::with_options(ScanOptions {
+            recursive,
+            include_hidden,
+            output,
+            quiet,
+            detection: media_filter.into(),
+            ..ScanOptions::new(path)
+        })
+    }
+
+    /// Creates a scanner from a set of [`ScanOptions`].
+    pub fn with_options(options: ScanOptions) -> Self {
+        let ScanOptions {
             path,
+            extra_paths,
             recursive,
             include_hidden,
             output,
             quiet,
-            media_filter,
+            detection,
+            max_depth,
+            newer_than,
+            older_than,
+            format,
+            allow_dangerous_path,
+            extensions,
+            use_ignore_file,
+            exclude_dirs,
+            use_cache,
+            cache_path,
+            follow_symlinks,
+            file_list,
+            json_summary,
+            print0,
+            limit,
+            against,
+            workers,
+            sort,
+            update,
+            explain,
+        } = options;
+        Self {
+            path,
+            extra_paths,
+            recursive,
+            include_hidden,
+            output,
+            quiet: quiet || json_summary || print0,
+            detection,
+            max_depth,
+            newer_than,
+            older_than,
+            format,
+            allow_dangerous_path,
+            extensions,
+            use_ignore_file,
+            exclude_dirs,
+            use_cache,
+            cache_path,
+            follow_symlinks,
+            file_list,
+            json_summary,
+            print0,
+            limit,
+            against,
+            workers,
+            sort,
+            update,
+            explain,
         }
     }
 
+    /// Applies a detection preset on top of the scanner's current options.
+    pub fn with_preset(mut self, preset: DetectionPreset) -> Self {
+        preset.apply(&mut self.detection);
+        self
+    }
+
+    /// Limits how many directory levels below the scan path are visited.
+    ///
+    /// A depth of 1 only scans files directly inside the path. When set, this
+    /// overrides the `recursive` flag.
+    pub fn with_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets the maximum Hamming distance for perceptual matches.
+    pub fn with_threshold(mut self, threshold: u32) -> Self {
+        self.detection.threshold = threshold;
+        self
+    }
+
+    /// Sets the output format of the duplicates report.
+    pub fn with_format(mut self, format: ReportFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Keeps Apple Live Photo image/video pairs together when grouping.
+    pub fn with_live_photos(mut self, enabled: bool) -> Self {
+        self.detection.live_photos = enabled;
+        self
+    }
+
+    /// Confirms perceptual matches with a larger hash before reporting them.
+    pub fn with_strict_perceptual(mut self, enabled: bool) -> Self {
+        self.detection.strict_perceptual = enabled;
+        self
+    }
+
+    /// Allows scanning a filesystem root or the user's home directory.
+    pub fn allow_dangerous_path(mut self, allow: bool) -> Self {
+        self.allow_dangerous_path = allow;
+        self
+    }
+
+    /// Returns the root recorded in the report, the scanned directory.
+    ///
+    /// Files read from a list can live anywhere and several roots have no
+    /// single one, so neither records a root.
+    fn recorded_root(&self) -> Option<PathBuf> {
+        (self.file_list.is_none() && self.extra_paths.is_empty()).then(|| self.path.clone())
+    }
+
+    /// Returns the directories to scan: `path` followed by the extra paths.
+    fn roots(&self) -> impl Iterator<Item = &PathBuf> {
+        std::iter::once(&self.path).chain(&self.extra_paths)
+    }
+
+    /// Walks every scan root and returns the files to scan, along with
+    /// whether the walk stopped early at the file limit and the number of
+    /// paths skipped because they could not be read.
+    fn collect_files(&self) -> Result<(Vec<PathBuf>, bool, usize)> {
+        let mut files = Vec::new();
+        let mut inaccessible = 0;
+        // Overlapping roots reach the same files; only needed with several roots
+        let mut seen = (!self.extra_paths.is_empty()).then(HashSet::new);
+
+        for root in self.roots() {
+            let ignore = load_ignore_matcher(root, self.use_ignore_file, &self.exclude_dirs)?;
+            let limit_reached = self.list_files(
+                root,
+                ignore.as_ref(),
+                &mut files,
+                seen.as_mut(),
+                &mut inaccessible,
+            )?;
+            if limit_reached {
+                return Ok((files, true, inaccessible));
+            }
+        }
+
+        Ok((files, false, inaccessible))
+    }
+
+    /// Walks `path`, appending the files to scan to `files`. Files already in
+    /// `seen`, compared as absolute paths, are skipped.
+    ///
+    /// Paths below `path` that cannot be read, such as directories without
+    /// read permission, are skipped with a warning and counted in
+    /// `inaccessible`; only an unreadable `path` fails the walk.
+    ///
+    /// Returns whether the walk stopped early because `files` reached the
+    /// file limit.
+    fn list_files(
+        &self,
+        path: &Path,
+        ignore: Option<&Gitignore>,
+        files: &mut Vec<PathBuf>,
+        mut seen: Option<&mut HashSet<PathBuf>>,
+        inaccessible: &mut usize,
+    ) -> Result<bool> {
+        if !path.exists() {
+            return Err(Error::PathNotFound(path.to_path_buf()));
+        }
+
+        // An explicit depth takes precedence over the recursive flag
+        let walker = match self.max_depth {
+            Some(depth) => WalkDir::new(path).max_depth(depth),
+            None if self.recursive => WalkDir::new(path),
+            None => WalkDir::new(path).max_depth(1),
+        };
+
+        // Reports of earlier scans, or the files this scan writes, are not media
+        let output = self.output_path();
+        let written = self.written_files();
+
+        let walker = walker
+            .follow_links(self.follow_symlinks)
+            .into_iter()
+            .filter_entry(|e| {
+                e.depth() == 0
+                    || ((self.include_hidden || !is_hidden(e)) && !is_ignored(e, ignore))
+            });
+
+        for entry in walker {
+            // Detection sees the cancellation too and flags the report
+            if self.detection.is_canceled() {
+                break;
+            }
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) if self.follow_symlinks && is_bad_link(&e) => {
+                    log::warn!("Skipping symbolic link: {}", e);
+                    continue;
+                }
+                Err(e) if e.depth() > 0 => {
+                    log::warn!("Skipping inaccessible path: {}", e);
+                    *inaccessible += 1;
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            if entry.file_type().is_file() && self.extensions.matches(entry.path()) {
+                if !self.modified_in_range(&entry) {
+                    continue;
+                }
+                if is_scan_output(entry.path(), &output, &written) {
+                    log::warn!("Skipping duplicates file {:?}", entry.path());
+                    continue;
+                }
+                if let Some(seen) = seen.as_deref_mut()
+                    && !seen.insert(std::path::absolute(entry.path())?)
+                {
+                    continue;
+                }
+                // Only stop once a file past the limit shows up, so an exact
+                // fit is not reported as partial
+                if self.limit.is_some_and(|limit| files.len() >= limit) {
+                    return Ok(true);
+                }
+                files.push(entry.into_path());
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Checks if the file was last modified between `newer_than` and
+    /// `older_than`. Files whose modification time cannot be read are left
+    /// out when either is set.
+    fn modified_in_range(&self, entry: &walkdir::DirEntry) -> bool {
+        if self.newer_than.is_none() && self.older_than.is_none() {
+            return true;
+        }
+        let Some(modified) = entry.metadata().ok().and_then(|m| m.modified().ok()) else {
+            log::warn!("Skipping file without a modification time: {:?}", entry.path());
+            return false;
+        };
+        let modified = DateTime::<Utc>::from(modified);
+        self.newer_than.is_none_or(|time| modified >= time)
+            && self.older_than.is_none_or(|time| modified <= time)
+    }
+
     /// Returns the output path for the duplicates file.
-    /// If not specified, defaults to duplicates.json in the scanned directory.
+    /// If not specified, defaults to duplicates.json (or the default name of
+    /// another format, such as duplicates.csv) in the scanned directory.
     fn output_path(&self) -> PathBuf {
         self.output
             .clone()
-            .unwrap_or_else(|| self.path.join(DEFAULT_OUTPUT_FILENAME))
+            .unwrap_or_else(|| self.path.join(self.format.default_filename()))
+    }
+
+    /// Returns the number of hashing threads: `workers`, or one per CPU.
+    fn worker_count(&self) -> usize {
+        self.workers
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, NonZeroUsize::get))
+    }
+
+    /// Returns the path to the hash cache.
+    /// If not specified, the cache sits next to the duplicates file.
+    fn cache_path(&self) -> PathBuf {
+        self.cache_path.clone().unwrap_or_else(|| {
+            let output_path = self.output_path();
+            output_path
+                .parent()
+                .unwrap_or(Path::new("."))
+                .join(DEFAULT_CACHE_FILENAME)
+        })
+    }
+
+    /// Returns the absolute paths of the report and hash cache this scan
+    /// writes, which are never scanned.
+    fn written_files(&self) -> Vec<PathBuf> {
+        [self.output_path(), self.cache_path()]
+            .iter()
+            .map(|path| std::path::absolute(path).unwrap_or_else(|_| path.clone()))
+            .collect()
     }
 }
 
 impl Command for Scanner {
-    fn execute(&self) -> Result<()> {
+    /// The duplicate report, whether or not it was saved.
+    type Output = duplicate::DuplicateReport;
+
+    fn execute(&self) -> Result<duplicate::DuplicateReport> {
+        if self.file_list.is_none()
+            && !self.allow_dangerous_path
+            && let Some(root) = self.roots().find(|root| safety::is_dangerous_root(root))
+        {
+            return Err(Error::DangerousPath(root.clone()));
+        }
+        if !self.extra_paths.is_empty() && self.output.is_none() {
+            return Err(Error::OutputRequired);
+        }
+        if self.update && self.format.manifest_format().is_none() {
+            return Err(Error::UpdateRequiresManifest);
+        }
+        if let (Some(newer_than), Some(older_than)) = (self.newer_than, self.older_than)
+            && newer_than >= older_than
+        {
+            return Err(Error::InvalidTimeRange {
+                newer_than,
+                older_than,
+            });
+        }
+        let baseline = self.against.as_deref().map(load_baseline).transpose()?;
+        let previous = if self.update {
+            load_previous(&self.output_path())?
+        } else {
+            None
+        };
+
         log::info!("Starting scan of directory: {:?}", self.path);
+        if !self.extra_paths.is_empty() {
+            log::info!("Also scanning: {:?}", self.extra_paths);
+        }
         log::debug!(
-            "Path: {:?}, recursive: {}, include_hidden: {}, output: {:?}, media_filter: {:?}",
+            "Path: {:?}, recursive: {}, max_depth: {:?}, include_hidden: {}, output: {:?}, detection: {:?}",
             self.path,
             self.recursive,
+            self.max_depth,
             self.include_hidden,
             self.output,
-            self.media_filter
+            self.detection
         );
 
         // Check if FFmpeg is available for video/audio processing
         let has_ffmpeg = hasher::is_ffmpeg_available();
-        let needs_ffmpeg = self.media_filter == MediaFilter::All
-            || self.media_filter == MediaFilter::VideosOnly
-            || self.media_filter == MediaFilter::AudioOnly;
+        let media_filter = self.detection.media_filter;
+        let needs_ffmpeg = self.detection.uses_perceptual()
+            && (media_filter == MediaFilter::All
+                || media_filter == MediaFilter::VideosOnly
+                || media_filter == MediaFilter::AudioOnly);
         if !has_ffmpeg && needs_ffmpeg {
             if !self.quiet {
                 println!(
@@ -99,7 +685,16 @@ impl Command for Scanner {
             None
         };
 
-        let files = list_files(&self.path, self.recursive, self.include_hidden)?;
+        let (files, limit_reached, inaccessible_paths) = match &self.file_list {
+            Some(source) => {
+                let files = read_file_list(source, &self.output_path(), &self.written_files())?;
+                (files, false, 0)
+            }
+            None => self.collect_files()?,
+        };
+        if limit_reached {
+            log::info!("Stopped collecting files at the limit of {:?}", self.limit);
+        }
 
         if let Some(sp) = spinner {
             sp.finish_with_message(format!(
@@ -118,7 +713,29 @@ impl Command for Scanner {
                     style(INFO_PREFIX).blue().bold()
                 );
             }
-            return Ok(());
+            if self.json_summary {
+                print_json_summary(&ScanSummary {
+                    partial: limit_reached || self.detection.is_canceled(),
+                    inaccessible_paths,
+                    ..ScanSummary::default()
+                })?;
+            }
+            return Ok(duplicate::DuplicateReport {
+                threshold: self.detection.threshold,
+                media_thresholds: self.detection.media_thresholds,
+                hash_algorithm: self.detection.hash_algorithm,
+                perceptual_config: self.detection.perceptual,
+                grouping: self.detection.grouping,
+                video_scenes: self.detection.video_scenes && self.detection.uses_perceptual(),
+                video_audio_match: self.detection.video_audio_match
+                    && self.detection.uses_perceptual(),
+                perceptual_skipped: !self.detection.uses_perceptual(),
+                quick: self.detection.quick,
+                root: self.recorded_root(),
+                inaccessible_paths,
+                canceled: self.detection.is_canceled(),
+                ..duplicate::DuplicateReport::default()
+            });
         }
 
         // Progress bar for duplicate detection
@@ -126,7 +743,7 @@ impl Command for Scanner {
             let pb = ProgressBar::new(files.len() as u64);
             pb.set_style(
                 ProgressStyle::default_bar()
-                    .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} {msg}")
+                    .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} ETA {eta} {msg}")
                     .unwrap()
                     .progress_chars("=>-"),
             );
@@ -136,35 +753,150 @@ impl Command for Scanner {
             None
         };
 
-        // Create progress callback
-        let progress_callback: Option<ProgressCallback> = if let Some(ref pb) = progress_bar {
-            let pb_clone = pb.clone();
-            let last_msg = Arc::new(Mutex::new(String::new()));
-            Some(Box::new(move |current, _total, phase| {
-                pb_clone.set_position(current as u64);
-                let mut last = last_msg.lock().unwrap();
-                if *last != phase {
-                    pb_clone.set_message(phase.to_string());
-                    *last = phase.to_string();
+        // Create progress callback; checkpoints are logged even without a bar
+        let progress_log = ProgressLog::default();
+        let pb_clone = progress_bar.clone();
+        // Rates are measured from the start of the current phase
+        let phase_start = Mutex::new(("", Instant::now()));
+        let progress_callback: Option<ProgressCallback> =
+            Some(Box::new(move |event: ProgressEvent| {
+                let Some(pb_clone) = &pb_clone else {
+                    progress_log.record(event);
+                    return;
+                };
+                // Log lines on stderr would otherwise break up the bar
+                pb_clone.suspend(|| progress_log.record(event));
+                let mut phase = phase_start.lock().unwrap();
+                if phase.0 != event.phase() {
+                    *phase = (event.phase(), Instant::now());
+                    pb_clone.reset_eta();
+                    pb_clone.set_message(event.phase());
                 }
-            }))
+                let elapsed = phase.1.elapsed().as_secs_f64();
+                match event {
+                    ProgressEvent::Hashing { done, total, bytes } => {
+                        pb_clone.set_length(total as u64);
+                        pb_clone.set_position(done as u64);
+                        if elapsed > 0.0 {
+                            let rate = format_size((bytes as f64 / elapsed) as u64);
+                            pb_clone.set_message(format!("{} ({}/s)", event.phase(), rate));
+                        }
+                    }
+                    ProgressEvent::Analyzing { done, total } => {
+                        pb_clone.set_length(total as u64);
+                        pb_clone.set_position(done as u64);
+                        if elapsed > 0.0 {
+                            let rate = format!("{:.1} files/s", done as f64 / elapsed);
+                            pb_clone.set_message(format!("{} ({})", event.phase(), rate));
+                        }
+                    }
+                    ProgressEvent::CollectingFiles | ProgressEvent::Merging => {}
+                }
+            }));
+
+        let mut cache = if self.use_cache {
+            HashCache::load(&self.cache_path())
         } else {
-            None
+            HashCache::disabled()
         };
 
-        let report = duplicate::find_duplicates_with_options(&files, progress_callback, self.media_filter)?;
+        // Hashing runs on a pool of its own so --workers bounds it
+        let workers = self.worker_count();
+        log::debug!("Hashing with {} workers", workers);
+        let pool = ThreadPoolBuilder::new().num_threads(workers).build()?;
+        // Explaining the matches needs the perceptual hashes of the files
+        let detection = DetectionOptions {
+            keep_hashes: self.detection.keep_hashes || self.explain,
+            ..self.detection.clone()
+        };
+        let (mut report, failures) = pool.install(|| match &baseline {
+            Some(baseline) => {
+                // Hashes are only comparable when computed the same way
+                let detection = DetectionOptions {
+                    hash_algorithm: baseline.hash_algorithm,
+                    perceptual: baseline.perceptual_config,
+                    ..detection.clone()
+                };
+                duplicate::find_duplicates_against(
+                    &files,
+                    &baseline.hashes,
+                    progress_callback,
+                    &detection,
+                    &mut cache,
+                )
+            }
+            None => duplicate::find_duplicates_detailed(
+                &files,
+                progress_callback,
+                &detection,
+                &mut cache,
+            ),
+        })?;
+        report.root = self.recorded_root();
+        report.inaccessible_paths = inaccessible_paths;
+        if let Some(order) = self.sort {
+            report.sort_groups(order);
+        }
 
         if let Some(pb) = progress_bar {
             pb.finish_and_clear();
         }
 
-        print_report(&report, self.quiet);
+        log::info!(
+            "Hash cache: {} hits, {} misses",
+            cache.hits(),
+            cache.misses()
+        );
+        // A stale cache only costs time on the next scan, so don't fail over it
+        if let Err(e) = cache.save() {
+            log::warn!("Could not save hash cache: {}", e);
+        }
 
-        // Save duplicates file if there are duplicates
-        if !report.groups.is_empty() {
+        print_report(&report, &failures, self.limit.filter(|_| limit_reached), self.quiet);
+        if self.explain {
+            if !self.quiet {
+                print_explanations(&report);
+            }
+            if !self.detection.keep_hashes {
+                report.hashes.clear();
+            }
+        }
+
+        let mut summary = ScanSummary::from_report(&report);
+        summary.ffmpeg_errors = count_ffmpeg_errors(&failures);
+        summary.partial = limit_reached || report.canceled;
+
+        if report.canceled && previous.is_some() {
+            // Groups of unread files would be dropped as resolved
+            if !self.quiet {
+                println!(
+                    "{} Not updating {}: the scan was canceled.",
+                    style(WARNING_PREFIX).yellow().bold(),
+                    style(self.output_path().display()).cyan()
+                );
+            }
+        } else if let Some(previous) = previous {
             let output_path = self.output_path();
-            let duplicates_file = DuplicatesFile::from_report(&report);
-            duplicates_file.save(&output_path)?;
+            let mut updated = DuplicatesFile::from_report(&report);
+            let update = updated.carry_over(previous);
+            let format = self.format.manifest_format().unwrap_or_default();
+            updated.save_as(&output_path, format)?;
+            if !self.quiet {
+                println!(
+                    "{} Updated: {} ({} groups kept, {} new, {} resolved)",
+                    style(SUCCESS_PREFIX).green().bold(),
+                    style(output_path.display()).cyan(),
+                    style(update.kept).cyan(),
+                    style(update.added).cyan(),
+                    style(update.resolved).cyan()
+                );
+            }
+            summary.report_path = Some(output_path);
+        } else if !report.groups.is_empty() || !report.hashes.is_empty() {
+            // Save duplicates file if there are duplicates, or hashes to
+            // compare against or regroup later
+            let output_path = self.output_path();
+            save_report(self.format.writer().as_ref(), &report, &output_path)?;
             if !self.quiet {
                 println!(
                     "{} Duplicates saved to: {}",
@@ -172,13 +904,65 @@ impl Command for Scanner {
                     style(output_path.display()).cyan()
                 );
             }
+            summary.report_path = Some(output_path);
         }
 
-        Ok(())
+        if self.json_summary {
+            print_json_summary(&summary)?;
+        }
+        if self.print0 {
+            let mut out = std::io::stdout().lock();
+            NulListWriter.write(&report, &mut out)?;
+            out.flush()?;
+        }
+
+        Ok(report)
+    }
+}
+
+/// Loads the baseline report given to `--against`, which must hold stored hashes.
+fn load_baseline(path: &Path) -> Result<DuplicatesFile> {
+    let baseline = DuplicatesFile::load(path)?;
+    if baseline.hashes.is_empty() {
+        return Err(Error::MissingHashes(path.to_path_buf()));
+    }
+    log::info!("Comparing against {} files of {:?}", baseline.hashes.len(), path);
+    Ok(baseline)
+}
+
+/// Loads the report `scan --update` updates, or returns `None` if there is
+/// none yet, in which case the scan writes a new one.
+fn load_previous(path: &Path) -> Result<Option<DuplicatesFile>> {
+    if !path.exists() {
+        log::info!("No report to update at {:?}, writing a new one", path);
+        return Ok(None);
     }
+    let previous = DuplicatesFile::load(path)?;
+    log::info!("Updating {} groups of {:?}", previous.entries.len(), path);
+    Ok(Some(previous))
+}
+
+/// Counts the failures caused by FFmpeg itself rather than by the files.
+fn count_ffmpeg_errors(failures: &[(PathBuf, Error)]) -> usize {
+    failures
+        .iter()
+        .filter(|(_, error)| matches!(error, Error::Ffmpeg(_)))
+        .count()
+}
+
+/// Prints the summary as a single line of JSON.
+fn print_json_summary(summary: &ScanSummary) -> Result<()> {
+    println!("{}", serde_json::to_string(summary)?);
+    Ok(())
 }
 
-fn print_report(report: &duplicate::DuplicateReport, quiet: bool) {
+/// Prints the report. `limit` is the file limit if the scan stopped at it.
+fn print_report(
+    report: &duplicate::DuplicateReport,
+    failures: &[(PathBuf, Error)],
+    limit: Option<usize>,
+    quiet: bool,
+) {
     if quiet {
         return;
     }
@@ -193,8 +977,66 @@ fn print_report(report: &duplicate::DuplicateReport, quiet: bool) {
         style(report.total_files).cyan()
     );
     println!("Errors encountered: {}", report.errors);
+    if report.inaccessible_paths > 0 {
+        println!(
+            "{} Skipped {} paths that could not be read; check their permissions.",
+            style(WARNING_PREFIX).yellow().bold(),
+            report.inaccessible_paths
+        );
+    }
+    let ffmpeg_errors = count_ffmpeg_errors(failures);
+    if ffmpeg_errors > 0 {
+        println!(
+            "{} FFmpeg errored on {} files; check your FFmpeg installation.",
+            style(WARNING_PREFIX).yellow().bold(),
+            ffmpeg_errors
+        );
+    }
+    if report.media_thresholds.is_empty() {
+        println!("Similarity threshold: {}", report.threshold);
+    } else {
+        println!(
+            "Similarity threshold: {} ({})",
+            report.threshold, report.media_thresholds
+        );
+    }
+    println!("Hash algorithm: {}", report.hash_algorithm);
+    if let Some(len) = report.quick {
+        println!(
+            "{} Quick scan: files were matched by size and their first and last {} only.",
+            style(WARNING_PREFIX).yellow().bold(),
+            format_size(len)
+        );
+        println!("   Results are approximate and unsafe to erase; run a full scan to confirm them.");
+    } else if report.perceptual_skipped {
+        println!("Perceptual analysis: skipped (exact duplicates only)");
+    } else {
+        println!("Perceptual hash: {}", report.perceptual_config);
+        println!("Grouping: {}", report.grouping);
+    }
+    if let Some(limit) = limit {
+        println!(
+            "{} Stopped after the first {} files (--limit): results are partial.",
+            style(WARNING_PREFIX).yellow().bold(),
+            limit
+        );
+    }
+    if report.canceled {
+        println!(
+            "{} Scan canceled: results are partial.",
+            style(WARNING_PREFIX).yellow().bold()
+        );
+    }
     println!();
 
+    if !failures.is_empty() {
+        println!("{}", style("Skipped:").yellow().bold());
+        for (path, error) in failures {
+            println!("  {} ({})", path.display(), error);
+        }
+        println!();
+    }
+
     if report.groups.is_empty() {
         println!(
             "{} No duplicates found.",
@@ -205,6 +1047,7 @@ fn print_report(report: &duplicate::DuplicateReport, quiet: bool) {
 
     let exact_count = report.exact_duplicate_count();
     let perceptual_count = report.perceptual_duplicate_count();
+    let mixed_count = report.mixed_duplicate_count();
     let exact_groups = report
         .groups
         .iter()
@@ -215,18 +1058,40 @@ fn print_report(report: &duplicate::DuplicateReport, quiet: bool) {
         .iter()
         .filter(|g| g.duplicate_type == DuplicateType::Perceptual)
         .count();
+    let mixed_groups = report
+        .groups
+        .iter()
+        .filter(|g| g.duplicate_type == DuplicateType::Mixed)
+        .count();
 
+    if report.quick.is_some() {
+        println!(
+            "Found {} groups of probable duplicates",
+            style(report.groups.len()).red().bold()
+        );
+        println!(
+            "Total probable duplicate files: {}",
+            style(report.probable_duplicate_count()).red().bold()
+        );
+    } else {
+        println!(
+            "Found {} duplicate groups ({} exact, {} perceptual, {} mixed)",
+            style(report.groups.len()).cyan().bold(),
+            style(exact_groups).cyan(),
+            style(perceptual_groups).yellow(),
+            style(mixed_groups).magenta()
+        );
+        println!(
+            "Total duplicate files: {} ({} exact, {} perceptual, {} mixed)",
+            style(report.duplicate_count()).cyan().bold(),
+            style(exact_count).cyan(),
+            style(perceptual_count).yellow(),
+            style(mixed_count).magenta()
+        );
+    }
     println!(
-        "Found {} duplicate groups ({} exact, {} perceptual)",
-        style(report.groups.len()).cyan().bold(),
-        style(exact_groups).cyan(),
-        style(perceptual_groups).yellow()
-    );
-    println!(
-        "Total duplicate files: {} ({} exact, {} perceptual)",
-        style(report.duplicate_count()).cyan().bold(),
-        style(exact_count).cyan(),
-        style(perceptual_count).yellow()
+        "Reclaimable space: {}",
+        style(format_size(report.reclaimable_bytes())).green().bold()
     );
     println!();
 
@@ -234,6 +1099,8 @@ fn print_report(report: &duplicate::DuplicateReport, quiet: bool) {
         let type_label = match group.duplicate_type {
             DuplicateType::Exact => style("[EXACT]").cyan().bold(),
             DuplicateType::Perceptual => style("[SIMILAR]").yellow().bold(),
+            DuplicateType::Mixed => style("[MIXED]").magenta().bold(),
+            DuplicateType::Probable => style("[PROBABLE]").red().bold(),
         };
 
         println!(
@@ -242,43 +1109,212 @@ fn print_report(report: &duplicate::DuplicateReport, quiet: bool) {
             type_label,
             style(group.files.len()).bold()
         );
-        for file in &group.files {
-            println!("  {}", file.display());
+        println!("  {}", group.files[0].display());
+        for file in &group.files[1..] {
+            match report.distances.get(file) {
+                Some(distance) => println!(
+                    "  {} {}",
+                    file.display(),
+                    style(format!("(distance {})", distance)).dim()
+                ),
+                None => println!("  {}", file.display()),
+            }
         }
         println!();
     }
 }
 
-// Utils
+/// Prints, for each file of the similar and mixed groups, the distance of
+/// its perceptual hash to the original's and a grid of the differing bits,
+/// in rows of the hash width.
+fn print_explanations(report: &duplicate::DuplicateReport) {
+    let explained: Vec<_> = report
+        .groups
+        .iter()
+        .enumerate()
+        .filter(|(_, group)| {
+            matches!(
+                group.duplicate_type,
+                DuplicateType::Perceptual | DuplicateType::Mixed
+            )
+        })
+        .collect();
+    if explained.is_empty() {
+        return;
+    }
 
-fn list_files(path: &PathBuf, recursive: bool, include_hidden: bool) -> Result<Vec<PathBuf>> {
-    if !path.exists() {
-        return Err(Error::PathNotFound(path.clone()));
+    println!("{}", style("=== Why Files Were Matched ===").bold().cyan());
+    println!(
+        "Each cell is a hash bit: {} same, {} different",
+        style("·").dim(),
+        style("█").red()
+    );
+    println!();
+
+    let width = report.perceptual_config.hash_size.max(1) as usize;
+    for (i, group) in explained {
+        let original = &group.files[0];
+        let original_hash = perceptual_hash_of(report, &group.files, original);
+        println!("Group {}: {}", style(i + 1).bold(), original.display());
+
+        for file in &group.files[1..] {
+            println!("  {}", file.display());
+            let checksum = report.checksums.get(file);
+            if checksum.is_some() && checksum == report.checksums.get(original) {
+                println!("    {}", style("identical content").dim());
+                continue;
+            }
+            let hash = perceptual_hash_of(report, &group.files, file);
+            let (Some(original_hash), Some(hash)) = (&original_hash, hash) else {
+                println!(
+                    "    {}",
+                    style("no single perceptual hash to compare (matched by scenes or sound)")
+                        .dim()
+                );
+                continue;
+            };
+
+            let bits = hasher::differing_bits(original_hash, &hash);
+            println!(
+                "    distance {} of {} bits",
+                style(hasher::hamming_distance(original_hash, &hash)).yellow(),
+                bits.len()
+            );
+            for row in bits.chunks(width) {
+                let cells: String = row
+                    .iter()
+                    .map(|&differs| {
+                        if differs {
+                            style("█").red().to_string()
+                        } else {
+                            style("·").dim().to_string()
+                        }
+                    })
+                    .collect();
+                println!("    {}", cells);
+            }
+        }
+        println!();
     }
+}
 
-    let mut files = Vec::new();
+/// Decodes the perceptual hash of `file`, or of an exact copy of it among
+/// `files`, since only one file of each exact group is hashed perceptually.
+fn perceptual_hash_of(
+    report: &duplicate::DuplicateReport,
+    files: &[PathBuf],
+    file: &Path,
+) -> Option<ImageHash> {
+    let checksum = report.checksums.get(file);
+    let copies = files
+        .iter()
+        .filter(|other| checksum.is_some() && report.checksums.get(*other) == checksum)
+        .map(PathBuf::as_path);
+    std::iter::once(file)
+        .chain(copies)
+        .find_map(|f| report.hashes.get(f)?.perceptual.as_deref())
+        .and_then(|encoded| ImageHash::from_base64(encoded).ok())
+}
 
-    let walker = if recursive {
-        WalkDir::new(path)
+// Utils
+
+/// Reads newline-separated paths from `source`, or from stdin if it is `-`.
+///
+/// Empty lines are ignored, and so are reports and caches, as checked by
+/// [`is_scan_output`] against `output` and `written`. Other paths are not
+/// checked here: files that cannot be read are reported as skipped by
+/// duplicate detection.
+fn read_file_list(source: &Path, output: &Path, written: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let reader: Box<dyn BufRead> = if source == Path::new("-") {
+        Box::new(std::io::stdin().lock())
     } else {
-        WalkDir::new(path).max_depth(1)
+        let file = File::open(source).map_err(Error::on_file(FileAction::Read, source))?;
+        Box::new(BufReader::new(file))
     };
 
-    let walker = walker
-        .into_iter()
-        .filter_entry(|e| e.depth() == 0 || include_hidden || !is_hidden(e));
-
-    for entry in walker {
-        let entry = entry?;
-
-        if entry.file_type().is_file() {
-            files.push(entry.into_path());
+    let mut files = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() {
+            continue;
+        }
+        let path = PathBuf::from(line);
+        if is_scan_output(&path, output, written) {
+            log::warn!("Skipping duplicates file {:?}", path);
+            continue;
         }
+        files.push(path);
     }
 
     Ok(files)
 }
 
+/// Checks if a traversal error comes from a broken link or a link pointing
+/// back to one of its parent directories.
+fn is_bad_link(error: &walkdir::Error) -> bool {
+    error.loop_ancestor().is_some()
+        || error
+            .io_error()
+            .is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound)
+}
+
+/// Builds a matcher from the `.mdeignore` file at the scan root, if there is
+/// one and `use_ignore_file` is set, and from the excluded directory globs.
+///
+/// Invalid lines in `.mdeignore` are skipped with a warning so one typo does
+/// not disable the whole file; an invalid excluded directory glob is an error.
+fn load_ignore_matcher(
+    root: &Path,
+    use_ignore_file: bool,
+    exclude_dirs: &[String],
+) -> Result<Option<Gitignore>> {
+    let ignore_file = root.join(IGNORE_FILENAME);
+    let use_ignore_file = use_ignore_file && ignore_file.is_file();
+    if !use_ignore_file && exclude_dirs.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GitignoreBuilder::new(root);
+    if use_ignore_file && let Some(e) = builder.add(&ignore_file) {
+        log::warn!("Problem reading {:?}: {}", ignore_file, e);
+    }
+    for pattern in exclude_dirs {
+        // A trailing slash makes a gitignore pattern match directories only
+        builder.add_line(None, &format!("{}/", pattern.trim_end_matches('/')))?;
+    }
+
+    let matcher = builder.build()?;
+    log::info!(
+        "Loaded {} ignore patterns for {:?}",
+        matcher.num_ignores(),
+        root
+    );
+    Ok(Some(matcher))
+}
+
+fn is_ignored(entry: &walkdir::DirEntry, ignore: Option<&Gitignore>) -> bool {
+    ignore.is_some_and(|matcher| {
+        matcher
+            .matched(entry.path(), entry.file_type().is_dir())
+            .is_ignore()
+    })
+}
+
+/// Checks if `path` is one of the `written` files of the scan, given as
+/// absolute paths, or is named like the report written to `output` or like
+/// duplicates.json, as reports of earlier scans are.
+fn is_scan_output(path: &Path, output: &Path, written: &[PathBuf]) -> bool {
+    let name = path.file_name();
+    if name == output.file_name() || name == Some(OsStr::new(DUPLICATES_FILENAME)) {
+        return true;
+    }
+    // Only files named like a written one need their absolute path compared
+    written.iter().any(|file| {
+        file.file_name() == name && std::path::absolute(path).is_ok_and(|path| path == *file)
+    })
+}
+
 fn is_hidden(entry: &walkdir::DirEntry) -> bool {
     entry
         .file_name()
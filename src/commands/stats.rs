@@ -0,0 +1,309 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use console::style;
+use serde::Serialize;
+
+use super::Command;
+use crate::error::Result;
+use crate::services::duplicate::{DuplicateType, DuplicatesFile, DUPLICATES_FILENAME};
+use crate::services::hasher::{self, MediaType};
+use crate::services::units::format_size;
+
+// Styled output prefixes (Classic ASCII)
+const INFO_PREFIX: &str = "[*]";
+
+/// Number and combined size of a set of duplicate files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Breakdown {
+    /// Number of duplicate files.
+    pub files: usize,
+    /// Combined size of the files in bytes.
+    pub bytes: u64,
+}
+
+/// The duplicate group that frees the most space.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LargestGroup {
+    /// The file kept from the group.
+    pub original: PathBuf,
+    /// Number of duplicates in the group.
+    pub duplicates: usize,
+    /// Combined size of the group's duplicates in bytes.
+    pub bytes: u64,
+}
+
+/// Summary of a duplicates file.
+///
+/// Sizes are read from the listed files, so duplicates deleted since the scan
+/// are counted as missing and free no space.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DuplicateStats {
+    /// Number of duplicate groups.
+    pub groups: usize,
+    /// Number of listed duplicates and their paired Live Photo videos,
+    /// including missing ones.
+    pub duplicates: usize,
+    /// Number of listed duplicates that no longer exist.
+    pub missing: usize,
+    /// Space freed by erasing every existing duplicate, in bytes.
+    pub reclaimable_bytes: u64,
+    /// The group that frees the most space, if any.
+    pub largest_group: Option<LargestGroup>,
+    /// Existing duplicates by media type (`image`, `video`, `audio`, `other`).
+    pub by_media_type: BTreeMap<String, Breakdown>,
+    /// Existing duplicates by duplicate type (`exact`, `perceptual`, `mixed`,
+    /// `probable`).
+    pub by_duplicate_type: BTreeMap<String, Breakdown>,
+    /// Existing duplicates by the directory they are in, so the folders
+    /// holding the most can be cleaned first.
+    pub by_directory: BTreeMap<PathBuf, Breakdown>,
+}
+
+impl DuplicateStats {
+    /// Computes the statistics of a duplicates file. Entries already erased
+    /// are left out.
+    pub fn from_file(file: &DuplicatesFile) -> Self {
+        let mut stats = Self {
+            groups: file.pending_entries().count(),
+            ..Self::default()
+        };
+
+        for entry in file.pending_entries() {
+            let mut group_bytes = 0;
+
+            // Paired Live Photo videos are erased along with their image
+            for duplicate in &entry.files_to_erase() {
+                stats.duplicates += 1;
+                let Ok(size) = hasher::file_size(duplicate) else {
+                    stats.missing += 1;
+                    continue;
+                };
+                group_bytes += size;
+
+                let media = stats
+                    .by_media_type
+                    .entry(media_type_name(duplicate).to_string())
+                    .or_default();
+                media.files += 1;
+                media.bytes += size;
+
+                let kind = stats
+                    .by_duplicate_type
+                    .entry(duplicate_type_name(entry.duplicate_type).to_string())
+                    .or_default();
+                kind.files += 1;
+                kind.bytes += size;
+
+                let directory = stats
+                    .by_directory
+                    .entry(parent_directory(duplicate).to_path_buf())
+                    .or_default();
+                directory.files += 1;
+                directory.bytes += size;
+            }
+
+            stats.reclaimable_bytes += group_bytes;
+            if stats
+                .largest_group
+                .as_ref()
+                .is_none_or(|largest| group_bytes > largest.bytes)
+            {
+                stats.largest_group = Some(LargestGroup {
+                    original: entry.original.clone(),
+                    duplicates: entry.duplicates.len(),
+                    bytes: group_bytes,
+                });
+            }
+        }
+
+        stats
+    }
+
+    /// Returns the directories holding duplicates, the most reclaimable
+    /// space first. Ties are ordered by path.
+    pub fn directories_by_size(&self) -> Vec<(&Path, Breakdown)> {
+        let mut directories: Vec<(&Path, Breakdown)> = self
+            .by_directory
+            .iter()
+            .map(|(directory, breakdown)| (directory.as_path(), *breakdown))
+            .collect();
+        directories.sort_by(|a, b| b.1.bytes.cmp(&a.1.bytes).then_with(|| a.0.cmp(b.0)));
+        directories
+    }
+}
+
+pub struct Stats {
+    path: PathBuf,
+    json: bool,
+    quiet: bool,
+    by_directory: bool,
+    manifest_name: String,
+}
+
+impl Stats {
+    /// Creates a stats command for `path`, either a duplicates file or the
+    /// directory containing duplicates.json.
+    pub fn new(path: PathBuf, json: bool, quiet: bool) -> Self {
+        Self {
+            path,
+            json,
+            quiet,
+            by_directory: false,
+            manifest_name: DUPLICATES_FILENAME.to_string(),
+        }
+    }
+
+    /// Also prints the duplicates of each directory, the most reclaimable
+    /// space first. JSON output always includes them.
+    pub fn with_by_directory(mut self, by_directory: bool) -> Self {
+        self.by_directory = by_directory;
+        self
+    }
+
+    /// Looks for `name` instead of duplicates.json when given a directory.
+    pub fn with_manifest_name(mut self, name: impl Into<String>) -> Self {
+        self.manifest_name = name.into();
+        self
+    }
+
+    /// Returns the path to the duplicates file.
+    fn duplicates_file_path(&self) -> PathBuf {
+        if self.path.is_dir() {
+            self.path.join(&self.manifest_name)
+        } else {
+            self.path.clone()
+        }
+    }
+
+    /// Loads the duplicates file and computes its statistics.
+    ///
+    /// Returns `None` if the duplicates file does not exist.
+    pub fn compute(&self) -> Result<Option<DuplicateStats>> {
+        let duplicates_path = self.duplicates_file_path();
+        log::debug!("Looking for duplicates file at: {:?}", duplicates_path);
+
+        if !duplicates_path.exists() {
+            return Ok(None);
+        }
+
+        let file = DuplicatesFile::load(&duplicates_path)?;
+        Ok(Some(DuplicateStats::from_file(&file)))
+    }
+}
+
+impl Command for Stats {
+    /// The statistics, or `None` if there is no duplicates file.
+    type Output = Option<DuplicateStats>;
+
+    fn execute(&self) -> Result<Option<DuplicateStats>> {
+        let Some(stats) = self.compute()? else {
+            if !self.quiet {
+                println!(
+                    "{} No duplicates file found at: {}\n   Run 'mde scan' first to detect duplicates.",
+                    style(INFO_PREFIX).blue().bold(),
+                    style(self.duplicates_file_path().display()).cyan()
+                );
+            }
+            return Ok(None);
+        };
+
+        // JSON is meant for scripts, so it is printed even when quiet
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&stats)?);
+        } else if !self.quiet {
+            print_stats(&stats, &self.duplicates_file_path());
+            if self.by_directory {
+                print_directories(&stats);
+            }
+        }
+
+        Ok(Some(stats))
+    }
+}
+
+/// Prints the statistics in a human-readable form.
+fn print_stats(stats: &DuplicateStats, path: &Path) {
+    println!(
+        "{}",
+        style("=== Duplicate Statistics ===").bold().cyan()
+    );
+    println!("File: {}", style(path.display()).cyan());
+    println!("Duplicate groups: {}", style(stats.groups).cyan());
+    println!(
+        "Duplicate files: {} ({} missing)",
+        style(stats.duplicates).cyan(),
+        stats.missing
+    );
+    println!(
+        "Reclaimable space: {}",
+        style(format_size(stats.reclaimable_bytes)).green().bold()
+    );
+    if let Some(largest) = &stats.largest_group {
+        println!(
+            "Largest group: {} ({} duplicates, {})",
+            style(largest.original.display()).cyan(),
+            largest.duplicates,
+            format_size(largest.bytes)
+        );
+    }
+
+    for (title, breakdown) in [
+        ("By media type:", &stats.by_media_type),
+        ("By duplicate type:", &stats.by_duplicate_type),
+    ] {
+        println!();
+        println!("{}", style(title).bold());
+        for (name, entry) in breakdown {
+            println!(
+                "  {}: {} files, {}",
+                name,
+                entry.files,
+                format_size(entry.bytes)
+            );
+        }
+    }
+}
+
+/// Prints the duplicates of each directory, the most reclaimable space first.
+fn print_directories(stats: &DuplicateStats) {
+    println!();
+    println!("{}", style("By directory:").bold());
+    for (directory, entry) in stats.directories_by_size() {
+        println!(
+            "  {}: {} files, {}",
+            style(directory.display()).cyan(),
+            entry.files,
+            format_size(entry.bytes)
+        );
+    }
+}
+
+/// Returns the directory a duplicate is reported under: its parent, or `.`
+/// for a bare file name.
+fn parent_directory(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// Returns the name a file's media type is reported under.
+fn media_type_name(path: &Path) -> &'static str {
+    match hasher::get_media_type(path) {
+        MediaType::Image => "image",
+        MediaType::Video => "video",
+        MediaType::Audio => "audio",
+        MediaType::Unknown => "other",
+    }
+}
+
+/// Returns the name a duplicate type is reported under.
+fn duplicate_type_name(duplicate_type: DuplicateType) -> &'static str {
+    match duplicate_type {
+        DuplicateType::Exact => "exact",
+        DuplicateType::Perceptual => "perceptual",
+        DuplicateType::Mixed => "mixed",
+        DuplicateType::Probable => "probable",
+    }
+}
@@ -0,0 +1,136 @@
+use std::fs;
+use std::path::PathBuf;
+
+use console::style;
+
+use super::Command;
+use crate::error::{Error, FileAction, Result};
+use crate::services::soft_delete::{self, SoftDeleteManifest, SoftDeletedFile};
+
+// Styled output prefixes (Classic ASCII)
+const SUCCESS_PREFIX: &str = "[OK]";
+const WARNING_PREFIX: &str = "[!]";
+const INFO_PREFIX: &str = "[*]";
+
+/// Outcome of an undo run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UndoSummary {
+    /// Number of files moved back to their original paths.
+    pub restored: usize,
+    /// Number of files left in the batch because their original path is taken
+    /// or could not be written.
+    pub skipped: usize,
+}
+
+pub struct Undoer {
+    path: PathBuf,
+    quiet: bool,
+}
+
+impl Undoer {
+    pub fn new(path: PathBuf, quiet: bool) -> Self {
+        Self { path, quiet }
+    }
+
+    /// Restores the most recent soft-deleted erase batch.
+    ///
+    /// Files whose original path is occupied are never overwritten; they stay
+    /// in the batch so a later undo can pick them up.
+    pub fn undo_latest(&self) -> Result<UndoSummary> {
+        let Some(batch_dir) = soft_delete::latest_batch(&self.path)? else {
+            if !self.quiet {
+                println!(
+                    "{} Nothing to undo in: {}\n   Only erases run with --soft can be undone.",
+                    style(INFO_PREFIX).blue().bold(),
+                    style(self.path.display()).cyan()
+                );
+            }
+            return Ok(UndoSummary::default());
+        };
+
+        log::info!("Restoring soft-deleted batch {:?}", batch_dir);
+        let mut manifest = SoftDeleteManifest::load(&batch_dir)?;
+        let mut summary = UndoSummary::default();
+        let mut remaining: Vec<SoftDeletedFile> = Vec::new();
+
+        for file in manifest.files {
+            let staged_path = batch_dir.join(&file.staged);
+
+            if !staged_path.exists() {
+                log::warn!("Soft-deleted copy of {:?} is gone", file.original);
+                continue;
+            }
+
+            if file.original.exists() {
+                log::warn!("Not overwriting existing file {:?}", file.original);
+                if !self.quiet {
+                    println!(
+                        "{} Already exists, not restored: {}",
+                        style(WARNING_PREFIX).yellow().bold(),
+                        style(file.original.display()).yellow()
+                    );
+                }
+                summary.skipped += 1;
+                remaining.push(file);
+                continue;
+            }
+
+            if let Some(parent) = file.original.parent()
+                && !parent.as_os_str().is_empty()
+            {
+                fs::create_dir_all(parent)
+                    .map_err(Error::on_file(FileAction::CreateDir, parent))?;
+            }
+
+            match fs::rename(&staged_path, &file.original) {
+                Ok(()) => {
+                    log::debug!("Restored: {:?}", file.original);
+                    summary.restored += 1;
+                }
+                Err(e) => {
+                    log::error!("Failed to restore {:?}: {}", file.original, e);
+                    summary.skipped += 1;
+                    remaining.push(file);
+                }
+            }
+        }
+
+        if remaining.is_empty() {
+            fs::remove_dir_all(&batch_dir)
+                .map_err(Error::on_file(FileAction::Remove, &batch_dir))?;
+            // Drop the soft-delete directory once its last batch is gone
+            soft_delete::remove_empty_soft_delete_dir(&self.path)?;
+        } else {
+            manifest.files = remaining;
+            manifest.save(&batch_dir)?;
+        }
+
+        Ok(summary)
+    }
+}
+
+impl Command for Undoer {
+    type Output = UndoSummary;
+
+    fn execute(&self) -> Result<UndoSummary> {
+        let summary = self.undo_latest()?;
+
+        if summary.restored > 0 && !self.quiet {
+            println!(
+                "{} Restored {} files.",
+                style(SUCCESS_PREFIX).green().bold(),
+                style(summary.restored).green().bold()
+            );
+        }
+
+        if summary.skipped > 0 && !self.quiet {
+            println!(
+                "{} {} files could not be restored and were kept for a later undo.",
+                style(WARNING_PREFIX).yellow().bold(),
+                style(summary.skipped).yellow()
+            );
+        }
+
+        Ok(summary)
+    }
+}
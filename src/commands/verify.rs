@@ -0,0 +1,294 @@
+use std::path::{Path, PathBuf};
+
+use console::style;
+use image_hasher::ImageHash;
+
+use super::Command;
+use crate::error::{Error, Result};
+use crate::services::duplicate::{DuplicateEntry, DuplicateType, DuplicatesFile, DUPLICATES_FILENAME};
+use crate::services::hasher::{self, MediaType, VideoSignature};
+
+// Styled output prefixes (Classic ASCII)
+const SUCCESS_PREFIX: &str = "[OK]";
+const ERROR_PREFIX: &str = "[X]";
+const INFO_PREFIX: &str = "[*]";
+
+/// Why a listed file no longer matches its group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MismatchReason {
+    /// The file no longer exists.
+    Missing,
+    /// The file exists but is no longer a duplicate of the original.
+    Changed,
+    /// The file could not be hashed.
+    Unreadable(String),
+}
+
+/// A file listed in duplicates.json that no longer matches its group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The original of the group.
+    pub original: PathBuf,
+    /// The file that failed verification; the original itself if it is the
+    /// one that changed.
+    pub file: PathBuf,
+    /// Why the file failed verification.
+    pub reason: MismatchReason,
+}
+
+/// Outcome of a verify run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifySummary {
+    /// Number of groups checked.
+    pub groups: usize,
+    /// Number of duplicates checked.
+    pub duplicates: usize,
+    /// Files that no longer match their group.
+    pub mismatches: Vec<Mismatch>,
+}
+
+pub struct Verifier {
+    path: PathBuf,
+    quiet: bool,
+    manifest_name: String,
+}
+
+impl Verifier {
+    pub fn new(path: PathBuf, quiet: bool) -> Self {
+        Self {
+            path,
+            quiet,
+            manifest_name: DUPLICATES_FILENAME.to_string(),
+        }
+    }
+
+    /// Reads the duplicates from `name` instead of duplicates.json.
+    pub fn with_manifest_name(mut self, name: impl Into<String>) -> Self {
+        self.manifest_name = name.into();
+        self
+    }
+
+    /// Returns the path to the duplicates file.
+    fn duplicates_file_path(&self) -> PathBuf {
+        self.path.join(&self.manifest_name)
+    }
+
+    /// Re-hashes every file listed in duplicates.json and reports those that
+    /// no longer match their group.
+    ///
+    /// Exact groups are checked with the hash algorithm recorded in the file,
+    /// perceptual groups with the recorded hash settings and similarity
+    /// threshold, or the threshold recorded for their media type. Duplicates in
+    /// mixed groups pass if either check does. Videos of a file scanned with
+    /// `--video-scenes` also pass if their scene signatures match, and those
+    /// of a file scanned with `--video-audio-match` if their soundtracks do,
+    /// as in the scan. Probable groups from a quick scan are hashed in full like exact
+    /// groups, confirming or ruling out each duplicate.
+    ///
+    /// Returns `None` if the duplicates file does not exist.
+    pub fn verify(&self) -> Result<Option<VerifySummary>> {
+        let duplicates_path = self.duplicates_file_path();
+        log::debug!("Looking for duplicates file at: {:?}", duplicates_path);
+
+        if !duplicates_path.exists() {
+            return Ok(None);
+        }
+
+        let file = DuplicatesFile::load(&duplicates_path)?;
+        let threshold = file
+            .similarity_threshold
+            .unwrap_or(hasher::DEFAULT_SIMILARITY_THRESHOLD);
+        let mut summary = VerifySummary {
+            groups: file.pending_entries().count(),
+            ..VerifySummary::default()
+        };
+
+        for entry in file.pending_entries() {
+            summary.duplicates += entry.duplicates.len();
+            // Groups only hold files of one media type
+            let media_type = hasher::get_media_type(&entry.original);
+            verify_entry(
+                entry,
+                &file,
+                file.media_thresholds.threshold_for(media_type, threshold),
+                &mut summary.mismatches,
+            );
+        }
+
+        log::info!(
+            "Verified {} groups: {} mismatches",
+            summary.groups,
+            summary.mismatches.len()
+        );
+        Ok(Some(summary))
+    }
+}
+
+impl Command for Verifier {
+    /// The verification outcome, or `None` if there is no duplicates file.
+    /// Mismatches are returned as [`Error::VerificationFailed`] instead.
+    type Output = Option<VerifySummary>;
+
+    fn execute(&self) -> Result<Option<VerifySummary>> {
+        let Some(summary) = self.verify()? else {
+            if !self.quiet {
+                println!(
+                    "{} No duplicates file found at: {}\n   Run 'mde scan' first to detect duplicates.",
+                    style(INFO_PREFIX).blue().bold(),
+                    style(self.duplicates_file_path().display()).cyan()
+                );
+            }
+            return Ok(None);
+        };
+
+        if summary.mismatches.is_empty() {
+            if !self.quiet {
+                println!(
+                    "{} All {} duplicates in {} groups still match.",
+                    style(SUCCESS_PREFIX).green().bold(),
+                    style(summary.duplicates).green().bold(),
+                    style(summary.groups).green()
+                );
+            }
+            return Ok(Some(summary));
+        }
+
+        if !self.quiet {
+            for mismatch in &summary.mismatches {
+                let reason = match &mismatch.reason {
+                    MismatchReason::Missing => "missing".to_string(),
+                    MismatchReason::Changed => "no longer matches".to_string(),
+                    MismatchReason::Unreadable(e) => format!("unreadable: {}", e),
+                };
+                println!(
+                    "{} {} ({}, original: {})",
+                    style(ERROR_PREFIX).red().bold(),
+                    style(mismatch.file.display()).red(),
+                    reason,
+                    mismatch.original.display()
+                );
+            }
+            println!(
+                "{} {} files no longer match. Run 'mde scan' again before erasing.",
+                style(ERROR_PREFIX).red().bold(),
+                style(summary.mismatches.len()).red().bold()
+            );
+        }
+
+        Err(Error::VerificationFailed(summary.mismatches.len()))
+    }
+}
+
+/// Hashes of a file, computed only for the checks its group needs.
+struct Fingerprint {
+    exact: Option<String>,
+    perceptual: Option<ImageHash>,
+    scenes: Option<VideoSignature>,
+    sound: Option<ImageHash>,
+}
+
+/// Checks one group of `file`, appending every file that no longer matches.
+fn verify_entry(
+    entry: &DuplicateEntry,
+    file: &DuplicatesFile,
+    threshold: u32,
+    mismatches: &mut Vec<Mismatch>,
+) {
+    // Scene frames are always hashed at the default size
+    let scene_threshold = threshold;
+    let threshold = file.perceptual_config.scale_threshold(threshold);
+    let mismatch = |path: &Path, reason| Mismatch {
+        original: entry.original.clone(),
+        file: path.to_path_buf(),
+        reason,
+    };
+
+    let original = match fingerprint(&entry.original, entry.duplicate_type, file) {
+        Ok(original) => original,
+        Err(reason) => {
+            // Without the original there is nothing to compare the duplicates to
+            mismatches.push(mismatch(&entry.original, reason));
+            return;
+        }
+    };
+
+    for duplicate in &entry.duplicates {
+        let candidate = match fingerprint(duplicate, entry.duplicate_type, file) {
+            Ok(candidate) => candidate,
+            Err(reason) => {
+                mismatches.push(mismatch(duplicate, reason));
+                continue;
+            }
+        };
+
+        let exact_match = original.exact.is_some() && original.exact == candidate.exact;
+        let perceptual_match = match (&original.perceptual, &candidate.perceptual) {
+            (Some(a), Some(b)) => hasher::are_similar(a, b, threshold).unwrap_or(false),
+            _ => false,
+        };
+        let scene_match = match (&original.scenes, &candidate.scenes) {
+            (Some(a), Some(b)) => a.matches(b, scene_threshold),
+            _ => false,
+        };
+        let sound_match = match (&original.sound, &candidate.sound) {
+            (Some(a), Some(b)) => hasher::are_similar(a, b, threshold).unwrap_or(false),
+            _ => false,
+        };
+
+        if !exact_match && !perceptual_match && !scene_match && !sound_match {
+            log::debug!("{:?} no longer matches {:?}", duplicate, entry.original);
+            mismatches.push(mismatch(duplicate, MismatchReason::Changed));
+        }
+    }
+}
+
+/// Computes the hashes needed to verify a file of a group of `duplicate_type`
+/// listed in `file`.
+fn fingerprint(
+    path: &Path,
+    duplicate_type: DuplicateType,
+    file: &DuplicatesFile,
+) -> std::result::Result<Fingerprint, MismatchReason> {
+    if !path.exists() {
+        return Err(MismatchReason::Missing);
+    }
+
+    let unreadable = |e: Error| MismatchReason::Unreadable(e.to_string());
+    let exact = match duplicate_type {
+        // Hashing probable duplicates in full confirms or rules them out
+        DuplicateType::Exact | DuplicateType::Mixed | DuplicateType::Probable => {
+            Some(hasher::exact_hash(path, file.hash_algorithm).map_err(unreadable)?)
+        }
+        DuplicateType::Perceptual => None,
+    };
+    let similar = matches!(duplicate_type, DuplicateType::Perceptual | DuplicateType::Mixed);
+    let perceptual = if similar {
+        hasher::media_perceptual_hash_with(path, file.perceptual_config).map_err(unreadable)?
+    } else {
+        None
+    };
+    let is_video = hasher::get_media_type(path) == MediaType::Video;
+    let scenes = if similar && file.video_scenes && is_video {
+        hasher::video_scene_signature(path).map_err(unreadable)?
+    } else {
+        None
+    };
+    let sound = if similar && file.video_audio_match && is_video {
+        hasher::video_audio_hash_with(path, file.perceptual_config).map_err(unreadable)?
+    } else {
+        None
+    };
+
+    if exact.is_none() && perceptual.is_none() && scenes.is_none() && sound.is_none() {
+        return Err(MismatchReason::Unreadable(
+            "no perceptual hash could be computed".to_string(),
+        ));
+    }
+
+    Ok(Fingerprint {
+        exact,
+        perceptual,
+        scenes,
+        sound,
+    })
+}
@@ -3,7 +3,7 @@
 //! This module provides a unified error type [`Error`] and a convenient
 //! [`Result`] type alias used throughout the application.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// A type alias for `Result<T, Error>`.
 pub type Result<T> = std::result::Result<T, Error>;
@@ -21,6 +21,21 @@ pub enum Error {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
+    /// An I/O operation on a specific file or directory failed.
+    ///
+    /// Preferred over [`Error::Io`] wherever the path is known, so the
+    /// message names it, e.g. "Failed to read /path/to/file: permission
+    /// denied".
+    #[error("Failed to {action} {path}: {source}")]
+    FileOperation {
+        /// What was being done to the file.
+        action: FileAction,
+        /// The file or directory the operation failed on.
+        path: PathBuf,
+        /// The underlying I/O error.
+        source: std::io::Error,
+    },
+
     /// An error occurred while traversing directories.
     ///
     /// This variant wraps [`walkdir::Error`] which can occur when
@@ -32,6 +47,50 @@ pub enum Error {
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
 
+    /// An error occurred while parsing or writing YAML.
+    #[error("YAML error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+
+    /// A TOML manifest could not be parsed.
+    #[error("TOML error: {0}")]
+    TomlParse(#[from] toml::de::Error),
+
+    /// A manifest could not be written as TOML.
+    #[error("TOML error: {0}")]
+    TomlWrite(#[from] toml::ser::Error),
+
+    /// A file could not be moved to the system trash.
+    ///
+    /// This variant wraps [`trash::Error`] and is automatically
+    /// converted via the `#[from]` attribute.
+    #[error("Trash error: {0}")]
+    Trash(#[from] trash::Error),
+
+    /// The `.mdeignore` file could not be turned into a matcher.
+    ///
+    /// This variant wraps [`ignore::Error`] and is automatically
+    /// converted via the `#[from]` attribute.
+    #[error("Ignore file error: {0}")]
+    Ignore(#[from] ignore::Error),
+
+    /// An interactive prompt could not be shown or answered.
+    #[error("Prompt error: {0}")]
+    Prompt(#[from] dialoguer::Error),
+
+    /// The thread pool hashing files could not be started.
+    ///
+    /// This variant wraps [`rayon::ThreadPoolBuildError`] and is automatically
+    /// converted via the `#[from]` attribute.
+    #[error("Thread pool error: {0}")]
+    ThreadPool(#[from] rayon::ThreadPoolBuildError),
+
+    /// FFmpeg or ffprobe could not be run or crashed on a file.
+    ///
+    /// Files FFmpeg merely cannot decode are not errors; they are skipped
+    /// like any other file that is not valid media.
+    #[error("FFmpeg error: {0}")]
+    Ffmpeg(String),
+
     /// The specified path does not exist.
     #[error("Path not found: {0}")]
     PathNotFound(PathBuf),
@@ -44,4 +103,148 @@ pub enum Error {
         /// The reason why the path is invalid.
         reason: String,
     },
+
+    /// The perceptual similarity threshold is out of range.
+    #[error("Invalid similarity threshold: {0} (expected a value between 0 and {max})", max = crate::services::hasher::MAX_SIMILARITY_THRESHOLD)]
+    InvalidThreshold(u32),
+
+    /// The perceptual hash size is out of range.
+    #[error("Invalid hash size: {0} (expected a value between {min} and {max})", min = crate::services::hasher::MIN_HASH_SIZE, max = crate::services::hasher::MAX_HASH_SIZE)]
+    InvalidHashSize(u32),
+
+    /// Perceptual hashes stored in a duplicates file were computed with
+    /// other settings than requested, so they cannot be compared.
+    #[error("Perceptual hashes were computed as {found}, not {expected}")]
+    PerceptualConfigMismatch {
+        /// The settings recorded in the file.
+        found: crate::services::hasher::PerceptualConfig,
+        /// The settings that were requested.
+        expected: crate::services::hasher::PerceptualConfig,
+    },
+
+    /// Two perceptual hashes of different sizes were compared.
+    #[error("Cannot compare perceptual hashes of {0} and {1} bytes: they were computed with different settings")]
+    HashSizeMismatch(usize, usize),
+
+    /// Exact hashes of a duplicates file were computed with another
+    /// algorithm than those they are combined with.
+    #[error("Exact hashes were computed with {found}, not {expected}")]
+    HashAlgorithmMismatch {
+        /// The algorithm recorded in the file.
+        found: crate::services::hasher::HashAlgorithm,
+        /// The algorithm that was expected.
+        expected: crate::services::hasher::HashAlgorithm,
+    },
+
+    /// A human-readable size could not be parsed.
+    #[error("Invalid size: '{0}' (expected a number with an optional K, M, G or T suffix)")]
+    InvalidSize(String),
+
+    /// The minimum file size is larger than the maximum file size.
+    #[error("Invalid size range: minimum size {min} is larger than maximum size {max}")]
+    InvalidSizeRange {
+        /// The minimum size in bytes.
+        min: u64,
+        /// The maximum size in bytes.
+        max: u64,
+    },
+
+    /// A duration or date could not be parsed.
+    #[error("Invalid time: '{0}' (expected a duration such as 30d or 12h, or a date such as 2024-05-01)")]
+    InvalidTime(String),
+
+    /// The earliest modification time to scan is not before the latest.
+    #[error("Invalid time range: files modified after {newer_than} cannot be modified before {older_than}")]
+    InvalidTimeRange {
+        /// The time files must be modified after.
+        newer_than: chrono::DateTime<chrono::Utc>,
+        /// The time files must be modified before.
+        older_than: chrono::DateTime<chrono::Utc>,
+    },
+
+    /// A duplicates file was written with an incompatible format version.
+    #[error("Unsupported duplicates file version {found} (expected {expected})")]
+    UnsupportedVersion {
+        /// The version found in the file.
+        found: String,
+        /// The version this build reads and writes.
+        expected: String,
+    },
+
+    /// Files listed in a duplicates file no longer match their group.
+    #[error("Verification failed: {0} files no longer match the duplicates file")]
+    VerificationFailed(usize),
+
+    /// A duplicates file to compare against has no stored hashes.
+    #[error("{0} has no stored hashes to compare against (scan it with --store-hashes)")]
+    MissingHashes(PathBuf),
+
+    /// `scan --update` was asked to update a report that is not a
+    /// duplicates manifest, such as a CSV report.
+    #[error("Only JSON, YAML, TOML and JSON Lines reports can be updated; use --format json, yaml, toml or jsonl with --update")]
+    UpdateRequiresManifest,
+
+    /// Several directories are scanned but no output file was given.
+    #[error("Several directories are scanned, so --output is required to choose where the report is written")]
+    OutputRequired,
+
+    /// A file to erase no longer has the exact hash recorded when it was
+    /// scanned, so it was edited since.
+    #[error("{0} changed since it was scanned")]
+    ChecksumMismatch(PathBuf),
+
+    /// The operation was stopped through its
+    /// [`CancelToken`](crate::services::cancel::CancelToken).
+    #[error("Canceled")]
+    Canceled,
+
+    /// Erasing failed and all staged files were restored.
+    #[error("Erase failed: all staged files were restored and nothing was deleted")]
+    EraseRolledBack,
+
+    /// The path resolves to a filesystem root or the user's home directory.
+    #[error(
+        "Refusing to operate on {0}: it is a filesystem root or home directory (pass --i-know-what-im-doing to override)"
+    )]
+    DangerousPath(PathBuf),
+}
+
+/// What was being done to a file when an [`Error::FileOperation`] occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileAction {
+    /// Opening, reading or inspecting the file.
+    Read,
+    /// Creating or writing the file.
+    Write,
+    /// Creating the directory.
+    CreateDir,
+    /// Deleting the file or directory.
+    Remove,
+    /// Moving or renaming the file or directory.
+    Move,
+}
+
+impl std::fmt::Display for FileAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let verb = match self {
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::CreateDir => "create directory",
+            Self::Remove => "remove",
+            Self::Move => "move",
+        };
+        f.write_str(verb)
+    }
+}
+
+impl Error {
+    /// Returns a function wrapping an I/O error while doing `action` to
+    /// `path` in [`Error::FileOperation`], for use with `map_err`.
+    pub fn on_file(action: FileAction, path: &Path) -> impl FnOnce(std::io::Error) -> Self {
+        move |source| Self::FileOperation {
+            action,
+            path: path.to_path_buf(),
+            source,
+        }
+    }
 }
@@ -1,7 +1,24 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::Path;
+
+use env_logger::Target;
 use log::LevelFilter;
 
-pub fn init(verbose: u8, quiet: bool) {
-    let level = if quiet {
+/// Sets up logging for the run.
+///
+/// The level comes from `level` if given, and otherwise from `verbose`, or is
+/// limited to errors when `silent`. Logs go to stderr, or are appended to
+/// `log_file` with timestamps, independently of the `--quiet` UI.
+///
+/// If `log_file` cannot be opened, logs go to stderr and the error is returned.
+pub fn init(
+    verbose: u8,
+    silent: bool,
+    level: Option<LevelFilter>,
+    log_file: Option<&Path>,
+) -> io::Result<()> {
+    let level = level.unwrap_or(if silent {
         LevelFilter::Error
     } else {
         match verbose {
@@ -9,12 +26,29 @@ pub fn init(verbose: u8, quiet: bool) {
             1 => LevelFilter::Info,
             _ => LevelFilter::Debug,
         }
-    };
+    });
+
+    let mut builder = env_logger::Builder::new();
+    builder.filter_level(level).format_timestamp(None);
 
-    env_logger::Builder::new()
-        .filter_level(level)
-        .format_timestamp(None)
-        .init();
+    let mut result = Ok(());
+    if let Some(path) = log_file {
+        match open_log_file(path) {
+            Ok(file) => {
+                builder
+                    .target(Target::Pipe(Box::new(file)))
+                    .format_timestamp_secs();
+            }
+            Err(e) => result = Err(e),
+        }
+    }
+    builder.init();
 
     log::debug!("Logger initialized with level: {:?}", level);
+    result
+}
+
+/// Opens `path` for appending, so scheduled runs add to the same log.
+fn open_log_file(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
 }
@@ -1,9 +1,17 @@
 mod cli;
 mod logger;
 
-fn main() {
-    if let Err(e) = cli::run() {
-        log::error!("{}", e);
-        std::process::exit(1);
+use std::process::ExitCode;
+
+use media_duplicate_eraser_rs::error::Error;
+
+fn main() -> ExitCode {
+    match cli::run() {
+        Ok(code) => code,
+        Err(Error::Canceled) => ExitCode::from(cli::EXIT_CANCELED),
+        Err(e) => {
+            log::error!("{}", e);
+            ExitCode::from(cli::EXIT_FAILURE)
+        }
     }
 }
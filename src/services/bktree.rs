@@ -0,0 +1,107 @@
+//! BK-tree index over perceptual hashes.
+//!
+//! A BK-tree organizes hashes by their Hamming distance so that all hashes
+//! within a radius of a query can be found without comparing against every
+//! stored hash. Thanks to the triangle inequality, only children whose edge
+//! distance lies in `[d - radius, d + radius]` need to be visited, where `d`
+//! is the distance between the query and the current node.
+
+use image_hasher::ImageHash;
+
+use super::hasher;
+
+/// A node in the tree. Children are stored as `(edge distance, node index)`.
+struct Node<T> {
+    hash: ImageHash,
+    value: T,
+    children: Vec<(u32, usize)>,
+}
+
+/// A BK-tree keyed on the Hamming distance between [`ImageHash`] values.
+pub struct BkTree<T> {
+    nodes: Vec<Node<T>>,
+}
+
+impl<T> Default for BkTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> BkTree<T> {
+    /// Creates an empty tree.
+    pub fn new() -> Self {
+        Self { nodes: Vec::new() }
+    }
+
+    /// Returns the number of hashes stored in the tree.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns `true` if the tree contains no hashes.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Inserts a hash with an associated value.
+    pub fn insert(&mut self, hash: ImageHash, value: T) {
+        let new_index = self.nodes.len();
+
+        if !self.nodes.is_empty() {
+            let mut current = 0;
+            loop {
+                let distance = hasher::hamming_distance(&self.nodes[current].hash, &hash);
+                let child = self.nodes[current]
+                    .children
+                    .iter()
+                    .find(|(edge, _)| *edge == distance)
+                    .map(|&(_, index)| index);
+
+                match child {
+                    Some(index) => current = index,
+                    None => {
+                        self.nodes[current].children.push((distance, new_index));
+                        break;
+                    }
+                }
+            }
+        }
+
+        self.nodes.push(Node {
+            hash,
+            value,
+            children: Vec::new(),
+        });
+    }
+
+    /// Returns the values of all hashes within `radius` of `hash`, together
+    /// with their distance to it.
+    pub fn find_within(&self, hash: &ImageHash, radius: u32) -> Vec<(u32, &T)> {
+        let mut found = Vec::new();
+        if self.nodes.is_empty() {
+            return found;
+        }
+
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            let distance = hasher::hamming_distance(&node.hash, hash);
+
+            if distance <= radius {
+                found.push((distance, &node.value));
+            }
+
+            let low = distance.saturating_sub(radius);
+            let high = distance.saturating_add(radius);
+            stack.extend(
+                node.children
+                    .iter()
+                    .filter(|(edge, _)| (low..=high).contains(edge))
+                    .map(|&(_, child)| child),
+            );
+        }
+
+        found
+    }
+}
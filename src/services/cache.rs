@@ -0,0 +1,307 @@
+//! Hash cache shared between scans.
+//!
+//! Re-scanning a mostly unchanged library would otherwise recompute every
+//! hash. [`HashCache`] remembers exact and perceptual hashes in a sidecar JSON
+//! file, keyed by path and validated against the file's size and modification
+//! time, so unchanged files skip hashing entirely.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use image_hasher::ImageHash;
+use serde::{Deserialize, Serialize};
+
+use super::hasher::{self, HashAlgorithm, PerceptualConfig};
+use crate::error::{Error, FileAction, Result};
+
+/// Default file name of the cache, stored next to the duplicates report.
+pub const DEFAULT_CACHE_FILENAME: &str = ".mde_cache.json";
+
+/// Version of the cache format. Caches with another version are discarded.
+///
+/// Bump it whenever a hash algorithm changes so stale hashes are not reused.
+const CACHE_VERSION: u32 = 3;
+
+/// Size and modification time a cache entry was computed for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    size: u64,
+    mtime_secs: u64,
+    mtime_nanos: u32,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Result<Self> {
+        let metadata = fs::metadata(path)?;
+        let mtime = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Ok(Self {
+            size: metadata.len(),
+            mtime_secs: mtime.as_secs(),
+            mtime_nanos: mtime.subsec_nanos(),
+        })
+    }
+}
+
+/// Cached hashes of a single file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    #[serde(flatten)]
+    stamp: FileStamp,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    exact: HashMap<HashAlgorithm, String>,
+    /// Base64-encoded perceptual hash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    perceptual: Option<String>,
+    /// Settings the perceptual hash was computed with.
+    #[serde(default)]
+    perceptual_config: PerceptualConfig,
+}
+
+impl CacheEntry {
+    fn new(stamp: FileStamp) -> Self {
+        Self {
+            stamp,
+            exact: HashMap::new(),
+            perceptual: None,
+            perceptual_config: PerceptualConfig::default(),
+        }
+    }
+}
+
+/// On-disk layout of the cache file.
+#[derive(Debug, Deserialize)]
+struct CacheFile {
+    version: u32,
+    entries: HashMap<PathBuf, CacheEntry>,
+}
+
+/// Borrowed form of [`CacheFile`] used when saving.
+#[derive(Serialize)]
+struct CacheFileRef<'a> {
+    version: u32,
+    entries: &'a HashMap<PathBuf, CacheEntry>,
+}
+
+/// Exact and perceptual hashes remembered between scans.
+#[derive(Debug)]
+pub struct HashCache {
+    path: Option<PathBuf>,
+    entries: HashMap<PathBuf, CacheEntry>,
+    dirty: bool,
+    hits: usize,
+    misses: usize,
+}
+
+impl HashCache {
+    /// Returns a cache that always computes hashes and is never saved.
+    pub fn disabled() -> Self {
+        Self {
+            path: None,
+            entries: HashMap::new(),
+            dirty: false,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Loads the cache stored at `path`.
+    ///
+    /// A missing, unreadable or outdated cache file is not an error: the
+    /// cache simply starts empty and is written to `path` on [`save`](Self::save).
+    pub fn load(path: &Path) -> Self {
+        let mut cache = Self::disabled();
+        cache.path = Some(path.to_path_buf());
+
+        if !path.exists() {
+            return cache;
+        }
+
+        match read_cache_file(path) {
+            Ok(file) if file.version == CACHE_VERSION => {
+                log::info!("Loaded {} cached hashes from {:?}", file.entries.len(), path);
+                cache.entries = file.entries;
+            }
+            Ok(file) => {
+                log::info!("Ignoring hash cache with version {}", file.version);
+            }
+            Err(e) => {
+                log::warn!("Ignoring unreadable hash cache {:?}: {}", path, e);
+            }
+        }
+
+        cache
+    }
+
+    /// Returns where the cache is saved, or `None` if it is disabled.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Returns the number of cached files.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no files are cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns how many hashes were served from the cache.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Returns how many hashes had to be computed.
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    /// Returns the exact hash of `path`, computing it only if the file changed.
+    pub fn exact_hash(&mut self, path: &Path, algorithm: HashAlgorithm) -> Result<String> {
+        if self.path.is_none() {
+            return hasher::exact_hash(path, algorithm);
+        }
+
+        let entry = self.fresh_entry(path)?;
+        if let Some(hash) = entry.exact.get(&algorithm) {
+            let hash = hash.clone();
+            self.hits += 1;
+            return Ok(hash);
+        }
+
+        let hash = hasher::exact_hash(path, algorithm)?;
+        entry.exact.insert(algorithm, hash.clone());
+        self.misses += 1;
+        self.dirty = true;
+        Ok(hash)
+    }
+
+    /// Returns the perceptual hash of `path` computed with `config`, computing
+    /// it only if the file or the settings changed.
+    ///
+    /// Files that cannot be hashed are not remembered and are retried next time.
+    pub fn perceptual_hash(
+        &mut self,
+        path: &Path,
+        config: PerceptualConfig,
+    ) -> Result<Option<ImageHash>> {
+        if let Some(hash) = self.cached_perceptual_hash(path, config)? {
+            return Ok(Some(hash));
+        }
+
+        let hash = hasher::media_perceptual_hash_with(path, config)?;
+        self.insert_perceptual_hash(path, config, hash.as_ref())?;
+        Ok(hash)
+    }
+
+    /// Returns the cached perceptual hash of `path`, or `None` if it has to be
+    /// computed because the file changed or was hashed with other settings
+    /// than `config`. A disabled cache always returns `None`.
+    ///
+    /// Together with [`insert_perceptual_hash`](Self::insert_perceptual_hash)
+    /// this lets callers compute the missing hashes outside the cache, e.g. in
+    /// parallel.
+    pub fn cached_perceptual_hash(
+        &mut self,
+        path: &Path,
+        config: PerceptualConfig,
+    ) -> Result<Option<ImageHash>> {
+        if self.path.is_none() {
+            return Ok(None);
+        }
+
+        let entry = self.fresh_entry(path)?;
+        let hash = entry
+            .perceptual
+            .as_deref()
+            .filter(|_| entry.perceptual_config == config)
+            .and_then(|encoded| ImageHash::from_base64(encoded).ok());
+        if hash.is_some() {
+            self.hits += 1;
+        }
+        Ok(hash)
+    }
+
+    /// Remembers a perceptual hash of `path` computed with `config` outside
+    /// the cache, replacing one computed with other settings.
+    ///
+    /// `None` means the file could not be hashed; it is not remembered and is
+    /// retried next time.
+    pub fn insert_perceptual_hash(
+        &mut self,
+        path: &Path,
+        config: PerceptualConfig,
+        hash: Option<&ImageHash>,
+    ) -> Result<()> {
+        if self.path.is_none() {
+            return Ok(());
+        }
+
+        self.misses += 1;
+        if let Some(hash) = hash {
+            let entry = self.fresh_entry(path)?;
+            entry.perceptual = Some(hash.to_base64());
+            entry.perceptual_config = config;
+            self.dirty = true;
+        }
+        Ok(())
+    }
+
+    /// Writes the cache back to disk if anything changed.
+    ///
+    /// Entries for files that no longer exist are dropped.
+    pub fn save(&mut self) -> Result<()> {
+        let Some(path) = self.path.clone() else {
+            return Ok(());
+        };
+
+        let before = self.entries.len();
+        self.entries.retain(|file, _| file.exists());
+        if !self.dirty && self.entries.len() == before {
+            return Ok(());
+        }
+
+        let file = File::create(&path).map_err(Error::on_file(FileAction::Write, &path))?;
+        let mut out = BufWriter::new(file);
+        let cache_file = CacheFileRef {
+            version: CACHE_VERSION,
+            entries: &self.entries,
+        };
+        serde_json::to_writer(&mut out, &cache_file)?;
+        out.flush().map_err(Error::on_file(FileAction::Write, &path))?;
+
+        self.dirty = false;
+        log::info!("Saved {} cached hashes to {:?}", self.entries.len(), path);
+        Ok(())
+    }
+
+    /// Returns the entry for `path`, resetting it if the file changed since it was cached.
+    fn fresh_entry(&mut self, path: &Path) -> Result<&mut CacheEntry> {
+        let stamp = FileStamp::of(path)?;
+        let key = std::path::absolute(path)?;
+
+        let entry = self
+            .entries
+            .entry(key)
+            .or_insert_with(|| CacheEntry::new(stamp));
+        if entry.stamp != stamp {
+            log::debug!("Invalidating cached hashes for {:?}", path);
+            *entry = CacheEntry::new(stamp);
+            self.dirty = true;
+        }
+        Ok(entry)
+    }
+}
+
+/// Reads and parses the cache file at `path`.
+fn read_cache_file(path: &Path) -> Result<CacheFile> {
+    let file = File::open(path).map_err(Error::on_file(FileAction::Read, path))?;
+    Ok(serde_json::from_reader(BufReader::new(file))?)
+}
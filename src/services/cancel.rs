@@ -0,0 +1,45 @@
+//! Cooperative cancellation of long-running scans and erases.
+//!
+//! A [`CancelToken`] is shared between the code doing the work, which checks
+//! it between files, and whoever may stop it: a Ctrl-C handler, or a button
+//! of a GUI running the scan on another thread.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Flag asking an operation to stop early. Clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Creates a token that is not canceled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks every operation holding this token, or a clone of it, to stop.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Checks whether [`cancel`](Self::cancel) was called.
+    pub fn is_canceled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl From<Arc<AtomicBool>> for CancelToken {
+    /// Wraps a flag the caller already shares, canceling once it is set.
+    fn from(flag: Arc<AtomicBool>) -> Self {
+        Self(flag)
+    }
+}
+
+/// Tokens are equal when they share the same flag.
+impl PartialEq for CancelToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CancelToken {}
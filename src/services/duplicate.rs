@@ -1,23 +1,34 @@
 //! Duplicate detection service.
 //!
 //! Implements a two-pass approach for finding duplicate media files:
-//! 1. **Fast pass**: Group by file size, then SHA256 hash (exact duplicates)
+//! 1. **Fast pass**: Group by file size and a quick hash of the first 64 KiB,
+//!    then SHA256 or BLAKE3 hash (exact duplicates)
 //! 2. **Slow pass**: Perceptual hash comparison (visually similar images/videos)
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::File;
-use std::io::BufWriter;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 use chrono::{DateTime, Utc};
 use image_hasher::ImageHash;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use super::hasher::{self, MediaType};
-use crate::error::Result;
+use super::bktree::BkTree;
+use super::cache::HashCache;
+use super::cancel::CancelToken;
+use super::hasher::{self, HashAlgorithm, MediaType, PerceptualConfig, VideoSignature};
+use super::units::format_size;
+use crate::error::{Error, FileAction, Result};
 
 /// Represents a group of duplicate files.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct DuplicateGroup {
     /// The files that are duplicates of each other.
     pub files: Vec<PathBuf>,
@@ -29,10 +40,16 @@ pub struct DuplicateGroup {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum DuplicateType {
-    /// Exact byte-for-byte duplicates (same SHA256 hash).
+    /// Exact byte-for-byte duplicates (same SHA256 or BLAKE3 hash).
     Exact,
     /// Visually similar media (similar perceptual hash).
     Perceptual,
+    /// Exact duplicates merged with visually similar media.
+    Mixed,
+    /// Files of the same size whose first and last bytes match, found by a
+    /// quick scan without reading them in full. Never erased, since they may
+    /// still differ in between.
+    Probable,
 }
 
 /// Filter for which media types to scan.
@@ -47,6 +64,9 @@ pub enum MediaFilter {
     VideosOnly,
     /// Scan only audio files.
     AudioOnly,
+    /// Scan every file regardless of type for exact duplicates only, skipping
+    /// perceptual analysis.
+    AnyFile,
 }
 
 impl MediaFilter {
@@ -54,7 +74,7 @@ impl MediaFilter {
     pub fn includes(&self, path: &Path) -> bool {
         let media_type = hasher::get_media_type(path);
         match self {
-            MediaFilter::All => true,
+            MediaFilter::All | MediaFilter::AnyFile => true,
             MediaFilter::ImagesOnly => media_type == MediaType::Image,
             MediaFilter::VideosOnly => media_type == MediaType::Video,
             MediaFilter::AudioOnly => media_type == MediaType::Audio,
@@ -73,6 +93,443 @@ impl MediaFilter {
             MediaFilter::ImagesOnly => media_type == MediaType::Image,
             MediaFilter::VideosOnly => media_type == MediaType::Video,
             MediaFilter::AudioOnly => media_type == MediaType::Audio,
+            MediaFilter::AnyFile => false,
+        }
+    }
+
+    /// Whether the filter runs the perceptual pass at all.
+    pub fn uses_perceptual(&self) -> bool {
+        *self != MediaFilter::AnyFile
+    }
+}
+
+/// Order of the groups in a report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupOrder {
+    /// Largest reclaimable space first.
+    Size,
+    /// By the path of the original to keep.
+    Path,
+    /// Most files first.
+    Count,
+}
+
+/// How perceptually similar files are joined into groups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Grouping {
+    /// Join every pair of similar files, so similarity chains: A is grouped
+    /// with C when both are similar to B, however far apart they are
+    /// (default).
+    #[default]
+    Transitive,
+    /// Only group files that are all similar to each other, built around a
+    /// representative, so chains are split.
+    Pairwise,
+}
+
+impl std::fmt::Display for Grouping {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Grouping::Transitive => write!(f, "transitive"),
+            Grouping::Pairwise => write!(f, "pairwise"),
+        }
+    }
+}
+
+/// How files of the same size are split before they are hashed in full.
+///
+/// Pre-screening only rules files out: every match is still confirmed with
+/// the full [`HashAlgorithm`], so it never changes which files are grouped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Prescreen {
+    /// Hash the first [`hasher::PARTIAL_HASH_LEN`] bytes of larger files
+    /// (default).
+    #[default]
+    Partial,
+    /// Also split smaller files by a CRC32 of their whole content, which
+    /// pays off when many distinct files share a size, such as
+    /// fixed-resolution thumbnails.
+    Crc32,
+    /// Hash every file sharing a size in full right away.
+    Off,
+}
+
+/// Similarity thresholds of single media types, used instead of the global
+/// threshold for files of that type.
+///
+/// Files with unrecognised extensions decoded as images use the image
+/// threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct MediaThresholds {
+    /// Threshold for images.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<u32>,
+    /// Threshold for videos.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video: Option<u32>,
+    /// Threshold for audio files. Spectrogram hashes often need a looser one
+    /// than photos.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio: Option<u32>,
+}
+
+impl MediaThresholds {
+    /// Checks if no media type has a threshold of its own.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns the threshold for files of `media_type`: its own if set,
+    /// otherwise `threshold`.
+    pub fn threshold_for(&self, media_type: MediaType, threshold: u32) -> u32 {
+        let own = match media_type {
+            MediaType::Image | MediaType::Unknown => self.image,
+            MediaType::Video => self.video,
+            MediaType::Audio => self.audio,
+        };
+        own.unwrap_or(threshold)
+    }
+
+    /// Checks that every threshold is within the accepted range.
+    fn validate(&self) -> Result<()> {
+        match [self.image, self.video, self.audio]
+            .into_iter()
+            .flatten()
+            .find(|&threshold| threshold > hasher::MAX_SIMILARITY_THRESHOLD)
+        {
+            Some(threshold) => Err(Error::InvalidThreshold(threshold)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Display for MediaThresholds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let thresholds: Vec<String> = [
+            ("images", self.image),
+            ("videos", self.video),
+            ("audio", self.audio),
+        ]
+        .into_iter()
+        .filter_map(|(name, threshold)| Some(format!("{} {}", name, threshold?)))
+        .collect();
+        write!(f, "{}", thresholds.join(", "))
+    }
+}
+
+/// How the file to keep is chosen within each duplicate group.
+///
+/// Ties are broken alphabetically so the choice is deterministic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OriginalStrategy {
+    /// Keep the file whose path sorts first (default).
+    #[default]
+    FirstAlphabetical,
+    /// Keep the file with the oldest modification time.
+    OldestMtime,
+    /// Keep the file with the newest modification time.
+    NewestMtime,
+    /// Keep the file with the shortest path.
+    ShortestPath,
+    /// Keep the file with the longest path.
+    LongestPath,
+    /// Keep the image with the most pixels, so downscaled copies are the
+    /// ones erased. Groups without readable image dimensions fall back to
+    /// alphabetical order.
+    HighestResolution,
+}
+
+impl OriginalStrategy {
+    /// Moves the file to keep to the front of `files`, leaving the others in
+    /// alphabetical order behind it.
+    pub fn order(self, files: &mut [PathBuf]) {
+        files.sort();
+
+        // `min_by_key` returns the first minimum, so ties keep alphabetical order
+        let original = match self {
+            OriginalStrategy::FirstAlphabetical => None,
+            OriginalStrategy::OldestMtime => position_min_by_key(files, |f| {
+                let mtime = modified(f);
+                (mtime.is_none(), mtime)
+            }),
+            OriginalStrategy::NewestMtime => {
+                position_min_by_key(files, |f| std::cmp::Reverse(modified(f)))
+            }
+            OriginalStrategy::ShortestPath => {
+                position_min_by_key(files, |f| f.as_os_str().len())
+            }
+            OriginalStrategy::LongestPath => {
+                position_min_by_key(files, |f| std::cmp::Reverse(f.as_os_str().len()))
+            }
+            OriginalStrategy::HighestResolution => {
+                position_min_by_key(files, |f| std::cmp::Reverse(pixel_count(f)))
+            }
+        };
+
+        if let Some(index) = original {
+            files[..=index].rotate_right(1);
+        }
+    }
+
+    /// Like [`order`](Self::order), but keeps a file under the first of
+    /// `preferred_dirs` holding one of `files`, chosen among those by this
+    /// strategy. Files outside every preferred directory are only kept when
+    /// none is inside one.
+    pub fn order_preferring(self, files: &mut [PathBuf], preferred_dirs: &[PathBuf]) {
+        self.order(files);
+
+        let preferred_dirs: Vec<PathBuf> =
+            preferred_dirs.iter().map(|d| absolute_path(d)).collect();
+        let rank = |file: &Path| {
+            let file = absolute_path(file);
+            preferred_dirs.iter().position(|dir| file.starts_with(dir))
+        };
+        let Some(best) = files.iter().filter_map(|f| rank(f)).min() else {
+            return;
+        };
+        if rank(&files[0]) == Some(best) {
+            return;
+        }
+
+        let mut candidates: Vec<PathBuf> =
+            files.iter().filter(|f| rank(f) == Some(best)).cloned().collect();
+        self.order(&mut candidates);
+        files.sort();
+        if let Some(index) = files.iter().position(|f| *f == candidates[0]) {
+            files[..=index].rotate_right(1);
+        }
+    }
+}
+
+/// Returns the index of the first element with the smallest key.
+fn position_min_by_key<K: Ord>(files: &[PathBuf], key: impl Fn(&Path) -> K) -> Option<usize> {
+    files
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, f)| key(f))
+        .map(|(index, _)| index)
+}
+
+/// Returns the modification time of a file, if it can be read.
+fn modified(path: &Path) -> Option<std::time::SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Returns the width times the height of an image, read from its header
+/// without decoding it. `None` for other media and unreadable images.
+fn pixel_count(path: &Path) -> Option<u64> {
+    if hasher::get_media_type(path) != MediaType::Image {
+        return None;
+    }
+    let (width, height) = image::image_dimensions(path).ok()?;
+    Some(u64::from(width) * u64::from(height))
+}
+
+/// Options controlling how duplicates are detected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectionOptions {
+    /// Which media types to scan.
+    pub media_filter: MediaFilter,
+    /// Maximum Hamming distance for two perceptual hashes to be considered
+    /// similar, on the scale of [`hasher::HASH_SIZE`] hashes.
+    pub threshold: u32,
+    /// Thresholds of single media types, used instead of `threshold` for
+    /// files of that type.
+    pub media_thresholds: MediaThresholds,
+    /// Size and algorithm of perceptual hashes.
+    pub perceptual: PerceptualConfig,
+    /// How similar files are joined into groups.
+    pub grouping: Grouping,
+    /// Attempt perceptual hashing of files with unrecognised extensions
+    /// (e.g. `.jfif` downloads) by decoding their content as an image.
+    /// Only applies when images are part of the scan.
+    pub decode_unknown: bool,
+    /// Keep Apple Live Photos (an image plus a `.mov` sharing its base name)
+    /// together, so erasing a duplicate image also erases its paired video.
+    pub live_photos: bool,
+    /// Confirm perceptual matches with a larger hash before reporting them,
+    /// trading extra decoding time for fewer false positives.
+    pub strict_perceptual: bool,
+    /// Skip perceptual analysis, so only exact duplicates are looked for
+    /// while every media type is still scanned.
+    pub exact_only: bool,
+    /// How the file to keep is chosen within each group.
+    pub original_strategy: OriginalStrategy,
+    /// Directories whose files are kept over files elsewhere, highest
+    /// priority first, overriding `original_strategy`.
+    pub preferred_dirs: Vec<PathBuf>,
+    /// Hash used to confirm exact duplicates.
+    pub hash_algorithm: HashAlgorithm,
+    /// How files of the same size are split before being hashed in full.
+    pub prescreen: Prescreen,
+    /// Files smaller than this many bytes are skipped.
+    pub min_size: Option<u64>,
+    /// Files larger than this many bytes are skipped.
+    pub max_size: Option<u64>,
+    /// Skip zero-byte files, which are all identical and so would otherwise
+    /// form one large group. Defaults to `true`.
+    pub ignore_empty: bool,
+    /// Keep the hashes needed to rebuild the groups in the report, so they
+    /// can be regrouped with another threshold without rescanning.
+    pub keep_hashes: bool,
+    /// Match videos by the frames where their scenes change instead of
+    /// frames at fixed positions, so trimmed copies are found. Slower, since
+    /// whole videos are decoded; the signatures are neither cached nor kept
+    /// by `keep_hashes`.
+    pub video_scenes: bool,
+    /// Also match videos by a fingerprint of their soundtrack, so re-encodes
+    /// that look too different still match when they sound alike. Costs an
+    /// extra FFmpeg pass per video; the fingerprints are neither cached nor
+    /// kept by `keep_hashes`. Ignored for videos matched by `video_scenes`.
+    pub video_audio_match: bool,
+    /// Number of bytes hashed at each end of a file for a quick scan. When
+    /// set, files of the same size whose ends match are reported as
+    /// [`DuplicateType::Probable`] without being hashed in full, and
+    /// perceptual analysis is skipped.
+    pub quick: Option<u64>,
+    /// Stops detection early once canceled: no further file is read, and
+    /// the report only holds the groups among the files already hashed,
+    /// with [`DuplicateReport::canceled`] set.
+    pub cancel: Option<CancelToken>,
+}
+
+impl Default for DetectionOptions {
+    fn default() -> Self {
+        Self {
+            media_filter: MediaFilter::All,
+            threshold: hasher::DEFAULT_SIMILARITY_THRESHOLD,
+            media_thresholds: MediaThresholds::default(),
+            perceptual: PerceptualConfig::default(),
+            grouping: Grouping::default(),
+            decode_unknown: false,
+            live_photos: false,
+            strict_perceptual: false,
+            exact_only: false,
+            original_strategy: OriginalStrategy::default(),
+            preferred_dirs: Vec::new(),
+            hash_algorithm: HashAlgorithm::default(),
+            prescreen: Prescreen::default(),
+            min_size: None,
+            max_size: None,
+            ignore_empty: true,
+            keep_hashes: false,
+            video_scenes: false,
+            video_audio_match: false,
+            quick: None,
+            cancel: None,
+        }
+    }
+}
+
+impl From<MediaFilter> for DetectionOptions {
+    fn from(media_filter: MediaFilter) -> Self {
+        Self {
+            media_filter,
+            ..Self::default()
+        }
+    }
+}
+
+impl DetectionOptions {
+    /// Checks that the options are within their accepted ranges.
+    pub fn validate(&self) -> Result<()> {
+        if self.threshold > hasher::MAX_SIMILARITY_THRESHOLD {
+            return Err(Error::InvalidThreshold(self.threshold));
+        }
+        self.media_thresholds.validate()?;
+        if !(hasher::MIN_HASH_SIZE..=hasher::MAX_HASH_SIZE).contains(&self.perceptual.hash_size) {
+            return Err(Error::InvalidHashSize(self.perceptual.hash_size));
+        }
+        if let (Some(min), Some(max)) = (self.min_size, self.max_size)
+            && min > max
+        {
+            return Err(Error::InvalidSizeRange { min, max });
+        }
+        Ok(())
+    }
+
+    /// Moves the file to keep to the front of `files`, preferring files under
+    /// `preferred_dirs` and then choosing by `original_strategy`.
+    pub fn order_original(&self, files: &mut [PathBuf]) {
+        self.original_strategy.order_preferring(files, &self.preferred_dirs);
+    }
+
+    /// Checks whether detection was canceled through `cancel`.
+    pub fn is_canceled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancelToken::is_canceled)
+    }
+
+    /// Whether the perceptual pass runs at all, which neither a quick scan,
+    /// `exact_only` nor [`MediaFilter::AnyFile`] does.
+    pub fn uses_perceptual(&self) -> bool {
+        self.quick.is_none() && !self.exact_only && self.media_filter.uses_perceptual()
+    }
+
+    /// Returns the threshold scaled to the configured perceptual hash size.
+    pub fn perceptual_threshold(&self) -> u32 {
+        self.perceptual.scale_threshold(self.threshold)
+    }
+
+    /// Returns the threshold for files of `media_type`: its own if set,
+    /// otherwise `threshold`. Unknown files are decoded as images.
+    pub fn threshold_for(&self, media_type: MediaType) -> u32 {
+        self.media_thresholds.threshold_for(media_type, self.threshold)
+    }
+
+    /// Checks if a file of `size` bytes is within the configured size range,
+    /// and not empty if empty files are ignored.
+    pub fn includes_size(&self, size: u64) -> bool {
+        !(self.ignore_empty && size == 0)
+            && self.min_size.is_none_or(|min| size >= min)
+            && self.max_size.is_none_or(|max| size <= max)
+    }
+
+    /// Checks if a file should be included in the scan.
+    pub fn includes(&self, path: &Path) -> bool {
+        self.media_filter.includes(path) || self.decodes_unknown(path)
+    }
+
+    /// Checks if a file should be processed for perceptual hashing.
+    pub fn includes_for_perceptual(&self, path: &Path) -> bool {
+        self.media_filter.includes_for_perceptual(path) || self.decodes_unknown(path)
+    }
+
+    /// Whether `path` has an unrecognised extension that should still be
+    /// decoded as an image.
+    fn decodes_unknown(&self, path: &Path) -> bool {
+        self.decode_unknown
+            && matches!(
+                self.media_filter,
+                MediaFilter::All | MediaFilter::ImagesOnly
+            )
+            && hasher::get_media_type(path) == MediaType::Unknown
+    }
+}
+
+/// Threshold used by the [`DetectionPreset::Social`] preset.
+pub const SOCIAL_PRESET_THRESHOLD: u32 = 16;
+
+/// Named bundles of detection options tuned for common goals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectionPreset {
+    /// Finds the originals of images that were recompressed and resized by
+    /// messaging apps (WhatsApp, Telegram, ...).
+    ///
+    /// Sets `threshold` to [`SOCIAL_PRESET_THRESHOLD`] and enables
+    /// `decode_unknown` so re-encoded downloads such as `.jfif` files are
+    /// compared with their originals.
+    Social,
+}
+
+impl DetectionPreset {
+    /// Applies the preset on top of the given options.
+    pub fn apply(self, options: &mut DetectionOptions) {
+        match self {
+            DetectionPreset::Social => {
+                options.threshold = SOCIAL_PRESET_THRESHOLD;
+                options.decode_unknown = true;
+            }
         }
     }
 }
@@ -87,9 +544,335 @@ pub struct DuplicateEntry {
     pub duplicates: Vec<PathBuf>,
     /// The type of duplication.
     pub duplicate_type: DuplicateType,
+    /// Combined size in bytes of the duplicate files, i.e. the space freed by erasing them.
+    #[serde(default)]
+    pub reclaimable_bytes: u64,
+    /// Hamming distance from each duplicate's perceptual hash to the
+    /// original's; 0 for exact copies. Duplicates without a perceptual hash
+    /// are left out.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub distances: BTreeMap<PathBuf, u32>,
+    /// Exact hash of each file of an exact entry, computed with the file's
+    /// hash algorithm, so erase can check that a file did not change since
+    /// it was scanned.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub checksums: BTreeMap<PathBuf, String>,
+    /// Live Photo companion video of each file of the entry that has one,
+    /// recorded by `scan --live-photos`. A duplicate's video is erased
+    /// together with it, and the original's video is kept.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub paired: BTreeMap<PathBuf, PathBuf>,
+    /// Whether the duplicates are still waiting to be erased.
+    #[serde(default, skip_serializing_if = "EntryStatus::is_pending")]
+    pub status: EntryStatus,
+    /// When the duplicates were erased, for entries kept as a record by
+    /// `erase --keep-manifest`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub erased_at: Option<DateTime<Utc>>,
+}
+
+impl DuplicateEntry {
+    /// Checks if the duplicates of this entry were already erased.
+    pub fn is_erased(&self) -> bool {
+        self.status == EntryStatus::Erased
+    }
+
+    /// Checks if all files of this entry are byte-identical, rather than
+    /// only similar.
+    pub fn is_exact(&self) -> bool {
+        self.duplicate_type == DuplicateType::Exact
+    }
+
+    /// Checks if the files of this entry were only matched by a quick scan,
+    /// so they may still differ.
+    pub fn is_probable(&self) -> bool {
+        self.duplicate_type == DuplicateType::Probable
+    }
+
+    /// Marks the duplicates of this entry as erased at `erased_at`.
+    pub fn mark_erased(mut self, erased_at: DateTime<Utc>) -> Self {
+        self.status = EntryStatus::Erased;
+        self.erased_at = Some(erased_at);
+        self
+    }
+
+    /// Returns the files erased with this entry: every duplicate, followed by
+    /// its paired Live Photo video if it has one.
+    pub fn files_to_erase(&self) -> Vec<PathBuf> {
+        self.duplicates
+            .iter()
+            .flat_map(|file| std::iter::once(file).chain(self.paired.get(file)))
+            .cloned()
+            .collect()
+    }
+
+    /// Recomputes the space freed by erasing the entry from the sizes of its
+    /// files on disk.
+    fn refresh_reclaimable_bytes(&mut self) {
+        self.reclaimable_bytes = self
+            .files_to_erase()
+            .iter()
+            .map(|f| hasher::file_size(f).unwrap_or(0))
+            .sum();
+    }
+
+    /// Makes `file`, one of the duplicates, the original to keep, and the
+    /// current original a duplicate. Does nothing if `file` is not listed.
+    pub fn set_original(&mut self, file: &Path) {
+        let Some(position) = self.duplicates.iter().position(|f| f == file) else {
+            return;
+        };
+        let kept = std::mem::replace(&mut self.duplicates[position], self.original.clone());
+        self.original = kept;
+        // Distances were measured to the previous original
+        self.distances.clear();
+        self.refresh_reclaimable_bytes();
+    }
+
+    /// Takes the duplicates under any of `protected_dirs` off the list, so
+    /// erasing the entry leaves them on disk. Returns the files taken off.
+    pub fn keep_protected(&mut self, protected_dirs: &[PathBuf]) -> Vec<PathBuf> {
+        let protected_dirs: Vec<PathBuf> =
+            protected_dirs.iter().map(|d| absolute_path(d)).collect();
+        let (kept, duplicates): (Vec<_>, Vec<_>) = std::mem::take(&mut self.duplicates)
+            .into_iter()
+            .partition(|file| {
+                let file = absolute_path(file);
+                protected_dirs.iter().any(|dir| file.starts_with(dir))
+            });
+        self.duplicates = duplicates;
+        if !kept.is_empty() {
+            for file in &kept {
+                self.distances.remove(file);
+            }
+            self.refresh_reclaimable_bytes();
+        }
+        kept
+    }
+
+    /// Makes the first duplicate still on disk the original if the original
+    /// is gone, so erasing the entry never removes every copy. Returns
+    /// whether the original was replaced.
+    pub fn ensure_original_exists(&mut self) -> bool {
+        if self.original.exists() {
+            return false;
+        }
+        let Some(file) = self.duplicates.iter().find(|f| f.exists()).cloned() else {
+            return false;
+        };
+        log::warn!(
+            "Original {:?} no longer exists; keeping {:?} instead",
+            self.original,
+            file
+        );
+        self.set_original(&file);
+        true
+    }
+}
+
+/// Whether the duplicates of an entry are still on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryStatus {
+    /// The duplicates are waiting to be erased.
+    #[default]
+    Pending,
+    /// The duplicates were erased; the entry is only kept as a record.
+    Erased,
+}
+
+impl EntryStatus {
+    fn is_pending(&self) -> bool {
+        *self == Self::Pending
+    }
+}
+
+/// What [`DuplicatesFile::carry_over`] kept from the previous manifest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ManifestUpdate {
+    /// Groups that were already listed, keeping their original.
+    pub kept: usize,
+    /// Groups that were not listed before.
+    pub added: usize,
+    /// Previously listed groups that are gone, because their files were
+    /// erased, removed or changed.
+    pub resolved: usize,
+}
+
+/// What [`DuplicatesFile::merge`] did to the groups of the merged manifests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ManifestMerge {
+    /// Groups folded into another group because they shared a file with it.
+    pub joined: usize,
+    /// Joined groups whose manifests kept different originals, so the
+    /// original was chosen again.
+    pub conflicts: usize,
+}
+
+/// Joins entries sharing files into one entry listing all their files.
+///
+/// The original the entries agree on is kept; otherwise one is chosen by
+/// `strategy` and counted in `summary.conflicts`. Distances are kept from the
+/// entries measured against the kept original.
+fn join_entries(
+    mut entries: Vec<DuplicateEntry>,
+    strategy: OriginalStrategy,
+    preferred_dirs: &[PathBuf],
+    summary: &mut ManifestMerge,
+) -> DuplicateEntry {
+    if entries.len() == 1 {
+        return entries.remove(0);
+    }
+
+    let mut files: Vec<PathBuf> = Vec::new();
+    for entry in &entries {
+        for file in std::iter::once(&entry.original).chain(&entry.duplicates) {
+            if !files.contains(file) {
+                files.push(file.clone());
+            }
+        }
+    }
+
+    let original = if entries.iter().all(|e| e.original == entries[0].original) {
+        entries[0].original.clone()
+    } else {
+        summary.conflicts += 1;
+        let mut ordered = files.clone();
+        strategy.order_preferring(&mut ordered, preferred_dirs);
+        ordered.swap_remove(0)
+    };
+
+    let first_type = entries[0].duplicate_type;
+    let duplicate_type = if entries.iter().all(|e| e.duplicate_type == first_type) {
+        first_type
+    } else if entries
+        .iter()
+        .any(|e| e.duplicate_type == DuplicateType::Probable)
+    {
+        // An unconfirmed match leaves the whole group unconfirmed
+        DuplicateType::Probable
+    } else {
+        DuplicateType::Mixed
+    };
+
+    let mut distances = BTreeMap::new();
+    let mut checksums = BTreeMap::new();
+    let mut paired = BTreeMap::new();
+    for entry in entries {
+        if entry.original == original {
+            distances.extend(entry.distances);
+        }
+        checksums.extend(entry.checksums);
+        paired.extend(entry.paired);
+    }
+    if duplicate_type == DuplicateType::Exact {
+        // Every file is a byte-identical copy of every other
+        distances = files.iter().map(|f| (f.clone(), 0)).collect();
+    }
+
+    files.retain(|f| *f != original);
+    distances.retain(|f, _| files.contains(f));
+    let mut entry = DuplicateEntry {
+        original,
+        duplicates: files,
+        duplicate_type,
+        reclaimable_bytes: 0,
+        distances,
+        checksums,
+        paired,
+        status: EntryStatus::Pending,
+        erased_at: None,
+    };
+    entry.refresh_reclaimable_bytes();
+    entry
+}
+
+/// Returns the deepest directory containing both `a` and `b`, or `None` if
+/// they share no ancestor, such as paths on different drives.
+fn common_ancestor(a: &Path, b: &Path) -> Option<PathBuf> {
+    let common: PathBuf = a
+        .components()
+        .zip(b.components())
+        .take_while(|(x, y)| x == y)
+        .map(|(x, _)| x)
+        .collect();
+    (common.components().next().is_some()).then_some(common)
+}
+
+/// Reads a JSON Lines manifest into the value of the same manifest in JSON:
+/// the first line holds every field but the entries, and each further
+/// non-empty line one entry.
+fn read_json_lines(reader: impl BufRead) -> Result<serde_json::Value> {
+    let mut lines = reader.lines();
+    let mut header: serde_json::Value = match lines.next() {
+        Some(line) => serde_json::from_str(&line?)?,
+        None => serde_json::Value::Null,
+    };
+    let mut entries = Vec::new();
+    for line in lines {
+        let line = line?;
+        if !line.trim().is_empty() {
+            entries.push(serde_json::from_str(&line)?);
+        }
+    }
+    if let Some(fields) = header.as_object_mut() {
+        fields.insert("entries".to_string(), serde_json::Value::Array(entries));
+    }
+    Ok(header)
+}
+
+/// Hashes of a file kept in the report to rebuild groups without rescanning.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileHashes {
+    /// Exact hash, recorded for files in exact duplicate groups.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exact: Option<String>,
+    /// Base64-encoded perceptual hash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub perceptual: Option<String>,
+}
+
+/// Default file name of the duplicates manifest written by scan and read by
+/// erase, stats and clean.
+pub const DUPLICATES_FILENAME: &str = "duplicates.json";
+
+/// Version written to new duplicates files.
+pub const DUPLICATES_FILE_VERSION: &str = "1.0";
+
+/// File format of a duplicates manifest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ManifestFormat {
+    /// Pretty-printed JSON.
+    #[default]
+    Json,
+    /// YAML.
+    Yaml,
+    /// TOML.
+    Toml,
+    /// JSON Lines: a first line with every field but the entries, then one
+    /// line per entry, so large manifests can be processed a group at a time.
+    JsonLines,
+}
+
+impl ManifestFormat {
+    /// Returns the format of the manifest at `path` from its extension:
+    /// YAML for `.yaml` and `.yml`, TOML for `.toml`, JSON Lines for
+    /// `.jsonl` and `.ndjson`, and JSON otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("yaml" | "yml") => Self::Yaml,
+            Some("toml") => Self::Toml,
+            Some("jsonl" | "ndjson") => Self::JsonLines,
+            _ => Self::Json,
+        }
+    }
 }
 
-/// The duplicates file structure that will be saved to JSON.
+/// The duplicates file structure that will be saved to JSON, YAML, TOML or
+/// JSON Lines.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DuplicatesFile {
     /// Version of the file format.
@@ -98,63 +881,435 @@ pub struct DuplicatesFile {
     pub scanned_at: DateTime<Utc>,
     /// Total number of files scanned.
     pub total_files_scanned: usize,
+    /// Perceptual similarity threshold used for the scan.
+    #[serde(default)]
+    pub similarity_threshold: Option<u32>,
+    /// Thresholds of single media types used instead of
+    /// `similarity_threshold`.
+    #[serde(default, skip_serializing_if = "MediaThresholds::is_empty")]
+    pub media_thresholds: MediaThresholds,
+    /// Hash used to confirm exact duplicates. Manifests written before this
+    /// field existed always used SHA-256.
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+    /// Size and algorithm of the perceptual hashes. Manifests written before
+    /// this field existed always used 16x16 double-gradient hashes.
+    #[serde(default)]
+    pub perceptual_config: PerceptualConfig,
+    /// How similar files were grouped. Manifests written before this field
+    /// existed always grouped transitively.
+    #[serde(default)]
+    pub grouping: Grouping,
+    /// Whether videos were matched by the frames where their scenes change,
+    /// as by `scan --video-scenes`, rather than by a single perceptual hash.
+    #[serde(default)]
+    pub video_scenes: bool,
+    /// Whether videos also matched when their soundtracks sound alike, as by
+    /// `scan --video-audio-match`.
+    #[serde(default)]
+    pub video_audio_match: bool,
     /// Number of duplicate groups found.
     pub duplicate_groups: usize,
     /// Total number of duplicate files (to be deleted).
     pub total_duplicates: usize,
+    /// Combined size in bytes of all duplicates, i.e. the space erasing frees.
+    #[serde(default)]
+    pub reclaimable_bytes: u64,
     /// The duplicate entries.
     pub entries: Vec<DuplicateEntry>,
+    /// Hashes stored by `scan --store-hashes`, used by `regroup`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hashes: BTreeMap<PathBuf, FileHashes>,
+    /// Absolute path of the scanned directory. Relative paths in files
+    /// written before paths were stored absolute are resolved against it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<PathBuf>,
 }
 
 impl DuplicatesFile {
     /// Creates a new DuplicatesFile from a DuplicateReport.
+    ///
+    /// Paths are stored absolute, so the file can be used from any working
+    /// directory.
     pub fn from_report(report: &DuplicateReport) -> Self {
         let entries: Vec<DuplicateEntry> = report
             .groups
             .iter()
             .map(|group| {
-                let mut files = group.files.clone();
-                // First file is the original to keep
-                let original = files.remove(0);
-                DuplicateEntry {
-                    original,
-                    duplicates: files,
+                let files = &group.files;
+                let paired: BTreeMap<PathBuf, PathBuf> = files
+                    .iter()
+                    .filter_map(|f| {
+                        let video = report.live_photo_pairs.get(f)?;
+                        Some((absolute_path(f), absolute_path(video)))
+                    })
+                    .collect();
+                let reclaimable_bytes = report.group_reclaimable_bytes(group);
+                let distances = files[1..]
+                    .iter()
+                    .filter_map(|f| Some((absolute_path(f), *report.distances.get(f)?)))
+                    .collect();
+                let checksums = if group.duplicate_type == DuplicateType::Exact {
+                    files
+                        .iter()
+                        .filter_map(|f| Some((absolute_path(f), report.checksums.get(f)?.clone())))
+                        .collect()
+                } else {
+                    BTreeMap::new()
+                };
+                // First file is the original to keep, as chosen by the `OriginalStrategy`
+                let mut entry = DuplicateEntry {
+                    original: absolute_path(&files[0]),
+                    duplicates: files[1..].iter().map(|f| absolute_path(f)).collect(),
                     duplicate_type: group.duplicate_type,
-                }
+                    reclaimable_bytes,
+                    distances,
+                    checksums,
+                    paired,
+                    status: EntryStatus::Pending,
+                    erased_at: None,
+                };
+                // The original may have been removed since the scan read it
+                entry.ensure_original_exists();
+                entry
             })
             .collect();
 
         let total_duplicates = entries.iter().map(|e| e.duplicates.len()).sum();
+        let reclaimable_bytes = entries.iter().map(|e| e.reclaimable_bytes).sum();
 
         Self {
-            version: "1.0".to_string(),
+            version: DUPLICATES_FILE_VERSION.to_string(),
             scanned_at: Utc::now(),
             total_files_scanned: report.total_files,
+            similarity_threshold: Some(report.threshold),
+            media_thresholds: report.media_thresholds,
+            hash_algorithm: report.hash_algorithm,
+            perceptual_config: report.perceptual_config,
+            grouping: report.grouping,
+            video_scenes: report.video_scenes,
+            video_audio_match: report.video_audio_match,
             duplicate_groups: report.groups.len(),
             total_duplicates,
+            reclaimable_bytes,
             entries,
+            hashes: report
+                .hashes
+                .iter()
+                .map(|(path, hashes)| (absolute_path(path), hashes.clone()))
+                .collect(),
+            root: report.root.as_deref().map(absolute_path),
+        }
+    }
+
+    /// Recomputes the group, duplicate and reclaimable totals from the
+    /// entries that are not erased yet.
+    pub fn refresh_totals(&mut self) {
+        self.duplicate_groups = self.pending_entries().count();
+        self.total_duplicates = self.pending_entries().map(|e| e.duplicates.len()).sum();
+        self.reclaimable_bytes = self.pending_entries().map(|e| e.reclaimable_bytes).sum();
+    }
+
+    /// Carries over what `previous`, an earlier manifest of the same files,
+    /// recorded on top of this freshly scanned one.
+    ///
+    /// Each group containing the original of a pending entry of `previous`
+    /// keeps that file as its original, so choices made since the last scan
+    /// stick. Erased entries of `previous` are kept as a record. Totals are
+    /// refreshed; everything else, such as `scanned_at`, stays as scanned.
+    pub fn carry_over(&mut self, previous: DuplicatesFile) -> ManifestUpdate {
+        let mut originals: HashSet<PathBuf> =
+            previous.pending_entries().map(|e| e.original.clone()).collect();
+        let mut update = ManifestUpdate::default();
+
+        for entry in &mut self.entries {
+            let known = std::iter::once(&entry.original)
+                .chain(&entry.duplicates)
+                .find(|f| originals.contains(*f))
+                .cloned();
+            match known {
+                Some(original) => {
+                    originals.remove(&original);
+                    entry.set_original(&original);
+                    update.kept += 1;
+                }
+                None => update.added += 1,
+            }
+        }
+        update.resolved = originals.len();
+
+        self.entries
+            .extend(previous.entries.into_iter().filter(DuplicateEntry::is_erased));
+        self.refresh_totals();
+        update
+    }
+
+    /// Merges `others`, manifests of other scans, into this one.
+    ///
+    /// Pending entries sharing a file are joined into one entry listing the
+    /// files of all of them. A joined entry keeps the original its entries
+    /// agree on; when they chose different originals, the file to keep is
+    /// chosen again by `strategy`, preferring files under `preferred_dirs`.
+    /// Erased entries and stored hashes are kept, the earliest scan time and
+    /// the common ancestor of the scanned directories are recorded, and
+    /// totals are refreshed.
+    ///
+    /// Fails if a manifest was hashed with another exact hash algorithm or
+    /// other perceptual hash settings, since its hashes could not be compared.
+    pub fn merge(
+        &mut self,
+        others: Vec<DuplicatesFile>,
+        strategy: OriginalStrategy,
+        preferred_dirs: &[PathBuf],
+    ) -> Result<ManifestMerge> {
+        for other in &others {
+            if other.hash_algorithm != self.hash_algorithm {
+                return Err(Error::HashAlgorithmMismatch {
+                    found: other.hash_algorithm,
+                    expected: self.hash_algorithm,
+                });
+            }
+            if other.perceptual_config != self.perceptual_config {
+                return Err(Error::PerceptualConfigMismatch {
+                    found: other.perceptual_config,
+                    expected: self.perceptual_config,
+                });
+            }
+        }
+
+        let mut summary = ManifestMerge::default();
+        let (mut erased, mut pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(DuplicateEntry::is_erased);
+        for other in others {
+            self.scanned_at = self.scanned_at.min(other.scanned_at);
+            self.total_files_scanned += other.total_files_scanned;
+            if self.similarity_threshold != other.similarity_threshold
+                || self.media_thresholds != other.media_thresholds
+            {
+                self.similarity_threshold = None;
+                self.media_thresholds = MediaThresholds::default();
+            }
+            self.root = match (&self.root, &other.root) {
+                (Some(root), Some(other_root)) => common_ancestor(root, other_root),
+                _ => None,
+            };
+            // Groups of either scan may have been matched by scene or sound
+            self.video_scenes |= other.video_scenes;
+            self.video_audio_match |= other.video_audio_match;
+            self.hashes.extend(other.hashes);
+            for entry in other.entries {
+                if entry.is_erased() {
+                    erased.push(entry);
+                } else {
+                    pending.push(entry);
+                }
+            }
+        }
+
+        // Entries sharing any file end up in the same set
+        let mut parents: Vec<usize> = (0..pending.len()).collect();
+        let mut entry_of_file: HashMap<&Path, usize> = HashMap::new();
+        for (index, entry) in pending.iter().enumerate() {
+            for file in std::iter::once(&entry.original).chain(&entry.duplicates) {
+                match entry_of_file.get(file.as_path()) {
+                    Some(&other) => union(&mut parents, index, other),
+                    None => {
+                        entry_of_file.insert(file.as_path(), index);
+                    }
+                }
+            }
+        }
+        let roots: Vec<usize> = (0..pending.len())
+            .map(|index| find_root(&mut parents, index))
+            .collect();
+
+        let mut joined: Vec<Vec<DuplicateEntry>> = Vec::new();
+        let mut slot_of_root: HashMap<usize, usize> = HashMap::new();
+        for (entry, root) in pending.into_iter().zip(roots) {
+            let slot = *slot_of_root.entry(root).or_insert_with(|| {
+                joined.push(Vec::new());
+                joined.len() - 1
+            });
+            joined[slot].push(entry);
         }
+
+        self.entries = joined
+            .into_iter()
+            .map(|entries| {
+                summary.joined += entries.len() - 1;
+                join_entries(entries, strategy, preferred_dirs, &mut summary)
+            })
+            .collect();
+        self.entries.extend(erased);
+        self.refresh_totals();
+        Ok(summary)
     }
 
-    /// Saves the duplicates file to the specified path.
+    /// Returns the entries whose duplicates are not erased yet.
+    pub fn pending_entries(&self) -> impl Iterator<Item = &DuplicateEntry> {
+        self.entries.iter().filter(|entry| !entry.is_erased())
+    }
+
+    /// Saves the duplicates file to the specified path, in the format its
+    /// extension selects (see [`ManifestFormat::from_path`]).
     pub fn save(&self, path: &Path) -> Result<()> {
-        let file = File::create(path)?;
-        let writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, self)?;
+        self.save_as(path, ManifestFormat::from_path(path))
+    }
+
+    /// Saves the duplicates file to the specified path in `format`.
+    pub fn save_as(&self, path: &Path, format: ManifestFormat) -> Result<()> {
+        let file = File::create(path).map_err(Error::on_file(FileAction::Write, path))?;
+        let mut writer = BufWriter::new(file);
+        self.write_to(&mut writer, format)?;
+        writer.flush().map_err(Error::on_file(FileAction::Write, path))?;
         log::info!("Duplicates file saved to {:?}", path);
         Ok(())
     }
 
-    /// Loads a duplicates file from the specified path.
+    /// Writes the duplicates file to `out` in `format`.
+    pub fn write_to(&self, out: &mut dyn Write, format: ManifestFormat) -> Result<()> {
+        match format {
+            ManifestFormat::Json => serde_json::to_writer_pretty(out, self)?,
+            ManifestFormat::Yaml => serde_yaml::to_writer(out, self)?,
+            ManifestFormat::Toml => out.write_all(toml::to_string_pretty(self)?.as_bytes())?,
+            ManifestFormat::JsonLines => {
+                let mut header = serde_json::to_value(self)?;
+                if let Some(fields) = header.as_object_mut() {
+                    fields.remove("entries");
+                }
+                serde_json::to_writer(&mut *out, &header)?;
+                writeln!(out)?;
+                for entry in &self.entries {
+                    serde_json::to_writer(&mut *out, entry)?;
+                    writeln!(out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads a duplicates file from the specified path, in the format its
+    /// extension selects (see [`ManifestFormat::from_path`]).
+    ///
+    /// Files written with another major version of the format are rejected
+    /// with [`Error::UnsupportedVersion`] before their contents are parsed.
     pub fn load(path: &Path) -> Result<Self> {
-        let file = File::open(path)?;
-        let duplicates: DuplicatesFile = serde_json::from_reader(file)?;
+        let file = File::open(path).map_err(Error::on_file(FileAction::Read, path))?;
+        let reader = BufReader::new(file);
+        let value: serde_json::Value = match ManifestFormat::from_path(path) {
+            ManifestFormat::Json => serde_json::from_reader(reader)?,
+            ManifestFormat::Yaml => serde_yaml::from_reader(reader)?,
+            ManifestFormat::Toml => toml::from_str(&io::read_to_string(reader)?)?,
+            ManifestFormat::JsonLines => read_json_lines(reader)?,
+        };
+
+        let found = match value.get("version") {
+            Some(serde_json::Value::String(version)) => version.clone(),
+            Some(version) => version.to_string(),
+            None => String::from("none"),
+        };
+        if !is_supported_version(&found) {
+            return Err(Error::UnsupportedVersion {
+                found,
+                expected: DUPLICATES_FILE_VERSION.to_string(),
+            });
+        }
+
+        let mut duplicates: DuplicatesFile = serde_json::from_value(value)?;
+        duplicates.resolve_relative_paths();
         Ok(duplicates)
     }
+
+    /// Resolves relative paths against the recorded root, so files written
+    /// with relative paths work from any working directory.
+    ///
+    /// Without a recorded root, relative paths are left as they are.
+    fn resolve_relative_paths(&mut self) {
+        let Some(root) = &self.root else {
+            return;
+        };
+
+        for entry in &mut self.entries {
+            for path in std::iter::once(&mut entry.original).chain(&mut entry.duplicates) {
+                if path.is_relative() {
+                    *path = root.join(&*path);
+                }
+            }
+            entry.distances = std::mem::take(&mut entry.distances)
+                .into_iter()
+                .map(|(path, distance)| (root.join(path), distance))
+                .collect();
+            entry.checksums = std::mem::take(&mut entry.checksums)
+                .into_iter()
+                .map(|(path, checksum)| (root.join(path), checksum))
+                .collect();
+            entry.paired = std::mem::take(&mut entry.paired)
+                .into_iter()
+                .map(|(image, video)| (root.join(image), root.join(video)))
+                .collect();
+        }
+        self.hashes = std::mem::take(&mut self.hashes)
+            .into_iter()
+            .map(|(path, hashes)| (root.join(path), hashes))
+            .collect();
+    }
+
+    /// Returns the listed files, including paired Live Photo videos, that are
+    /// not inside the recorded root.
+    ///
+    /// Always empty when no root is recorded.
+    pub fn paths_outside_root(&self) -> Vec<&Path> {
+        let Some(root) = &self.root else {
+            return Vec::new();
+        };
+
+        self.entries
+            .iter()
+            .flat_map(|entry| {
+                std::iter::once(&entry.original)
+                    .chain(&entry.duplicates)
+                    .chain(entry.paired.values())
+            })
+            .filter(|path| !path.starts_with(root))
+            .map(PathBuf::as_path)
+            .collect()
+    }
+}
+
+/// Drops paths naming a file already listed, such as a symbolic link next to
+/// its target or `./a.png` next to `a.png`, keeping the first path given.
+///
+/// Paths that cannot be canonicalized are compared as given.
+fn dedup_paths(files: &[PathBuf]) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    let unique: Vec<PathBuf> = files
+        .iter()
+        .filter(|path| {
+            let key = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+            seen.insert(key)
+        })
+        .cloned()
+        .collect();
+    if unique.len() < files.len() {
+        log::info!("Skipped {} paths listed more than once", files.len() - unique.len());
+    }
+    unique
+}
+
+/// Returns `path` made absolute against the working directory, or unchanged
+/// if that fails. Symbolic links are not resolved.
+fn absolute_path(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Checks if a duplicates file version shares the major version this build writes.
+fn is_supported_version(version: &str) -> bool {
+    version.split('.').next() == DUPLICATES_FILE_VERSION.split('.').next()
 }
 
 /// Result of duplicate detection.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct DuplicateReport {
     /// Groups of duplicate files found.
     pub groups: Vec<DuplicateGroup>,
@@ -162,11 +1317,100 @@ pub struct DuplicateReport {
     pub total_files: usize,
     /// Number of files that could not be processed.
     pub errors: usize,
+    /// Perceptual similarity threshold used for the scan.
+    pub threshold: u32,
+    /// Thresholds of single media types used instead of `threshold`.
+    pub media_thresholds: MediaThresholds,
+    /// Hash used to confirm exact duplicates.
+    pub hash_algorithm: HashAlgorithm,
+    /// Size and algorithm of the perceptual hashes.
+    pub perceptual_config: PerceptualConfig,
+    /// How similar files were grouped.
+    pub grouping: Grouping,
+    /// Whether videos were matched by scene signature, as set by
+    /// [`DetectionOptions::video_scenes`].
+    pub video_scenes: bool,
+    /// Whether videos also matched by soundtrack, as set by
+    /// [`DetectionOptions::video_audio_match`].
+    pub video_audio_match: bool,
+    /// Sizes of the scanned files in bytes, recorded while grouping by size.
+    pub file_sizes: HashMap<PathBuf, u64>,
+    /// Whether perceptual analysis was skipped, so only exact duplicates were looked for.
+    pub perceptual_skipped: bool,
+    /// Bytes hashed at each end of the files if this was a quick scan, whose
+    /// groups are only probable duplicates.
+    pub quick: Option<u64>,
+    /// Hashes needed to rebuild the groups, if [`DetectionOptions::keep_hashes`] is set.
+    pub hashes: BTreeMap<PathBuf, FileHashes>,
+    /// Hamming distance from each duplicate's perceptual hash to its group's
+    /// original, 0 in exact groups. Files without a perceptual hash are left out.
+    pub distances: HashMap<PathBuf, u32>,
+    /// Exact hash of the files found to be exact duplicates.
+    pub checksums: HashMap<PathBuf, String>,
+    /// Live Photo companion video of each grouped image that has one, if
+    /// [`DetectionOptions::live_photos`] is set.
+    pub live_photo_pairs: HashMap<PathBuf, PathBuf>,
+    /// Directory that was scanned, recorded in the duplicates file. Left
+    /// unset by the detection functions; the scan command fills it in.
+    pub root: Option<PathBuf>,
+    /// Number of paths skipped while collecting the files to scan because
+    /// they could not be read. Left unset by the detection functions; the
+    /// scan command fills it in.
+    pub inaccessible_paths: usize,
+    /// Whether detection was canceled through [`DetectionOptions::cancel`],
+    /// so files were left unread and the groups are partial.
+    pub canceled: bool,
 }
 
 impl DuplicateReport {
-    /// Returns the total number of duplicate files (excluding one original per group).
-    pub fn duplicate_count(&self) -> usize {
+    /// Returns the size of `path` in bytes, reading it from disk only if it
+    /// was not recorded during the scan. Unreadable files count as empty.
+    pub fn file_size(&self, path: &Path) -> u64 {
+        self.file_sizes
+            .get(path)
+            .copied()
+            .unwrap_or_else(|| hasher::file_size(path).unwrap_or(0))
+    }
+
+    /// Returns the combined size of all duplicates (excluding one original
+    /// per group) and their paired Live Photo videos, i.e. the space erasing
+    /// them frees.
+    pub fn reclaimable_bytes(&self) -> u64 {
+        self.groups
+            .iter()
+            .map(|g| self.group_reclaimable_bytes(g))
+            .sum()
+    }
+
+    /// Returns the combined size of the duplicates of `group` and their
+    /// paired Live Photo videos, i.e. the space erasing the group frees.
+    pub fn group_reclaimable_bytes(&self, group: &DuplicateGroup) -> u64 {
+        group
+            .files
+            .iter()
+            .skip(1)
+            .flat_map(|f| std::iter::once(f).chain(self.live_photo_pairs.get(f)))
+            .map(|f| self.file_size(f))
+            .sum()
+    }
+
+    /// Sorts the groups by `order`. Ties are broken by the path of the
+    /// original, so the order is the same on every run.
+    pub fn sort_groups(&mut self, order: GroupOrder) {
+        let mut groups = std::mem::take(&mut self.groups);
+        groups.sort_by_cached_key(|group| {
+            let weight: u64 = match order {
+                GroupOrder::Size => self.group_reclaimable_bytes(group),
+                GroupOrder::Count => group.files.len() as u64,
+                GroupOrder::Path => 0,
+            };
+            (std::cmp::Reverse(weight), group.files.first().cloned())
+        });
+        self.groups = groups;
+    }
+
+    /// Returns the total number of duplicate files (excluding one original per group).
+    pub fn duplicate_count(&self) -> usize {
         self.groups
             .iter()
             .map(|g| g.files.len().saturating_sub(1))
@@ -190,15 +1434,173 @@ impl DuplicateReport {
             .map(|g| g.files.len().saturating_sub(1))
             .sum()
     }
+
+    /// Returns the number of duplicates in groups mixing exact and perceptual matches.
+    pub fn mixed_duplicate_count(&self) -> usize {
+        self.groups
+            .iter()
+            .filter(|g| g.duplicate_type == DuplicateType::Mixed)
+            .map(|g| g.files.len().saturating_sub(1))
+            .sum()
+    }
+
+    /// Returns the number of probable duplicates found by a quick scan.
+    pub fn probable_duplicate_count(&self) -> usize {
+        self.groups
+            .iter()
+            .filter(|g| g.duplicate_type == DuplicateType::Probable)
+            .map(|g| g.files.len().saturating_sub(1))
+            .sum()
+    }
 }
 
-/// Progress callback for duplicate detection.
-/// Called with (current_file_index, total_files, phase_name).
-pub type ProgressCallback = Box<dyn Fn(usize, usize, &str) + Send + Sync>;
+/// A step of duplicate detection reported to a [`ProgressCallback`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressEvent {
+    /// Selecting the files to compare by media type and size.
+    CollectingFiles,
+    /// Computing exact hashes; `done` of `total` files are processed, which
+    /// together hold `bytes` bytes.
+    Hashing {
+        done: usize,
+        total: usize,
+        bytes: u64,
+    },
+    /// Computing perceptual hashes; `done` of `total` files are processed.
+    Analyzing { done: usize, total: usize },
+    /// Combining exact and perceptual matches into the final groups.
+    Merging,
+}
+
+impl ProgressEvent {
+    /// Returns a human-readable name of the phase this event belongs to.
+    pub fn phase(&self) -> &'static str {
+        match self {
+            Self::CollectingFiles => "Collecting files",
+            Self::Hashing { .. } => "Hashing files",
+            Self::Analyzing { .. } => "Analyzing media",
+            Self::Merging => "Merging groups",
+        }
+    }
+}
+
+/// Progress callback for duplicate detection, called with each [`ProgressEvent`].
+pub type ProgressCallback = Box<dyn Fn(ProgressEvent) + Send + Sync>;
+
+/// Percentage of a phase between two [`ProgressLog`] lines.
+pub const PROGRESS_LOG_STEP: usize = 10;
+
+/// Time after which [`ProgressLog`] logs again even if progress is slow.
+pub const PROGRESS_LOG_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Turns [`ProgressEvent`]s into info-level log lines, so runs without a
+/// progress bar, such as `--quiet` runs on a server, still show how far they
+/// got.
+///
+/// A line is logged when a phase starts, every [`PROGRESS_LOG_STEP`] percent
+/// of it, and otherwise at most once per interval.
+#[derive(Debug)]
+pub struct ProgressLog {
+    interval: Duration,
+    last: Mutex<Option<LoggedProgress>>,
+}
+
+/// The progress [`ProgressLog`] last logged.
+#[derive(Debug)]
+struct LoggedProgress {
+    phase: &'static str,
+    step: usize,
+    done: usize,
+    at: Instant,
+}
+
+impl Default for ProgressLog {
+    fn default() -> Self {
+        Self::new(PROGRESS_LOG_INTERVAL)
+    }
+}
+
+impl ProgressLog {
+    /// Creates a log that reports slow progress at most once per `interval`.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// Logs `event` at info level if it is a checkpoint.
+    pub fn record(&self, event: ProgressEvent) {
+        if let Some(line) = self.checkpoint(event) {
+            log::info!("{}", line);
+        }
+    }
+
+    /// Returns the line to log for `event`, or `None` if too little progress
+    /// was made since the last one.
+    pub fn checkpoint(&self, event: ProgressEvent) -> Option<String> {
+        let (done, total, line) = match event {
+            ProgressEvent::Hashing { done, total, bytes } => (
+                done,
+                total,
+                format!(
+                    "{}: {}% ({}/{} files, {})",
+                    event.phase(),
+                    percent(done, total),
+                    done,
+                    total,
+                    format_size(bytes)
+                ),
+            ),
+            ProgressEvent::Analyzing { done, total } => (
+                done,
+                total,
+                format!(
+                    "{}: {}% ({}/{} files)",
+                    event.phase(),
+                    percent(done, total),
+                    done,
+                    total
+                ),
+            ),
+            ProgressEvent::CollectingFiles | ProgressEvent::Merging => {
+                (0, 0, event.phase().to_string())
+            }
+        };
+        let step = percent(done, total) / PROGRESS_LOG_STEP;
+
+        let mut last = self.last.lock().unwrap();
+        let due = match last.as_ref() {
+            Some(last) if last.phase == event.phase() => {
+                step > last.step || (done > last.done && last.at.elapsed() >= self.interval)
+            }
+            _ => true,
+        };
+        if !due {
+            return None;
+        }
+        *last = Some(LoggedProgress {
+            phase: event.phase(),
+            step,
+            done,
+            at: Instant::now(),
+        });
+        Some(line)
+    }
+}
+
+/// Share of `total` that `done` represents, in whole percent.
+fn percent(done: usize, total: usize) -> usize {
+    if total == 0 {
+        100
+    } else {
+        done * 100 / total
+    }
+}
 
 /// Finds duplicate media files using a two-pass approach.
 pub fn find_duplicates(files: &[PathBuf]) -> Result<DuplicateReport> {
-    find_duplicates_with_options(files, None, MediaFilter::All)
+    find_duplicates_with_options(files, None, &DetectionOptions::default())
 }
 
 /// Finds duplicate media files using a two-pass approach with optional progress callback.
@@ -206,89 +1608,439 @@ pub fn find_duplicates_with_progress(
     files: &[PathBuf],
     progress: Option<ProgressCallback>,
 ) -> Result<DuplicateReport> {
-    find_duplicates_with_options(files, progress, MediaFilter::All)
+    find_duplicates_with_options(files, progress, &DetectionOptions::default())
 }
 
 /// Finds duplicate media files with full options.
 pub fn find_duplicates_with_options(
     files: &[PathBuf],
     progress: Option<ProgressCallback>,
-    filter: MediaFilter,
+    options: &DetectionOptions,
+) -> Result<DuplicateReport> {
+    find_duplicates_with_cache(files, progress, options, &mut HashCache::disabled())
+}
+
+/// Finds duplicate media files, reusing hashes from `cache` for unchanged files.
+///
+/// Newly computed hashes are added to the cache; saving it is left to the caller.
+pub fn find_duplicates_with_cache(
+    files: &[PathBuf],
+    progress: Option<ProgressCallback>,
+    options: &DetectionOptions,
+    cache: &mut HashCache,
 ) -> Result<DuplicateReport> {
+    find_duplicates_detailed(files, progress, options, cache).map(|(report, _)| report)
+}
+
+/// Finds duplicate media files like [`find_duplicates_with_cache`], also
+/// returning every file that could not be processed and why.
+///
+/// Failing files are skipped rather than aborting the scan, so the report
+/// holds the partial results for all other files.
+pub fn find_duplicates_detailed(
+    files: &[PathBuf],
+    progress: Option<ProgressCallback>,
+    options: &DetectionOptions,
+    cache: &mut HashCache,
+) -> Result<(DuplicateReport, Vec<(PathBuf, Error)>)> {
+    detect_duplicates(files, progress, options, cache, &mut |_| {})
+}
+
+/// Duplicate groups yielded by [`find_duplicates_iter`] while detection runs
+/// on a background thread.
+pub struct DuplicateGroups {
+    receiver: Receiver<DuplicateGroup>,
+    handle: JoinHandle<Result<DuplicateReport>>,
+}
+
+impl DuplicateGroups {
+    /// Waits for detection to finish and returns the full report, which holds
+    /// every group whether or not it was consumed from the iterator.
+    pub fn finish(self) -> Result<DuplicateReport> {
+        match self.handle.join() {
+            Ok(result) => result,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+}
+
+impl Iterator for DuplicateGroups {
+    type Item = DuplicateGroup;
+
+    fn next(&mut self) -> Option<DuplicateGroup> {
+        self.receiver.recv().ok()
+    }
+}
+
+/// Finds duplicate media files on a background thread, yielding each group
+/// as soon as it is final.
+///
+/// Exact groups that perceptual analysis cannot extend, such as duplicates
+/// of non-media files or every group with `--media any`, are yielded as soon
+/// as they are hashed. The other groups follow once the perceptual pass is
+/// done, and all of them wait for the end when Live Photos are paired.
+/// Groups are yielded in no particular order; call
+/// [`DuplicateGroups::finish`] for the report and any detection error, in
+/// which case the iterator ends early. No hash cache is used.
+pub fn find_duplicates_iter(
+    files: Vec<PathBuf>,
+    progress: Option<ProgressCallback>,
+    options: DetectionOptions,
+) -> DuplicateGroups {
+    let (sender, receiver) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        let mut cache = HashCache::disabled();
+        // The receiver may be gone; the report is still built for `finish`
+        let mut send = |group: &DuplicateGroup| {
+            let _ = sender.send(group.clone());
+        };
+        detect_duplicates(&files, progress, &options, &mut cache, &mut send)
+            .map(|(report, _)| report)
+    });
+
+    DuplicateGroups { receiver, handle }
+}
+
+/// Runs duplicate detection, passing every group to `on_group` as soon as it
+/// is final. See [`find_duplicates_iter`] for when that is.
+fn detect_duplicates(
+    files: &[PathBuf],
+    progress: Option<ProgressCallback>,
+    options: &DetectionOptions,
+    cache: &mut HashCache,
+    on_group: &mut dyn FnMut(&DuplicateGroup),
+) -> Result<(DuplicateReport, Vec<(PathBuf, Error)>)> {
+    options.validate()?;
+
+    if let Some(cb) = progress.as_ref() {
+        cb(ProgressEvent::CollectingFiles);
+    }
+
+    // A file passed twice would otherwise be reported as its own duplicate
+    let files = dedup_paths(files);
+
     // Filter files based on media type if not scanning all
-    let filtered_files: Vec<PathBuf> = if filter == MediaFilter::All {
-        files.to_vec()
-    } else {
+    let filtered_files: Vec<PathBuf> = if matches!(
+        options.media_filter,
+        MediaFilter::All | MediaFilter::AnyFile
+    ) {
         files
-            .iter()
-            .filter(|p| filter.includes(p))
-            .cloned()
-            .collect()
+    } else {
+        files.into_iter().filter(|p| options.includes(p)).collect()
     };
 
     let mut exact_groups: Vec<DuplicateGroup> = Vec::new();
-    let mut errors = 0;
-    let total_files = filtered_files.len();
+    // Exact groups no later pass can change, already passed to `on_group`
+    let mut settled_groups: Vec<DuplicateGroup> = Vec::new();
+    let mut hashes: BTreeMap<PathBuf, FileHashes> = BTreeMap::new();
+    let mut checksums: HashMap<PathBuf, String> = HashMap::new();
+    let mut failures: Vec<(PathBuf, Error)> = Vec::new();
 
-    log::info!("Starting duplicate detection for {} files", total_files);
+    log::info!(
+        "Starting duplicate detection for {} files",
+        filtered_files.len()
+    );
 
     // Pass 1: Group by file size
     log::debug!("Pass 1: Grouping by file size");
-    let size_groups = group_by_size(&filtered_files, &mut errors);
+    let mut out_of_range = 0;
+    let mut size_groups =
+        group_by_size(&filtered_files, options, &mut failures, &mut out_of_range);
+    if out_of_range > 0 {
+        log::info!("Skipped {} files outside the size range", out_of_range);
+    }
+
+    // Links to the same data always share a size; erasing them frees nothing
+    let mut links = 0;
+    for paths in size_groups.values_mut() {
+        if paths.len() > 1 {
+            links += remove_links(paths);
+        }
+    }
+    if links > 0 {
+        log::info!("Skipped {} links to files already scanned", links);
+    }
 
-    // Pass 2: Within each size group, find exact duplicates by SHA256
-    log::debug!("Pass 2: Finding exact duplicates by SHA256");
+    // Files outside the size range and extra links are not part of the scan
+    let total_files = filtered_files.len() - out_of_range - links;
+
+    // Remember the sizes so the report does not stat every duplicate again
+    let file_sizes: HashMap<PathBuf, u64> = size_groups
+        .iter()
+        .flat_map(|(size, paths)| paths.iter().map(|path| (path.clone(), *size)))
+        .collect();
+
+    // Pass 2: Within each size group, find exact duplicates by content hash
+    if let Some(len) = options.quick {
+        log::debug!(
+            "Pass 2: Finding probable duplicates by their first and last {} bytes",
+            len
+        );
+    } else {
+        log::debug!(
+            "Pass 2: Finding exact duplicates by {}",
+            options.hash_algorithm
+        );
+    }
     let mut files_for_perceptual: Vec<PathBuf> = Vec::new();
-    let mut processed = 0;
+    let mut hash_progress = HashProgress::new(&progress, total_files);
 
-    for (_size, paths) in size_groups {
-        if paths.len() < 2 {
-            // Only one file with this size, still needs perceptual comparison
-            files_for_perceptual.extend(paths.clone());
-            processed += paths.len();
-            if let Some(cb) = progress.as_ref() {
-                cb(processed, total_files, "Hashing files");
+    for (size, paths) in size_groups {
+        if options.is_canceled() {
+            log::info!("Detection canceled, skipping the remaining files");
+            break;
+        }
+        // A quick scan stops at matching ends, without reading any file in full
+        if let Some(len) = options.quick {
+            if paths.len() > 1 {
+                for mut group in group_by_head_tail_hash(&paths, len, &mut failures) {
+                    options.order_original(&mut group.files);
+                    on_group(&group);
+                    settled_groups.push(group);
+                }
             }
+            hash_progress.advance(paths.len(), size);
             continue;
         }
+        // Files whose beginnings differ cannot be identical, so larger files
+        // are split by a quick hash before any of them is read in full
+        let prescreened = match options.prescreen {
+            _ if paths.len() < 2 => None,
+            Prescreen::Off => None,
+            _ if size > hasher::PARTIAL_HASH_LEN => {
+                Some(group_by_prescreen(&paths, &mut failures, |path| {
+                    hasher::partial_hash(path, hasher::PARTIAL_HASH_LEN)
+                }))
+            }
+            // Small files are read whole either way, but a CRC32 costs far less
+            Prescreen::Crc32 => Some(group_by_prescreen(
+                &paths,
+                &mut failures,
+                hasher::crc32_hash,
+            )),
+            Prescreen::Partial => None,
+        };
+        let candidate_groups = match prescreened {
+            Some(groups) => {
+                let ruled_out = paths.len() - groups.iter().map(Vec::len).sum::<usize>();
+                hash_progress.advance(ruled_out, size);
+                groups
+            }
+            None => vec![paths],
+        };
+
+        for paths in candidate_groups {
+            if paths.len() < 2 {
+                // No other file can be identical, but it still needs perceptual comparison
+                files_for_perceptual.extend(paths.clone());
+                hash_progress.advance(paths.len(), size);
+                continue;
+            }
+
+            let (groups, non_duplicates) = find_exact_duplicates_with_progress(
+                &paths,
+                &mut failures,
+                &mut hash_progress,
+                size,
+                options,
+                cache,
+            );
 
-        let (groups, non_duplicates) =
-            find_exact_duplicates_with_progress(&paths, &mut errors, &progress, &mut processed, total_files);
+            // Add one representative from each exact duplicate group for perceptual comparison
+            for (hash, mut group) in groups {
+                for file in &group.files {
+                    if options.keep_hashes {
+                        hashes.entry(file.clone()).or_default().exact = Some(hash.clone());
+                    }
+                    checksums.insert(file.clone(), hash.clone());
+                }
 
-        // Add one representative from each exact duplicate group for perceptual comparison
-        for group in &groups {
-            if let Some(representative) = group.files.first() {
+                let Some(representative) = group.files.first() else {
+                    continue;
+                };
+                // Without a perceptual hash the group can never be merged
+                if !options.live_photos && !options.includes_for_perceptual(representative) {
+                    options.order_original(&mut group.files);
+                    on_group(&group);
+                    settled_groups.push(group);
+                    continue;
+                }
                 files_for_perceptual.push(representative.clone());
+                exact_groups.push(group);
             }
-        }
 
-        exact_groups.extend(groups);
-        files_for_perceptual.extend(non_duplicates);
+            files_for_perceptual.extend(non_duplicates);
+        }
     }
 
     // Pass 3: Perceptual hash comparison
-    log::debug!("Pass 3: Finding perceptual duplicates");
-    let perceptual_groups =
-        find_perceptual_duplicates_with_progress(&files_for_perceptual, &mut errors, &progress, filter);
+    let perceptual_skipped = !options.uses_perceptual();
+    // Videos matched by scene are left out of the single-hash comparison
+    let scene_videos: Vec<PathBuf> = if options.video_scenes && !perceptual_skipped {
+        let (videos, others) = files_for_perceptual
+            .into_iter()
+            .partition(|f| hasher::get_media_type(f) == MediaType::Video);
+        files_for_perceptual = others;
+        videos
+    } else {
+        Vec::new()
+    };
+    let perceptual_hashes = if perceptual_skipped {
+        log::debug!("Pass 3: Skipped, only exact duplicates were requested");
+        Vec::new()
+    } else {
+        log::debug!("Pass 3: Finding perceptual duplicates");
+        let perceptual_hashes = perceptual_hashes_with_progress(
+            &files_for_perceptual,
+            &mut failures,
+            &progress,
+            options,
+            cache,
+        );
+        if options.keep_hashes {
+            for (file, hash) in &perceptual_hashes {
+                hashes.entry(file.clone()).or_default().perceptual = Some(hash.to_base64());
+            }
+        }
+        perceptual_hashes
+    };
+    // Videos that may also match by sound are grouped on their own below
+    let sound_videos: HashSet<PathBuf> = if options.video_audio_match && !perceptual_skipped {
+        files_for_perceptual
+            .iter()
+            .filter(|f| hasher::get_media_type(f) == MediaType::Video)
+            .cloned()
+            .collect()
+    } else {
+        HashSet::new()
+    };
+    let mut perceptual_groups = if sound_videos.is_empty() {
+        group_similar_media(
+            &perceptual_hashes,
+            options.threshold,
+            options.media_thresholds,
+            options.perceptual,
+            options.grouping,
+        )
+    } else {
+        let others: Vec<(PathBuf, ImageHash)> = perceptual_hashes
+            .iter()
+            .filter(|(file, _)| !sound_videos.contains(file))
+            .cloned()
+            .collect();
+        group_similar_media(
+            &others,
+            options.threshold,
+            options.media_thresholds,
+            options.perceptual,
+            options.grouping,
+        )
+    };
+
+    if options.strict_perceptual && !perceptual_skipped {
+        if options.is_canceled() {
+            // Matches that cannot be confirmed are not reported
+            perceptual_groups.clear();
+        } else {
+            log::debug!("Confirming perceptual matches with strict hashes");
+            perceptual_groups =
+                confirm_perceptual_groups(perceptual_groups, &mut failures, options);
+        }
+    }
+
+    if !scene_videos.is_empty() && !options.is_canceled() {
+        log::debug!("Matching {} videos by scene", scene_videos.len());
+        let signatures = video_signatures_with_progress(&scene_videos, &mut failures, &progress);
+        perceptual_groups.extend(group_matching_signatures(
+            &signatures,
+            options.threshold_for(MediaType::Video),
+            options.grouping,
+        ));
+    }
+
+    if !sound_videos.is_empty() && !options.is_canceled() {
+        log::debug!("Matching {} videos by picture or sound", sound_videos.len());
+        let mut videos: Vec<PathBuf> = sound_videos.iter().cloned().collect();
+        videos.sort();
+        let pictures: HashMap<PathBuf, ImageHash> = perceptual_hashes
+            .iter()
+            .filter(|(file, _)| sound_videos.contains(file))
+            .cloned()
+            .collect();
+        let sounds = soundtrack_hashes_with_progress(&videos, &mut failures, &progress, options);
+        perceptual_groups.extend(group_videos_by_picture_or_sound(
+            &videos,
+            &pictures,
+            &sounds,
+            options.perceptual.scale_threshold(options.threshold_for(MediaType::Video)),
+            options.grouping,
+        ));
+    }
+
+    if let Some(cb) = progress.as_ref() {
+        cb(ProgressEvent::Merging);
+    }
+
+    let mut perceptual_hashes: HashMap<PathBuf, ImageHash> =
+        perceptual_hashes.into_iter().collect();
+    share_exact_group_hashes(&exact_groups, &mut perceptual_hashes);
 
     // Merge perceptual groups with exact groups where they overlap
-    let final_groups = merge_groups(exact_groups, perceptual_groups);
+    let mut final_groups = merge_groups(exact_groups, perceptual_groups);
+
+    // Put the file to keep first in every group
+    for group in &mut final_groups {
+        options.order_original(&mut group.files);
+    }
+
+    let mut live_photo_pairs = HashMap::new();
+    if options.live_photos {
+        log::debug!("Pairing Live Photo companion videos");
+        (final_groups, live_photo_pairs) = pair_live_photos(final_groups, live_photo_companion);
+    }
+    for group in &final_groups {
+        on_group(group);
+    }
+    final_groups.extend(settled_groups);
 
     log::info!(
         "Duplicate detection complete: {} groups found",
         final_groups.len()
     );
 
-    Ok(DuplicateReport {
+    let distances = original_distances(&final_groups, &perceptual_hashes);
+    let report = DuplicateReport {
         groups: final_groups,
         total_files,
-        errors,
-    })
+        errors: failures.len(),
+        threshold: options.threshold,
+        media_thresholds: options.media_thresholds,
+        hash_algorithm: options.hash_algorithm,
+        perceptual_config: options.perceptual,
+        grouping: options.grouping,
+        video_scenes: options.video_scenes && !perceptual_skipped,
+        video_audio_match: options.video_audio_match && !perceptual_skipped,
+        file_sizes,
+        perceptual_skipped,
+        quick: options.quick,
+        hashes,
+        distances,
+        checksums,
+        live_photo_pairs,
+        root: None,
+        inaccessible_paths: 0,
+        canceled: options.is_canceled(),
+    };
+    Ok((report, failures))
 }
 
 /// Merges exact and perceptual groups, expanding exact groups when their
 /// representative is found in a perceptual group.
-fn merge_groups(
+///
+/// A merged group stays [`DuplicateType::Exact`] when all of its files come
+/// from one exact group, and becomes [`DuplicateType::Mixed`] when exact
+/// duplicates are joined with other similar files.
+pub fn merge_groups(
     exact_groups: Vec<DuplicateGroup>,
     perceptual_groups: Vec<DuplicateGroup>,
 ) -> Vec<DuplicateGroup> {
@@ -308,7 +2060,8 @@ fn merge_groups(
     // Process perceptual groups
     for perceptual_group in perceptual_groups {
         let mut merged_files: Vec<PathBuf> = Vec::new();
-        let mut has_exact_duplicates = false;
+        let mut exact_groups_merged = 0;
+        let mut similar_files = 0;
 
         for file in perceptual_group.files {
             if let Some(&exact_idx) = file_to_exact_group.get(&file) {
@@ -316,10 +2069,11 @@ fn merge_groups(
                 if !merged_exact_groups[exact_idx] {
                     merged_files.extend(exact_groups[exact_idx].files.clone());
                     merged_exact_groups[exact_idx] = true;
-                    has_exact_duplicates = true;
+                    exact_groups_merged += 1;
                 }
             } else {
                 merged_files.push(file);
+                similar_files += 1;
             }
         }
 
@@ -328,14 +2082,16 @@ fn merge_groups(
             merged_files.sort();
             merged_files.dedup();
 
+            // Files from a single exact group are still byte-identical
+            let duplicate_type = match (exact_groups_merged, similar_files) {
+                (0, _) => DuplicateType::Perceptual,
+                (1, 0) => DuplicateType::Exact,
+                _ => DuplicateType::Mixed,
+            };
+
             final_groups.push(DuplicateGroup {
                 files: merged_files,
-                // If it contains exact duplicates, mark as perceptual since it's a mixed group
-                duplicate_type: if has_exact_duplicates {
-                    DuplicateType::Perceptual
-                } else {
-                    DuplicateType::Perceptual
-                },
+                duplicate_type,
             });
         }
     }
@@ -350,18 +2106,92 @@ fn merge_groups(
     final_groups
 }
 
-/// Groups files by their size.
-fn group_by_size(files: &[PathBuf], errors: &mut usize) -> HashMap<u64, Vec<PathBuf>> {
-    let mut size_map: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+/// Image extensions that can carry a Live Photo companion video.
+const LIVE_PHOTO_IMAGE_EXTENSIONS: &[&str] = &["heic", "heif", "jpg", "jpeg"];
+
+/// Extensions of Live Photo companion videos, checked in order.
+const LIVE_PHOTO_VIDEO_EXTENSIONS: &[&str] = &["mov", "MOV"];
+
+/// Returns the companion video of a Live Photo image, if one exists next to it.
+fn live_photo_companion(path: &Path) -> Option<PathBuf> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    if !LIVE_PHOTO_IMAGE_EXTENSIONS.contains(&ext.as_str()) {
+        return None;
+    }
+
+    LIVE_PHOTO_VIDEO_EXTENSIONS
+        .iter()
+        .map(|video_ext| path.with_extension(video_ext))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Keeps Live Photo pairs together, finding the video of each image with
+/// `companion`.
+///
+/// Every image of a group that has a companion video is paired with it, and
+/// a complete pair is promoted to original so the kept image keeps its
+/// video. Paired videos are removed from every group so they cannot be
+/// erased independently of their image; erasing an image erases its video
+/// instead. Returns the groups and the video paired with each image.
+fn pair_live_photos(
+    groups: Vec<DuplicateGroup>,
+    companion: impl Fn(&Path) -> Option<PathBuf>,
+) -> (Vec<DuplicateGroup>, HashMap<PathBuf, PathBuf>) {
+    let mut result: Vec<DuplicateGroup> = Vec::with_capacity(groups.len());
+    let mut pairs: HashMap<PathBuf, PathBuf> = HashMap::new();
+
+    for mut group in groups {
+        let mut videos: Vec<Option<PathBuf>> =
+            group.files.iter().map(|f| companion(f)).collect();
+
+        if let Some(first_paired) = videos.iter().position(Option::is_some) {
+            // Keep a complete Live Photo as the original
+            group.files[..=first_paired].rotate_right(1);
+            videos[..=first_paired].rotate_right(1);
+        }
+        for (image, video) in group.files.iter().zip(videos) {
+            if let Some(video) = video {
+                pairs.insert(image.clone(), video);
+            }
+        }
+
+        result.push(group);
+    }
+
+    // Paired videos now follow their image only
+    let paired: HashSet<&PathBuf> = pairs.values().collect();
+    for group in &mut result {
+        group.files.retain(|f| !paired.contains(f));
+    }
+    result.retain(|g| g.files.len() > 1);
+    pairs.retain(|image, _| result.iter().any(|g| g.files.contains(image)));
+
+    (result, pairs)
+}
+
+/// Groups files by their size, skipping files outside the configured size range.
+///
+/// Sizes are ordered so later passes, and so pairwise grouping, see the files
+/// in the same order on every run.
+fn group_by_size(
+    files: &[PathBuf],
+    options: &DetectionOptions,
+    failures: &mut Vec<(PathBuf, Error)>,
+    out_of_range: &mut usize,
+) -> BTreeMap<u64, Vec<PathBuf>> {
+    let mut size_map: BTreeMap<u64, Vec<PathBuf>> = BTreeMap::new();
 
     for path in files {
         match hasher::file_size(path) {
+            Ok(size) if !options.includes_size(size) => {
+                *out_of_range += 1;
+            }
             Ok(size) => {
                 size_map.entry(size).or_default().push(path.clone());
             }
             Err(e) => {
                 log::warn!("Could not get size of {:?}: {}", path, e);
-                *errors += 1;
+                failures.push((path.clone(), e));
             }
         }
     }
@@ -369,41 +2199,159 @@ fn group_by_size(files: &[PathBuf], errors: &mut usize) -> HashMap<u64, Vec<Path
     size_map
 }
 
-/// Finds exact duplicates with progress reporting.
-fn find_exact_duplicates_with_progress(
+/// Groups files by a cheap hash `prescreen` computes, such as a partial hash
+/// or a CRC32.
+///
+/// Only files in the same group can be identical; a full hash must still
+/// confirm it. Files that cannot be read are recorded in `failures`.
+fn group_by_prescreen<K: Ord>(
     files: &[PathBuf],
-    errors: &mut usize,
-    progress: &Option<ProgressCallback>,
-    processed: &mut usize,
+    failures: &mut Vec<(PathBuf, Error)>,
+    prescreen: impl Fn(&Path) -> Result<K>,
+) -> Vec<Vec<PathBuf>> {
+    let mut partial_map: BTreeMap<K, Vec<PathBuf>> = BTreeMap::new();
+
+    for path in files {
+        match prescreen(path) {
+            Ok(hash) => {
+                partial_map.entry(hash).or_default().push(path.clone());
+            }
+            Err(e) => {
+                log::warn!("Could not hash {:?}: {}", path, e);
+                failures.push((path.clone(), e));
+            }
+        }
+    }
+
+    partial_map.into_values().collect()
+}
+
+/// Groups files of the same size by a hash of their first and last `len`
+/// bytes, returning the groups of more than one file as probable duplicates.
+///
+/// Files that cannot be read are recorded in `failures`.
+fn group_by_head_tail_hash(
+    files: &[PathBuf],
+    len: u64,
+    failures: &mut Vec<(PathBuf, Error)>,
+) -> Vec<DuplicateGroup> {
+    let mut ends_map: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+
+    for path in files {
+        match hasher::head_tail_hash(path, len) {
+            Ok(hash) => {
+                ends_map.entry(hash).or_default().push(path.clone());
+            }
+            Err(e) => {
+                log::warn!("Could not hash {:?}: {}", path, e);
+                failures.push((path.clone(), e));
+            }
+        }
+    }
+
+    ends_map
+        .into_values()
+        .filter(|files| files.len() > 1)
+        .map(|files| DuplicateGroup {
+            files,
+            duplicate_type: DuplicateType::Probable,
+        })
+        .collect()
+}
+
+/// Removes paths that link to a file already in `paths`, keeping the first one.
+///
+/// Files whose identity cannot be determined are always kept. Returns the
+/// number of paths removed.
+fn remove_links(paths: &mut Vec<PathBuf>) -> usize {
+    let before = paths.len();
+    let mut seen: HashSet<(u64, u64)> = HashSet::new();
+    paths.retain(|path| match hasher::file_id(path) {
+        Some(id) if !seen.insert(id) => {
+            log::debug!("Skipping {:?}: it links to a file already scanned", path);
+            false
+        }
+        _ => true,
+    });
+    before - paths.len()
+}
+
+/// Files and bytes handled so far by the exact hash pass, reported through
+/// [`ProgressEvent::Hashing`].
+struct HashProgress<'a> {
+    callback: &'a Option<ProgressCallback>,
+    done: usize,
     total: usize,
-) -> (Vec<DuplicateGroup>, Vec<PathBuf>) {
-    let mut hash_map: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    bytes: u64,
+}
+
+impl<'a> HashProgress<'a> {
+    fn new(callback: &'a Option<ProgressCallback>, total: usize) -> Self {
+        Self {
+            callback,
+            done: 0,
+            total,
+            bytes: 0,
+        }
+    }
+
+    /// Counts `files` more files of `size` bytes each as processed.
+    fn advance(&mut self, files: usize, size: u64) {
+        self.done += files;
+        self.bytes += files as u64 * size;
+        if let Some(cb) = self.callback {
+            cb(ProgressEvent::Hashing {
+                done: self.done,
+                total: self.total,
+                bytes: self.bytes,
+            });
+        }
+    }
+}
+
+/// Finds exact duplicates of `size` bytes each with progress reporting.
+///
+/// Each group is returned with the hash its files share, ordered by that
+/// hash so every run returns them alike. Once detection is canceled, the
+/// remaining files are left out.
+fn find_exact_duplicates_with_progress(
+    files: &[PathBuf],
+    failures: &mut Vec<(PathBuf, Error)>,
+    progress: &mut HashProgress,
+    size: u64,
+    options: &DetectionOptions,
+    cache: &mut HashCache,
+) -> (Vec<(String, DuplicateGroup)>, Vec<PathBuf>) {
+    let mut hash_map: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
 
     for path in files {
-        match hasher::sha256_hash(path) {
+        if options.is_canceled() {
+            break;
+        }
+        match cache.exact_hash(path, options.hash_algorithm) {
             Ok(hash) => {
                 hash_map.entry(hash).or_default().push(path.clone());
             }
             Err(e) => {
                 log::warn!("Could not hash {:?}: {}", path, e);
-                *errors += 1;
+                failures.push((path.clone(), e));
             }
         }
-        *processed += 1;
-        if let Some(cb) = progress {
-            cb(*processed, total, "Hashing files");
-        }
+        progress.advance(1, size);
     }
 
     let mut groups = Vec::new();
     let mut non_duplicates = Vec::new();
 
-    for (_hash, paths) in hash_map {
+    for (hash, paths) in hash_map {
         if paths.len() > 1 {
-            groups.push(DuplicateGroup {
-                files: paths,
-                duplicate_type: DuplicateType::Exact,
-            });
+            groups.push((
+                hash,
+                DuplicateGroup {
+                    files: paths,
+                    duplicate_type: DuplicateType::Exact,
+                },
+            ));
         } else {
             non_duplicates.extend(paths);
         }
@@ -412,75 +2360,786 @@ fn find_exact_duplicates_with_progress(
     (groups, non_duplicates)
 }
 
-/// Finds perceptually similar media files with progress reporting.
-fn find_perceptual_duplicates_with_progress(
+/// Computes the perceptual hashes of media files with progress reporting.
+///
+/// Hashes are returned in input order; files that cannot be hashed are left
+/// out, as are those not yet hashed when detection is canceled.
+fn perceptual_hashes_with_progress(
     files: &[PathBuf],
-    errors: &mut usize,
+    failures: &mut Vec<(PathBuf, Error)>,
     progress: &Option<ProgressCallback>,
-    filter: MediaFilter,
-) -> Vec<DuplicateGroup> {
-    // Compute perceptual hashes for all supported media files
-    let mut hashes: Vec<(PathBuf, ImageHash)> = Vec::new();
+    options: &DetectionOptions,
+    cache: &mut HashCache,
+) -> Vec<(PathBuf, ImageHash)> {
     let total = files.len();
+    let done = AtomicUsize::new(0);
+    let report_progress = || {
+        let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Some(cb) = progress {
+            cb(ProgressEvent::Analyzing { done, total });
+        }
+    };
+
+    // Serve unchanged files from the cache and collect the rest for hashing
+    let mut hashes: Vec<Option<ImageHash>> = vec![None; total];
+    let mut pending: Vec<usize> = Vec::new();
 
     for (i, path) in files.iter().enumerate() {
         // Check if file should be processed based on filter
-        if !filter.includes_for_perceptual(path) {
-            if let Some(cb) = progress {
-                cb(i + 1, total, "Analyzing media");
-            }
+        if !options.includes_for_perceptual(path) {
+            report_progress();
             continue;
         }
 
-        // Use the unified media perceptual hash function
-        match hasher::media_perceptual_hash(path) {
+        match cache.cached_perceptual_hash(path, options.perceptual) {
             Ok(Some(hash)) => {
-                hashes.push((path.clone(), hash));
+                hashes[i] = Some(hash);
+                report_progress();
             }
-            Ok(None) => {
-                // Not a supported media file, skip
-                log::debug!("Skipping unsupported file: {:?}", path);
+            Ok(None) => pending.push(i),
+            Err(e) => {
+                log::warn!("Could not compute perceptual hash for {:?}: {}", path, e);
+                failures.push((path.clone(), e));
+                report_progress();
+            }
+        }
+    }
+
+    // Decoding is CPU-heavy, so hash the remaining files in parallel
+    let computed: Vec<(usize, Result<Option<ImageHash>>)> = pending
+        .par_iter()
+        .filter(|_| !options.is_canceled())
+        .map(|&i| {
+            let result = hasher::media_perceptual_hash_with(&files[i], options.perceptual);
+            report_progress();
+            (i, result)
+        })
+        .collect();
+
+    // Record the results in input order so grouping stays deterministic
+    for (i, result) in computed {
+        let path = &files[i];
+        match result {
+            Ok(hash) => {
+                if let Err(e) =
+                    cache.insert_perceptual_hash(path, options.perceptual, hash.as_ref())
+                {
+                    log::debug!("Could not cache perceptual hash for {:?}: {}", path, e);
+                }
+                if hash.is_none() {
+                    // Not a supported media file, skip
+                    log::debug!("Skipping unsupported file: {:?}", path);
+                }
+                hashes[i] = hash;
             }
             Err(e) => {
                 log::warn!("Could not compute perceptual hash for {:?}: {}", path, e);
-                *errors += 1;
+                failures.push((path.clone(), e));
             }
         }
-        if let Some(cb) = progress {
-            cb(i + 1, total, "Analyzing media");
+    }
+
+    files
+        .iter()
+        .zip(hashes)
+        .filter_map(|(path, hash)| Some((path.clone(), hash?)))
+        .collect()
+}
+
+/// Settings [`regroup`] rebuilds duplicate groups with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegroupOptions {
+    /// Maximum Hamming distance for two perceptual hashes to be considered
+    /// similar, on the scale of [`hasher::HASH_SIZE`] hashes.
+    pub threshold: u32,
+    /// Thresholds of single media types, used instead of `threshold` for
+    /// files of that type, and only with files of their own type.
+    pub media_thresholds: MediaThresholds,
+    /// Size and algorithm the stored perceptual hashes were computed with;
+    /// the thresholds are scaled to its hash size.
+    pub perceptual: PerceptualConfig,
+    /// How similar files are joined into groups.
+    pub grouping: Grouping,
+    /// How the file to keep is chosen within each group.
+    pub original_strategy: OriginalStrategy,
+    /// Directories whose files are kept over files elsewhere, highest
+    /// priority first, overriding `original_strategy`.
+    pub preferred_dirs: Vec<PathBuf>,
+}
+
+impl Default for RegroupOptions {
+    fn default() -> Self {
+        Self {
+            threshold: hasher::DEFAULT_SIMILARITY_THRESHOLD,
+            media_thresholds: MediaThresholds::default(),
+            perceptual: PerceptualConfig::default(),
+            grouping: Grouping::default(),
+            original_strategy: OriginalStrategy::default(),
+            preferred_dirs: Vec::new(),
         }
     }
+}
 
-    // Find similar media using union-find approach
-    let mut groups: Vec<DuplicateGroup> = Vec::new();
-    let mut used: Vec<bool> = vec![false; hashes.len()];
+/// Rebuilds duplicate groups from hashes stored by a scan, with the
+/// thresholds and grouping of `options`, without reading the files' contents.
+///
+/// Files sharing an exact hash form exact groups, which are merged with the
+/// perceptual groups as in a scan. The Live Photo pairs the scan recorded,
+/// `live_photo_pairs`, are kept together as in a scan: paired videos are
+/// left out of the groups and follow their image. Strict perceptual
+/// confirmation is not applied. Invalid stored perceptual hashes are ignored.
+/// The original of each group is chosen by `original_strategy`, preferring
+/// files under `preferred_dirs` as [`OriginalStrategy::order_preferring`] does.
+///
+/// Returns the groups and the distance of each duplicate to its original, as
+/// in [`DuplicateReport::distances`].
+pub fn regroup(
+    hashes: &BTreeMap<PathBuf, FileHashes>,
+    options: &RegroupOptions,
+    live_photo_pairs: &HashMap<PathBuf, PathBuf>,
+) -> Result<(Vec<DuplicateGroup>, HashMap<PathBuf, u32>)> {
+    if options.threshold > hasher::MAX_SIMILARITY_THRESHOLD {
+        return Err(Error::InvalidThreshold(options.threshold));
+    }
+    options.media_thresholds.validate()?;
+
+    // Ordered by hash so the groups come out the same on every run
+    let mut by_exact_hash: BTreeMap<&str, Vec<PathBuf>> = BTreeMap::new();
+    let mut perceptual_hashes: Vec<(PathBuf, ImageHash)> = Vec::new();
 
-    for i in 0..hashes.len() {
-        if used[i] {
+    let hash_len = options.perceptual.hash_len();
+    // Paired videos are only erased with their image
+    let paired_videos: HashSet<&PathBuf> = live_photo_pairs.values().collect();
+    for (path, stored) in hashes {
+        if paired_videos.contains(path) {
             continue;
         }
+        if let Some(exact) = &stored.exact {
+            by_exact_hash.entry(exact).or_default().push(path.clone());
+        }
+        if let Some(hash) = decode_stored_hash(path, stored, hash_len) {
+            perceptual_hashes.push((path.clone(), hash));
+        }
+    }
+
+    let exact_groups: Vec<DuplicateGroup> = by_exact_hash
+        .into_values()
+        .filter(|files| files.len() > 1)
+        .map(|files| DuplicateGroup {
+            files,
+            duplicate_type: DuplicateType::Exact,
+        })
+        .collect();
+    let perceptual_groups = group_similar_media(
+        &perceptual_hashes,
+        options.threshold,
+        options.media_thresholds,
+        options.perceptual,
+        options.grouping,
+    );
+    let mut perceptual_hashes: HashMap<PathBuf, ImageHash> =
+        perceptual_hashes.into_iter().collect();
+    share_exact_group_hashes(&exact_groups, &mut perceptual_hashes);
+
+    let mut groups = merge_groups(exact_groups, perceptual_groups);
+    for group in &mut groups {
+        options
+            .original_strategy
+            .order_preferring(&mut group.files, &options.preferred_dirs);
+    }
+    if !live_photo_pairs.is_empty() {
+        (groups, _) = pair_live_photos(groups, |image| live_photo_pairs.get(image).cloned());
+    }
+    let distances = original_distances(&groups, &perceptual_hashes);
+    Ok((groups, distances))
+}
+
+/// Decodes the perceptual hash stored for `path`, skipping it with a warning
+/// if it is invalid or not `hash_len` bytes long, as hashes computed with
+/// other settings are, so they are never compared with the rest.
+fn decode_stored_hash(path: &Path, stored: &FileHashes, hash_len: usize) -> Option<ImageHash> {
+    let encoded = stored.perceptual.as_ref()?;
+    match ImageHash::from_base64(encoded) {
+        Ok(hash) if hash.as_bytes().len() == hash_len => Some(hash),
+        Ok(_) => {
+            log::warn!(
+                "Ignoring perceptual hash of another size stored for {:?}",
+                path
+            );
+            None
+        }
+        Err(_) => {
+            log::warn!("Ignoring invalid perceptual hash stored for {:?}", path);
+            None
+        }
+    }
+}
 
-        let mut group_files = vec![hashes[i].0.clone()];
-        used[i] = true;
+/// Finds the files among `files` that duplicate a file of an earlier scan,
+/// using the hashes that scan stored with [`DetectionOptions::keep_hashes`].
+///
+/// Each group lists a baseline file as the original, followed by the files
+/// matching it. Files are matched by exact hash first, computed with
+/// `options.hash_algorithm`, and otherwise to the closest baseline perceptual
+/// hash within `options.threshold`, computed with `options.perceptual`. Both
+/// must be the settings of the baseline. Files that
+/// only duplicate each other, or are themselves in the baseline, are not
+/// reported. The baseline only stores exact hashes of files it found exact
+/// duplicates of, so other exact copies are matched by their perceptual hash.
+pub fn find_duplicates_against(
+    files: &[PathBuf],
+    baseline: &BTreeMap<PathBuf, FileHashes>,
+    progress: Option<ProgressCallback>,
+    options: &DetectionOptions,
+    cache: &mut HashCache,
+) -> Result<(DuplicateReport, Vec<(PathBuf, Error)>)> {
+    options.validate()?;
 
-        for j in (i + 1)..hashes.len() {
-            if used[j] {
-                continue;
+    if let Some(cb) = progress.as_ref() {
+        cb(ProgressEvent::CollectingFiles);
+    }
+
+    let files: Vec<PathBuf> = dedup_paths(files)
+        .into_iter()
+        .filter(|p| options.includes(p) && !baseline.contains_key(&absolute_path(p)))
+        .collect();
+    let mut failures: Vec<(PathBuf, Error)> = Vec::new();
+    let mut out_of_range = 0;
+    let size_groups = group_by_size(&files, options, &mut failures, &mut out_of_range);
+    let total_files = files.len() - out_of_range;
+    let file_sizes: HashMap<PathBuf, u64> = size_groups
+        .iter()
+        .flat_map(|(size, paths)| paths.iter().map(|path| (path.clone(), *size)))
+        .collect();
+
+    // Matching files of each baseline file, with their type and distance
+    let mut matches: BTreeMap<&Path, Vec<(PathBuf, DuplicateType, u32)>> = BTreeMap::new();
+
+    let baseline_exact: HashMap<&str, &Path> = baseline
+        .iter()
+        .filter_map(|(path, hashes)| Some((hashes.exact.as_deref()?, path.as_path())))
+        .collect();
+    let mut hash_progress = HashProgress::new(&progress, total_files);
+    let mut unmatched: Vec<PathBuf> = Vec::new();
+    let mut checksums: HashMap<PathBuf, String> = HashMap::new();
+    'sizes: for (size, paths) in size_groups {
+        for path in paths {
+            if options.is_canceled() {
+                log::info!("Detection canceled, skipping the remaining files");
+                break 'sizes;
+            }
+            if baseline_exact.is_empty() {
+                unmatched.push(path);
+            } else {
+                match cache.exact_hash(&path, options.hash_algorithm) {
+                    Ok(hash) => match baseline_exact.get(hash.as_str()) {
+                        Some(&original) => {
+                            checksums.insert(original.to_path_buf(), hash.clone());
+                            checksums.insert(path.clone(), hash);
+                            let found = (path, DuplicateType::Exact, 0);
+                            matches.entry(original).or_default().push(found);
+                        }
+                        None => unmatched.push(path),
+                    },
+                    Err(e) => {
+                        log::warn!("Could not hash {:?}: {}", path, e);
+                        failures.push((path, e));
+                    }
+                }
             }
+            hash_progress.advance(1, size);
+        }
+    }
 
-            if hasher::are_similar(&hashes[i].1, &hashes[j].1) {
-                group_files.push(hashes[j].0.clone());
-                used[j] = true;
+    let perceptual_skipped = !options.uses_perceptual();
+    if !perceptual_skipped {
+        let mut tree = BkTree::new();
+        let hash_len = options.perceptual.hash_len();
+        for (path, stored) in baseline {
+            if let Some(hash) = decode_stored_hash(path, stored, hash_len) {
+                tree.insert(hash, path.as_path());
             }
         }
 
-        if group_files.len() > 1 {
-            groups.push(DuplicateGroup {
-                files: group_files,
-                duplicate_type: DuplicateType::Perceptual,
-            });
+        if !tree.is_empty() {
+            unmatched.retain(|f| options.includes_for_perceptual(f));
+            let hashes = perceptual_hashes_with_progress(
+                &unmatched,
+                &mut failures,
+                &progress,
+                options,
+                cache,
+            );
+            for (path, hash) in hashes {
+                // Files of different types are hashed differently
+                let media_type = perceptual_media_type(&path);
+                let threshold = options
+                    .perceptual
+                    .scale_threshold(options.threshold_for(media_type));
+                let closest = tree
+                    .find_within(&hash, threshold)
+                    .into_iter()
+                    .filter(|(_, original)| perceptual_media_type(original) == media_type)
+                    .min_by_key(|&(distance, original)| (distance, *original));
+                if let Some((distance, &original)) = closest {
+                    let found = (path, DuplicateType::Perceptual, distance);
+                    matches.entry(original).or_default().push(found);
+                }
+            }
+        }
+    }
+
+    if let Some(cb) = progress.as_ref() {
+        cb(ProgressEvent::Merging);
+    }
+
+    let mut groups = Vec::new();
+    let mut distances = HashMap::new();
+    for (original, mut found) in matches {
+        found.sort_by(|a, b| a.0.cmp(&b.0));
+        let duplicate_type = if found.iter().all(|m| m.1 == DuplicateType::Exact) {
+            DuplicateType::Exact
+        } else if found.iter().all(|m| m.1 == DuplicateType::Perceptual) {
+            DuplicateType::Perceptual
+        } else {
+            DuplicateType::Mixed
+        };
+        let mut files = vec![original.to_path_buf()];
+        for (path, _, distance) in found {
+            distances.insert(path.clone(), distance);
+            files.push(path);
+        }
+        groups.push(DuplicateGroup {
+            files,
+            duplicate_type,
+        });
+    }
+
+    log::info!(
+        "Found {} baseline files with duplicates among {} files",
+        groups.len(),
+        total_files
+    );
+
+    let report = DuplicateReport {
+        groups,
+        total_files,
+        errors: failures.len(),
+        threshold: options.threshold,
+        media_thresholds: options.media_thresholds,
+        hash_algorithm: options.hash_algorithm,
+        perceptual_config: options.perceptual,
+        grouping: options.grouping,
+        file_sizes,
+        perceptual_skipped,
+        distances,
+        checksums,
+        canceled: options.is_canceled(),
+        ..DuplicateReport::default()
+    };
+    Ok((report, failures))
+}
+
+/// Gives every file of an exact group the perceptual hash of the member that
+/// was hashed, since only one file per exact group is perceptually hashed.
+fn share_exact_group_hashes(
+    exact_groups: &[DuplicateGroup],
+    hashes: &mut HashMap<PathBuf, ImageHash>,
+) {
+    for group in exact_groups {
+        let Some(hash) = group.files.iter().find_map(|f| hashes.get(f)).cloned() else {
+            continue;
+        };
+        for file in &group.files {
+            hashes.entry(file.clone()).or_insert_with(|| hash.clone());
+        }
+    }
+}
+
+/// Computes the Hamming distance from every duplicate to the original, the
+/// first file of its group.
+///
+/// Exact groups are always at distance 0. In other groups, files missing
+/// from `hashes` get no distance.
+fn original_distances(
+    groups: &[DuplicateGroup],
+    hashes: &HashMap<PathBuf, ImageHash>,
+) -> HashMap<PathBuf, u32> {
+    let mut distances = HashMap::new();
+
+    for group in groups {
+        let Some((original, duplicates)) = group.files.split_first() else {
+            continue;
+        };
+        if group.duplicate_type == DuplicateType::Exact {
+            distances.extend(duplicates.iter().map(|f| (f.clone(), 0)));
+            continue;
+        }
+        let Some(original_hash) = hashes.get(original) else {
+            continue;
+        };
+        for file in duplicates {
+            if let Some(hash) = hashes.get(file) {
+                distances.insert(file.clone(), hasher::hamming_distance(original_hash, hash));
+            }
+        }
+    }
+
+    distances
+}
+
+/// Groups files whose perceptual hashes are within `threshold` of each other.
+///
+/// Neighbours are found with a [`BkTree`] range query. With
+/// [`Grouping::Transitive`] they are joined with union-find, so similarity
+/// chains; with [`Grouping::Pairwise`] groups are built with
+/// [`group_around_representatives`]. Groups are ordered by their first file,
+/// and files keep their input order within a group.
+fn group_similar_hashes(
+    hashes: &[(PathBuf, ImageHash)],
+    threshold: u32,
+    grouping: Grouping,
+) -> Vec<DuplicateGroup> {
+    let mut tree = BkTree::new();
+    for (index, (_, hash)) in hashes.iter().enumerate() {
+        tree.insert(hash.clone(), index);
+    }
+
+    if grouping == Grouping::Pairwise {
+        // Closest neighbours first, so they are the ones kept
+        let neighbors = |index: usize| -> Vec<usize> {
+            let mut found = tree.find_within(&hashes[index].1, threshold);
+            found.sort_unstable_by_key(|&(distance, &neighbor)| (distance, neighbor));
+            found.into_iter().map(|(_, &neighbor)| neighbor).collect()
+        };
+        let similar =
+            |a: usize, b: usize| hasher::hamming_distance(&hashes[a].1, &hashes[b].1) <= threshold;
+        return group_around_representatives(
+            hashes.iter().map(|(path, _)| path),
+            neighbors,
+            similar,
+        );
+    }
+
+    let mut parents: Vec<usize> = (0..hashes.len()).collect();
+    for (index, (_, hash)) in hashes.iter().enumerate() {
+        for (_, &neighbor) in tree.find_within(hash, threshold) {
+            union(&mut parents, index, neighbor);
+        }
+    }
+
+    collect_union_groups(&mut parents, hashes.iter().map(|(path, _)| path))
+}
+
+/// Groups files whose perceptual hashes are within the threshold of their
+/// media type, set by `media_thresholds` and otherwise `threshold`.
+///
+/// Files are only compared with files of the same media type, since each
+/// type is hashed differently. Each type is grouped like
+/// [`group_similar_hashes`], images first, then videos and audio files.
+fn group_similar_media(
+    hashes: &[(PathBuf, ImageHash)],
+    threshold: u32,
+    media_thresholds: MediaThresholds,
+    config: PerceptualConfig,
+    grouping: Grouping,
+) -> Vec<DuplicateGroup> {
+    let mut groups = Vec::new();
+    for media_type in [MediaType::Image, MediaType::Video, MediaType::Audio] {
+        let same_type: Vec<(PathBuf, ImageHash)> = hashes
+            .iter()
+            .filter(|(path, _)| perceptual_media_type(path) == media_type)
+            .cloned()
+            .collect();
+        let threshold = media_thresholds.threshold_for(media_type, threshold);
+        let threshold = config.scale_threshold(threshold);
+        groups.extend(group_similar_hashes(&same_type, threshold, grouping));
+    }
+    groups
+}
+
+/// Returns the media type `path` is perceptually hashed as: files with
+/// unrecognised extensions are only hashed when decoded as images.
+fn perceptual_media_type(path: &Path) -> MediaType {
+    match hasher::get_media_type(path) {
+        MediaType::Unknown => MediaType::Image,
+        media_type => media_type,
+    }
+}
+
+/// Groups videos whose scene signatures match, comparing every pair.
+///
+/// Matches are joined as set by `grouping` like [`group_similar_hashes`], and
+/// groups come out in the same order.
+fn group_matching_signatures(
+    signatures: &[(PathBuf, VideoSignature)],
+    threshold: u32,
+    grouping: Grouping,
+) -> Vec<DuplicateGroup> {
+    let matching = |a: usize, b: usize| signatures[a].1.matches(&signatures[b].1, threshold);
+    let paths: Vec<&PathBuf> = signatures.iter().map(|(path, _)| path).collect();
+    group_matching_pairs(&paths, matching, grouping)
+}
+
+/// Groups videos that look alike or sound alike, comparing every pair.
+///
+/// Two videos match when their `pictures` hashes, or failing that their
+/// `sounds` hashes, are within `threshold`; videos missing from both maps
+/// match nothing. Matches are joined as set by `grouping` like
+/// [`group_similar_hashes`].
+fn group_videos_by_picture_or_sound(
+    videos: &[PathBuf],
+    pictures: &HashMap<PathBuf, ImageHash>,
+    sounds: &HashMap<PathBuf, ImageHash>,
+    threshold: u32,
+    grouping: Grouping,
+) -> Vec<DuplicateGroup> {
+    let close = |hashes: &HashMap<PathBuf, ImageHash>, a: usize, b: usize| {
+        let (Some(first), Some(second)) = (hashes.get(&videos[a]), hashes.get(&videos[b])) else {
+            return false;
+        };
+        hasher::hamming_distance(first, second) <= threshold
+    };
+    let matching = |a: usize, b: usize| close(pictures, a, b) || close(sounds, a, b);
+    let paths: Vec<&PathBuf> = videos.iter().collect();
+    group_matching_pairs(&paths, matching, grouping)
+}
+
+/// Groups the files of `paths` for which `matching` holds, testing every
+/// pair, and joins them as set by `grouping`.
+fn group_matching_pairs(
+    paths: &[&PathBuf],
+    matching: impl Fn(usize, usize) -> bool,
+    grouping: Grouping,
+) -> Vec<DuplicateGroup> {
+    if grouping == Grouping::Pairwise {
+        let neighbors = |index: usize| -> Vec<usize> {
+            (0..paths.len()).filter(|&other| matching(index, other)).collect()
+        };
+        return group_around_representatives(paths.iter().copied(), neighbors, &matching);
+    }
+
+    let mut parents: Vec<usize> = (0..paths.len()).collect();
+    for index in 0..paths.len() {
+        for other in index + 1..paths.len() {
+            if matching(index, other) {
+                union(&mut parents, index, other);
+            }
+        }
+    }
+
+    collect_union_groups(&mut parents, paths.iter().copied())
+}
+
+/// Builds perceptual groups in which every file matches every other one.
+///
+/// Files not grouped yet are taken in input order as representatives, where
+/// `paths` yields the file of each index. Each of the representative's
+/// `neighbors` that is not grouped yet joins its group if it also `matches`
+/// every file already in it, so the order of `neighbors` decides which files
+/// are kept when they do not all match each other.
+fn group_around_representatives<'a>(
+    paths: impl Iterator<Item = &'a PathBuf>,
+    neighbors: impl Fn(usize) -> Vec<usize>,
+    matches: impl Fn(usize, usize) -> bool,
+) -> Vec<DuplicateGroup> {
+    let paths: Vec<&PathBuf> = paths.collect();
+    let mut grouped = vec![false; paths.len()];
+    let mut groups = Vec::new();
+
+    for representative in 0..paths.len() {
+        if grouped[representative] {
+            continue;
+        }
+        let mut members = vec![representative];
+        for neighbor in neighbors(representative) {
+            if neighbor != representative
+                && !grouped[neighbor]
+                && members.iter().all(|&member| matches(member, neighbor))
+            {
+                members.push(neighbor);
+            }
+        }
+        if members.len() < 2 {
+            continue;
+        }
+
+        members.sort_unstable();
+        for &member in &members {
+            grouped[member] = true;
         }
+        groups.push(DuplicateGroup {
+            files: members.into_iter().map(|member| paths[member].clone()).collect(),
+            duplicate_type: DuplicateType::Perceptual,
+        });
     }
 
     groups
 }
+
+/// Builds a perceptual group from every union-find set of more than one
+/// file, where `paths` yields the file of each index in `parents`.
+fn collect_union_groups<'a>(
+    parents: &mut [usize],
+    paths: impl Iterator<Item = &'a PathBuf>,
+) -> Vec<DuplicateGroup> {
+    let mut group_of_root: HashMap<usize, usize> = HashMap::new();
+    let mut members: Vec<Vec<PathBuf>> = Vec::new();
+    for (index, path) in paths.enumerate() {
+        let root = find_root(parents, index);
+        let slot = *group_of_root.entry(root).or_insert_with(|| {
+            members.push(Vec::new());
+            members.len() - 1
+        });
+        members[slot].push(path.clone());
+    }
+
+    members
+        .into_iter()
+        .filter(|files| files.len() > 1)
+        .map(|files| DuplicateGroup {
+            files,
+            duplicate_type: DuplicateType::Perceptual,
+        })
+        .collect()
+}
+
+/// Finds the representative of `index` in a union-find forest, compressing paths.
+fn find_root(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
+
+/// Joins the sets containing `a` and `b`, keeping the smaller index as root.
+fn union(parents: &mut [usize], a: usize, b: usize) {
+    let root_a = find_root(parents, a);
+    let root_b = find_root(parents, b);
+    if root_a != root_b {
+        parents[root_a.max(root_b)] = root_a.min(root_b);
+    }
+}
+
+/// Computes the scene signatures of `videos` in parallel.
+///
+/// Videos that cannot be decoded are left out; FFmpeg errors are recorded in
+/// `failures`.
+fn video_signatures_with_progress(
+    videos: &[PathBuf],
+    failures: &mut Vec<(PathBuf, Error)>,
+    progress: &Option<ProgressCallback>,
+) -> Vec<(PathBuf, VideoSignature)> {
+    let total = videos.len();
+    let done = AtomicUsize::new(0);
+
+    let results: Vec<(PathBuf, Result<Option<VideoSignature>>)> = videos
+        .par_iter()
+        .map(|path| {
+            let result = hasher::video_scene_signature(path);
+            let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+            if let Some(cb) = progress {
+                cb(ProgressEvent::Analyzing { done, total });
+            }
+            (path.clone(), result)
+        })
+        .collect();
+
+    let mut signatures = Vec::new();
+    for (path, result) in results {
+        match result {
+            Ok(Some(signature)) => signatures.push((path, signature)),
+            Ok(None) => log::debug!("Could not compute scene signature for {:?}", path),
+            Err(e) => {
+                log::warn!("Could not compute scene signature for {:?}: {}", path, e);
+                failures.push((path, e));
+            }
+        }
+    }
+    signatures
+}
+
+/// Computes the soundtrack hashes of `videos` in parallel.
+///
+/// Videos without a usable soundtrack are left out; FFmpeg errors are
+/// recorded in `failures`.
+fn soundtrack_hashes_with_progress(
+    videos: &[PathBuf],
+    failures: &mut Vec<(PathBuf, Error)>,
+    progress: &Option<ProgressCallback>,
+    options: &DetectionOptions,
+) -> HashMap<PathBuf, ImageHash> {
+    let total = videos.len();
+    let done = AtomicUsize::new(0);
+
+    let results: Vec<(PathBuf, Result<Option<ImageHash>>)> = videos
+        .par_iter()
+        .map(|path| {
+            let result = hasher::video_audio_hash_with(path, options.perceptual);
+            let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+            if let Some(cb) = progress {
+                cb(ProgressEvent::Analyzing { done, total });
+            }
+            (path.clone(), result)
+        })
+        .collect();
+
+    let mut sounds = HashMap::new();
+    for (path, result) in results {
+        match result {
+            Ok(Some(hash)) => {
+                sounds.insert(path, hash);
+            }
+            Ok(None) => log::debug!("Could not compute soundtrack hash for {:?}", path),
+            Err(e) => {
+                log::warn!("Could not compute soundtrack hash for {:?}: {}", path, e);
+                failures.push((path, e));
+            }
+        }
+    }
+    sounds
+}
+
+/// Re-checks candidate perceptual groups with a larger hash.
+///
+/// Each group is regrouped using [`hasher::strict_perceptual_hash`], so only
+/// files that still match at the higher resolution stay together.
+fn confirm_perceptual_groups(
+    groups: Vec<DuplicateGroup>,
+    failures: &mut Vec<(PathBuf, Error)>,
+    options: &DetectionOptions,
+) -> Vec<DuplicateGroup> {
+    let mut confirmed: Vec<DuplicateGroup> = Vec::new();
+
+    for group in groups {
+        let mut hashes: Vec<(PathBuf, ImageHash)> = Vec::new();
+
+        for path in group.files {
+            match hasher::strict_perceptual_hash(&path, options.perceptual) {
+                Ok(Some(hash)) => hashes.push((path, hash)),
+                Ok(None) => {
+                    log::debug!("Could not compute strict hash for {:?}", path);
+                }
+                Err(e) => {
+                    log::warn!("Could not compute strict hash for {:?}: {}", path, e);
+                    failures.push((path, e));
+                }
+            }
+        }
+
+        // Groups only hold files of one media type
+        let Some((first, _)) = hashes.first() else {
+            continue;
+        };
+        let threshold = options.threshold_for(perceptual_media_type(first));
+        let threshold = hasher::strict_threshold(threshold, options.perceptual);
+        let regrouped = group_similar_hashes(&hashes, threshold, options.grouping);
+        if regrouped.is_empty() {
+            log::debug!("Strict check rejected candidate group of {} files", hashes.len());
+        }
+        confirmed.extend(regrouped);
+    }
+
+    confirmed
+}
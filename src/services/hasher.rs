@@ -1,37 +1,77 @@
 //! Hashing utilities for duplicate detection.
 //!
 //! This module provides two types of hashing:
-//! - **Cryptographic (SHA256)**: For detecting exact duplicates
+//! - **Cryptographic (SHA256 or BLAKE3)**: For detecting exact duplicates
 //! - **Perceptual (pHash)**: For detecting visually similar images and videos
 
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
 use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Once;
 
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::{AnimationDecoder, ImageFormat};
 use image_hasher::{HashAlg, HasherConfig, ImageHash};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
-use crate::error::Result;
+use super::media_registry::{MediaRegistry, HEIF_EXTENSIONS, RAW_EXTENSIONS};
+use crate::error::{Error, FileAction, Result};
 
-/// Size of the buffer used for reading files when computing SHA256.
+/// Size of the buffer used for reading files when computing exact hashes.
 const BUFFER_SIZE: usize = 8192;
 
-/// Supported image extensions for perceptual hashing.
-const IMAGE_EXTENSIONS: &[&str] = &[
-    "jpg", "jpeg", "png", "gif", "bmp", "webp", "tiff", "tif", "ico",
-];
+/// Number of leading bytes read by the quick hash that splits size groups
+/// before full hashing.
+pub const PARTIAL_HASH_LEN: u64 = 64 * 1024;
 
-/// Supported video extensions for perceptual hashing.
-const VIDEO_EXTENSIONS: &[&str] = &[
-    "mp4", "mkv", "avi", "mov", "wmv", "flv", "webm", "m4v", "mpeg", "mpg", "3gp",
-];
+/// Default width and height of the perceptual hash used for grouping.
+pub const HASH_SIZE: u32 = 16;
+
+/// Width and height of the larger hash used to confirm perceptual matches
+/// of [`HASH_SIZE`] hashes. Other hash sizes are confirmed with a hash
+/// scaled by the same factor.
+pub const STRICT_HASH_SIZE: u32 = 32;
+
+/// Smallest accepted perceptual hash size.
+pub const MIN_HASH_SIZE: u32 = 4;
+
+/// Largest accepted perceptual hash size.
+pub const MAX_HASH_SIZE: u32 = 64;
+
+/// Positions of the frames sampled from animated images, as fractions of
+/// their frame count.
+const ANIMATION_FRAME_POSITIONS: [f64; 5] = [0.1, 0.3, 0.5, 0.7, 0.9];
+
+/// Width and height animation frames are scaled to before hashing.
+const ANIMATION_FRAME_SIZE: u32 = 128;
+
+/// Warns the first time an image needing FFmpeg to decode is skipped.
+static MISSING_DECODER_WARNING: Once = Once::new();
+
+/// Files at least this large are memory-mapped for exact hashing: 64 MiB.
+pub const DEFAULT_MMAP_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+/// Current [`mmap_threshold`]; `u64::MAX` disables mapping.
+static MMAP_THRESHOLD: AtomicU64 = AtomicU64::new(DEFAULT_MMAP_THRESHOLD);
+
+/// Number of header bytes read when sniffing file content.
+const SNIFF_LEN: u64 = 64;
 
-/// Supported audio extensions for perceptual hashing.
-const AUDIO_EXTENSIONS: &[&str] = &[
-    // Lossless
-    "wav", "flac", "aiff", "ape",
-    // Lossy
-    "mp3", "m4a", "aac", "ogg", "opus", "wma",
+/// Magic numbers of common formats that are never audio or video.
+const NON_AV_SIGNATURES: &[&[u8]] = &[
+    b"\x89PNG\r\n\x1a\n", // PNG
+    b"\xFF\xD8\xFF", // JPEG
+    b"GIF87a", // GIF
+    b"GIF89a", // GIF
+    b"BM", // BMP
+    b"II*\0", // TIFF (little-endian)
+    b"MM\0*", // TIFF (big-endian)
+    b"%PDF", // PDF
+    b"PK\x03\x04", // ZIP
 ];
 
 /// Media type classification for files.
@@ -43,28 +83,305 @@ pub enum MediaType {
     Unknown,
 }
 
-/// Determines the media type of a file based on its extension.
+/// Determines the media type of a file based on its extension, as mapped by
+/// the [`MediaRegistry::global`] registry.
 pub fn get_media_type(path: &Path) -> MediaType {
-    let ext = path
-        .extension()
-        .and_then(|e| e.to_str())
-        .map(|e| e.to_lowercase());
+    MediaRegistry::global().media_type(path)
+}
+
+/// Cheaply checks a file header to decide whether it could be audio or video.
+///
+/// Returns `false` for empty or unreadable files, files starting with a
+/// well-known image or document signature, and plain text, so FFmpeg is not
+/// spawned for files that merely carry a video or audio extension.
+pub fn may_be_audio_or_video(path: &Path) -> bool {
+    let mut header = Vec::with_capacity(SNIFF_LEN as usize);
+    let read = File::open(path).and_then(|file| file.take(SNIFF_LEN).read_to_end(&mut header));
+
+    if let Err(e) = read {
+        log::debug!("Could not read header of {:?}: {}", path, e);
+        return false;
+    }
+
+    if header.is_empty() {
+        return false;
+    }
+
+    if NON_AV_SIGNATURES.iter().any(|sig| header.starts_with(sig)) {
+        return false;
+    }
 
-    match ext.as_deref() {
-        Some(e) if IMAGE_EXTENSIONS.contains(&e) => MediaType::Image,
-        Some(e) if VIDEO_EXTENSIONS.contains(&e) => MediaType::Video,
-        Some(e) if AUDIO_EXTENSIONS.contains(&e) => MediaType::Audio,
-        _ => MediaType::Unknown,
+    // WebP shares the RIFF container with WAV and AVI
+    if header.len() >= 12 && &header[0..4] == b"RIFF" && &header[8..12] == b"WEBP" {
+        return false;
     }
+
+    let is_text = header
+        .iter()
+        .all(|b| b.is_ascii_graphic() || b.is_ascii_whitespace());
+    !is_text
 }
 
-/// Computes the SHA256 hash of a file.
+/// Cryptographic hash used to detect exact duplicates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    /// SHA-256 (default, matches manifests written by earlier versions).
+    #[default]
+    Sha256,
+    /// BLAKE3, several times faster than SHA-256 on large files.
+    Blake3,
+}
+
+impl std::fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HashAlgorithm::Sha256 => write!(f, "sha256"),
+            HashAlgorithm::Blake3 => write!(f, "blake3"),
+        }
+    }
+}
+
+/// Algorithm used to compute perceptual hashes, one of image_hasher's
+/// [`HashAlg`] variants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PerceptualAlgorithm {
+    /// Compares each pixel to the mean brightness.
+    Mean,
+    /// Compares each pixel to its right neighbour.
+    Gradient,
+    /// Compares each pixel to the one below it.
+    VertGradient,
+    /// Combines horizontal and vertical gradients (default).
+    #[default]
+    DoubleGradient,
+    /// Compares blocks of pixels to the median, robust to small crops.
+    Blockhash,
+}
+
+impl From<PerceptualAlgorithm> for HashAlg {
+    fn from(algorithm: PerceptualAlgorithm) -> Self {
+        match algorithm {
+            PerceptualAlgorithm::Mean => HashAlg::Mean,
+            PerceptualAlgorithm::Gradient => HashAlg::Gradient,
+            PerceptualAlgorithm::VertGradient => HashAlg::VertGradient,
+            PerceptualAlgorithm::DoubleGradient => HashAlg::DoubleGradient,
+            PerceptualAlgorithm::Blockhash => HashAlg::Blockhash,
+        }
+    }
+}
+
+impl std::fmt::Display for PerceptualAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PerceptualAlgorithm::Mean => write!(f, "mean"),
+            PerceptualAlgorithm::Gradient => write!(f, "gradient"),
+            PerceptualAlgorithm::VertGradient => write!(f, "vert-gradient"),
+            PerceptualAlgorithm::DoubleGradient => write!(f, "double-gradient"),
+            PerceptualAlgorithm::Blockhash => write!(f, "blockhash"),
+        }
+    }
+}
+
+/// Size and algorithm of perceptual hashes.
+///
+/// Hashes computed with different settings cannot be compared, so the
+/// settings are recorded next to stored hashes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PerceptualConfig {
+    /// Width and height of the hash.
+    pub hash_size: u32,
+    /// Algorithm computing the hash.
+    pub algorithm: PerceptualAlgorithm,
+}
+
+impl Default for PerceptualConfig {
+    fn default() -> Self {
+        Self {
+            hash_size: HASH_SIZE,
+            algorithm: PerceptualAlgorithm::default(),
+        }
+    }
+}
+
+impl std::fmt::Display for PerceptualConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{0}x{0} {1}", self.hash_size, self.algorithm)
+    }
+}
+
+impl PerceptualConfig {
+    /// Returns the larger configuration used to confirm matches, scaled from
+    /// this one like [`STRICT_HASH_SIZE`] is from [`HASH_SIZE`].
+    pub fn strict(self) -> Self {
+        Self {
+            hash_size: self.hash_size * STRICT_HASH_SIZE / HASH_SIZE,
+            ..self
+        }
+    }
+
+    /// Scales a threshold for [`HASH_SIZE`] hashes to the bit count of these
+    /// hashes, so a threshold tolerates the same share of differing bits
+    /// whatever the hash size.
+    pub fn scale_threshold(self, threshold: u32) -> u32 {
+        threshold * (self.hash_size * self.hash_size) / (HASH_SIZE * HASH_SIZE)
+    }
+
+    /// Returns the length in bytes of the hashes computed with these
+    /// settings. Hashes of another length were computed otherwise and cannot
+    /// be compared with them.
+    pub fn hash_len(self) -> usize {
+        hash_image(&image::DynamicImage::new_luma8(1, 1), self)
+            .as_bytes()
+            .len()
+    }
+}
+
+/// Computes the hash of a file's contents with the given algorithm.
 ///
 /// This is used for detecting exact duplicates (byte-identical files).
-pub fn sha256_hash(path: &Path) -> Result<String> {
-    let file = File::open(path)?;
+/// The result is a lowercase hex string. Files of at least
+/// [`mmap_threshold`] bytes are memory-mapped instead of read in chunks.
+pub fn exact_hash(path: &Path, algorithm: HashAlgorithm) -> Result<String> {
+    exact_hash_with(path, algorithm, mmap_threshold())
+}
+
+/// Like [`exact_hash`], memory-mapping files of at least `mmap_threshold`
+/// bytes, or none when it is `None`.
+///
+/// Mapping saves copying large files through a buffer. When the map fails,
+/// for example on some network filesystems, the file is read in chunks.
+pub fn exact_hash_with(
+    path: &Path,
+    algorithm: HashAlgorithm,
+    mmap_threshold: Option<u64>,
+) -> Result<String> {
+    let file = File::open(path).map_err(Error::on_file(FileAction::Read, path))?;
+
+    if let Some(threshold) = mmap_threshold
+        && file.metadata().is_ok_and(|meta| meta.len() >= threshold)
+    {
+        // SAFETY: the map is only read while hashing. A file modified or
+        // truncated by another process meanwhile may hash to garbage or
+        // fault, which buffered reads of a changing file cannot rule out
+        // either.
+        match unsafe { memmap2::Mmap::map(&file) } {
+            Ok(map) => return Ok(hash_bytes(&map, algorithm)),
+            Err(err) => log::debug!("Cannot map {}, reading it instead: {}", path.display(), err),
+        }
+    }
+
+    let mut reader = BufReader::new(file);
+
+    match algorithm {
+        HashAlgorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            read_chunks(&mut reader, |chunk| hasher.update(chunk))
+                .map_err(Error::on_file(FileAction::Read, path))?;
+            Ok(format!("{:x}", hasher.finalize()))
+        }
+        HashAlgorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            read_chunks(&mut reader, |chunk| {
+                hasher.update(chunk);
+            })
+            .map_err(Error::on_file(FileAction::Read, path))?;
+            Ok(hasher.finalize().to_hex().to_string())
+        }
+    }
+}
+
+/// Hashes bytes already in memory, as [`exact_hash`] hashes a file.
+fn hash_bytes(bytes: &[u8], algorithm: HashAlgorithm) -> String {
+    match algorithm {
+        HashAlgorithm::Sha256 => format!("{:x}", Sha256::digest(bytes)),
+        HashAlgorithm::Blake3 => blake3::hash(bytes).to_hex().to_string(),
+    }
+}
+
+/// Size from which [`exact_hash`] memory-maps files, or `None` when it never
+/// does. Defaults to [`DEFAULT_MMAP_THRESHOLD`].
+pub fn mmap_threshold() -> Option<u64> {
+    match MMAP_THRESHOLD.load(Ordering::Relaxed) {
+        u64::MAX => None,
+        threshold => Some(threshold),
+    }
+}
+
+/// Sets the size from which [`exact_hash`] memory-maps files for the rest of
+/// the process; `None` always reads them in chunks.
+pub fn set_mmap_threshold(threshold: Option<u64>) {
+    MMAP_THRESHOLD.store(threshold.unwrap_or(u64::MAX), Ordering::Relaxed);
+}
+
+/// Computes a BLAKE3 hash of the first `len` bytes of a file.
+///
+/// Files whose beginnings differ cannot be identical, so this cheaply rules
+/// out most candidates before [`exact_hash`] reads whole files. Equal partial
+/// hashes prove nothing on their own. The result is a lowercase hex string.
+pub fn partial_hash(path: &Path, len: u64) -> Result<String> {
+    let file = File::open(path).map_err(Error::on_file(FileAction::Read, path))?;
+    let mut reader = BufReader::new(file).take(len);
+
+    let mut hasher = blake3::Hasher::new();
+    read_chunks(&mut reader, |chunk| {
+        hasher.update(chunk);
+    })
+    .map_err(Error::on_file(FileAction::Read, path))?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Computes a CRC32 checksum of a whole file.
+///
+/// Far cheaper to compute than [`exact_hash`], but collisions are common
+/// enough that equal checksums only make files candidates for it.
+pub fn crc32_hash(path: &Path) -> Result<u32> {
+    let file = File::open(path).map_err(Error::on_file(FileAction::Read, path))?;
     let mut reader = BufReader::new(file);
-    let mut hasher = Sha256::new();
+
+    let mut hasher = crc32fast::Hasher::new();
+    read_chunks(&mut reader, |chunk| hasher.update(chunk))
+        .map_err(Error::on_file(FileAction::Read, path))?;
+    Ok(hasher.finalize())
+}
+
+/// Computes a BLAKE3 hash of the first and last `len` bytes of a file.
+///
+/// Quick scans treat files of the same size whose ends hash alike as
+/// probable duplicates without reading them in full, so files differing only
+/// in the middle collide. Files no larger than `2 * len` are hashed whole.
+/// The result is a lowercase hex string.
+pub fn head_tail_hash(path: &Path, len: u64) -> Result<String> {
+    let mut file = File::open(path).map_err(Error::on_file(FileAction::Read, path))?;
+    let size = file.metadata().map_err(Error::on_file(FileAction::Read, path))?.len();
+
+    let mut hasher = blake3::Hasher::new();
+    let head = len.min(size);
+    read_chunks(&mut BufReader::new(&mut file).take(head), |chunk| {
+        hasher.update(chunk);
+    })
+    .map_err(Error::on_file(FileAction::Read, path))?;
+    // In short files the tail starts right where the head ended
+    let tail_start = size.saturating_sub(len).max(head);
+    file.seek(SeekFrom::Start(tail_start))
+        .map_err(Error::on_file(FileAction::Read, path))?;
+    read_chunks(&mut BufReader::new(&mut file), |chunk| {
+        hasher.update(chunk);
+    })
+    .map_err(Error::on_file(FileAction::Read, path))?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Computes the SHA256 hash of a file.
+///
+/// Shorthand for [`exact_hash`] with [`HashAlgorithm::Sha256`].
+pub fn sha256_hash(path: &Path) -> Result<String> {
+    exact_hash(path, HashAlgorithm::Sha256)
+}
+
+/// Feeds the contents of `reader` to `update` in fixed-size chunks.
+fn read_chunks(reader: &mut impl Read, mut update: impl FnMut(&[u8])) -> io::Result<()> {
     let mut buffer = [0u8; BUFFER_SIZE];
 
     loop {
@@ -72,11 +389,10 @@ pub fn sha256_hash(path: &Path) -> Result<String> {
         if bytes_read == 0 {
             break;
         }
-        hasher.update(&buffer[..bytes_read]);
+        update(&buffer[..bytes_read]);
     }
 
-    let result = hasher.finalize();
-    Ok(format!("{:x}", result))
+    Ok(())
 }
 
 /// Computes the perceptual hash (pHash) of an image.
@@ -84,92 +400,279 @@ pub fn sha256_hash(path: &Path) -> Result<String> {
 /// This is used for detecting visually similar images that may have
 /// different compression, format, or minor modifications.
 ///
+/// Camera RAW files the `image` crate cannot open are hashed through the
+/// preview FFmpeg extracts from them, and HEIC, HEIF and AVIF images through
+/// the picture FFmpeg decodes from them.
+///
+/// The image is first turned to its EXIF display orientation, so a photo
+/// rotated on disk matches a copy that only carries an orientation tag.
+///
+/// Animated GIFs and APNGs are hashed from frames sampled across the
+/// animation, so animations sharing only their first frame do not match.
+///
 /// Returns `None` if the file is not a valid image.
 pub fn perceptual_hash(path: &Path) -> Result<Option<ImageHash>> {
-    let img = match image::open(path) {
-        Ok(img) => img,
+    perceptual_hash_with(path, PerceptualConfig::default())
+}
+
+/// Like [`perceptual_hash`], with the hash size and algorithm of `config`.
+pub fn perceptual_hash_with(path: &Path, config: PerceptualConfig) -> Result<Option<ImageHash>> {
+    if let Some(hash) = animation_perceptual_hash(path, config) {
+        return Ok(Some(hash));
+    }
+
+    let img = match open_image(path) {
+        Ok(img) => apply_exif_orientation(path, img),
+        Err(e) if is_raw(path) || is_heif(path) => {
+            log::debug!("Could not open {:?} directly, decoding it with FFmpeg: {}", path, e);
+            let Some(img) = ffmpeg_image(path)? else {
+                return Ok(None);
+            };
+            // FFmpeg already turns HEIF images as their container says
+            if is_heif(path) {
+                img
+            } else {
+                apply_exif_orientation(path, img)
+            }
+        }
         Err(e) => {
             log::debug!("Could not open image {:?}: {}", path, e);
             return Ok(None);
         }
     };
 
-    let hasher = HasherConfig::new()
-        .hash_alg(HashAlg::DoubleGradient)
-        .hash_size(16, 16)
-        .to_hasher();
+    Ok(Some(hash_image(&img, config)))
+}
+
+/// Hashes an animated GIF or APNG from frames sampled across the animation,
+/// stacked into one image like the frames of a video.
+///
+/// Returns `None` for static images, including animations with a single
+/// frame, so they are hashed from their only frame.
+fn animation_perceptual_hash(path: &Path, config: PerceptualConfig) -> Option<ImageHash> {
+    let frames = animation_frames(path)?;
+    if frames.len() < 2 {
+        return None;
+    }
+
+    let size = ANIMATION_FRAME_SIZE;
+    let height = size * ANIMATION_FRAME_POSITIONS.len() as u32;
+    let mut composite = image::RgbaImage::new(size, height);
+    for (i, position) in ANIMATION_FRAME_POSITIONS.iter().enumerate() {
+        let frame = &frames[(frames.len() as f64 * position) as usize];
+        image::imageops::replace(&mut composite, frame, 0, i64::from(i as u32 * size));
+    }
 
-    let hash = hasher.hash_image(&img);
-    Ok(Some(hash))
+    log::debug!("Hashed {} frames of animation {:?}", frames.len(), path);
+    Some(hash_image(&image::DynamicImage::ImageRgba8(composite), config))
 }
 
-/// Computes the perceptual hash of a video by extracting key frames.
+/// Decodes an image, telling its format from its content rather than its
+/// extension when they differ, so extensions registered as images through
+/// [`MediaRegistry`] are decoded too.
+fn open_image(path: &Path) -> image::ImageResult<image::DynamicImage> {
+    image::ImageReader::open(path)?.with_guessed_format()?.decode()
+}
+
+/// Decodes the frames of an animated GIF or APNG, scaled down so long
+/// animations stay small in memory. Decoding stops at the first broken frame.
 ///
-/// Extracts frames at regular intervals and computes a combined hash.
-/// Returns `None` if the file is not a valid video or FFmpeg is not available.
-pub fn video_perceptual_hash(path: &Path) -> Result<Option<ImageHash>> {
-    use ffmpeg_sidecar::command::FfmpegCommand;
-    use ffmpeg_sidecar::event::FfmpegEvent;
+/// Returns `None` if the file is neither a GIF nor an APNG.
+fn animation_frames(path: &Path) -> Option<Vec<image::RgbaImage>> {
+    let format = image::ImageReader::open(path)
+        .ok()?
+        .with_guessed_format()
+        .ok()?
+        .format()?;
+    let reader = BufReader::new(File::open(path).ok()?);
+    let frames = match format {
+        ImageFormat::Gif => GifDecoder::new(reader).ok()?.into_frames(),
+        ImageFormat::Png => {
+            let decoder = PngDecoder::new(reader).ok()?;
+            if !decoder.is_apng().ok()? {
+                return None;
+            }
+            decoder.apng().ok()?.into_frames()
+        }
+        _ => return None,
+    };
 
-    // Number of frames to extract for hashing
-    const FRAMES_TO_EXTRACT: usize = 5;
-    // Frame dimensions for hashing (smaller = faster)
-    const FRAME_WIDTH: u32 = 160;
-    const FRAME_HEIGHT: u32 = 120;
+    let size = ANIMATION_FRAME_SIZE;
+    Some(
+        frames
+            .map_while(|frame| frame.ok())
+            .map(|frame| image::imageops::thumbnail(frame.buffer(), size, size))
+            .collect(),
+    )
+}
+
+/// Reads the EXIF orientation tag of a file, from 1 (upright) to 8.
+///
+/// Returns `None` if the file has no EXIF data or no orientation tag.
+pub fn exif_orientation(path: &Path) -> Option<u32> {
+    let file = File::open(path).ok()?;
+    let exif = exif::Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .ok()?;
+    exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)?
+        .value
+        .get_uint(0)
+}
 
+/// Rotates and flips `img` to the display orientation recorded in the EXIF
+/// data of `path`. Images without an orientation tag are returned unchanged.
+fn apply_exif_orientation(path: &Path, img: image::DynamicImage) -> image::DynamicImage {
+    let Some(orientation) = exif_orientation(path) else {
+        return img;
+    };
+    if orientation != 1 {
+        log::debug!("Applying EXIF orientation {} to {:?}", orientation, path);
+    }
+
+    match orientation {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => img,
+    }
+}
+
+/// Checks if a file has a camera RAW extension.
+pub fn is_raw(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| RAW_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Checks if a file has a HEIF container extension: HEIC, HEIF or AVIF.
+pub fn is_heif(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| HEIF_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Decodes the first picture of a file with FFmpeg: the preview of a camera
+/// RAW file, or the primary image of a HEIF file.
+///
+/// Returns `None` if FFmpeg is not available, which is logged once per run,
+/// or finds no decodable image.
+fn ffmpeg_image(path: &Path) -> Result<Option<image::DynamicImage>> {
     let path_str = path.to_string_lossy();
 
-    // Use FFmpeg to extract frames as raw RGB data
-    // We'll extract 5 frames evenly distributed throughout the video
-    let mut child = match FfmpegCommand::new()
-        .input(&*path_str)
-        .args([
-            "-vf",
-            &format!("select='not(mod(n\\,30))',scale={}:{}", FRAME_WIDTH, FRAME_HEIGHT),
+    // Decode the first picture FFmpeg finds in the file and emit it as PNG
+    let Some(output) = run_media_tool(
+        "ffmpeg",
+        &[
+            "-i",
+            &path_str,
             "-frames:v",
-            &FRAMES_TO_EXTRACT.to_string(),
+            "1",
             "-f",
-            "rawvideo",
-            "-pix_fmt",
-            "rgb24",
+            "image2pipe",
+            "-vcodec",
+            "png",
             "-",
-        ])
-        .spawn()
-    {
-        Ok(child) => child,
-        Err(e) => {
-            log::debug!("Could not spawn FFmpeg for {:?}: {}", path, e);
-            return Ok(None);
-        }
+        ],
+        path,
+    )?
+    else {
+        MISSING_DECODER_WARNING.call_once(|| {
+            log::warn!(
+                "FFmpeg is not installed: HEIC, AVIF and camera RAW images the image \
+                 decoder cannot read are left out of perceptual comparison"
+            );
+        });
+        return Ok(None);
     };
 
-    let iter = match child.iter() {
-        Ok(iter) => iter,
+    if !output.status.success() || output.stdout.is_empty() {
+        log::debug!(
+            "FFmpeg could not decode a picture from {:?} (status: {:?})",
+            path,
+            output.status
+        );
+        return Ok(None);
+    }
+
+    match image::load_from_memory(&output.stdout) {
+        Ok(img) => Ok(Some(img)),
         Err(e) => {
-            log::debug!("Could not create FFmpeg iterator for {:?}: {}", path, e);
-            return Ok(None);
+            log::debug!("Could not decode the picture FFmpeg read from {:?}: {}", path, e);
+            Ok(None)
         }
+    }
+}
+
+/// Computes the perceptual hash of a video by extracting key frames.
+///
+/// Extracts frames at fixed fractions of the video's duration, so re-encodes
+/// with a different frame rate sample the same content, and computes a
+/// combined hash. Returns `None` if the file is not a valid video or FFmpeg
+/// is not available, and an [`Error::Ffmpeg`] if FFmpeg crashes.
+pub fn video_perceptual_hash(path: &Path) -> Result<Option<ImageHash>> {
+    video_perceptual_hash_with(path, PerceptualConfig::default())
+}
+
+/// Like [`video_perceptual_hash`], with the hash size and algorithm of `config`.
+pub fn video_perceptual_hash_with(
+    path: &Path,
+    config: PerceptualConfig,
+) -> Result<Option<ImageHash>> {
+    // Positions of the extracted frames, as fractions of the duration
+    const FRAME_POSITIONS: [f64; 5] = [0.1, 0.3, 0.5, 0.7, 0.9];
+    // Frame dimensions for hashing (smaller = faster)
+    const FRAME_WIDTH: u32 = 160;
+    const FRAME_HEIGHT: u32 = 120;
+
+    if !may_be_audio_or_video(path) {
+        log::debug!("Skipping FFmpeg for {:?}: content is not video", path);
+        return Ok(None);
+    }
+
+    let Some(duration) = media_duration(path)? else {
+        log::debug!("Could not determine the duration of {:?}", path);
+        return Ok(None);
     };
 
-    let mut frame_data: Vec<u8> = Vec::new();
-    let mut frames_collected = 0;
+    let path_str = path.to_string_lossy();
     let frame_size = (FRAME_WIDTH * FRAME_HEIGHT * 3) as usize;
+    let mut frame_data: Vec<u8> = Vec::with_capacity(frame_size * FRAME_POSITIONS.len());
 
-    for event in iter {
-        match event {
-            FfmpegEvent::OutputFrame(frame) => {
-                frame_data.extend_from_slice(&frame.data);
-                frames_collected += 1;
-                if frames_collected >= FRAMES_TO_EXTRACT {
-                    break;
-                }
-            }
-            FfmpegEvent::Error(e) => {
-                log::debug!("FFmpeg error for {:?}: {}", path, e);
-                return Ok(None);
-            }
-            _ => {}
+    // Seek to each position and extract a single frame as raw RGB data
+    for position in FRAME_POSITIONS {
+        let timestamp = format!("{:.3}", duration * position);
+        let Some(output) = run_media_tool(
+            "ffmpeg",
+            &[
+                "-ss",
+                &timestamp,
+                "-i",
+                &path_str,
+                "-frames:v",
+                "1",
+                "-vf",
+                &format!("scale={}:{}", FRAME_WIDTH, FRAME_HEIGHT),
+                "-f",
+                "rawvideo",
+                "-pix_fmt",
+                "rgb24",
+                "-",
+            ],
+            path,
+        )?
+        else {
+            return Ok(None);
+        };
+
+        if !output.status.success() || output.stdout.len() < frame_size {
+            log::debug!("No frame extracted from {:?} at {}s", path, timestamp);
+            continue;
         }
+        frame_data.extend_from_slice(&output.stdout[..frame_size]);
     }
 
     if frame_data.is_empty() {
@@ -203,33 +706,259 @@ pub fn video_perceptual_hash(path: &Path) -> Result<Option<ImageHash>> {
 
     let img = image::DynamicImage::ImageRgb8(img_buffer);
 
-    let hasher = HasherConfig::new()
-        .hash_alg(HashAlg::DoubleGradient)
-        .hash_size(16, 16)
-        .to_hasher();
+    Ok(Some(hash_image(&img, config)))
+}
+
+/// Minimum FFmpeg scene score for a frame to count as a scene change.
+const SCENE_CHANGE_SCORE: f64 = 0.3;
+
+/// Maximum number of scene frames kept in a [`VideoSignature`].
+const MAX_SCENE_FRAMES: usize = 64;
+
+/// Share of the shorter signature's scenes that must appear in the other for
+/// two videos to match.
+pub const MIN_SHARED_SCENES: f64 = 0.5;
+
+/// Perceptual hashes of the frames where a video's scenes change.
+///
+/// Unlike the single composite hash of [`video_perceptual_hash`], scenes are
+/// matched one by one, so a trimmed copy still shares most of its scenes with
+/// the full video.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VideoSignature {
+    /// Hashes of the first frame and of every scene change, in playback order.
+    pub frames: Vec<ImageHash>,
+}
+
+impl VideoSignature {
+    /// Returns the share of the shorter signature's frames that have a match
+    /// within `threshold` in the other signature, from 0.0 to 1.0.
+    pub fn shared_scenes(&self, other: &VideoSignature, threshold: u32) -> f64 {
+        let (shorter, longer) = if self.frames.len() <= other.frames.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        if shorter.frames.is_empty() {
+            return 0.0;
+        }
+
+        let matched = shorter
+            .frames
+            .iter()
+            .filter(|frame| {
+                longer
+                    .frames
+                    .iter()
+                    .any(|candidate| are_similar(frame, candidate, threshold).unwrap_or(false))
+            })
+            .count();
+        matched as f64 / shorter.frames.len() as f64
+    }
+
+    /// Checks if at least [`MIN_SHARED_SCENES`] of the shorter signature's
+    /// scenes appear in the other.
+    pub fn matches(&self, other: &VideoSignature, threshold: u32) -> bool {
+        self.shared_scenes(other, threshold) >= MIN_SHARED_SCENES
+    }
+}
+
+/// Computes the scene signature of a video from the frames where FFmpeg
+/// detects a scene change, plus the first frame.
+///
+/// Slower than [`video_perceptual_hash`] since the whole video is decoded.
+/// Returns `None` if the file is not a valid video or FFmpeg is not available,
+/// and an [`Error::Ffmpeg`] if FFmpeg crashes.
+pub fn video_scene_signature(path: &Path) -> Result<Option<VideoSignature>> {
+    const FRAME_WIDTH: u32 = 160;
+    const FRAME_HEIGHT: u32 = 120;
+
+    if !may_be_audio_or_video(path) {
+        log::debug!("Skipping FFmpeg for {:?}: content is not video", path);
+        return Ok(None);
+    }
+
+    let filter = format!(
+        "select='eq(n,0)+gt(scene,{})',scale={}:{}",
+        SCENE_CHANGE_SCORE, FRAME_WIDTH, FRAME_HEIGHT
+    );
+    let Some(output) = run_media_tool(
+        "ffmpeg",
+        &[
+            "-i",
+            &path.to_string_lossy(),
+            "-vf",
+            &filter,
+            "-vsync",
+            "vfr",
+            "-frames:v",
+            &MAX_SCENE_FRAMES.to_string(),
+            "-f",
+            "rawvideo",
+            "-pix_fmt",
+            "rgb24",
+            "-",
+        ],
+        path,
+    )?
+    else {
+        return Ok(None);
+    };
+
+    if !output.status.success() {
+        log::debug!("FFmpeg could not detect scenes in {:?}", path);
+        return Ok(None);
+    }
+
+    let frame_size = (FRAME_WIDTH * FRAME_HEIGHT * 3) as usize;
+    let frames: Vec<ImageHash> = output
+        .stdout
+        .chunks_exact(frame_size)
+        .filter_map(|frame| image::RgbImage::from_raw(FRAME_WIDTH, FRAME_HEIGHT, frame.to_vec()))
+        .map(|frame| {
+            let frame = image::DynamicImage::ImageRgb8(frame);
+            hash_image(&frame, PerceptualConfig::default())
+        })
+        .collect();
+
+    if frames.is_empty() {
+        log::debug!("No frames extracted from {:?}", path);
+        return Ok(None);
+    }
+
+    log::debug!("Found {} scenes in {:?}", frames.len(), path);
+    Ok(Some(VideoSignature { frames }))
+}
+
+/// Returns the duration of a media file in seconds, as reported by ffprobe.
+fn media_duration(path: &Path) -> Result<Option<f64>> {
+    let Some(output) = run_media_tool(
+        "ffprobe",
+        &[
+            "-v",
+            "error",
+            "-show_entries",
+            "format=duration",
+            "-of",
+            "default=noprint_wrappers=1:nokey=1",
+            &path.to_string_lossy(),
+        ],
+        path,
+    )?
+    else {
+        return Ok(None);
+    };
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|duration| duration.is_finite() && *duration > 0.0))
+}
+
+/// Runs FFmpeg or ffprobe on `path` and returns its output, or `None` if the
+/// program is not installed.
+///
+/// A non-zero exit status is returned as is, since FFmpeg also exits with one
+/// for files it cannot decode. The program failing to start for another
+/// reason or being killed by a signal is an [`Error::Ffmpeg`].
+fn run_media_tool(program: &str, args: &[&str], path: &Path) -> Result<Option<Output>> {
+    let output = match Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            log::debug!("Could not spawn {} for {:?}: {}", program, path, e);
+            return Ok(None);
+        }
+        Err(e) => return Err(Error::Ffmpeg(format!("could not run {}: {}", program, e))),
+    };
+
+    // Exit codes are set by FFmpeg itself; a missing code means it crashed
+    if output.status.code().is_none() {
+        return Err(Error::Ffmpeg(format!("{} crashed ({})", program, output.status)));
+    }
 
-    let hash = hasher.hash_image(&img);
-    Ok(Some(hash))
+    Ok(Some(output))
 }
 
 /// Computes the perceptual hash of an audio file by generating a spectrogram.
 ///
 /// Uses FFmpeg to create a spectrogram image from the audio, then hashes it
 /// like a regular image. Returns `None` if the file is not valid audio or
-/// FFmpeg is not available.
+/// FFmpeg is not available, and an [`Error::Ffmpeg`] if FFmpeg crashes.
 pub fn audio_perceptual_hash(path: &Path) -> Result<Option<ImageHash>> {
-    use std::process::{Command, Stdio};
+    audio_perceptual_hash_with(path, PerceptualConfig::default())
+}
 
-    let path_str = path.to_string_lossy();
+/// Like [`audio_perceptual_hash`], with the hash size and algorithm of `config`.
+pub fn audio_perceptual_hash_with(
+    path: &Path,
+    config: PerceptualConfig,
+) -> Result<Option<ImageHash>> {
+    if !may_be_audio_or_video(path) {
+        log::debug!("Skipping FFmpeg for {:?}: content is not audio", path);
+        return Ok(None);
+    }
 
-    // Use FFmpeg to generate spectrogram as PNG to stdout
     // showspectrumpic creates a single image from the entire audio
-    let output = match Command::new("ffmpeg")
-        .args([
+    let spectrogram = spectrogram(path, "showspectrumpic=s=512x256:color=intensity")?;
+    Ok(spectrogram.map(|img| hash_image(&img, config)))
+}
+
+/// Mean brightness, out of 255, below which a soundtrack's spectrogram is
+/// taken to be silence.
+const SILENCE_LEVEL: f64 = 2.0;
+
+/// Computes the perceptual hash of a video's soundtrack from its spectrogram,
+/// with the hash size and algorithm of `config`.
+///
+/// Unlike [`audio_perceptual_hash_with`], the spectrogram has no legend, so
+/// videos without sound all look alike; they return `None`, as do files
+/// without an audio track or when FFmpeg is not available. FFmpeg crashing is
+/// an [`Error::Ffmpeg`].
+pub fn video_audio_hash_with(path: &Path, config: PerceptualConfig) -> Result<Option<ImageHash>> {
+    if !may_be_audio_or_video(path) {
+        log::debug!("Skipping FFmpeg for {:?}: content is not video", path);
+        return Ok(None);
+    }
+
+    // Only the first audio track; FFmpeg fails if there is none
+    let filter = "[0:a:0]showspectrumpic=s=512x256:color=intensity:legend=0";
+    let Some(img) = spectrogram(path, filter)? else {
+        return Ok(None);
+    };
+
+    let luma = img.to_luma8();
+    let pixels = luma.as_raw();
+    let brightness = pixels.iter().map(|&p| f64::from(p)).sum::<f64>() / pixels.len().max(1) as f64;
+    if brightness < SILENCE_LEVEL {
+        log::debug!("Soundtrack of {:?} is silent", path);
+        return Ok(None);
+    }
+
+    Ok(Some(hash_image(&img, config)))
+}
+
+/// Renders the audio of `path` as an image with the FFmpeg `filter`, or
+/// returns `None` if FFmpeg is not available or cannot render it.
+fn spectrogram(path: &Path, filter: &str) -> Result<Option<image::DynamicImage>> {
+    // Use FFmpeg to generate spectrogram as PNG to stdout
+    let Some(output) = run_media_tool(
+        "ffmpeg",
+        &[
             "-i",
-            &path_str,
+            &path.to_string_lossy(),
             "-lavfi",
-            "showspectrumpic=s=512x256:color=intensity",
+            filter,
             "-frames:v",
             "1",
             "-f",
@@ -237,17 +966,11 @@ pub fn audio_perceptual_hash(path: &Path) -> Result<Option<ImageHash>> {
             "-vcodec",
             "png",
             "-",
-        ])
-        .stdin(Stdio::null())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::null())
-        .output()
-    {
-        Ok(output) => output,
-        Err(e) => {
-            log::debug!("Could not spawn FFmpeg for audio {:?}: {}", path, e);
-            return Ok(None);
-        }
+        ],
+        path,
+    )?
+    else {
+        return Ok(None);
     };
 
     if !output.status.success() || output.stdout.is_empty() {
@@ -260,21 +983,13 @@ pub fn audio_perceptual_hash(path: &Path) -> Result<Option<ImageHash>> {
     }
 
     // Load the PNG from stdout bytes
-    let img = match image::load_from_memory(&output.stdout) {
-        Ok(img) => img,
+    match image::load_from_memory(&output.stdout) {
+        Ok(img) => Ok(Some(img)),
         Err(e) => {
             log::debug!("Could not decode spectrogram PNG for {:?}: {}", path, e);
-            return Ok(None);
+            Ok(None)
         }
-    };
-
-    let hasher = HasherConfig::new()
-        .hash_alg(HashAlg::DoubleGradient)
-        .hash_size(16, 16)
-        .to_hasher();
-
-    let hash = hasher.hash_image(&img);
-    Ok(Some(hash))
+    }
 }
 
 /// Computes the perceptual hash for any supported media type.
@@ -284,42 +999,187 @@ pub fn audio_perceptual_hash(path: &Path) -> Result<Option<ImageHash>> {
 ///
 /// Returns `None` if the file is not a supported media type or cannot be processed.
 pub fn media_perceptual_hash(path: &Path) -> Result<Option<ImageHash>> {
+    media_perceptual_hash_with(path, PerceptualConfig::default())
+}
+
+/// Computes a perceptual hash with the [strict](PerceptualConfig::strict)
+/// settings of `config` for any supported media type.
+///
+/// The larger hash captures more detail and is used to confirm matches found
+/// with [`media_perceptual_hash_with`]. Distances between strict hashes should
+/// be compared against a threshold scaled by [`strict_threshold`].
+pub fn strict_perceptual_hash(path: &Path, config: PerceptualConfig) -> Result<Option<ImageHash>> {
+    media_perceptual_hash_with(path, config.strict())
+}
+
+/// Like [`media_perceptual_hash`], with the hash size and algorithm of `config`.
+pub fn media_perceptual_hash_with(
+    path: &Path,
+    config: PerceptualConfig,
+) -> Result<Option<ImageHash>> {
     match get_media_type(path) {
-        MediaType::Image => perceptual_hash(path),
-        MediaType::Video => video_perceptual_hash(path),
-        MediaType::Audio => audio_perceptual_hash(path),
+        MediaType::Image => perceptual_hash_with(path, config),
+        MediaType::Video => video_perceptual_hash_with(path, config),
+        MediaType::Audio => audio_perceptual_hash_with(path, config),
         MediaType::Unknown => {
             // Try as image first (some formats might not have standard extensions)
-            perceptual_hash(path)
+            perceptual_hash_with(path, config)
         }
     }
 }
 
+/// Hashes a decoded image with the perceptual hash settings of `config`.
+fn hash_image(img: &image::DynamicImage, config: PerceptualConfig) -> ImageHash {
+    HasherConfig::new()
+        .hash_alg(config.algorithm.into())
+        .hash_size(config.hash_size, config.hash_size)
+        .to_hasher()
+        .hash_image(img)
+}
+
 /// Calculates the Hamming distance between two perceptual hashes.
 ///
 /// Lower distance means more similar images.
 /// - 0: Identical images
 /// - 1-10: Very similar (likely duplicates)
 /// - 10+: Different images
+///
+/// # Panics
+///
+/// Panics if the hashes have different sizes, as hashes computed with
+/// different [`PerceptualConfig`]s do. Use [`try_hamming_distance`] for
+/// hashes that may have been.
 pub fn hamming_distance(hash1: &ImageHash, hash2: &ImageHash) -> u32 {
-    hash1.dist(hash2)
+    match try_hamming_distance(hash1, hash2) {
+        Ok(distance) => distance,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// Calculates the Hamming distance between two perceptual hashes, failing
+/// with [`Error::HashSizeMismatch`] if they have different sizes rather than
+/// comparing only part of them.
+pub fn try_hamming_distance(hash1: &ImageHash, hash2: &ImageHash) -> Result<u32> {
+    let (len1, len2) = (hash1.as_bytes().len(), hash2.as_bytes().len());
+    if len1 != len2 {
+        return Err(Error::HashSizeMismatch(len1, len2));
+    }
+    Ok(hash1.dist(hash2))
 }
 
-/// Threshold for considering two images as perceptually similar.
+/// Returns, for each bit of two perceptual hashes in the order they store
+/// them, whether it differs. There are [`hamming_distance`] differing bits;
+/// hashes of different sizes are compared up to the shorter one.
+pub fn differing_bits(hash1: &ImageHash, hash2: &ImageHash) -> Vec<bool> {
+    hash1
+        .as_bytes()
+        .iter()
+        .zip(hash2.as_bytes())
+        .flat_map(|(a, b)| {
+            let diff = a ^ b;
+            (0..8).map(move |bit| diff & (1 << bit) != 0)
+        })
+        .collect()
+}
+
+/// Default threshold for considering two images as perceptually similar.
 /// Images with Hamming distance <= this value are considered duplicates.
-pub const SIMILARITY_THRESHOLD: u32 = 10;
+pub const DEFAULT_SIMILARITY_THRESHOLD: u32 = 10;
+
+/// Largest accepted similarity threshold.
+pub const MAX_SIMILARITY_THRESHOLD: u32 = 64;
 
-/// Checks if two perceptual hashes are similar enough to be considered duplicates.
-pub fn are_similar(hash1: &ImageHash, hash2: &ImageHash) -> bool {
-    hamming_distance(hash1, hash2) <= SIMILARITY_THRESHOLD
+/// Scales a threshold for [`HASH_SIZE`] hashes to the bit count of the
+/// strict hashes of `config`.
+pub fn strict_threshold(threshold: u32, config: PerceptualConfig) -> u32 {
+    config.strict().scale_threshold(threshold)
+}
+
+/// Checks if two perceptual hashes are within `threshold` of each other.
+///
+/// Fails with [`Error::HashSizeMismatch`] if the hashes have different
+/// sizes, since they were then computed with different settings.
+pub fn are_similar(hash1: &ImageHash, hash2: &ImageHash, threshold: u32) -> Result<bool> {
+    Ok(try_hamming_distance(hash1, hash2)? <= threshold)
+}
+
+/// Result of comparing two files with [`compare`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    /// The files are of the same media type and were compared.
+    Compared {
+        /// Whether the files are byte-for-byte identical.
+        exact: bool,
+        /// Hamming distance between the files' perceptual hashes, or `None`
+        /// if either could not be hashed, e.g. because it is not media.
+        distance: Option<u32>,
+        /// Similarity from 0 to 100: 100 for exact duplicates, otherwise the
+        /// share of matching perceptual hash bits, and 0 without a distance.
+        similarity: f64,
+    },
+    /// The files are of different media types, e.g. an image and a video,
+    /// so their perceptual hashes cannot be compared.
+    NotComparable(MediaType, MediaType),
+}
+
+/// Compares two files, without building a whole duplicate report.
+///
+/// Files are exact duplicates if their SHA-256 hashes match, and their
+/// perceptual hashes are computed with [`media_perceptual_hash`].
+pub fn compare(a: &Path, b: &Path) -> Result<Comparison> {
+    let (type_a, type_b) = (get_media_type(a), get_media_type(b));
+    if type_a != type_b {
+        return Ok(Comparison::NotComparable(type_a, type_b));
+    }
+
+    // Files of different sizes cannot be identical, so skip reading them
+    let exact = file_size(a)? == file_size(b)? && sha256_hash(a)? == sha256_hash(b)?;
+    let (distance, similarity) = match (media_perceptual_hash(a)?, media_perceptual_hash(b)?) {
+        (Some(hash_a), Some(hash_b)) => {
+            let distance = hamming_distance(&hash_a, &hash_b);
+            let bits = (hash_a.as_bytes().len() * 8) as f64;
+            (Some(distance), 100.0 * (1.0 - distance as f64 / bits))
+        }
+        _ => (None, 0.0),
+    };
+
+    Ok(Comparison::Compared {
+        exact,
+        distance,
+        similarity: if exact { 100.0 } else { similarity },
+    })
 }
 
 /// Gets the file size in bytes.
 pub fn file_size(path: &Path) -> Result<u64> {
-    let metadata = std::fs::metadata(path)?;
+    let metadata = std::fs::metadata(path)
+        .map_err(Error::on_file(FileAction::Read, path))?;
     Ok(metadata.len())
 }
 
+/// Identifies the physical file behind `path`: hard links and symbolic links
+/// to the same data share an identifier.
+///
+/// Returns `None` on platforms without inode information or if the file
+/// cannot be read.
+#[cfg(unix)]
+pub fn file_id(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+/// Identifies the physical file behind `path`: hard links and symbolic links
+/// to the same data share an identifier.
+///
+/// Returns `None` on platforms without inode information or if the file
+/// cannot be read.
+#[cfg(not(unix))]
+pub fn file_id(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
+
 /// Checks if FFmpeg is available on the system.
 pub fn is_ffmpeg_available() -> bool {
     ffmpeg_sidecar::command::ffmpeg_is_installed()
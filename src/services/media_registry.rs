@@ -0,0 +1,165 @@
+//! Mapping of file extensions to media types.
+//!
+//! [`MediaRegistry`] starts from the extensions supported out of the box and
+//! can be extended from a JSON file, so formats FFmpeg or the `image` crate
+//! can still read are scanned as media without recompiling:
+//!
+//! ```json
+//! { "image": ["jxl"], "video": ["mts", "m2ts"], "audio": ["dsf"] }
+//! ```
+//!
+//! [`hasher::get_media_type`](super::hasher::get_media_type) consults the
+//! registry installed for the process with [`MediaRegistry::install`], or the
+//! built-in one.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+use super::hasher::MediaType;
+use crate::error::{Error, FileAction, Result};
+
+/// Supported image extensions for perceptual hashing.
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "bmp", "webp", "tiff", "tif", "ico",
+];
+
+/// Camera RAW extensions, hashed through their embedded preview when the
+/// `image` crate cannot decode them.
+pub(crate) const RAW_EXTENSIONS: &[&str] = &[
+    "cr2", "cr3", "crw", "nef", "nrw", "arw", "srf", "sr2", "dng", "raf", "orf", "rw2", "pef",
+    "srw", "raw", "rwl", "3fr", "erf", "kdc", "mrw", "x3f",
+];
+
+/// HEIF container extensions, such as iPhone photos, decoded with FFmpeg
+/// when the `image` crate cannot decode them.
+pub(crate) const HEIF_EXTENSIONS: &[&str] = &["heic", "heif", "avif"];
+
+/// Supported video extensions for perceptual hashing.
+const VIDEO_EXTENSIONS: &[&str] = &[
+    "mp4", "mkv", "avi", "mov", "wmv", "flv", "webm", "m4v", "mpeg", "mpg", "3gp",
+];
+
+/// Supported audio extensions for perceptual hashing.
+const AUDIO_EXTENSIONS: &[&str] = &[
+    // Lossless
+    "wav", "flac", "aiff", "ape",
+    // Lossy
+    "mp3", "m4a", "aac", "ogg", "opus", "wma",
+];
+
+/// Registry used when none was installed.
+static GLOBAL: OnceLock<MediaRegistry> = OnceLock::new();
+
+/// Extensions to register, as read from a media types file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct MediaTypesFile {
+    image: Vec<String>,
+    video: Vec<String>,
+    audio: Vec<String>,
+}
+
+/// Media type of each known extension, compared case-insensitively.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaRegistry {
+    types: HashMap<String, MediaType>,
+}
+
+impl Default for MediaRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl MediaRegistry {
+    /// Creates a registry of the extensions supported out of the box.
+    pub fn builtin() -> Self {
+        let mut registry = Self {
+            types: HashMap::new(),
+        };
+        for (extensions, media_type) in [
+            (IMAGE_EXTENSIONS, MediaType::Image),
+            (RAW_EXTENSIONS, MediaType::Image),
+            (HEIF_EXTENSIONS, MediaType::Image),
+            (VIDEO_EXTENSIONS, MediaType::Video),
+            (AUDIO_EXTENSIONS, MediaType::Audio),
+        ] {
+            for extension in extensions {
+                registry.register(extension, media_type);
+            }
+        }
+        registry
+    }
+
+    /// Creates a registry of the built-in extensions plus those in the media
+    /// types file at `path`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut registry = Self::builtin();
+        registry.extend_from_file(path)?;
+        Ok(registry)
+    }
+
+    /// Registers the extensions of the media types file at `path`. They
+    /// take precedence over extensions already registered.
+    pub fn extend_from_file(&mut self, path: &Path) -> Result<()> {
+        let file = File::open(path).map_err(Error::on_file(FileAction::Read, path))?;
+        let types: MediaTypesFile = serde_json::from_reader(BufReader::new(file))?;
+
+        for (extensions, media_type) in [
+            (types.image, MediaType::Image),
+            (types.video, MediaType::Video),
+            (types.audio, MediaType::Audio),
+        ] {
+            for extension in extensions {
+                self.register(&extension, media_type);
+            }
+        }
+        log::debug!("Registered media types from {:?}", path);
+        Ok(())
+    }
+
+    /// Maps `extension`, with or without a leading dot, to `media_type`.
+    ///
+    /// [`MediaType::Unknown`] removes the extension, so files with it are no
+    /// longer treated as media.
+    pub fn register(&mut self, extension: &str, media_type: MediaType) {
+        let extension = extension.trim().trim_start_matches('.').to_lowercase();
+        if extension.is_empty() {
+            return;
+        }
+        if media_type == MediaType::Unknown {
+            self.types.remove(&extension);
+        } else {
+            self.types.insert(extension, media_type);
+        }
+    }
+
+    /// Determines the media type of a file from its extension.
+    pub fn media_type(&self, path: &Path) -> MediaType {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .and_then(|e| self.types.get(&e.to_lowercase()))
+            .copied()
+            .unwrap_or(MediaType::Unknown)
+    }
+
+    /// Makes this the registry consulted by
+    /// [`hasher::get_media_type`](super::hasher::get_media_type) for the
+    /// rest of the process.
+    ///
+    /// Fails, handing the registry back, once a registry was installed or
+    /// the built-in one was used, so install it before scanning.
+    pub fn install(self) -> std::result::Result<(), Self> {
+        GLOBAL.set(self)
+    }
+
+    /// Returns the installed registry, or the built-in one if none was.
+    pub fn global() -> &'static Self {
+        GLOBAL.get_or_init(Self::builtin)
+    }
+}
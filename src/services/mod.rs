@@ -1,4 +1,13 @@
 //! Shared services used across commands.
 
+pub mod bktree;
+pub mod cache;
+pub mod cancel;
 pub mod duplicate;
 pub mod hasher;
+pub mod media_registry;
+pub mod relocate;
+pub mod report;
+pub mod safety;
+pub mod soft_delete;
+pub mod units;
@@ -0,0 +1,70 @@
+//! Moving files that may cross filesystem boundaries.
+//!
+//! `fs::rename` only works within one filesystem and fails with
+//! [`io::ErrorKind::CrossesDevices`] otherwise, which happens as soon as the
+//! erase staging directory lives on another mount than the duplicates. The
+//! functions here fall back to copying the file and deleting the source.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Moves the file `from` to `to`, copying it when they are on different filesystems.
+pub fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    move_file_with(from, to, |from, to| fs::rename(from, to))
+}
+
+/// Moves the file `from` to `to` using `rename`, falling back to copy then
+/// delete when `rename` reports that the paths are on different filesystems.
+///
+/// If the source cannot be removed after copying, the copy is removed again
+/// so the file is never left in both places.
+pub fn move_file_with<R>(from: &Path, to: &Path, rename: R) -> io::Result<()>
+where
+    R: Fn(&Path, &Path) -> io::Result<()>,
+{
+    match rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            log::debug!("Copying {:?} to {:?} across filesystems", from, to);
+            fs::copy(from, to)?;
+            if let Err(e) = fs::remove_file(from) {
+                let _ = fs::remove_file(to);
+                return Err(e);
+            }
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// Moves the directory `from`, which must hold only files, to `to`.
+///
+/// Across filesystems every file is moved with [`move_file`]. If that fails,
+/// the files already moved are put back before the error is returned.
+pub fn move_dir(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            log::debug!("Moving {:?} to {:?} file by file", from, to);
+            fs::create_dir_all(to)?;
+
+            let names = fs::read_dir(from)?
+                .map(|entry| entry.map(|entry| entry.file_name()))
+                .collect::<io::Result<Vec<_>>>()?;
+
+            let mut moved = Vec::new();
+            for name in names {
+                if let Err(e) = move_file(&from.join(&name), &to.join(&name)) {
+                    for name in &moved {
+                        let _ = move_file(&to.join(name), &from.join(name));
+                    }
+                    let _ = fs::remove_dir(to);
+                    return Err(e);
+                }
+                moved.push(name);
+            }
+
+            fs::remove_dir(from)
+        }
+        result => result,
+    }
+}
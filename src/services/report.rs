@@ -0,0 +1,181 @@
+//! Output writers for duplicate reports.
+//!
+//! Every supported output format implements [`ReportWriter`], so commands can
+//! pick a writer from a [`ReportFormat`] without matching on the format
+//! wherever a report is written.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use super::duplicate::{
+    DuplicateReport, DuplicateType, DuplicatesFile, ManifestFormat, DUPLICATES_FILENAME,
+};
+use super::hasher;
+use crate::error::{Error, FileAction, Result};
+
+/// Writes a [`DuplicateReport`] in a specific output format.
+pub trait ReportWriter {
+    /// Writes the report to `out`.
+    fn write(&self, report: &DuplicateReport, out: &mut dyn Write) -> Result<()>;
+}
+
+/// Output format of the duplicates report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportFormat {
+    /// Pretty-printed JSON manifest, readable by `erase`.
+    #[default]
+    Json,
+    /// YAML manifest, readable by `erase`.
+    Yaml,
+    /// TOML manifest, readable by `erase`.
+    Toml,
+    /// JSON Lines manifest with one line per group, readable by `erase`.
+    Jsonl,
+    /// One row per duplicate file, for spreadsheets.
+    Csv,
+}
+
+impl ReportFormat {
+    /// Returns the writer for this format.
+    pub fn writer(self) -> Box<dyn ReportWriter> {
+        match self.manifest_format() {
+            Some(format) => Box::new(ManifestReportWriter(format)),
+            None => Box::new(CsvReportWriter),
+        }
+    }
+
+    /// Returns the manifest format of this report format, or `None` for
+    /// reports `erase` cannot read.
+    pub fn manifest_format(self) -> Option<ManifestFormat> {
+        match self {
+            ReportFormat::Json => Some(ManifestFormat::Json),
+            ReportFormat::Yaml => Some(ManifestFormat::Yaml),
+            ReportFormat::Toml => Some(ManifestFormat::Toml),
+            ReportFormat::Jsonl => Some(ManifestFormat::JsonLines),
+            ReportFormat::Csv => None,
+        }
+    }
+
+    /// Returns the format of a report saved at `path` from its extension:
+    /// CSV for `.csv`, and otherwise the manifest format
+    /// [`ManifestFormat::from_path`] picks.
+    pub fn from_path(path: &Path) -> Self {
+        if path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("csv"))
+        {
+            return ReportFormat::Csv;
+        }
+        match ManifestFormat::from_path(path) {
+            ManifestFormat::Json => ReportFormat::Json,
+            ManifestFormat::Yaml => ReportFormat::Yaml,
+            ManifestFormat::Toml => ReportFormat::Toml,
+            ManifestFormat::JsonLines => ReportFormat::Jsonl,
+        }
+    }
+
+    /// Returns the file name reports in this format are saved under by default.
+    pub fn default_filename(self) -> &'static str {
+        match self {
+            ReportFormat::Json => DUPLICATES_FILENAME,
+            ReportFormat::Yaml => "duplicates.yaml",
+            ReportFormat::Toml => "duplicates.toml",
+            ReportFormat::Jsonl => "duplicates.jsonl",
+            ReportFormat::Csv => "duplicates.csv",
+        }
+    }
+}
+
+/// Writes the report as a [`DuplicatesFile`] manifest in the given format.
+pub struct ManifestReportWriter(pub ManifestFormat);
+
+impl ReportWriter for ManifestReportWriter {
+    fn write(&self, report: &DuplicateReport, out: &mut dyn Write) -> Result<()> {
+        DuplicatesFile::from_report(report).write_to(out, self.0)
+    }
+}
+
+/// Column headers of the CSV report.
+const CSV_HEADER: &[&str] = &[
+    "group_id",
+    "duplicate_type",
+    "original",
+    "duplicate",
+    "size_bytes",
+];
+
+/// Writes the report as CSV with one row per duplicate file.
+///
+/// Groups are numbered from 1 in the same order as the JSON manifest, and the
+/// original is chosen the same way.
+pub struct CsvReportWriter;
+
+impl ReportWriter for CsvReportWriter {
+    fn write(&self, report: &DuplicateReport, out: &mut dyn Write) -> Result<()> {
+        writeln!(out, "{}", CSV_HEADER.join(","))?;
+
+        let duplicates_file = DuplicatesFile::from_report(report);
+        for (index, entry) in duplicates_file.entries.iter().enumerate() {
+            let duplicate_type = match entry.duplicate_type {
+                DuplicateType::Exact => "exact",
+                DuplicateType::Perceptual => "perceptual",
+                DuplicateType::Mixed => "mixed",
+                DuplicateType::Probable => "probable",
+            };
+            let original = entry.original.to_string_lossy();
+
+            for duplicate in &entry.duplicates {
+                let size = hasher::file_size(duplicate).unwrap_or(0);
+                writeln!(
+                    out,
+                    "{},{},{},{},{}",
+                    index + 1,
+                    duplicate_type,
+                    csv_field(&original),
+                    csv_field(&duplicate.to_string_lossy()),
+                    size
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Quotes a CSV field if it contains a delimiter, quote or line break,
+/// doubling any embedded quotes (RFC 4180).
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Writes the path of every duplicate, without the originals, each followed
+/// by a NUL byte, so any file name can be read back with `xargs -0`.
+///
+/// Paths are absolute and come in the same order as in the JSON manifest.
+pub struct NulListWriter;
+
+impl ReportWriter for NulListWriter {
+    fn write(&self, report: &DuplicateReport, out: &mut dyn Write) -> Result<()> {
+        let duplicates_file = DuplicatesFile::from_report(report);
+        for duplicate in duplicates_file.entries.iter().flat_map(|e| &e.duplicates) {
+            out.write_all(duplicate.as_os_str().as_encoded_bytes())?;
+            out.write_all(b"\0")?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the report to the file at `path` using the given writer.
+pub fn save_report(writer: &dyn ReportWriter, report: &DuplicateReport, path: &Path) -> Result<()> {
+    let file = File::create(path).map_err(Error::on_file(FileAction::Write, path))?;
+    let mut out = BufWriter::new(file);
+    writer.write(report, &mut out)?;
+    out.flush().map_err(Error::on_file(FileAction::Write, path))?;
+    log::info!("Duplicates report saved to {:?}", path);
+    Ok(())
+}
@@ -0,0 +1,38 @@
+//! Guardrails against operating on dangerous locations.
+//!
+//! Scanning or erasing from a filesystem root or the user's home directory
+//! is almost always a mistake, so commands refuse to do so unless the user
+//! explicitly opts in.
+
+use std::path::{Path, PathBuf};
+
+/// Environment variables that point at the user's home directory,
+/// covering both Unix (`HOME`) and Windows (`USERPROFILE`).
+const HOME_ENV_VARS: &[&str] = &["HOME", "USERPROFILE"];
+
+/// Returns the canonicalized home directories known for the current user.
+fn home_dirs() -> Vec<PathBuf> {
+    HOME_ENV_VARS
+        .iter()
+        .filter_map(std::env::var_os)
+        .filter(|value| !value.is_empty())
+        .filter_map(|value| PathBuf::from(value).canonicalize().ok())
+        .collect()
+}
+
+/// Checks whether `path` resolves to a dangerous root.
+///
+/// Dangerous roots are filesystem roots (`/`, `C:\`, ...) and the user's
+/// home directory. Paths that cannot be resolved are not considered dangerous;
+/// commands report them as missing instead.
+pub fn is_dangerous_root(path: &Path) -> bool {
+    let Ok(canonical) = path.canonicalize() else {
+        return false;
+    };
+
+    if canonical.parent().is_none() {
+        return true;
+    }
+
+    home_dirs().iter().any(|home| *home == canonical)
+}
@@ -0,0 +1,140 @@
+//! Soft-deleted erase batches that can be undone.
+//!
+//! In soft-delete mode the erase command keeps its staged files instead of
+//! deleting them. Each run becomes a timestamped batch directory under
+//! [`SOFT_DELETE_DIR_NAME`], holding the staged files and a
+//! [`SoftDeleteManifest`] that maps every staged file back to its original path.
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, FileAction, Result};
+
+/// Directory, inside the erased directory, holding soft-deleted batches.
+pub const SOFT_DELETE_DIR_NAME: &str = ".mde_soft_deleted";
+
+/// Name of the manifest file inside each batch directory.
+pub const MANIFEST_FILENAME: &str = "manifest.json";
+
+/// Default number of days soft-deleted batches are kept before being pruned.
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+/// A file moved into a soft-delete batch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SoftDeletedFile {
+    /// Where the file was before it was erased.
+    pub original: PathBuf,
+    /// Name of the staged file, relative to the batch directory.
+    pub staged: PathBuf,
+}
+
+/// Records which files a soft-delete batch holds and where they came from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoftDeleteManifest {
+    /// When the erase ran.
+    pub erased_at: DateTime<Utc>,
+    /// The files in the batch.
+    pub files: Vec<SoftDeletedFile>,
+}
+
+impl SoftDeleteManifest {
+    /// Saves the manifest into the batch directory.
+    pub fn save(&self, batch_dir: &Path) -> Result<()> {
+        let path = batch_dir.join(MANIFEST_FILENAME);
+        let file = File::create(&path).map_err(Error::on_file(FileAction::Write, &path))?;
+        let mut out = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut out, self)?;
+        out.flush().map_err(Error::on_file(FileAction::Write, &path))?;
+        Ok(())
+    }
+
+    /// Loads the manifest of a batch directory.
+    pub fn load(batch_dir: &Path) -> Result<Self> {
+        let path = batch_dir.join(MANIFEST_FILENAME);
+        let file = File::open(&path).map_err(Error::on_file(FileAction::Read, &path))?;
+        Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+}
+
+/// Returns the directory holding the soft-deleted batches of `root`.
+pub fn soft_delete_dir(root: &Path) -> PathBuf {
+    root.join(SOFT_DELETE_DIR_NAME)
+}
+
+/// Returns a new, not yet existing, batch directory named after `erased_at`.
+pub fn new_batch_dir(root: &Path, erased_at: DateTime<Utc>) -> PathBuf {
+    let name = erased_at.format("%Y%m%d-%H%M%S%.3f").to_string();
+    let mut batch_dir = soft_delete_dir(root).join(&name);
+    let mut suffix = 1;
+    while batch_dir.exists() {
+        batch_dir = soft_delete_dir(root).join(format!("{}-{}", name, suffix));
+        suffix += 1;
+    }
+    batch_dir
+}
+
+/// Lists the batch directories of `root`, oldest first.
+///
+/// Directories without a readable manifest are ignored.
+pub fn batches(root: &Path) -> Result<Vec<PathBuf>> {
+    let dir = soft_delete_dir(root);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut batches: Vec<(DateTime<Utc>, PathBuf)> = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        match SoftDeleteManifest::load(&path) {
+            Ok(manifest) => batches.push((manifest.erased_at, path)),
+            Err(e) => log::debug!("Skipping {:?} without a valid manifest: {}", path, e),
+        }
+    }
+
+    batches.sort();
+    Ok(batches.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Returns the most recent batch directory of `root`, if any.
+pub fn latest_batch(root: &Path) -> Result<Option<PathBuf>> {
+    Ok(batches(root)?.pop())
+}
+
+/// Permanently deletes batches erased more than `retention_days` days ago.
+///
+/// Returns the number of batches removed.
+pub fn prune(root: &Path, retention_days: u32) -> Result<usize> {
+    // A retention too long to represent means nothing is old enough to prune
+    let Some(cutoff) = Duration::try_days(i64::from(retention_days))
+        .and_then(|retention| Utc::now().checked_sub_signed(retention))
+    else {
+        return Ok(0);
+    };
+    let mut pruned = 0;
+
+    for batch_dir in batches(root)? {
+        let manifest = SoftDeleteManifest::load(&batch_dir)?;
+        if manifest.erased_at < cutoff {
+            fs::remove_dir_all(&batch_dir)
+                .map_err(Error::on_file(FileAction::Remove, &batch_dir))?;
+            log::info!("Pruned soft-deleted batch {:?}", batch_dir);
+            pruned += 1;
+        }
+    }
+
+    remove_empty_soft_delete_dir(root)?;
+    Ok(pruned)
+}
+
+/// Removes the soft-delete directory of `root` once it holds no batches.
+pub fn remove_empty_soft_delete_dir(root: &Path) -> Result<()> {
+    let dir = soft_delete_dir(root);
+    if dir.is_dir() && fs::read_dir(&dir)?.next().is_none() {
+        fs::remove_dir(&dir).map_err(Error::on_file(FileAction::Remove, &dir))?;
+    }
+    Ok(())
+}
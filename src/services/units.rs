@@ -0,0 +1,94 @@
+//! Human-readable byte size and time parsing and formatting.
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+
+use crate::error::{Error, Result};
+
+/// Binary unit suffixes used by [`format_size`].
+const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+
+/// Parses a human-readable size such as `4K`, `10M`, `1.5GB` or `2048`.
+///
+/// Units are case-insensitive binary multiples (`1K` = 1024 bytes). A trailing
+/// `B` or `iB` is accepted, so `10M`, `10MB` and `10MiB` are equivalent.
+/// A bare number is a byte count.
+pub fn parse_size(input: &str) -> Result<u64> {
+    let trimmed = input.trim();
+    let split = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+
+    let value: f64 = number
+        .parse()
+        .map_err(|_| Error::InvalidSize(input.to_string()))?;
+
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        _ => return Err(Error::InvalidSize(input.to_string())),
+    };
+
+    Ok((value * multiplier as f64).round() as u64)
+}
+
+/// Parses a point in time: a duration back from now such as `30d` or `12h`,
+/// an ISO date such as `2024-05-01` (midnight UTC), or an RFC 3339 date and
+/// time such as `2024-05-01T08:30:00+02:00`.
+pub fn parse_time(input: &str) -> Result<DateTime<Utc>> {
+    let trimmed = input.trim();
+    if let Ok(duration) = parse_duration(trimmed) {
+        return Ok(Utc::now() - duration);
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(trimmed) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(trimmed, "%Y-%m-%d")
+        .map(|date| date.and_time(NaiveTime::MIN).and_utc())
+        .map_err(|_| Error::InvalidTime(input.to_string()))
+}
+
+/// Parses a duration such as `90s`, `45m`, `12h`, `30d` or `2w`.
+///
+/// Units are case-insensitive and required, so a bare number is rejected.
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    let trimmed = input.trim();
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+
+    let value: i64 = number
+        .parse()
+        .map_err(|_| Error::InvalidTime(input.to_string()))?;
+
+    let duration = match unit.trim().to_ascii_lowercase().as_str() {
+        "s" => Duration::try_seconds(value),
+        "m" => Duration::try_minutes(value),
+        "h" => Duration::try_hours(value),
+        "d" => Duration::try_days(value),
+        "w" => Duration::try_weeks(value),
+        _ => None,
+    };
+    duration.ok_or_else(|| Error::InvalidTime(input.to_string()))
+}
+
+/// Formats a byte count using binary units, e.g. `1.5 GiB`.
+pub fn format_size(bytes: u64) -> String {
+    let mut value = bytes as f64;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
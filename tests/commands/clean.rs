@@ -107,3 +107,43 @@ fn test_clean_is_idempotent() {
     assert!(result2.is_ok(), "Second clean should also succeed");
     assert_path_not_exists(&duplicates_path);
 }
+
+#[test]
+fn test_clean_removes_unsupported_duplicates_json() {
+    let tmp = temp_dir();
+    let duplicates_path = tmp.path().join("duplicates.json");
+    fs::write(&duplicates_path, r#"{"version": "99.0", "entries": "unknown"}"#).unwrap();
+
+    let cleaner = Cleaner::new(tmp.path().to_path_buf(), true);
+    cleaner
+        .execute()
+        .expect("Clean should remove files from other versions");
+
+    assert_path_not_exists(&duplicates_path);
+}
+
+#[test]
+fn test_clean_uses_custom_manifest_name() {
+    let tmp = temp_dir();
+    let default_path = create_duplicates_json(tmp.path());
+    let custom_path = tmp.path().join("photos.json");
+    fs::copy(&default_path, &custom_path).unwrap();
+
+    Cleaner::new(tmp.path().to_path_buf(), true)
+        .with_manifest_name("photos.json")
+        .execute()
+        .unwrap();
+
+    assert_path_not_exists(&custom_path);
+    assert_path_exists(&default_path);
+}
+
+#[test]
+fn test_clean_reports_whether_file_was_removed() {
+    let tmp = temp_dir();
+    create_duplicates_json(tmp.path());
+    let cleaner = Cleaner::new(tmp.path().to_path_buf(), true);
+
+    assert!(cleaner.execute().unwrap(), "First clean should remove the file");
+    assert!(!cleaner.execute().unwrap(), "Nothing is left to remove");
+}
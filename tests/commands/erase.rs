@@ -2,10 +2,18 @@
 
 use std::fs;
 
-use media_duplicate_eraser_rs::commands::erase::Eraser;
-use media_duplicate_eraser_rs::commands::scan::Scanner;
+use media_duplicate_eraser_rs::commands::erase::{
+    review_entries, DeleteMode, EraseSummary, Eraser, ReviewChoice,
+};
+use media_duplicate_eraser_rs::commands::scan::{ScanOptions, Scanner};
 use media_duplicate_eraser_rs::commands::Command;
-use media_duplicate_eraser_rs::services::duplicate::MediaFilter;
+use media_duplicate_eraser_rs::error::Error;
+use media_duplicate_eraser_rs::services::cancel::CancelToken;
+use media_duplicate_eraser_rs::services::duplicate::{
+    self, DetectionOptions, DuplicateEntry, DuplicateType, DuplicatesFile, EntryStatus,
+};
+use media_duplicate_eraser_rs::services::hasher::HashAlgorithm;
+use media_duplicate_eraser_rs::services::report::ReportFormat;
 
 use crate::common::{assert_path_exists, assert_path_not_exists, images_fixtures_dir, temp_dir};
 
@@ -31,7 +39,11 @@ fn setup_duplicates() -> (tempfile::TempDir, std::path::PathBuf, std::path::Path
 
 /// Helper to run scan and create duplicates.json
 fn run_scan(dir: &std::path::Path) {
-    let scanner = Scanner::new(dir.to_path_buf(), false, false, None, true, MediaFilter::All);
+    let scanner = Scanner::with_options(ScanOptions {
+        recursive: false,
+        quiet: true,
+        ..ScanOptions::new(dir.to_path_buf())
+    });
     scanner.execute().expect("Scan should succeed");
 }
 
@@ -81,6 +93,37 @@ fn test_erase_deletes_duplicate_files() {
     assert_path_exists(&unique);
 }
 
+#[test]
+fn test_erase_keep_manifest_marks_entries_erased() {
+    let (tmp, original, duplicate) = setup_duplicates();
+    run_scan(tmp.path());
+    let duplicates_json = tmp.path().join("duplicates.json");
+
+    let summary = Eraser::new(tmp.path().to_path_buf(), true)
+        .with_keep_manifest(true)
+        .erase_duplicates()
+        .expect("Erase should succeed");
+    assert_eq!(summary.deleted, 1);
+    // duplicate.txt sorts first, so it is the file kept
+    assert_path_exists(&duplicate);
+    assert_path_not_exists(&original);
+    assert_path_exists(&duplicates_json);
+
+    let manifest = DuplicatesFile::load(&duplicates_json).unwrap();
+    assert_eq!(manifest.entries.len(), 1);
+    assert_eq!(manifest.entries[0].status, EntryStatus::Erased);
+    assert!(manifest.entries[0].erased_at.is_some());
+    assert_eq!(manifest.total_duplicates, 0);
+
+    // Erased entries are only a record, so there is nothing left to erase
+    let summary = Eraser::new(tmp.path().to_path_buf(), true)
+        .with_keep_manifest(true)
+        .erase_duplicates()
+        .expect("Erase should succeed");
+    assert_eq!(summary, EraseSummary::default());
+    assert_eq!(DuplicatesFile::load(&duplicates_json).unwrap().entries.len(), 1);
+}
+
 #[test]
 fn test_erase_handles_missing_duplicates_json() {
     // Setup: Create a temp directory without duplicates.json
@@ -180,6 +223,55 @@ fn test_erase_skips_already_deleted_files() {
     assert_eq!(remaining_count, 1, "Exactly one file should remain");
 }
 
+#[test]
+fn test_erase_keeps_a_duplicate_when_the_original_is_gone() {
+    let tmp = temp_dir();
+    let files: Vec<_> = ["a.txt", "b.txt", "c.txt"]
+        .iter()
+        .map(|name| tmp.path().join(name))
+        .collect();
+    for file in &files {
+        fs::write(file, "duplicate content").unwrap();
+    }
+
+    run_scan(tmp.path());
+    let manifest = DuplicatesFile::load(&tmp.path().join("duplicates.json")).unwrap();
+    assert_eq!(manifest.entries[0].original, files[0]);
+
+    // The original is removed between scan and erase
+    fs::remove_file(&files[0]).unwrap();
+
+    let eraser = Eraser::new(tmp.path().to_path_buf(), true);
+    let summary = eraser.erase_duplicates().expect("Erase should succeed");
+
+    assert_eq!(summary.deleted, 1);
+    assert_path_exists(&files[1]);
+    assert_path_not_exists(&files[2]);
+}
+
+#[test]
+fn test_manifest_promotes_existing_file_when_original_is_gone() {
+    let tmp = temp_dir();
+    let files: Vec<_> = ["a.txt", "b.txt", "c.txt"]
+        .iter()
+        .map(|name| tmp.path().join(name))
+        .collect();
+    for file in &files {
+        fs::write(file, "duplicate content").unwrap();
+    }
+    let report = duplicate::find_duplicates(&files).unwrap();
+    assert_eq!(report.groups[0].files[0], files[0]);
+
+    // The original is removed between the scan and the manifest being built
+    fs::remove_file(&files[0]).unwrap();
+
+    let manifest = DuplicatesFile::from_report(&report);
+    let entry = &manifest.entries[0];
+    assert_eq!(entry.original, files[1]);
+    assert_eq!(entry.duplicates, [files[0].clone(), files[2].clone()]);
+    assert_eq!(entry.reclaimable_bytes, 17);
+}
+
 #[test]
 fn test_erase_preserves_directory_structure() {
     // Setup: Create a more complex directory structure
@@ -198,7 +290,10 @@ fn test_erase_preserves_directory_structure() {
     fs::write(&duplicate, "photo content").unwrap();
 
     // Run scan recursively
-    let scanner = Scanner::new(tmp.path().to_path_buf(), true, false, None, true, MediaFilter::All);
+    let scanner = Scanner::with_options(ScanOptions {
+        quiet: true,
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    });
     scanner.execute().unwrap();
 
     // Execute: Run eraser
@@ -275,6 +370,49 @@ fn test_erase_with_multiple_duplicate_groups() {
     );
 }
 
+#[test]
+fn test_erase_rolls_back_when_a_duplicate_changed_since_scan() {
+    let tmp = temp_dir();
+    let group1_a = tmp.path().join("group1_a.txt");
+    let group1_b = tmp.path().join("group1_b.txt");
+    let group2_a = tmp.path().join("group2_a.txt");
+    let group2_b = tmp.path().join("group2_b.txt");
+    fs::write(&group1_a, "content for group 1").unwrap();
+    fs::write(&group1_b, "content for group 1").unwrap();
+    fs::write(&group2_a, "content for group 2").unwrap();
+    fs::write(&group2_b, "content for group 2").unwrap();
+    run_scan(tmp.path());
+
+    let duplicates_json = tmp.path().join("duplicates.json");
+    let manifest = DuplicatesFile::load(&duplicates_json).unwrap();
+    assert!(
+        manifest.entries.iter().all(|e| e.checksums.len() == 2),
+        "Scan should record the hash of every file of exact groups"
+    );
+
+    // Edited after the scan: erasing it would lose the edit
+    fs::write(&group2_b, "edited after the scan").unwrap();
+
+    let summary = Eraser::new(tmp.path().to_path_buf(), true)
+        .erase_duplicates()
+        .expect("A rolled back erase should still report a summary");
+    assert!(summary.rolled_back);
+    for file in [&group1_a, &group1_b, &group2_a, &group2_b] {
+        assert_path_exists(file);
+    }
+    assert_eq!(fs::read_to_string(&group2_b).unwrap(), "edited after the scan");
+    assert_path_exists(&duplicates_json);
+
+    let summary = Eraser::new(tmp.path().to_path_buf(), true)
+        .with_verify_checksums(false)
+        .erase_duplicates()
+        .expect("Erase should succeed without verification");
+    assert!(!summary.rolled_back);
+    assert_eq!(summary.deleted, 2);
+    assert_path_not_exists(&group1_b);
+    assert_path_not_exists(&group2_b);
+}
+
 #[test]
 fn test_erase_is_idempotent() {
     // Setup: Create duplicate files and scan
@@ -338,7 +476,11 @@ fn test_erase_deletes_duplicate_images() {
     let (tmp, copied_files) = setup_image_duplicates();
 
     // Run scan to detect duplicates
-    let scanner = Scanner::new(tmp.path().to_path_buf(), false, false, None, true, MediaFilter::All);
+    let scanner = Scanner::with_options(ScanOptions {
+        recursive: false,
+        quiet: true,
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    });
     scanner.execute().expect("Scan should succeed");
 
     // Verify scan created duplicates.json
@@ -394,7 +536,11 @@ fn test_erase_preserves_unique_images() {
     fs::copy(&image_b_src, &image_b_dest).unwrap();
 
     // Run scan
-    let scanner = Scanner::new(tmp.path().to_path_buf(), false, false, None, true, MediaFilter::All);
+    let scanner = Scanner::with_options(ScanOptions {
+        recursive: false,
+        quiet: true,
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    });
     scanner.execute().expect("Scan should succeed");
 
     // duplicates.json might or might not exist depending on perceptual similarity
@@ -427,7 +573,11 @@ fn test_erase_image_duplicates_keeps_one_copy() {
     fs::copy(&image_a_copy_src, &image_a_copy_dest).unwrap();
 
     // Run scan
-    let scanner = Scanner::new(tmp.path().to_path_buf(), false, false, None, true, MediaFilter::All);
+    let scanner = Scanner::with_options(ScanOptions {
+        recursive: false,
+        quiet: true,
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    });
     scanner.execute().expect("Scan should succeed");
 
     let duplicates_json = tmp.path().join("duplicates.json");
@@ -449,3 +599,736 @@ fn test_erase_image_duplicates_keeps_one_copy() {
         "Exactly one copy of image_a should remain after erase"
     );
 }
+
+#[cfg(unix)]
+#[test]
+fn test_erase_refuses_filesystem_root_unless_allowed() {
+    let eraser = Eraser::new("/".into(), true);
+    assert!(
+        matches!(eraser.execute(), Err(Error::DangerousPath(_))),
+        "Eraser should refuse to run on the filesystem root"
+    );
+
+    // With the override there is no duplicates.json at the root, so this is a no-op
+    let eraser = Eraser::new("/".into(), true).allow_dangerous_path(true);
+    assert!(eraser.execute().is_ok());
+}
+
+#[test]
+fn test_erase_stops_at_reclaim_target() {
+    let tmp = temp_dir();
+
+    // A large group and a small group of exact duplicates
+    let large = vec![0u8; 4096];
+    let large_original = tmp.path().join("large_a.bin");
+    let large_duplicate = tmp.path().join("large_b.bin");
+    fs::write(&large_original, &large).unwrap();
+    fs::write(&large_duplicate, &large).unwrap();
+
+    let small_original = tmp.path().join("small_a.txt");
+    let small_duplicate = tmp.path().join("small_b.txt");
+    fs::write(&small_original, "small").unwrap();
+    fs::write(&small_duplicate, "small").unwrap();
+
+    run_scan(tmp.path());
+
+    // The largest group alone satisfies the target
+    let eraser = Eraser::new(tmp.path().to_path_buf(), true).with_reclaim_target(Some(1024));
+    eraser.execute().expect("Erase should succeed");
+
+    assert!(
+        large_original.exists() != large_duplicate.exists(),
+        "Exactly one copy of the large file should remain"
+    );
+    assert_path_exists(&small_original);
+    assert_path_exists(&small_duplicate);
+
+    // The small group is kept in the manifest for a later run
+    let manifest = tmp.path().join("duplicates.json");
+    assert_path_exists(&manifest);
+    let content = fs::read_to_string(&manifest).unwrap();
+    assert!(content.contains("small_"), "Remaining group should stay in the manifest");
+    assert!(!content.contains("large_"), "Erased group should be dropped from the manifest");
+}
+
+#[test]
+fn test_erase_duplicates_returns_summary_with_skipped_files() {
+    let (tmp, original, _duplicate) = setup_duplicates();
+    let content = fs::read(&original).unwrap();
+    fs::write(tmp.path().join("duplicate_2.txt"), &content).unwrap();
+
+    run_scan(tmp.path());
+
+    // Remove one of the listed duplicates between scan and erase
+    let manifest = DuplicatesFile::load(&tmp.path().join("duplicates.json")).unwrap();
+    assert_eq!(manifest.entries[0].duplicates.len(), 2);
+    fs::remove_file(&manifest.entries[0].duplicates[0]).unwrap();
+
+    let eraser = Eraser::new(tmp.path().to_path_buf(), true);
+    let summary = eraser.erase_duplicates().expect("Erase should succeed");
+
+    assert_eq!(summary.deleted, 1, "The remaining duplicate should be deleted");
+    assert_eq!(summary.skipped_missing, 1, "The removed duplicate should be skipped");
+    assert_eq!(summary.reclaimed_bytes, content.len() as u64);
+    assert!(!summary.rolled_back);
+    assert_path_exists(&manifest.entries[0].original);
+}
+
+#[test]
+fn test_plan_previews_erase_without_touching_disk() {
+    let (tmp, original, _duplicate) = setup_duplicates();
+    let content = fs::read(&original).unwrap();
+    fs::write(tmp.path().join("duplicate_2.txt"), &content).unwrap();
+    run_scan(tmp.path());
+
+    let duplicates_json = tmp.path().join("duplicates.json");
+    let manifest = DuplicatesFile::load(&duplicates_json).unwrap();
+    let (gone, kept) = (&manifest.entries[0].duplicates[0], &manifest.entries[0].duplicates[1]);
+    fs::remove_file(gone).unwrap();
+    let before = fs::read_to_string(&duplicates_json).unwrap();
+
+    let eraser = Eraser::new(tmp.path().to_path_buf(), true);
+    let plan = eraser.plan().expect("Planning should succeed");
+
+    assert_eq!(plan.files, vec![kept.clone()]);
+    assert_eq!(plan.missing, vec![gone.clone()]);
+    assert_eq!(plan.total_bytes, content.len() as u64);
+    assert_eq!(plan.selected.len(), 1);
+    assert!(plan.remaining.is_empty());
+    assert_path_exists(kept);
+    assert_eq!(fs::read_to_string(&duplicates_json).unwrap(), before);
+
+    // Erasing deletes exactly the planned files
+    let summary = eraser.erase_duplicates().unwrap();
+    assert_eq!(summary.deleted, plan.files.len());
+    assert_eq!(summary.skipped_missing, plan.missing.len());
+    assert_eq!(summary.reclaimed_bytes, plan.total_bytes);
+    assert_path_not_exists(kept);
+}
+
+#[test]
+fn test_plan_is_empty_without_duplicates_json() {
+    let tmp = temp_dir();
+
+    let plan = Eraser::new(tmp.path().to_path_buf(), true).plan().unwrap();
+    assert!(plan.files.is_empty());
+    assert!(plan.missing.is_empty());
+    assert_eq!(plan.total_bytes, 0);
+}
+
+#[test]
+#[ignore = "moves files into the real system trash"]
+fn test_erase_moves_duplicates_to_trash() {
+    let (tmp, original, duplicate) = setup_duplicates();
+    run_scan(tmp.path());
+
+    let summary = Eraser::new(tmp.path().to_path_buf(), true)
+        .with_delete_mode(DeleteMode::Trash)
+        .erase_duplicates()
+        .expect("Erase should succeed");
+
+    assert_eq!(summary.deleted, 1);
+    assert!(!summary.rolled_back);
+    assert!(
+        original.exists() != duplicate.exists(),
+        "Exactly one file of the pair should have been trashed"
+    );
+    assert_path_not_exists(&tmp.path().join(".mde_erase_staging"));
+}
+
+#[test]
+fn test_erase_moves_duplicates_into_review_directory() {
+    let tmp = temp_dir();
+    for dir in ["a", "b"] {
+        fs::create_dir(tmp.path().join(dir)).unwrap();
+        fs::write(tmp.path().join(dir).join("photo.txt"), "same content").unwrap();
+    }
+    Scanner::with_options(ScanOptions {
+        quiet: true,
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    })
+    .execute()
+    .expect("Scan should succeed");
+    let entry = DuplicatesFile::load(&tmp.path().join("duplicates.json"))
+        .unwrap()
+        .entries
+        .remove(0);
+    let duplicate = entry.duplicates[0].clone();
+    let relative = duplicate.strip_prefix(tmp.path()).unwrap();
+
+    // Taken destinations get a counter instead of being overwritten
+    let review = temp_dir();
+    let taken = review.path().join(relative);
+    fs::create_dir_all(taken.parent().unwrap()).unwrap();
+    fs::write(&taken, "already here").unwrap();
+
+    let summary = Eraser::new(tmp.path().to_path_buf(), true)
+        .with_delete_mode(DeleteMode::Move(review.path().to_path_buf()))
+        .erase_duplicates()
+        .expect("Erase should succeed");
+
+    assert_eq!(summary.deleted, 1);
+    assert!(!summary.rolled_back);
+    assert_path_exists(&entry.original);
+    assert_path_not_exists(&duplicate);
+    assert_eq!(fs::read_to_string(&taken).unwrap(), "already here");
+    let moved = taken.with_file_name("photo-1.txt");
+    assert_eq!(fs::read_to_string(&moved).unwrap(), "same content");
+    assert_path_not_exists(&tmp.path().join(".mde_erase_staging"));
+}
+
+#[test]
+fn test_erase_dry_run_changes_nothing() {
+    let (tmp, original, duplicate) = setup_duplicates();
+    run_scan(tmp.path());
+    let duplicates_json = tmp.path().join("duplicates.json");
+
+    let summary = Eraser::new(tmp.path().to_path_buf(), true)
+        .with_dry_run(true)
+        .erase_duplicates()
+        .expect("Dry run should succeed");
+
+    assert!(summary.dry_run);
+    assert_eq!(summary.deleted, 1, "One file should be reported as erasable");
+    assert_eq!(summary.reclaimed_bytes, fs::metadata(&original).unwrap().len());
+    assert_path_exists(&original);
+    assert_path_exists(&duplicate);
+    assert_path_exists(&duplicates_json);
+}
+
+#[test]
+fn test_manifest_records_hash_algorithm() {
+    let (tmp, _original, _duplicate) = setup_duplicates();
+    let output = tmp.path().join("duplicates.json");
+    let scanner = Scanner::with_options(ScanOptions {
+        recursive: false,
+        quiet: true,
+        detection: DetectionOptions {
+            hash_algorithm: HashAlgorithm::Blake3,
+            ..DetectionOptions::default()
+        },
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    });
+    scanner.execute().expect("Scan should succeed");
+
+    let manifest = DuplicatesFile::load(&output).unwrap();
+    assert_eq!(manifest.hash_algorithm, HashAlgorithm::Blake3);
+    assert_eq!(manifest.total_duplicates, 1);
+
+    // A mismatch only warns; the erase still goes ahead
+    let summary = Eraser::new(tmp.path().to_path_buf(), true)
+        .with_hash_algorithm(Some(HashAlgorithm::Sha256))
+        .erase_duplicates()
+        .expect("Erase should succeed");
+    assert_eq!(summary.deleted, 1);
+}
+
+#[test]
+fn test_review_entries_overrides_original_and_keeps_skipped_groups() {
+    let (tmp, original, duplicate) = setup_duplicates();
+    let other = tmp.path().join("other.txt");
+    fs::write(&other, "other").unwrap();
+    let entry = |original: &std::path::Path, duplicate: &std::path::Path| DuplicateEntry {
+        original: original.to_path_buf(),
+        duplicates: vec![duplicate.to_path_buf()],
+        duplicate_type: DuplicateType::Exact,
+        reclaimable_bytes: 0,
+        distances: Default::default(),
+        checksums: Default::default(),
+        paired: Default::default(),
+        status: Default::default(),
+        erased_at: None,
+    };
+    let entries = vec![entry(&original, &duplicate), entry(&other, &original)];
+
+    let mut choices = vec![ReviewChoice::Keep(1), ReviewChoice::Skip].into_iter();
+    let (selected, skipped) =
+        review_entries(entries, |_| Ok(choices.next().unwrap())).expect("Review should succeed");
+
+    assert_eq!(selected.len(), 1);
+    assert_eq!(selected[0].original, duplicate, "The chosen file should be kept");
+    assert_eq!(selected[0].duplicates, vec![original.clone()]);
+    assert_eq!(
+        selected[0].reclaimable_bytes,
+        fs::metadata(&original).unwrap().len()
+    );
+
+    assert_eq!(skipped.len(), 1, "Skipped groups should be returned untouched");
+    assert_eq!(skipped[0].original, other);
+}
+
+#[test]
+fn test_erase_only_exact_keeps_similar_groups() {
+    let (tmp, original, duplicate) = setup_duplicates();
+    let photo = tmp.path().join("photo.jpg");
+    let similar = tmp.path().join("photo_small.jpg");
+    fs::write(&photo, "a photo").unwrap();
+    fs::write(&similar, "the same photo, smaller").unwrap();
+    let duplicates_json = tmp.path().join("duplicates.json");
+    let content = format!(
+        r#"{{
+        "version": "1.0",
+        "scanned_at": "2024-01-01T00:00:00Z",
+        "total_files_scanned": 4,
+        "duplicate_groups": 2,
+        "total_duplicates": 2,
+        "entries": [
+            {{"original": {:?}, "duplicates": [{:?}], "duplicate_type": "exact"}},
+            {{"original": {:?}, "duplicates": [{:?}], "duplicate_type": "perceptual"}}
+        ]
+    }}"#,
+        original, duplicate, photo, similar
+    );
+    fs::write(&duplicates_json, content).unwrap();
+
+    let summary = Eraser::new(tmp.path().to_path_buf(), true)
+        .with_only_exact(true)
+        .erase_duplicates()
+        .expect("Erase should succeed");
+
+    assert_eq!(summary.deleted, 1);
+    assert_path_not_exists(&duplicate);
+    assert_path_exists(&similar);
+    let kept = DuplicatesFile::load(&duplicates_json).unwrap();
+    assert_eq!(kept.entries.len(), 1, "The similar group should be kept for later");
+    assert_eq!(kept.entries[0].original, photo);
+    assert!(!kept.entries[0].is_exact());
+}
+
+#[test]
+fn test_erase_never_deletes_protected_files() {
+    let (tmp, original, duplicate) = setup_duplicates();
+    let library = tmp.path().join("library");
+    fs::create_dir(&library).unwrap();
+    let protected = library.join("copy.txt");
+    fs::copy(&original, &protected).unwrap();
+    let photo = tmp.path().join("photo.jpg");
+    let protected_photo = library.join("photo.jpg");
+    fs::write(&photo, "a photo").unwrap();
+    fs::write(&protected_photo, "a photo").unwrap();
+    let duplicates_json = tmp.path().join("duplicates.json");
+    let content = format!(
+        r#"{{
+        "version": "1.0",
+        "scanned_at": "2024-01-01T00:00:00Z",
+        "total_files_scanned": 5,
+        "duplicate_groups": 2,
+        "total_duplicates": 3,
+        "entries": [
+            {{"original": {:?}, "duplicates": [{:?}, {:?}], "duplicate_type": "exact"}},
+            {{"original": {:?}, "duplicates": [{:?}], "duplicate_type": "exact"}}
+        ]
+    }}"#,
+        original, duplicate, protected, photo, protected_photo
+    );
+    fs::write(&duplicates_json, content).unwrap();
+
+    let summary = Eraser::new(tmp.path().to_path_buf(), true)
+        .with_protected_dirs(vec![library])
+        .erase_duplicates()
+        .expect("Erase should succeed");
+
+    assert_eq!(summary.deleted, 1);
+    assert_eq!(summary.protected, 2);
+    assert_path_not_exists(&duplicate);
+    assert_path_exists(&original);
+    assert_path_exists(&protected);
+    assert_path_exists(&protected_photo);
+    assert_path_exists(&photo);
+}
+
+#[test]
+fn test_erase_rejects_unsupported_manifest_version() {
+    let (tmp, original, duplicate) = setup_duplicates();
+    let duplicates_json = tmp.path().join("duplicates.json");
+    let content = format!(
+        r#"{{
+        "version": "99.0",
+        "scanned_at": "2024-01-01T00:00:00Z",
+        "total_files_scanned": 2,
+        "duplicate_groups": 1,
+        "total_duplicates": 1,
+        "entries": [{{"original": {:?}, "duplicates": [{:?}], "duplicate_type": "exact"}}]
+    }}"#,
+        original, duplicate
+    );
+    fs::write(&duplicates_json, content).unwrap();
+
+    let result = DuplicatesFile::load(&duplicates_json);
+    assert!(matches!(
+        result,
+        Err(Error::UnsupportedVersion { ref found, .. }) if found == "99.0"
+    ));
+
+    let result = Eraser::new(tmp.path().to_path_buf(), true).execute();
+    assert!(matches!(result, Err(Error::UnsupportedVersion { .. })));
+    assert_path_exists(&duplicate);
+}
+
+#[test]
+fn test_erase_uses_custom_manifest_name() {
+    let (tmp, original, duplicate) = setup_duplicates();
+    let manifest = tmp.path().join("photos.json");
+
+    Scanner::with_options(ScanOptions {
+        recursive: false,
+        quiet: true,
+        output: Some(manifest.clone()),
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    })
+    .execute()
+    .expect("Scan should succeed");
+    assert_path_exists(&manifest);
+    assert_path_not_exists(&tmp.path().join("duplicates.json"));
+
+    // The default name finds nothing to erase
+    Eraser::new(tmp.path().to_path_buf(), true)
+        .execute()
+        .unwrap();
+    assert_path_exists(&original);
+    assert_path_exists(&duplicate);
+
+    Eraser::new(tmp.path().to_path_buf(), true)
+        .with_manifest_name("photos.json")
+        .execute()
+        .unwrap();
+    assert_path_not_exists(&manifest);
+    assert!(
+        original.exists() != duplicate.exists(),
+        "Exactly one file of the pair should remain"
+    );
+}
+
+#[test]
+fn test_erase_reads_yaml_manifest() {
+    let (tmp, original, duplicate) = setup_duplicates();
+    let manifest = tmp.path().join("duplicates.yaml");
+
+    Scanner::with_options(ScanOptions {
+        recursive: false,
+        quiet: true,
+        output: Some(manifest.clone()),
+        format: ReportFormat::Yaml,
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    })
+    .execute()
+    .expect("Scan should succeed");
+    let yaml = fs::read_to_string(&manifest).unwrap();
+    assert!(yaml.starts_with("version:"), "The manifest should be YAML: {}", yaml);
+
+    let summary = Eraser::new(tmp.path().to_path_buf(), true)
+        .with_manifest_name("duplicates.yaml")
+        .erase_duplicates()
+        .expect("Erase should succeed");
+    assert_eq!(summary.deleted, 1);
+    assert_path_not_exists(&manifest);
+    assert!(
+        original.exists() != duplicate.exists(),
+        "Exactly one file of the pair should remain"
+    );
+}
+
+#[test]
+fn test_erase_reads_jsonl_manifest() {
+    let (tmp, original, duplicate) = setup_duplicates();
+    let manifest = tmp.path().join("duplicates.jsonl");
+
+    Scanner::with_options(ScanOptions {
+        recursive: false,
+        quiet: true,
+        output: Some(manifest.clone()),
+        format: ReportFormat::Jsonl,
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    })
+    .execute()
+    .expect("Scan should succeed");
+    let jsonl = fs::read_to_string(&manifest).unwrap();
+    assert_eq!(jsonl.lines().count(), 2, "Expected a header and one group");
+
+    let summary = Eraser::new(tmp.path().to_path_buf(), true)
+        .with_manifest_name("duplicates.jsonl")
+        .erase_duplicates()
+        .expect("Erase should succeed");
+    assert_eq!(summary.deleted, 1);
+    assert_path_not_exists(&manifest);
+    assert!(original.exists() != duplicate.exists());
+}
+
+#[test]
+fn test_erase_reads_manifest_from_any_path() {
+    let (tmp, original, duplicate) = setup_duplicates();
+    let elsewhere = temp_dir();
+    let manifest = elsewhere.path().join("scan-results.json");
+
+    Scanner::with_options(ScanOptions {
+        recursive: false,
+        quiet: true,
+        output: Some(manifest.clone()),
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    })
+    .execute()
+    .expect("Scan should succeed");
+
+    let summary = Eraser::new(elsewhere.path().to_path_buf(), true)
+        .with_manifest_path(Some(manifest.clone()))
+        .with_delete_mode(DeleteMode::Soft)
+        .erase_duplicates()
+        .unwrap();
+
+    assert_eq!(summary.deleted, 1);
+    assert!(original.exists() != duplicate.exists());
+    assert_path_not_exists(&manifest);
+    // Erased files are soft-deleted where they were scanned, not next to the manifest
+    assert_path_exists(&tmp.path().join(".mde_soft_deleted"));
+    assert_path_not_exists(&elsewhere.path().join(".mde_soft_deleted"));
+}
+
+#[test]
+fn test_canceled_erase_restores_staged_files() {
+    let (tmp, original, duplicate) = setup_duplicates();
+    run_scan(tmp.path());
+    let cancel = CancelToken::new();
+    cancel.cancel();
+
+    let eraser = Eraser::new(tmp.path().to_path_buf(), true).with_cancel(Some(cancel));
+    let summary = eraser.erase_duplicates().unwrap();
+
+    assert!(summary.canceled);
+    assert!(!summary.rolled_back);
+    assert_eq!(summary.deleted, 0);
+    assert_path_exists(&original);
+    assert_path_exists(&duplicate);
+    assert_path_exists(&tmp.path().join("duplicates.json"));
+    assert_path_not_exists(&tmp.path().join(".mde_erase_staging"));
+    assert!(matches!(eraser.execute(), Err(Error::Canceled)));
+}
+
+#[test]
+fn test_erase_stages_in_custom_directory() {
+    let (tmp, original, duplicate) = setup_duplicates();
+    let staging = temp_dir();
+    run_scan(tmp.path());
+
+    let summary = Eraser::new(tmp.path().to_path_buf(), true)
+        .with_staging_dir(Some(staging.path().to_path_buf()))
+        .with_delete_mode(DeleteMode::Soft)
+        .erase_duplicates()
+        .unwrap();
+
+    assert_eq!(summary.deleted, 1);
+    assert!(original.exists() != duplicate.exists());
+    // The staging area is cleaned up and the undo batch stays with the erased files
+    assert_path_not_exists(&staging.path().join(".mde_erase_staging"));
+    assert_path_not_exists(&tmp.path().join(".mde_erase_staging"));
+    assert_path_exists(&tmp.path().join(".mde_soft_deleted"));
+}
+
+#[test]
+fn test_scan_stores_absolute_paths_and_root() {
+    let (tmp, _original, _duplicate) = setup_duplicates();
+    run_scan(tmp.path());
+
+    let file = DuplicatesFile::load(&tmp.path().join("duplicates.json")).unwrap();
+    assert_eq!(file.root.as_deref(), Some(tmp.path()));
+    for entry in &file.entries {
+        assert!(entry.original.is_absolute());
+        assert!(entry.duplicates.iter().all(|p| p.is_absolute()));
+    }
+    assert!(file.paths_outside_root().is_empty());
+}
+
+#[test]
+fn test_erase_resolves_relative_paths_against_root() {
+    // Relative entries must not depend on the working directory erase runs in
+    let (tmp, original, duplicate) = setup_duplicates();
+    let content = format!(
+        r#"{{
+        "version": "1.0",
+        "scanned_at": "2024-01-01T00:00:00Z",
+        "total_files_scanned": 2,
+        "duplicate_groups": 1,
+        "total_duplicates": 1,
+        "root": {:?},
+        "entries": [{{"original": "original.txt", "duplicates": ["duplicate.txt"], "duplicate_type": "exact"}}]
+    }}"#,
+        tmp.path()
+    );
+    fs::write(tmp.path().join("duplicates.json"), content).unwrap();
+
+    let file = DuplicatesFile::load(&tmp.path().join("duplicates.json")).unwrap();
+    assert_eq!(file.entries[0].duplicates, vec![duplicate.clone()]);
+
+    let summary = Eraser::new(tmp.path().to_path_buf(), true)
+        .erase_duplicates()
+        .unwrap();
+    assert_eq!(summary.deleted, 1);
+    assert_path_exists(&original);
+    assert_path_not_exists(&duplicate);
+}
+
+#[test]
+fn test_paths_outside_root_are_reported() {
+    let (tmp, original, _duplicate) = setup_duplicates();
+    let elsewhere = temp_dir();
+    let stray = elsewhere.path().join("original.txt");
+    fs::copy(&original, &stray).unwrap();
+    let content = format!(
+        r#"{{
+        "version": "1.0",
+        "scanned_at": "2024-01-01T00:00:00Z",
+        "total_files_scanned": 2,
+        "duplicate_groups": 1,
+        "total_duplicates": 1,
+        "root": {:?},
+        "entries": [{{"original": {:?}, "duplicates": [{:?}], "duplicate_type": "exact"}}]
+    }}"#,
+        tmp.path(),
+        original,
+        stray
+    );
+    fs::write(tmp.path().join("duplicates.json"), content).unwrap();
+
+    let file = DuplicatesFile::load(&tmp.path().join("duplicates.json")).unwrap();
+    assert_eq!(file.paths_outside_root(), vec![stray.as_path()]);
+}
+
+#[test]
+fn test_live_photo_pairs_are_resolved_against_root() {
+    let tmp = temp_dir();
+    let elsewhere = temp_dir();
+    let stray = elsewhere.path().join("b.mov");
+    let content = format!(
+        r#"{{
+        "version": "1.0",
+        "scanned_at": "2024-01-01T00:00:00Z",
+        "total_files_scanned": 4,
+        "duplicate_groups": 1,
+        "total_duplicates": 1,
+        "root": {:?},
+        "entries": [{{"original": "a.jpg", "duplicates": ["b.jpg"], "duplicate_type": "perceptual",
+            "paired": {{"a.jpg": "a.mov", "b.jpg": {:?}}}}}]
+    }}"#,
+        tmp.path(),
+        stray
+    );
+    fs::write(tmp.path().join("duplicates.json"), content).unwrap();
+
+    let file = DuplicatesFile::load(&tmp.path().join("duplicates.json")).unwrap();
+    let paired = &file.entries[0].paired;
+    assert_eq!(
+        paired.get(&tmp.path().join("a.jpg")),
+        Some(&tmp.path().join("a.mov"))
+    );
+    assert_eq!(paired.get(&tmp.path().join("b.jpg")), Some(&stray));
+    assert_eq!(file.paths_outside_root(), vec![stray.as_path()]);
+}
+
+#[test]
+fn test_erase_execute_returns_summary() {
+    let (tmp, _original, _duplicate) = setup_duplicates();
+    run_scan(tmp.path());
+
+    let summary = Eraser::new(tmp.path().to_path_buf(), true)
+        .execute()
+        .expect("Eraser should execute without error");
+
+    assert_eq!(summary.deleted, 1);
+    assert_eq!(
+        summary.reclaimed_bytes,
+        "This is the content that will be duplicated".len() as u64
+    );
+    assert!(!summary.dry_run);
+    assert!(!summary.rolled_back);
+    assert!(!summary.declined, "Quiet erases should not ask for confirmation");
+}
+
+#[test]
+fn test_erase_with_assume_yes_does_not_ask_for_confirmation() {
+    let (tmp, original, duplicate) = setup_duplicates();
+    run_scan(tmp.path());
+
+    // Not quiet, so only --yes keeps erase from prompting or refusing
+    let summary = Eraser::new(tmp.path().to_path_buf(), false)
+        .with_assume_yes(true)
+        .erase_duplicates()
+        .expect("Erase should succeed");
+
+    assert!(!summary.declined);
+    assert_eq!(summary.deleted, 1);
+    assert_path_exists(&duplicate);
+    assert_path_not_exists(&original);
+}
+
+#[test]
+fn test_erase_takes_live_photo_video_with_its_image() {
+    let tmp = temp_dir();
+    let copy_dir = tmp.path().join("copy");
+    fs::create_dir(&copy_dir).unwrap();
+    for dir in [tmp.path(), copy_dir.as_path()] {
+        fs::write(dir.join("IMG_0001.HEIC"), "live photo still").unwrap();
+    }
+    // The videos differ, so only the pairing ties the copy's video to its image
+    fs::write(tmp.path().join("IMG_0001.MOV"), "live photo motion").unwrap();
+    fs::write(copy_dir.join("IMG_0001.MOV"), "re-encoded motion").unwrap();
+
+    Scanner::with_options(ScanOptions {
+        quiet: true,
+        detection: DetectionOptions {
+            live_photos: true,
+            ..DetectionOptions::default()
+        },
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    })
+    .execute()
+    .expect("Scan should succeed");
+
+    let manifest = DuplicatesFile::load(&tmp.path().join("duplicates.json")).unwrap();
+    assert_eq!(manifest.entries.len(), 1);
+    let entry = &manifest.entries[0];
+    assert_eq!(entry.paired.len(), 2, "Both images should have a video");
+    let kept = entry.original.clone();
+    let erased = entry.duplicates[0].clone();
+    assert_eq!(
+        entry.files_to_erase(),
+        vec![erased.clone(), erased.with_extension("MOV")]
+    );
+
+    let summary = Eraser::new(tmp.path().to_path_buf(), true)
+        .erase_duplicates()
+        .expect("Erase should succeed");
+
+    assert_eq!(summary.deleted, 2);
+    assert_path_not_exists(&erased);
+    assert_path_not_exists(&erased.with_extension("MOV"));
+    assert_path_exists(&kept);
+    assert_path_exists(&kept.with_extension("MOV"));
+}
+
+#[test]
+fn test_erase_skips_probable_duplicates() {
+    let (tmp, original, duplicate) = setup_duplicates();
+    Scanner::with_options(ScanOptions {
+        recursive: false,
+        quiet: true,
+        detection: DetectionOptions {
+            quick: Some(8),
+            ..DetectionOptions::default()
+        },
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    })
+    .execute()
+    .expect("Scan should succeed");
+    let duplicates_json = tmp.path().join("duplicates.json");
+    let manifest = DuplicatesFile::load(&duplicates_json).unwrap();
+    assert_eq!(manifest.entries.len(), 1);
+    assert!(manifest.entries[0].is_probable());
+
+    let summary = Eraser::new(tmp.path().to_path_buf(), true)
+        .erase_duplicates()
+        .expect("Erase should succeed");
+
+    assert_eq!(summary.deleted, 0);
+    assert_path_exists(&original);
+    assert_path_exists(&duplicate);
+    assert_path_exists(&duplicates_json);
+}
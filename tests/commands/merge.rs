@@ -0,0 +1,174 @@
+//! Integration tests for the merge command.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use media_duplicate_eraser_rs::commands::merge::{MergeSummary, Merger};
+use media_duplicate_eraser_rs::commands::Command;
+use media_duplicate_eraser_rs::error::Error;
+use media_duplicate_eraser_rs::services::duplicate::{DuplicatesFile, OriginalStrategy};
+use media_duplicate_eraser_rs::services::hasher::HashAlgorithm;
+
+use crate::common::{assert_path_exists, temp_dir};
+
+/// Writes identical files named `names` into `dir` and returns their paths.
+fn write_copies(dir: &Path, names: &[&str]) -> Vec<PathBuf> {
+    names
+        .iter()
+        .map(|name| {
+            let path = dir.join(name);
+            fs::write(&path, "This is the content that will be duplicated").unwrap();
+            path
+        })
+        .collect()
+}
+
+/// Writes a duplicates file listing each group as an original followed by
+/// its duplicates.
+fn write_manifest(path: &Path, hash_algorithm: &str, groups: &[&[PathBuf]]) {
+    let entries: Vec<String> = groups
+        .iter()
+        .map(|files| {
+            format!(
+                r#"{{"original": {:?}, "duplicates": {:?}, "duplicate_type": "exact"}}"#,
+                files[0],
+                &files[1..]
+            )
+        })
+        .collect();
+    let content = format!(
+        r#"{{
+        "version": "1.0",
+        "scanned_at": "2024-01-01T00:00:00Z",
+        "total_files_scanned": 10,
+        "hash_algorithm": "{}",
+        "duplicate_groups": {},
+        "total_duplicates": 0,
+        "entries": [{}]
+    }}"#,
+        hash_algorithm,
+        groups.len(),
+        entries.join(", ")
+    );
+    fs::write(path, content).unwrap();
+}
+
+#[test]
+fn test_merge_joins_groups_sharing_a_file() {
+    let tmp = temp_dir();
+    let files = write_copies(tmp.path(), &["a.txt", "b.txt", "c.txt"]);
+    let others = write_copies(tmp.path(), &["d.txt", "e.txt"]);
+    let first = tmp.path().join("first.json");
+    let second = tmp.path().join("second.json");
+    write_manifest(&first, "sha256", &[&[files[0].clone(), files[1].clone()]]);
+    write_manifest(
+        &second,
+        "sha256",
+        &[&[files[1].clone(), files[2].clone()], &others],
+    );
+    let output = tmp.path().join("combined.json");
+
+    let summary = Merger::new(vec![first, second], output.clone(), true)
+        .execute()
+        .expect("Merge should succeed");
+
+    assert_eq!(
+        summary,
+        MergeSummary {
+            manifests: 2,
+            groups: 2,
+            duplicates: 3,
+            joined: 1,
+            conflicts: 1,
+        }
+    );
+    let merged = DuplicatesFile::load(&output).unwrap();
+    assert_eq!(merged.duplicate_groups, 2);
+    assert_eq!(merged.total_duplicates, 3);
+    assert_eq!(merged.total_files_scanned, 20);
+    assert_eq!(merged.entries[0].original, files[0], "Files should be kept alphabetically");
+    assert_eq!(merged.entries[0].duplicates, vec![files[1].clone(), files[2].clone()]);
+    let size = fs::metadata(&files[1]).unwrap().len();
+    assert_eq!(merged.entries[0].reclaimable_bytes, 2 * size);
+    assert_eq!(merged.entries[1].original, others[0]);
+    for file in &files {
+        assert_path_exists(file);
+    }
+}
+
+#[test]
+fn test_merge_resolves_conflicting_originals_with_strategy() {
+    let tmp = temp_dir();
+    let files = write_copies(tmp.path(), &["a.txt", "bb.txt", "ccc.txt"]);
+    let first = tmp.path().join("first.json");
+    let second = tmp.path().join("second.yaml");
+    write_manifest(&first, "sha256", &[&[files[0].clone(), files[1].clone()]]);
+    let json = tmp.path().join("second.json");
+    write_manifest(&json, "sha256", &[&[files[2].clone(), files[1].clone()]]);
+    DuplicatesFile::load(&json).unwrap().save(&second).unwrap();
+    let output = tmp.path().join("combined.toml");
+
+    let summary = Merger::new(vec![first, second], output.clone(), true)
+        .with_original_strategy(OriginalStrategy::LongestPath)
+        .merge()
+        .expect("Merge should succeed");
+
+    assert_eq!(summary.conflicts, 1);
+    let merged = DuplicatesFile::load(&output).unwrap();
+    assert_eq!(merged.entries.len(), 1);
+    assert_eq!(merged.entries[0].original, files[2], "The longest path should be kept");
+    assert_eq!(merged.entries[0].duplicates.len(), 2);
+}
+
+#[test]
+fn test_merge_keeps_agreed_original() {
+    let tmp = temp_dir();
+    let files = write_copies(tmp.path(), &["a.txt", "b.txt", "c.txt"]);
+    let first = tmp.path().join("first.json");
+    let second = tmp.path().join("second.json");
+    write_manifest(&first, "sha256", &[&[files[2].clone(), files[0].clone()]]);
+    write_manifest(&second, "sha256", &[&[files[2].clone(), files[1].clone()]]);
+    let output = tmp.path().join("combined.json");
+
+    let summary = Merger::new(vec![first, second], output.clone(), true)
+        .merge()
+        .expect("Merge should succeed");
+
+    assert_eq!(summary.joined, 1);
+    assert_eq!(summary.conflicts, 0);
+    let merged = DuplicatesFile::load(&output).unwrap();
+    assert_eq!(merged.entries[0].original, files[2]);
+}
+
+#[test]
+fn test_merge_rejects_other_hash_algorithm() {
+    let tmp = temp_dir();
+    let files = write_copies(tmp.path(), &["a.txt", "b.txt"]);
+    let first = tmp.path().join("first.json");
+    let second = tmp.path().join("second.json");
+    write_manifest(&first, "sha256", &[&files]);
+    write_manifest(&second, "blake3", &[&files]);
+    let output = tmp.path().join("combined.json");
+
+    let result = Merger::new(vec![first, second], output.clone(), true).merge();
+
+    assert!(matches!(
+        result,
+        Err(Error::HashAlgorithmMismatch {
+            found: HashAlgorithm::Blake3,
+            expected: HashAlgorithm::Sha256,
+        })
+    ));
+    assert!(!output.exists());
+}
+
+#[test]
+fn test_merge_fails_on_missing_manifest() {
+    let tmp = temp_dir();
+    let missing = tmp.path().join("missing.json");
+    let output = tmp.path().join("combined.json");
+
+    let result = Merger::new(vec![missing], output, true).merge();
+
+    assert!(matches!(result, Err(Error::PathNotFound(_))));
+}
@@ -2,4 +2,9 @@
 
 pub mod clean;
 pub mod erase;
+pub mod merge;
+pub mod regroup;
 pub mod scan;
+pub mod stats;
+pub mod undo;
+pub mod verify;
@@ -0,0 +1,320 @@
+//! Integration tests for the regroup command.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+use image_hasher::ImageHash;
+
+use media_duplicate_eraser_rs::commands::regroup::Regrouper;
+use media_duplicate_eraser_rs::commands::scan::{ScanOptions, Scanner};
+use media_duplicate_eraser_rs::commands::Command;
+use media_duplicate_eraser_rs::error::Error;
+use media_duplicate_eraser_rs::services::duplicate::{
+    DetectionOptions, DuplicateGroup, DuplicateReport, DuplicateType, DuplicatesFile, FileHashes,
+    Grouping, MediaThresholds,
+};
+use media_duplicate_eraser_rs::services::hasher::PerceptualConfig;
+
+use crate::common::{images_fixtures_dir, temp_dir};
+
+/// Copies the image fixtures into a temp dir and scans them with `threshold`.
+fn scan_images(threshold: u32, store_hashes: bool) -> tempfile::TempDir {
+    scan_images_with(DetectionOptions {
+        threshold,
+        keep_hashes: store_hashes,
+        ..DetectionOptions::default()
+    })
+}
+
+/// Copies the image fixtures into a temp dir and scans them with `detection`.
+fn scan_images_with(detection: DetectionOptions) -> tempfile::TempDir {
+    let tmp = temp_dir();
+    for entry in fs::read_dir(images_fixtures_dir()).unwrap() {
+        let path = entry.unwrap().path();
+        fs::copy(&path, tmp.path().join(path.file_name().unwrap())).unwrap();
+    }
+
+    let scanner = Scanner::with_options(ScanOptions {
+        quiet: true,
+        use_cache: false,
+        detection,
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    });
+    scanner.execute().expect("Scan should succeed");
+    tmp
+}
+
+/// Returns the groups of a duplicates file as sorted file names.
+fn group_names(file: &DuplicatesFile) -> Vec<Vec<String>> {
+    let mut groups: Vec<Vec<String>> = file
+        .entries
+        .iter()
+        .map(|entry| {
+            let mut names: Vec<String> = std::iter::once(&entry.original)
+                .chain(&entry.duplicates)
+                .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        })
+        .collect();
+    groups.sort();
+    groups
+}
+
+#[test]
+fn test_regroup_matches_rescan_with_new_threshold() {
+    let tmp = scan_images(0, true);
+    let manifest = tmp.path().join("duplicates.json");
+    assert!(!DuplicatesFile::load(&manifest).unwrap().hashes.is_empty());
+
+    let summary = Regrouper::new(tmp.path().to_path_buf(), 10, true)
+        .regroup()
+        .unwrap()
+        .expect("Stored hashes should be found");
+
+    let regrouped = DuplicatesFile::load(&manifest).unwrap();
+    let rescanned = DuplicatesFile::load(&scan_images(10, false).path().join("duplicates.json"))
+        .unwrap();
+
+    assert_eq!(summary.groups_after, regrouped.entries.len());
+    assert_eq!(regrouped.similarity_threshold, Some(10));
+    assert_eq!(group_names(&regrouped), group_names(&rescanned));
+    // Hashes are kept so the file can be regrouped again
+    assert!(!regrouped.hashes.is_empty());
+    assert!(
+        regrouped
+            .entries
+            .iter()
+            .all(|e| e.distances.len() == e.duplicates.len()),
+        "Distances should be rebuilt from the stored hashes"
+    );
+}
+
+#[test]
+fn test_regroup_keeps_media_type_thresholds_of_the_scan() {
+    let image_threshold = MediaThresholds {
+        image: Some(10),
+        ..MediaThresholds::default()
+    };
+    let tmp = scan_images_with(DetectionOptions {
+        threshold: 0,
+        media_thresholds: image_threshold,
+        keep_hashes: true,
+        ..DetectionOptions::default()
+    });
+    let manifest = tmp.path().join("duplicates.json");
+    let rescanned = DuplicatesFile::load(&scan_images(10, false).path().join("duplicates.json"))
+        .unwrap();
+
+    Regrouper::new(tmp.path().to_path_buf(), 0, true)
+        .regroup()
+        .unwrap()
+        .expect("Stored hashes should be found");
+    let regrouped = DuplicatesFile::load(&manifest).unwrap();
+    assert_eq!(regrouped.media_thresholds, image_threshold);
+    assert_eq!(group_names(&regrouped), group_names(&rescanned));
+
+    // Thresholds passed to regroup replace those of the scan
+    let strict = MediaThresholds {
+        image: Some(0),
+        ..MediaThresholds::default()
+    };
+    Regrouper::new(tmp.path().to_path_buf(), 10, true)
+        .with_media_thresholds(Some(strict))
+        .regroup()
+        .unwrap()
+        .expect("Stored hashes should be found");
+    let regrouped = DuplicatesFile::load(&manifest).unwrap();
+    let strict_scan = DuplicatesFile::load(&scan_images(0, false).path().join("duplicates.json"))
+        .unwrap();
+    assert_eq!(regrouped.media_thresholds, strict);
+    assert_eq!(group_names(&regrouped), group_names(&strict_scan));
+}
+
+#[test]
+fn test_regroup_without_stored_hashes_leaves_file_untouched() {
+    let tmp = scan_images(10, false);
+    let manifest = tmp.path().join("duplicates.json");
+    let before = fs::read_to_string(&manifest).unwrap();
+
+    let regrouper = Regrouper::new(tmp.path().to_path_buf(), 0, true);
+    assert!(regrouper.regroup().unwrap().is_none());
+    assert!(regrouper.execute().is_ok());
+    assert_eq!(fs::read_to_string(&manifest).unwrap(), before);
+}
+
+#[test]
+fn test_regroup_rejects_mismatched_hash_settings() {
+    let small = PerceptualConfig {
+        hash_size: 8,
+        ..PerceptualConfig::default()
+    };
+    let tmp = scan_images_with(DetectionOptions {
+        perceptual: small,
+        keep_hashes: true,
+        ..DetectionOptions::default()
+    });
+    let manifest = tmp.path().join("duplicates.json");
+    let before = fs::read_to_string(&manifest).unwrap();
+    assert_eq!(DuplicatesFile::load(&manifest).unwrap().perceptual_config, small);
+
+    let result = Regrouper::new(tmp.path().to_path_buf(), 10, true)
+        .with_perceptual_config(Some(PerceptualConfig::default()))
+        .regroup();
+    assert!(matches!(
+        result,
+        Err(Error::PerceptualConfigMismatch { found, .. }) if found == small
+    ));
+    assert_eq!(fs::read_to_string(&manifest).unwrap(), before);
+
+    // Matching settings, or none at all, use the stored hashes
+    let regrouper = Regrouper::new(tmp.path().to_path_buf(), 10, true);
+    assert!(regrouper.regroup().unwrap().is_some());
+    let regrouper = regrouper.with_perceptual_config(Some(small));
+    assert!(regrouper.regroup().unwrap().is_some());
+    let regrouped = DuplicatesFile::load(&manifest).unwrap();
+    assert_eq!(regrouped.perceptual_config, small);
+}
+
+#[test]
+fn test_pairwise_grouping_splits_chained_matches() {
+    // b is 12 bits from a and c, which are 24 bits apart: a~b~c chains at threshold 15
+    let tmp = temp_dir();
+    let bits = [
+        ("a.png", [0x00, 0x00, 0x00]),
+        ("b.png", [0xFF, 0x0F, 0x00]),
+        ("c.png", [0xFF, 0xFF, 0xFF]),
+    ];
+    let hashes: BTreeMap<_, _> = bits
+        .into_iter()
+        .map(|(name, bits)| {
+            let mut bytes = [0u8; 32];
+            bytes[..3].copy_from_slice(&bits);
+            let hash: ImageHash = ImageHash::from_bytes(&bytes).unwrap();
+            let stored = FileHashes {
+                exact: None,
+                perceptual: Some(hash.to_base64()),
+            };
+            (tmp.path().join(name), stored)
+        })
+        .collect();
+    let manifest = tmp.path().join("duplicates.json");
+    DuplicatesFile::from_report(&DuplicateReport {
+        total_files: 3,
+        hashes,
+        ..DuplicateReport::default()
+    })
+    .save(&manifest)
+    .unwrap();
+
+    let regrouper = Regrouper::new(tmp.path().to_path_buf(), 15, true);
+    let summary = regrouper.regroup().unwrap().unwrap();
+    assert_eq!(summary.grouping, Grouping::Transitive);
+    let transitive = DuplicatesFile::load(&manifest).unwrap();
+    assert_eq!(group_names(&transitive), vec![vec!["a.png", "b.png", "c.png"]]);
+
+    let regrouper = regrouper.with_grouping(Some(Grouping::Pairwise));
+    let summary = regrouper.regroup().unwrap().unwrap();
+    assert_eq!(summary.grouping, Grouping::Pairwise);
+    let pairwise = DuplicatesFile::load(&manifest).unwrap();
+    assert_eq!(pairwise.grouping, Grouping::Pairwise);
+    assert_eq!(group_names(&pairwise), vec![vec!["a.png", "b.png"]]);
+
+    // The mode is kept when the file is regrouped again
+    let regrouper = Regrouper::new(tmp.path().to_path_buf(), 15, true);
+    assert_eq!(regrouper.regroup().unwrap().unwrap().grouping, Grouping::Pairwise);
+}
+
+#[test]
+fn test_regroup_keeps_live_photo_pairs() {
+    let tmp = temp_dir();
+    let path = |name: &str| tmp.path().join(name);
+    // Images and videos all hash alike, but the videos only follow their image
+    let hash: ImageHash = ImageHash::from_bytes(&[0u8; 32]).unwrap();
+    let hash = hash.to_base64();
+    let hashes: BTreeMap<_, _> = ["a.jpg", "b.jpg", "a.mov", "b.mov"]
+        .into_iter()
+        .map(|name| {
+            let stored = FileHashes {
+                exact: None,
+                perceptual: Some(hash.clone()),
+            };
+            (path(name), stored)
+        })
+        .collect();
+    let live_photo_pairs: HashMap<_, _> = [
+        (path("a.jpg"), path("a.mov")),
+        (path("b.jpg"), path("b.mov")),
+    ]
+    .into_iter()
+    .collect();
+    let manifest = tmp.path().join("duplicates.json");
+    DuplicatesFile::from_report(&DuplicateReport {
+        groups: vec![DuplicateGroup {
+            files: vec![path("a.jpg"), path("b.jpg")],
+            duplicate_type: DuplicateType::Perceptual,
+        }],
+        total_files: 4,
+        hashes,
+        live_photo_pairs,
+        ..DuplicateReport::default()
+    })
+    .save(&manifest)
+    .unwrap();
+
+    Regrouper::new(tmp.path().to_path_buf(), 0, true)
+        .regroup()
+        .unwrap()
+        .expect("Stored hashes should be found");
+
+    let regrouped = DuplicatesFile::load(&manifest).unwrap();
+    assert_eq!(group_names(&regrouped), vec![vec!["a.jpg", "b.jpg"]]);
+    let expected: BTreeMap<_, _> = [
+        (path("a.jpg"), path("a.mov")),
+        (path("b.jpg"), path("b.mov")),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(regrouped.entries[0].paired, expected);
+}
+
+#[test]
+fn test_regroup_keeps_videos_matched_by_scene() {
+    let tmp = temp_dir();
+    let path = |name: &str| tmp.path().join(name);
+    // The videos were matched by scene, and their single hashes are far apart
+    let hashes: BTreeMap<_, _> = [("a.mp4", [0u8; 32]), ("b.mp4", [0xFF; 32])]
+        .into_iter()
+        .map(|(name, bytes)| {
+            let hash: ImageHash = ImageHash::from_bytes(&bytes).unwrap();
+            let stored = FileHashes {
+                exact: None,
+                perceptual: Some(hash.to_base64()),
+            };
+            (path(name), stored)
+        })
+        .collect();
+    let manifest = tmp.path().join("duplicates.json");
+    DuplicatesFile::from_report(&DuplicateReport {
+        groups: vec![DuplicateGroup {
+            files: vec![path("a.mp4"), path("b.mp4")],
+            duplicate_type: DuplicateType::Perceptual,
+        }],
+        total_files: 2,
+        video_scenes: true,
+        hashes,
+        ..DuplicateReport::default()
+    })
+    .save(&manifest)
+    .unwrap();
+
+    Regrouper::new(tmp.path().to_path_buf(), 0, true)
+        .regroup()
+        .unwrap()
+        .expect("Stored hashes should be found");
+
+    let regrouped = DuplicatesFile::load(&manifest).unwrap();
+    assert!(regrouped.video_scenes);
+    assert_eq!(group_names(&regrouped), vec![vec!["a.mp4", "b.mp4"]]);
+}
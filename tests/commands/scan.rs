@@ -1,10 +1,22 @@
 //! Integration tests for the scan command.
 
-use media_duplicate_eraser_rs::commands::scan::Scanner;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use media_duplicate_eraser_rs::commands::scan::{ExtensionFilter, ScanOptions, ScanSummary, Scanner};
 use media_duplicate_eraser_rs::commands::Command;
-use media_duplicate_eraser_rs::services::duplicate::{self, DuplicateType, MediaFilter};
+use media_duplicate_eraser_rs::error::Error;
+use media_duplicate_eraser_rs::services::duplicate::{
+    self, DetectionOptions, DetectionPreset, DuplicateGroup, DuplicateReport, DuplicateType,
+    DuplicatesFile, GroupOrder, MediaFilter, MediaThresholds, OriginalStrategy, Prescreen,
+    ProgressCallback, ProgressEvent, ProgressLog,
+};
+use media_duplicate_eraser_rs::services::cache::HashCache;
+use media_duplicate_eraser_rs::services::cancel::CancelToken;
+use media_duplicate_eraser_rs::services::hasher::{self, HashAlgorithm};
 
-use crate::common::{fixture_path, images_fixtures_dir, temp_dir, text_fixtures_dir};
+use crate::common::{fixture_path, images_fixtures_dir, run_ffmpeg, temp_dir, text_fixtures_dir};
 
 #[test]
 fn test_scan_detects_exact_duplicates() {
@@ -56,6 +68,90 @@ fn test_scan_does_not_report_unique_files_as_duplicates() {
     assert_eq!(report.errors, 0, "Should have no errors");
 }
 
+#[test]
+fn test_scan_counts_a_file_passed_twice_once() {
+    let tmp = temp_dir();
+    let file = tmp.path().join("a.txt");
+    std::fs::write(&file, "only one copy").unwrap();
+    let link = tmp.path().join("link.txt");
+    std::os::unix::fs::symlink(&file, &link).unwrap();
+    let files = vec![file.clone(), tmp.path().join(".").join("a.txt"), link];
+
+    let report = duplicate::find_duplicates(&files).unwrap();
+
+    assert!(report.groups.is_empty(), "A file is not its own duplicate");
+    assert_eq!(report.total_files, 1);
+}
+
+#[test]
+fn test_scan_against_baseline_reports_only_copies_of_baseline_files() {
+    let library = temp_dir();
+    for name in ["image_a.png", "unique.png"] {
+        std::fs::copy(images_fixtures_dir().join(name), library.path().join(name)).unwrap();
+    }
+    let baseline = library.path().join("library.json");
+    Scanner::with_options(ScanOptions {
+        output: Some(baseline.clone()),
+        quiet: true,
+        use_cache: false,
+        detection: DetectionOptions {
+            keep_hashes: true,
+            ..DetectionOptions::default()
+        },
+        ..ScanOptions::new(library.path().to_path_buf())
+    })
+    .execute()
+    .expect("Baseline scan should succeed");
+    assert!(baseline.exists(), "Stored hashes should be saved without duplicates");
+
+    let incoming = temp_dir();
+    let download = incoming.path().join("download.png");
+    std::fs::copy(images_fixtures_dir().join("image_a.png"), &download).unwrap();
+    std::fs::write(incoming.path().join("notes.txt"), "not in the library").unwrap();
+
+    let report = Scanner::with_options(ScanOptions {
+        quiet: true,
+        use_cache: false,
+        against: Some(baseline),
+        ..ScanOptions::new(incoming.path().to_path_buf())
+    })
+    .execute()
+    .expect("Scan should succeed");
+
+    assert_eq!(report.total_files, 2);
+    assert_eq!(report.groups.len(), 1);
+    assert_eq!(
+        report.groups[0].files,
+        vec![library.path().join("image_a.png"), download],
+        "The library file should be kept as the original"
+    );
+}
+
+#[test]
+fn test_scan_against_baseline_without_hashes_fails() {
+    let library = temp_dir();
+    std::fs::write(library.path().join("a.txt"), "same content").unwrap();
+    std::fs::write(library.path().join("b.txt"), "same content").unwrap();
+    let baseline = library.path().join("duplicates.json");
+    Scanner::with_options(ScanOptions {
+        quiet: true,
+        use_cache: false,
+        ..ScanOptions::new(library.path().to_path_buf())
+    })
+    .execute()
+    .expect("Baseline scan should succeed");
+
+    let incoming = temp_dir();
+    let result = Scanner::with_options(ScanOptions {
+        quiet: true,
+        against: Some(baseline.clone()),
+        ..ScanOptions::new(incoming.path().to_path_buf())
+    })
+    .execute();
+
+    assert!(matches!(result, Err(Error::MissingHashes(path)) if path == baseline));
+}
+
 #[test]
 fn test_scan_handles_empty_input() {
     let files: Vec<std::path::PathBuf> = vec![];
@@ -103,7 +199,11 @@ fn test_scan_reports_correct_counts() {
 fn test_scanner_executes_without_error() {
     let tmp = temp_dir();
     let output = tmp.path().join("duplicates.json");
-    let scanner = Scanner::new(text_fixtures_dir(), true, false, Some(output.clone()), true, MediaFilter::All);
+    let scanner = Scanner::with_options(ScanOptions {
+        output: Some(output.clone()),
+        quiet: true,
+        ..ScanOptions::new(text_fixtures_dir())
+    });
     let result = scanner.execute();
 
     assert!(result.is_ok(), "Scanner should execute without error");
@@ -216,7 +316,12 @@ fn test_scan_image_reports_correct_counts() {
 fn test_scanner_executes_on_images_without_error() {
     let tmp = temp_dir();
     let output = tmp.path().join("duplicates.json");
-    let scanner = Scanner::new(images_fixtures_dir(), false, false, Some(output.clone()), true, MediaFilter::All);
+    let scanner = Scanner::with_options(ScanOptions {
+        recursive: false,
+        output: Some(output.clone()),
+        quiet: true,
+        ..ScanOptions::new(images_fixtures_dir())
+    });
     let result = scanner.execute();
 
     assert!(result.is_ok(), "Scanner should execute on images without error");
@@ -265,7 +370,11 @@ fn test_scan_mixed_files_in_directory() {
     }
 
     // Scan recursively
-    let scanner = Scanner::new(tmp.path().to_path_buf(), true, false, Some(output.clone()), true, MediaFilter::All);
+    let scanner = Scanner::with_options(ScanOptions {
+        output: Some(output.clone()),
+        quiet: true,
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    });
     let result = scanner.execute();
 
     assert!(result.is_ok(), "Scanner should handle mixed file types");
@@ -278,3 +387,1635 @@ fn test_scan_mixed_files_in_directory() {
         "Output should contain entries"
     );
 }
+
+#[test]
+fn test_social_preset_matches_unrecognised_extension() {
+    let tmp = temp_dir();
+    let original = tmp.path().join("image_a.png");
+    let shared = tmp.path().join("IMG-WA0001.jfif");
+    std::fs::copy(fixture_path("images/image_a.png"), &original).unwrap();
+    std::fs::copy(fixture_path("images/image_a.png"), &shared).unwrap();
+    let files = vec![original, shared];
+
+    let mut options = DetectionOptions::from(MediaFilter::ImagesOnly);
+    let report = duplicate::find_duplicates_with_options(&files, None, &options).unwrap();
+    assert!(
+        report.groups.is_empty(),
+        "Unrecognised extensions should be ignored without the preset"
+    );
+
+    DetectionPreset::Social.apply(&mut options);
+    assert_eq!(options.threshold, duplicate::SOCIAL_PRESET_THRESHOLD);
+    let report = duplicate::find_duplicates_with_options(&files, None, &options).unwrap();
+    assert_eq!(report.groups.len(), 1, "Preset should pick up the .jfif copy");
+    assert_eq!(report.groups[0].files.len(), 2);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_scanner_refuses_filesystem_root() {
+    let scanner = Scanner::with_options(ScanOptions {
+        quiet: true,
+        ..ScanOptions::new("/".into())
+    });
+    let result = scanner.execute();
+
+    assert!(
+        matches!(result, Err(Error::DangerousPath(_))),
+        "Scanner should refuse to scan the filesystem root"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_scan_skips_unreadable_directories() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tmp = temp_dir();
+    std::fs::write(tmp.path().join("a.txt"), "same content").unwrap();
+    std::fs::write(tmp.path().join("b.txt"), "same content").unwrap();
+    let locked = tmp.path().join("locked");
+    std::fs::create_dir(&locked).unwrap();
+    std::fs::write(locked.join("c.txt"), "same content").unwrap();
+    std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+
+    // Permissions are not enforced for root, so there is nothing to skip
+    if std::fs::read_dir(&locked).is_ok() {
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+        return;
+    }
+
+    let result = Scanner::with_options(ScanOptions {
+        quiet: true,
+        use_cache: false,
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    })
+    .execute();
+    std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let report = result.expect("Scan should continue past the unreadable directory");
+    assert_eq!(report.inaccessible_paths, 1);
+    assert_eq!(report.groups.len(), 1);
+    assert_eq!(report.groups[0].files.len(), 2);
+}
+
+#[test]
+fn test_video_audio_match_groups_videos_that_only_sound_alike() {
+    if !hasher::is_ffmpeg_available() {
+        eprintln!("Skipping: FFmpeg is not installed");
+        return;
+    }
+
+    let tmp = temp_dir();
+    // Pictures that look nothing alike; the first two share their soundtrack
+    let clips = [
+        ("bars.mp4", "smptebars", "anoisesrc=d=3:seed=7"),
+        ("pattern.mp4", "testsrc", "anoisesrc=d=3:seed=7"),
+        ("colors.mp4", "rgbtestsrc", "anullsrc=duration=3"),
+    ];
+    let mut files = Vec::new();
+    for (name, picture, sound) in clips {
+        let clip = tmp.path().join(name);
+        run_ffmpeg(&[
+            "-f",
+            "lavfi",
+            "-i",
+            &format!("{}=duration=3:size=320x240:rate=25", picture),
+            "-f",
+            "lavfi",
+            "-i",
+            sound,
+            "-shortest",
+            &clip.to_string_lossy(),
+        ]);
+        files.push(clip);
+    }
+
+    let mut options = DetectionOptions::from(MediaFilter::VideosOnly);
+    let report = duplicate::find_duplicates_with_options(&files, None, &options).unwrap();
+    assert!(report.groups.is_empty(), "The pictures alone should not match");
+
+    options.video_audio_match = true;
+    let report = duplicate::find_duplicates_with_options(&files, None, &options).unwrap();
+    assert_eq!(report.groups.len(), 1);
+    let mut grouped = report.groups[0].files.clone();
+    grouped.sort();
+    assert_eq!(grouped, vec![files[0].clone(), files[1].clone()]);
+    assert_eq!(report.groups[0].duplicate_type, DuplicateType::Perceptual);
+}
+
+#[test]
+fn test_live_photos_keep_image_and_video_together() {
+    let tmp = temp_dir();
+    let copy_dir = tmp.path().join("copy");
+    std::fs::create_dir(&copy_dir).unwrap();
+
+    for dir in [tmp.path(), copy_dir.as_path()] {
+        std::fs::write(dir.join("IMG_0001.HEIC"), "live photo still").unwrap();
+        std::fs::write(dir.join("IMG_0001.MOV"), "live photo motion").unwrap();
+    }
+
+    let files = vec![
+        tmp.path().join("IMG_0001.HEIC"),
+        tmp.path().join("IMG_0001.MOV"),
+        copy_dir.join("IMG_0001.HEIC"),
+        copy_dir.join("IMG_0001.MOV"),
+    ];
+
+    let options = DetectionOptions {
+        live_photos: true,
+        ..DetectionOptions::default()
+    };
+    let report = duplicate::find_duplicates_with_options(&files, None, &options).unwrap();
+
+    assert_eq!(report.groups.len(), 1, "Videos should follow their image");
+    let group = &report.groups[0];
+    assert!(group.files.iter().all(|f| f.extension().unwrap() == "HEIC"));
+
+    // Every image is paired with the video next to it
+    for image in &group.files {
+        assert_eq!(
+            report.live_photo_pairs.get(image),
+            Some(&image.with_extension("MOV"))
+        );
+    }
+
+    // Erasing the duplicate image also frees its video
+    let freed = ("live photo still".len() + "live photo motion".len()) as u64;
+    assert_eq!(report.reclaimable_bytes(), freed);
+    assert_eq!(DuplicatesFile::from_report(&report).reclaimable_bytes, freed);
+}
+
+#[test]
+fn test_quick_scan_reports_probable_duplicates() {
+    let tmp = temp_dir();
+    let files: Vec<_> = ["a.bin", "b.bin", "c.bin", "d.bin"]
+        .iter()
+        .map(|name| tmp.path().join(name))
+        .collect();
+    std::fs::write(&files[0], "head, middle 1, tail").unwrap();
+    std::fs::write(&files[1], "head, middle 1, tail").unwrap();
+    // Only the middle differs, which a quick scan does not read
+    std::fs::write(&files[2], "head, middle 2, tail").unwrap();
+    std::fs::write(&files[3], "head, middle 1, tall").unwrap();
+
+    let options = DetectionOptions {
+        media_filter: MediaFilter::AnyFile,
+        quick: Some(6),
+        ..DetectionOptions::default()
+    };
+    let report = duplicate::find_duplicates_with_options(&files, None, &options).unwrap();
+
+    assert_eq!(report.quick, Some(6));
+    assert!(report.perceptual_skipped);
+    assert!(
+        report.checksums.is_empty(),
+        "No file should be hashed in full"
+    );
+    assert_eq!(report.groups.len(), 1);
+    assert_eq!(report.groups[0].duplicate_type, DuplicateType::Probable);
+    let mut grouped = report.groups[0].files.clone();
+    grouped.sort();
+    assert_eq!(grouped, files[..3].to_vec());
+    assert_eq!(report.probable_duplicate_count(), 2);
+    assert_eq!(report.exact_duplicate_count(), 0);
+}
+
+#[test]
+fn test_scanner_respects_max_depth() {
+    let tmp = temp_dir();
+    let nested = tmp.path().join("a").join("b");
+    std::fs::create_dir_all(&nested).unwrap();
+
+    std::fs::write(tmp.path().join("top.txt"), "same content").unwrap();
+    std::fs::write(tmp.path().join("a").join("middle.txt"), "same content").unwrap();
+    std::fs::write(nested.join("deep.txt"), "same content").unwrap();
+
+    let output = tmp.path().join("duplicates.json");
+    let scanner = Scanner::with_options(ScanOptions {
+        output: Some(output.clone()),
+        quiet: true,
+        max_depth: Some(2),
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    });
+    scanner.execute().expect("Scan should succeed");
+
+    let content = std::fs::read_to_string(&output).unwrap();
+    assert!(content.contains("top.txt"));
+    assert!(content.contains("middle.txt"));
+    assert!(
+        !content.contains("deep.txt"),
+        "Files below the maximum depth should not be scanned"
+    );
+}
+
+#[test]
+fn test_strict_perceptual_only_narrows_matches() {
+    let files = get_image_files();
+
+    let loose = duplicate::find_duplicates(&files).unwrap();
+    let options = DetectionOptions {
+        strict_perceptual: true,
+        ..DetectionOptions::default()
+    };
+    let strict = duplicate::find_duplicates_with_options(&files, None, &options).unwrap();
+
+    assert!(
+        strict.duplicate_count() <= loose.duplicate_count(),
+        "The strict check should never add duplicates"
+    );
+
+    // Byte-identical copies always survive the strict check
+    let image_a_group = strict
+        .groups
+        .iter()
+        .find(|g| g.files.iter().any(|f| f.ends_with("image_a.png")))
+        .expect("image_a.png should still be grouped");
+    assert!(image_a_group.files.iter().any(|f| f.ends_with("image_a copy.png")));
+}
+
+#[test]
+fn test_animations_sharing_a_first_frame_are_not_grouped() {
+    let files = vec![
+        fixture_path("animated/animation_a.gif"),
+        fixture_path("animated/animation_b.gif"),
+    ];
+
+    let report = duplicate::find_duplicates(&files).unwrap();
+    assert!(report.groups.is_empty(), "Only the first frames are identical");
+}
+
+#[test]
+fn test_format_conversions_are_grouped_with_exact_copies() {
+    // A PNG, an exact copy of it and a JPEG export of the same photo
+    let tmp = temp_dir();
+    let png = tmp.path().join("photo.png");
+    let copy = tmp.path().join("photo copy.png");
+    let jpeg = tmp.path().join("photo.jpg");
+    std::fs::copy(fixture_path("converted/photo.png"), &png).unwrap();
+    std::fs::copy(fixture_path("converted/photo.png"), &copy).unwrap();
+    std::fs::copy(fixture_path("converted/photo.jpg"), &jpeg).unwrap();
+
+    let report = duplicate::find_duplicates(&[png.clone(), copy.clone(), jpeg.clone()]).unwrap();
+
+    // Each file is reported once, in a single group
+    let mut expected = vec![png, copy, jpeg];
+    expected.sort();
+    assert_eq!(sorted_groups(&report.groups), vec![expected]);
+    assert_eq!(report.groups[0].duplicate_type, DuplicateType::Mixed);
+}
+
+#[test]
+fn test_threshold_out_of_range_is_rejected() {
+    let files = get_image_files();
+    let options = DetectionOptions {
+        threshold: 65,
+        ..DetectionOptions::default()
+    };
+    let result = duplicate::find_duplicates_with_options(&files, None, &options);
+
+    assert!(
+        matches!(result, Err(Error::InvalidThreshold(65))),
+        "Thresholds above 64 bits should be rejected"
+    );
+}
+
+#[test]
+fn test_media_type_threshold_overrides_global_threshold() {
+    let files = get_image_files();
+    let scan = |options: DetectionOptions| {
+        let report = duplicate::find_duplicates_with_options(&files, None, &options).unwrap();
+        sorted_groups(&report.groups)
+    };
+
+    let strict = scan(DetectionOptions {
+        threshold: 0,
+        ..DetectionOptions::default()
+    });
+    let loose = scan(DetectionOptions {
+        threshold: 64,
+        ..DetectionOptions::default()
+    });
+    assert_ne!(strict, loose, "The thresholds should group the images differently");
+
+    // Only the image threshold applies to images
+    let images_loose = scan(DetectionOptions {
+        threshold: 0,
+        media_thresholds: MediaThresholds {
+            image: Some(64),
+            audio: Some(0),
+            ..MediaThresholds::default()
+        },
+        ..DetectionOptions::default()
+    });
+    let images_strict = scan(DetectionOptions {
+        threshold: 64,
+        media_thresholds: MediaThresholds {
+            image: Some(0),
+            video: Some(64),
+            ..MediaThresholds::default()
+        },
+        ..DetectionOptions::default()
+    });
+    assert_eq!(images_loose, loose);
+    assert_eq!(images_strict, strict);
+}
+
+#[test]
+fn test_media_type_threshold_out_of_range_is_rejected() {
+    let files = get_image_files();
+    let options = DetectionOptions {
+        media_thresholds: MediaThresholds {
+            audio: Some(65),
+            ..MediaThresholds::default()
+        },
+        ..DetectionOptions::default()
+    };
+    let result = duplicate::find_duplicates_with_options(&files, None, &options);
+
+    assert!(matches!(result, Err(Error::InvalidThreshold(65))));
+}
+
+#[test]
+fn test_scan_records_threshold_in_output() {
+    let tmp = temp_dir();
+    let output = tmp.path().join("duplicates.json");
+    let scanner = Scanner::with_options(ScanOptions {
+        output: Some(output.clone()),
+        quiet: true,
+        ..ScanOptions::new(images_fixtures_dir())
+    })
+    .with_threshold(4);
+    scanner.execute().expect("Scan should succeed");
+
+    let file = DuplicatesFile::load(&output).unwrap();
+    assert_eq!(file.similarity_threshold, Some(4));
+    assert!(file.media_thresholds.is_empty());
+}
+
+#[test]
+fn test_scan_records_media_type_thresholds_in_output() {
+    let tmp = temp_dir();
+    let output = tmp.path().join("duplicates.json");
+    let media_thresholds = MediaThresholds {
+        image: Some(8),
+        audio: Some(14),
+        ..MediaThresholds::default()
+    };
+    let scanner = Scanner::with_options(ScanOptions {
+        output: Some(output.clone()),
+        quiet: true,
+        detection: DetectionOptions {
+            media_thresholds,
+            ..DetectionOptions::default()
+        },
+        ..ScanOptions::new(images_fixtures_dir())
+    });
+    scanner.execute().expect("Scan should succeed");
+
+    let file = DuplicatesFile::load(&output).unwrap();
+    assert_eq!(file.media_thresholds, media_thresholds);
+}
+
+#[test]
+fn test_scan_options_defaults() {
+    let options = ScanOptions::new(text_fixtures_dir());
+
+    assert!(options.recursive, "Scans should be recursive by default");
+    assert!(!options.include_hidden);
+    assert!(!options.quiet);
+    assert!(!options.allow_dangerous_path);
+    assert_eq!(options.output, None);
+    assert_eq!(options.max_depth, None);
+    assert_eq!(options.detection, DetectionOptions::default());
+}
+
+#[test]
+fn test_original_strategy_picks_file_to_keep() {
+    let tmp = temp_dir();
+    let nested = tmp.path().join("backup").join("old");
+    std::fs::create_dir_all(&nested).unwrap();
+
+    let short = tmp.path().join("z.txt");
+    let long = nested.join("a.txt");
+    std::fs::write(&long, "same content").unwrap();
+    std::fs::write(&short, "same content").unwrap();
+    let files = vec![short.clone(), long.clone()];
+
+    for (strategy, expected) in [
+        (OriginalStrategy::FirstAlphabetical, &long),
+        (OriginalStrategy::ShortestPath, &short),
+        (OriginalStrategy::LongestPath, &long),
+    ] {
+        let options = DetectionOptions {
+            original_strategy: strategy,
+            ..DetectionOptions::default()
+        };
+        let report = duplicate::find_duplicates_with_options(&files, None, &options).unwrap();
+        assert_eq!(&report.groups[0].files[0], expected, "{:?}", strategy);
+    }
+}
+
+#[test]
+fn test_highest_resolution_keeps_largest_image() {
+    let tmp = temp_dir();
+    let picture = image::open(images_fixtures_dir().join("image_a.png")).unwrap();
+    let (width, height) = (picture.width(), picture.height());
+    let full = tmp.path().join("z_full.png");
+    let small = tmp.path().join("a_small.png");
+    picture.save(&full).unwrap();
+    picture
+        .resize_exact(width / 2, height / 2, image::imageops::FilterType::Triangle)
+        .save(&small)
+        .unwrap();
+
+    let options = DetectionOptions {
+        original_strategy: OriginalStrategy::HighestResolution,
+        ..DetectionOptions::default()
+    };
+    let report =
+        duplicate::find_duplicates_with_options(&[small.clone(), full.clone()], None, &options)
+            .unwrap();
+    assert_eq!(report.groups.len(), 1);
+    assert_eq!(report.groups[0].files, [full, small]);
+
+    // Files without dimensions fall back to alphabetical order
+    let mut files = vec![tmp.path().join("b.txt"), tmp.path().join("a.txt")];
+    OriginalStrategy::HighestResolution.order(&mut files);
+    assert_eq!(files, [tmp.path().join("a.txt"), tmp.path().join("b.txt")]);
+}
+
+#[test]
+fn test_original_strategy_ties_fall_back_to_alphabetical() {
+    let tmp = temp_dir();
+    let a = tmp.path().join("a.txt");
+    let b = tmp.path().join("b.txt");
+    let mut files = vec![b.clone(), a.clone()];
+
+    OriginalStrategy::ShortestPath.order(&mut files);
+    assert_eq!(files, [a.clone(), b.clone()]);
+
+    // Missing files have no modification time and are never preferred
+    std::fs::write(&b, "exists").unwrap();
+    OriginalStrategy::OldestMtime.order(&mut files);
+    assert_eq!(files, [b, a]);
+}
+
+#[test]
+fn test_scan_filters_files_by_modification_time() {
+    let tmp = temp_dir();
+    let now = std::time::SystemTime::now();
+    let two_months_ago = now - std::time::Duration::from_secs(60 * 24 * 60 * 60);
+    let files = [
+        ("old_a.txt", two_months_ago),
+        ("old_b.txt", two_months_ago),
+        ("new.txt", now),
+    ];
+    for (name, modified) in files {
+        let path = tmp.path().join(name);
+        std::fs::write(&path, "same content").unwrap();
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(modified).unwrap();
+    }
+    let month_ago = chrono::Utc::now() - chrono::Duration::days(30);
+    let scan = |newer_than, older_than| {
+        Scanner::with_options(ScanOptions {
+            output: Some(tmp.path().join("duplicates.json")),
+            quiet: true,
+            use_cache: false,
+            newer_than,
+            older_than,
+            ..ScanOptions::new(tmp.path().to_path_buf())
+        })
+        .execute()
+    };
+
+    // Files left out are not counted as scanned
+    let recent = scan(Some(month_ago), None).expect("Scan should succeed");
+    assert_eq!(recent.total_files, 1);
+    assert!(recent.groups.is_empty());
+
+    let old = scan(None, Some(month_ago)).expect("Scan should succeed");
+    assert_eq!(old.total_files, 2);
+    assert_eq!(old.groups.len(), 1);
+
+    let result = scan(Some(month_ago), Some(month_ago));
+    assert!(matches!(result, Err(Error::InvalidTimeRange { .. })));
+}
+
+#[test]
+fn test_preferred_dirs_override_original_strategy() {
+    let tmp = temp_dir();
+    let other = tmp.path().join("a").join("x.txt");
+    let download = tmp.path().join("downloads").join("x.txt");
+    let nested = tmp.path().join("library").join("deep").join("x.txt");
+    let library = tmp.path().join("library").join("y.txt");
+    let files = vec![nested.clone(), download.clone(), library.clone(), other.clone()];
+    let order = |preferred: &[PathBuf]| {
+        let mut ordered = files.clone();
+        OriginalStrategy::ShortestPath.order_preferring(&mut ordered, preferred);
+        ordered
+    };
+
+    // The strategy still chooses among the files of the preferred directory
+    assert_eq!(
+        order(&[tmp.path().join("library")]),
+        [library.clone(), other.clone(), download.clone(), nested.clone()]
+    );
+    // The first directory holding a file wins
+    let preferred = [
+        tmp.path().join("missing"),
+        tmp.path().join("downloads"),
+        tmp.path().join("library"),
+    ];
+    assert_eq!(order(&preferred)[0], download);
+    // Without a file in a preferred directory, the strategy alone decides
+    assert_eq!(order(&[tmp.path().join("elsewhere")])[0], other);
+}
+
+#[test]
+fn test_scan_writes_hash_cache_unless_disabled() {
+    let tmp = temp_dir();
+    let output = tmp.path().join("out").join("duplicates.json");
+    std::fs::create_dir(output.parent().unwrap()).unwrap();
+    let cache = tmp.path().join("out").join(".mde_cache.json");
+
+    let scanner = Scanner::with_options(ScanOptions {
+        output: Some(output.clone()),
+        quiet: true,
+        use_cache: false,
+        ..ScanOptions::new(text_fixtures_dir())
+    });
+    scanner.execute().expect("Scan should succeed");
+    assert!(!cache.exists(), "--no-cache should not write a cache");
+
+    let scanner = Scanner::with_options(ScanOptions {
+        output: Some(output.clone()),
+        quiet: true,
+        ..ScanOptions::new(text_fixtures_dir())
+    });
+    scanner.execute().expect("Scan should succeed");
+    assert!(cache.exists(), "The cache should sit next to the report");
+
+    // A second scan with the cache gives the same result
+    let first = DuplicatesFile::load(&output).unwrap();
+    scanner.execute().expect("Cached scan should succeed");
+    let second = DuplicatesFile::load(&output).unwrap();
+    assert_eq!(first.total_duplicates, second.total_duplicates);
+}
+
+#[test]
+fn test_scan_honors_mdeignore() {
+    let tmp = temp_dir();
+    let modules = tmp.path().join("node_modules");
+    std::fs::create_dir(&modules).unwrap();
+    std::fs::write(tmp.path().join("a.txt"), "same content").unwrap();
+    std::fs::write(tmp.path().join("b.txt"), "same content").unwrap();
+    std::fs::write(modules.join("c.txt"), "same content").unwrap();
+    std::fs::write(tmp.path().join("d.tmp"), "same content").unwrap();
+    std::fs::write(tmp.path().join(".mdeignore"), "node_modules/\n*.tmp\n").unwrap();
+
+    let output = tmp.path().join("duplicates.json");
+    let scan = |use_ignore_file| {
+        let scanner = Scanner::with_options(ScanOptions {
+            output: Some(output.clone()),
+            quiet: true,
+            use_ignore_file,
+            ..ScanOptions::new(tmp.path().to_path_buf())
+        });
+        scanner.execute().expect("Scan should succeed");
+        DuplicatesFile::load(&output).unwrap()
+    };
+
+    let manifest = scan(true);
+    assert_eq!(manifest.total_files_scanned, 2, "Ignored paths should not be scanned");
+    assert_eq!(manifest.total_duplicates, 1);
+
+    let manifest = scan(false);
+    assert_eq!(manifest.total_duplicates, 3, "--no-ignore should scan everything");
+}
+
+#[test]
+fn test_extension_filter_is_case_insensitive() {
+    let filter = ExtensionFilter::new(["CR2", ".nef"], Vec::<String>::new());
+    assert!(filter.matches(Path::new("raw/IMG_0001.cr2")));
+    assert!(filter.matches(Path::new("raw/DSC_0001.NEF")));
+    assert!(!filter.matches(Path::new("raw/IMG_0001.jpg")));
+    assert!(!filter.matches(Path::new("raw/no_extension")));
+
+    let filter = ExtensionFilter::new(Vec::<String>::new(), ["gif"]);
+    assert!(filter.matches(Path::new("photo.png")));
+    assert!(!filter.matches(Path::new("animation.GIF")));
+}
+
+#[test]
+fn test_extension_filter_intersects_with_media_filter() {
+    let tmp = temp_dir();
+    for name in ["a.png", "b.png", "c.gif", "d.gif", "e.txt", "f.txt"] {
+        std::fs::copy(fixture_path("images/image_a.png"), tmp.path().join(name)).unwrap();
+    }
+    let output = tmp.path().join("out.json");
+
+    let scanner = Scanner::with_options(ScanOptions {
+        output: Some(output.clone()),
+        quiet: true,
+        detection: MediaFilter::ImagesOnly.into(),
+        extensions: ExtensionFilter::new(Vec::<String>::new(), ["GIF"]),
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    });
+    scanner.execute().expect("Scan should succeed");
+
+    let manifest = DuplicatesFile::load(&output).unwrap();
+    assert_eq!(manifest.total_files_scanned, 2, "Only the .png files should remain");
+    assert_eq!(manifest.total_duplicates, 1);
+}
+
+#[test]
+fn test_size_range_skips_files_before_hashing() {
+    let tmp = temp_dir();
+    std::fs::write(tmp.path().join("small_a.txt"), "tiny").unwrap();
+    std::fs::write(tmp.path().join("small_b.txt"), "tiny").unwrap();
+    std::fs::write(tmp.path().join("large_a.txt"), "x".repeat(4096)).unwrap();
+    std::fs::write(tmp.path().join("large_b.txt"), "x".repeat(4096)).unwrap();
+    let files: Vec<_> = std::fs::read_dir(tmp.path())
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .collect();
+
+    let options = DetectionOptions {
+        min_size: Some(1024),
+        ..DetectionOptions::default()
+    };
+    let report = duplicate::find_duplicates_with_options(&files, None, &options).unwrap();
+    assert_eq!(report.total_files, 2, "Files below --min-size should not be counted");
+    assert_eq!(report.groups.len(), 1);
+    assert!(report.groups[0].files[0].ends_with("large_a.txt"));
+
+    let options = DetectionOptions {
+        max_size: Some(1024),
+        ..DetectionOptions::default()
+    };
+    let report = duplicate::find_duplicates_with_options(&files, None, &options).unwrap();
+    assert_eq!(report.total_files, 2, "Files above --max-size should not be counted");
+    assert!(report.groups[0].files[0].ends_with("small_a.txt"));
+}
+
+#[test]
+fn test_empty_files_are_skipped_unless_included() {
+    let tmp = temp_dir();
+    for name in ["empty_a.txt", "empty_b.txt", "empty_c.txt"] {
+        std::fs::write(tmp.path().join(name), "").unwrap();
+    }
+    std::fs::write(tmp.path().join("copy_a.txt"), "content").unwrap();
+    std::fs::write(tmp.path().join("copy_b.txt"), "content").unwrap();
+    let files: Vec<PathBuf> = std::fs::read_dir(tmp.path())
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .collect();
+
+    let report = duplicate::find_duplicates(&files).unwrap();
+    assert_eq!(report.total_files, 2, "Empty files should not be counted");
+    assert_eq!(report.groups.len(), 1);
+    assert!(report.groups[0].files[0].ends_with("copy_a.txt"));
+
+    let options = DetectionOptions {
+        ignore_empty: false,
+        ..DetectionOptions::default()
+    };
+    let report = duplicate::find_duplicates_with_options(&files, None, &options).unwrap();
+    assert_eq!(report.total_files, 5);
+    assert_eq!(report.groups.len(), 2);
+    assert!(report.groups.iter().any(|g| g.files.len() == 3));
+}
+
+#[test]
+fn test_inverted_size_range_is_rejected() {
+    let options = DetectionOptions {
+        min_size: Some(2048),
+        max_size: Some(1024),
+        ..DetectionOptions::default()
+    };
+    let result = duplicate::find_duplicates_with_options(&get_image_files(), None, &options);
+
+    assert!(matches!(
+        result,
+        Err(Error::InvalidSizeRange {
+            min: 2048,
+            max: 1024
+        })
+    ));
+}
+
+#[test]
+fn test_progress_events_cover_every_phase() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&events);
+    let progress: ProgressCallback = Box::new(move |event| recorded.lock().unwrap().push(event));
+
+    let files = get_image_files();
+    let bytes: u64 = files.iter().map(|f| hasher::file_size(f).unwrap()).sum();
+    duplicate::find_duplicates_with_progress(&files, Some(progress)).unwrap();
+
+    let events = events.lock().unwrap();
+    assert_eq!(events.first(), Some(&ProgressEvent::CollectingFiles));
+    assert_eq!(events.last(), Some(&ProgressEvent::Merging));
+    assert!(events.contains(&ProgressEvent::Hashing {
+        done: files.len(),
+        total: files.len(),
+        bytes,
+    }));
+    assert!(
+        events
+            .iter()
+            .any(|e| matches!(e, ProgressEvent::Analyzing { done, total } if done == total))
+    );
+}
+
+#[test]
+fn test_progress_log_is_throttled_to_checkpoints() {
+    let log = ProgressLog::default();
+    let lines: Vec<String> = (1..=1000)
+        .filter_map(|done| {
+            log.checkpoint(ProgressEvent::Hashing {
+                done,
+                total: 1000,
+                bytes: done as u64,
+            })
+        })
+        .collect();
+
+    // The start of the phase and every tenth of it
+    assert_eq!(lines.len(), 11);
+    assert!(lines[0].starts_with("Hashing files: 0% (1/1000 files"));
+    assert!(lines[10].starts_with("Hashing files: 100% (1000/1000 files"));
+
+    // A new phase is always logged, a repeated step never is
+    let first = ProgressEvent::Analyzing {
+        done: 1,
+        total: 1000,
+    };
+    let second = ProgressEvent::Analyzing {
+        done: 2,
+        total: 1000,
+    };
+    assert_eq!(
+        log.checkpoint(first).as_deref(),
+        Some("Analyzing media: 0% (1/1000 files)")
+    );
+    assert_eq!(log.checkpoint(second), None);
+
+    // Slow phases are still logged once the interval has passed
+    let log = ProgressLog::new(Duration::ZERO);
+    assert!(log.checkpoint(first).is_some());
+    assert!(log.checkpoint(second).is_some());
+    assert_eq!(log.checkpoint(second), None, "Nothing happened since the last line");
+}
+
+#[test]
+fn test_detailed_detection_lists_failing_files() {
+    let tmp = temp_dir();
+    let a = tmp.path().join("a.txt");
+    let b = tmp.path().join("b.txt");
+    let missing = tmp.path().join("missing.txt");
+    std::fs::write(&a, "same content").unwrap();
+    std::fs::write(&b, "same content").unwrap();
+
+    let files = vec![a, b, missing.clone()];
+    let (report, failures) = duplicate::find_duplicates_detailed(
+        &files,
+        None,
+        &DetectionOptions::default(),
+        &mut HashCache::disabled(),
+    )
+    .unwrap();
+
+    assert_eq!(report.groups.len(), 1, "Other files should still be compared");
+    assert_eq!(report.errors, 1);
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].0, missing);
+    assert!(matches!(&failures[0].1, Error::FileOperation { path, .. } if *path == missing));
+}
+
+#[test]
+fn test_merge_keeps_pure_exact_groups_exact() {
+    let exact = |files: &[&str]| DuplicateGroup {
+        files: files.iter().map(PathBuf::from).collect(),
+        duplicate_type: DuplicateType::Exact,
+    };
+    let similar = |files: &[&str]| DuplicateGroup {
+        files: files.iter().map(PathBuf::from).collect(),
+        duplicate_type: DuplicateType::Perceptual,
+    };
+
+    let merged = duplicate::merge_groups(
+        vec![exact(&["a.png", "a copy.png"]), exact(&["b.png", "b copy.png"])],
+        vec![
+            similar(&["a.png", "a copy.png"]),
+            similar(&["b.png", "c.png"]),
+            similar(&["d.png", "e.png"]),
+        ],
+    );
+    let type_of = |name: &str| {
+        merged
+            .iter()
+            .find(|g| g.files.contains(&PathBuf::from(name)))
+            .map(|g| g.duplicate_type)
+    };
+
+    assert_eq!(type_of("a.png"), Some(DuplicateType::Exact));
+    assert_eq!(type_of("c.png"), Some(DuplicateType::Mixed));
+    assert_eq!(type_of("d.png"), Some(DuplicateType::Perceptual));
+
+    let report = DuplicateReport {
+        groups: merged,
+        total_files: 7,
+        errors: 0,
+        threshold: 10,
+        media_thresholds: Default::default(),
+        hash_algorithm: HashAlgorithm::default(),
+        perceptual_config: Default::default(),
+        grouping: Default::default(),
+        video_scenes: false,
+        video_audio_match: false,
+        file_sizes: Default::default(),
+        perceptual_skipped: false,
+        quick: None,
+        hashes: Default::default(),
+        distances: Default::default(),
+        checksums: Default::default(),
+        live_photo_pairs: Default::default(),
+        root: None,
+        inaccessible_paths: 0,
+        canceled: false,
+    };
+    assert_eq!(report.exact_duplicate_count(), 1);
+    assert_eq!(report.mixed_duplicate_count(), 2);
+    assert_eq!(report.perceptual_duplicate_count(), 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_hard_links_are_not_offered_for_deletion() {
+    let tmp = temp_dir();
+    let original = tmp.path().join("a.txt");
+    std::fs::write(&original, "same content").unwrap();
+    std::fs::hard_link(&original, tmp.path().join("b.txt")).unwrap();
+
+    let output = tmp.path().join("duplicates.json");
+    let scan = |follow_symlinks| {
+        let scanner = Scanner::with_options(ScanOptions {
+            output: Some(output.clone()),
+            quiet: true,
+            use_cache: false,
+            follow_symlinks,
+            ..ScanOptions::new(tmp.path().to_path_buf())
+        });
+        scanner.execute().expect("Scan should succeed");
+    };
+
+    scan(false);
+    assert!(!output.exists(), "Hard links should not be reported as duplicates");
+
+    // A real copy is still a duplicate, but only one path of the linked file is kept
+    std::fs::write(tmp.path().join("c.txt"), "same content").unwrap();
+    std::os::unix::fs::symlink(&original, tmp.path().join("d.txt")).unwrap();
+    scan(true);
+    let manifest = DuplicatesFile::load(&output).unwrap();
+    assert_eq!(manifest.total_files_scanned, 2);
+    assert_eq!(manifest.total_duplicates, 1);
+}
+
+#[test]
+fn test_scan_reads_paths_from_file_list() {
+    let tmp = temp_dir();
+    let a = tmp.path().join("a.txt");
+    let b = tmp.path().join("b.txt");
+    std::fs::write(&a, "same content").unwrap();
+    std::fs::write(&b, "same content").unwrap();
+    // Not listed, so never scanned
+    std::fs::write(tmp.path().join("c.txt"), "same content").unwrap();
+
+    let list = tmp.path().join("files.txt");
+    let missing = tmp.path().join("missing.txt");
+    std::fs::write(
+        &list,
+        format!("{}\n\n{}\r\n{}\n", a.display(), b.display(), missing.display()),
+    )
+    .unwrap();
+
+    let output = tmp.path().join("duplicates.json");
+    let scanner = Scanner::with_options(ScanOptions {
+        output: Some(output.clone()),
+        quiet: true,
+        use_cache: false,
+        file_list: Some(list),
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    });
+    scanner
+        .execute()
+        .expect("Missing listed paths should not abort the scan");
+
+    let manifest = DuplicatesFile::load(&output).unwrap();
+    assert_eq!(manifest.total_duplicates, 1, "Only the listed files should be compared");
+}
+
+#[test]
+fn test_scan_records_reclaimable_bytes() {
+    let tmp = temp_dir();
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        std::fs::write(tmp.path().join(name), "0123456789").unwrap();
+    }
+    std::fs::write(tmp.path().join("unique.txt"), "unique content").unwrap();
+
+    let files: Vec<PathBuf> = ["a.txt", "b.txt", "c.txt", "unique.txt"]
+        .iter()
+        .map(|name| tmp.path().join(name))
+        .collect();
+    let report = duplicate::find_duplicates(&files).unwrap();
+    // Two of the three copies are duplicates of the one kept
+    assert_eq!(report.reclaimable_bytes(), 20);
+
+    let output = tmp.path().join("duplicates.json");
+    Scanner::with_options(ScanOptions {
+        output: Some(output.clone()),
+        quiet: true,
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    })
+    .execute()
+    .expect("Scan should succeed");
+
+    let file = DuplicatesFile::load(&output).unwrap();
+    assert_eq!(file.reclaimable_bytes, 20);
+}
+
+#[test]
+fn test_reclaimable_bytes_uses_sizes_recorded_during_scan() {
+    let tmp = temp_dir();
+    let files: Vec<PathBuf> = ["a.txt", "b.txt"]
+        .iter()
+        .map(|name| tmp.path().join(name))
+        .collect();
+    for file in &files {
+        std::fs::write(file, "0123456789").unwrap();
+    }
+    let mut report = duplicate::find_duplicates(&files).unwrap();
+
+    // Sizes come from the scan, so files removed since are still counted
+    std::fs::remove_file(&files[1]).unwrap();
+    assert_eq!(report.reclaimable_bytes(), 10);
+
+    // Without a recorded size a vanished file counts as empty
+    report.file_sizes.clear();
+    assert_eq!(report.reclaimable_bytes(), 0);
+}
+
+#[test]
+fn test_scan_summary_totals_report() {
+    let files: Vec<PathBuf> = std::fs::read_dir(text_fixtures_dir())
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+    let report = duplicate::find_duplicates(&files).unwrap();
+
+    let summary = ScanSummary::from_report(&report);
+    assert_eq!(summary.files_scanned, report.total_files);
+    assert_eq!(summary.duplicate_groups, report.groups.len());
+    assert_eq!(summary.duplicates, report.duplicate_count());
+    assert_eq!(
+        summary.exact_duplicates + summary.perceptual_duplicates + summary.mixed_duplicates,
+        summary.duplicates
+    );
+    assert_eq!(summary.reclaimable_bytes, report.reclaimable_bytes());
+    assert_eq!(summary.report_path, None);
+
+    let json: serde_json::Value = serde_json::to_value(&summary).unwrap();
+    assert_eq!(json["files_scanned"], report.total_files);
+    assert_eq!(json["errors"], 0);
+    assert_eq!(json["ffmpeg_errors"], 0);
+}
+
+#[test]
+fn test_json_summary_still_saves_report() {
+    let tmp = temp_dir();
+    std::fs::write(tmp.path().join("a.txt"), "same content").unwrap();
+    std::fs::write(tmp.path().join("b.txt"), "same content").unwrap();
+
+    Scanner::with_options(ScanOptions {
+        json_summary: true,
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    })
+    .execute()
+    .expect("Scan should succeed");
+
+    assert!(tmp.path().join("duplicates.json").exists());
+}
+
+#[test]
+fn test_large_files_sharing_a_prefix_are_confirmed_by_full_hash() {
+    let tmp = temp_dir();
+    let len = hasher::PARTIAL_HASH_LEN as usize * 2;
+    let content = vec![7u8; len];
+    let mut differs_late = content.clone();
+    differs_late[len - 1] = 8;
+    let mut differs_early = content.clone();
+    differs_early[0] = 8;
+
+    let files: Vec<PathBuf> = [
+        ("a.bin", &content),
+        ("b.bin", &content),
+        ("c.bin", &differs_late),
+        ("d.bin", &differs_early),
+    ]
+    .iter()
+    .map(|(name, data)| {
+        let path = tmp.path().join(name);
+        std::fs::write(&path, data).unwrap();
+        path
+    })
+    .collect();
+
+    let report = duplicate::find_duplicates(&files).unwrap();
+
+    assert_eq!(report.groups.len(), 1);
+    assert_eq!(report.groups[0].files, vec![files[0].clone(), files[1].clone()]);
+    assert_eq!(report.groups[0].duplicate_type, DuplicateType::Exact);
+}
+
+#[test]
+fn test_prescreen_does_not_change_groups() {
+    let tmp = temp_dir();
+    // Small files of one size, like thumbnails, most of them distinct
+    let files: Vec<PathBuf> = (0..6)
+        .map(|i| {
+            let path = tmp.path().join(format!("thumb_{}.bin", i));
+            std::fs::write(&path, format!("thumbnail {}", i % 4)).unwrap();
+            path
+        })
+        .collect();
+
+    let groups_with = |prescreen| {
+        let options = DetectionOptions {
+            media_filter: MediaFilter::AnyFile,
+            prescreen,
+            ..DetectionOptions::default()
+        };
+        let report = duplicate::find_duplicates_with_options(&files, None, &options).unwrap();
+        let mut groups: Vec<Vec<PathBuf>> = report.groups.into_iter().map(|g| g.files).collect();
+        groups.sort();
+        groups
+    };
+
+    let expected = vec![
+        vec![files[0].clone(), files[4].clone()],
+        vec![files[1].clone(), files[5].clone()],
+    ];
+    assert_eq!(groups_with(Prescreen::Partial), expected);
+    assert_eq!(groups_with(Prescreen::Crc32), expected);
+    assert_eq!(groups_with(Prescreen::Off), expected);
+}
+
+#[test]
+fn test_any_file_filter_finds_only_exact_duplicates() {
+    let tmp = temp_dir();
+    std::fs::write(tmp.path().join("report.pdf"), "%PDF-1.4 same").unwrap();
+    std::fs::write(tmp.path().join("report copy.pdf"), "%PDF-1.4 same").unwrap();
+    std::fs::copy(fixture_path("images/image_a.png"), tmp.path().join("a.png")).unwrap();
+    std::fs::copy(fixture_path("images/image_b.png"), tmp.path().join("b.png")).unwrap();
+
+    let files: Vec<PathBuf> = std::fs::read_dir(tmp.path())
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .collect();
+    let options = DetectionOptions::from(MediaFilter::AnyFile);
+    let report = duplicate::find_duplicates_with_options(&files, None, &options).unwrap();
+
+    assert!(report.perceptual_skipped);
+    assert_eq!(report.total_files, 4);
+    assert_eq!(report.groups.len(), 1);
+    assert_eq!(report.groups[0].duplicate_type, DuplicateType::Exact);
+    assert!(report.groups[0].files[0].ends_with("report copy.pdf"));
+}
+
+#[test]
+fn test_exclude_dir_prunes_matching_directories() {
+    let tmp = temp_dir();
+    let nested_thumbs = tmp.path().join("2024").join("@eaDir");
+    let old = tmp.path().join("archive").join("old");
+    let old_elsewhere = tmp.path().join("2024").join("old");
+    for dir in [&nested_thumbs, &old, &old_elsewhere] {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join("copy.txt"), "same content").unwrap();
+    }
+    std::fs::write(tmp.path().join("a.txt"), "same content").unwrap();
+    // A file is never matched by a directory pattern
+    std::fs::write(tmp.path().join("@eaDir"), "same content").unwrap();
+
+    let output = tmp.path().join("duplicates.json");
+    let scanner = Scanner::with_options(ScanOptions {
+        output: Some(output.clone()),
+        quiet: true,
+        exclude_dirs: vec!["@eaDir".to_string(), "archive/old".to_string()],
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    });
+    scanner.execute().expect("Scan should succeed");
+
+    let manifest = DuplicatesFile::load(&output).unwrap();
+    let mut scanned: Vec<PathBuf> = manifest
+        .entries
+        .iter()
+        .flat_map(|e| std::iter::once(e.original.clone()).chain(e.duplicates.clone()))
+        .collect();
+    scanned.sort();
+    assert_eq!(
+        scanned,
+        vec![
+            old_elsewhere.join("copy.txt"),
+            tmp.path().join("@eaDir"),
+            tmp.path().join("a.txt"),
+        ]
+    );
+}
+
+#[test]
+fn test_scan_execute_returns_report() {
+    let tmp = temp_dir();
+    std::fs::write(tmp.path().join("a.txt"), "same content").unwrap();
+    std::fs::write(tmp.path().join("b.txt"), "same content").unwrap();
+    std::fs::write(tmp.path().join("c.txt"), "other content").unwrap();
+
+    let report = Scanner::with_options(ScanOptions {
+        output: Some(tmp.path().join("duplicates.json")),
+        quiet: true,
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    })
+    .execute()
+    .expect("Scan should succeed");
+
+    assert_eq!(report.total_files, 3);
+    assert_eq!(report.groups.len(), 1);
+    assert_eq!(report.groups[0].files.len(), 2);
+    assert_eq!(report.groups[0].duplicate_type, DuplicateType::Exact);
+    assert_eq!(report.root.as_deref(), Some(tmp.path()));
+}
+
+#[test]
+fn test_scan_with_one_worker_matches_default_workers() {
+    let tmp = temp_dir();
+    let scan = |workers| {
+        Scanner::with_options(ScanOptions {
+            output: Some(tmp.path().join("duplicates.json")),
+            quiet: true,
+            use_cache: false,
+            workers,
+            ..ScanOptions::new(images_fixtures_dir())
+        })
+        .execute()
+        .expect("Scan should succeed")
+    };
+    let files = |report: &DuplicateReport| -> Vec<Vec<PathBuf>> {
+        report.groups.iter().map(|g| g.files.clone()).collect()
+    };
+
+    let serial = scan(Some(1));
+    let parallel = scan(None);
+    assert!(!serial.groups.is_empty());
+    assert_eq!(files(&serial), files(&parallel));
+    assert_eq!(serial.distances, parallel.distances);
+}
+
+#[test]
+fn test_scan_skips_existing_duplicates_files() {
+    let tmp = temp_dir();
+    std::fs::write(tmp.path().join("a.txt"), "first file").unwrap();
+    std::fs::write(tmp.path().join("b.txt"), "second file").unwrap();
+    // Identical reports left by earlier scans would otherwise form a group
+    let stale = r#"{"version": "1.0", "entries": []}"#;
+    std::fs::write(tmp.path().join("duplicates.json"), stale).unwrap();
+    std::fs::create_dir(tmp.path().join("old")).unwrap();
+    std::fs::write(tmp.path().join("old").join("duplicates.json"), stale).unwrap();
+    std::fs::write(tmp.path().join("old").join("report.json"), stale).unwrap();
+
+    let report = Scanner::with_options(ScanOptions {
+        output: Some(tmp.path().join("old").join("report.json")),
+        quiet: true,
+        use_cache: false,
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    })
+    .execute()
+    .expect("Scan should succeed");
+
+    assert_eq!(report.total_files, 2, "Only a.txt and b.txt should be scanned");
+    assert!(report.groups.is_empty());
+}
+
+#[test]
+fn test_scan_skips_its_hash_cache_and_listed_reports() {
+    let tmp = temp_dir();
+    let a = tmp.path().join("a.txt");
+    let b = tmp.path().join("b.txt");
+    std::fs::write(&a, "first file").unwrap();
+    std::fs::write(&b, "second file").unwrap();
+    let cache = tmp.path().join("hashes.json");
+    let scan = |file_list: Option<PathBuf>| {
+        Scanner::with_options(ScanOptions {
+            quiet: true,
+            cache_path: Some(cache.clone()),
+            file_list,
+            ..ScanOptions::new(tmp.path().to_path_buf())
+        })
+        .execute()
+        .expect("Scan should succeed")
+    };
+
+    // The second scan finds the cache and report the first one wrote
+    scan(None);
+    assert!(cache.exists());
+    assert_eq!(scan(None).total_files, 2, "The hash cache should not be scanned");
+
+    let list = tmp.path().join("files.txt");
+    let report = tmp.path().join("duplicates.json");
+    let listed = [&a, &b, &cache, &report].map(|path| path.display().to_string());
+    std::fs::write(&list, listed.join("\n")).unwrap();
+    assert_eq!(scan(Some(list)).total_files, 2, "Listed reports and caches should not be scanned");
+}
+
+#[test]
+fn test_scan_execute_returns_empty_report_without_files() {
+    let tmp = temp_dir();
+
+    let report = Scanner::with_options(ScanOptions {
+        quiet: true,
+        detection: DetectionOptions {
+            threshold: 7,
+            ..DetectionOptions::default()
+        },
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    })
+    .execute()
+    .expect("Scan should succeed");
+
+    assert!(report.groups.is_empty());
+    assert_eq!(report.total_files, 0);
+    assert_eq!(report.threshold, 7);
+}
+
+#[test]
+fn test_scan_records_distance_to_original() {
+    let report = duplicate::find_duplicates(&get_image_files()).unwrap();
+    assert!(!report.groups.is_empty(), "Should find duplicate images");
+
+    for group in &report.groups {
+        assert!(
+            !report.distances.contains_key(&group.files[0]),
+            "The original has no distance"
+        );
+        for file in &group.files[1..] {
+            let distance = report.distances.get(file).copied();
+            assert!(distance.is_some(), "Every image duplicate should have a distance");
+            if group.duplicate_type == DuplicateType::Exact {
+                assert_eq!(distance, Some(0));
+            }
+        }
+    }
+
+    let copy = images_fixtures_dir().join("image_a copy.png");
+    let original = images_fixtures_dir().join("image_a.png");
+    if let Some(group) = report.groups.iter().find(|g| g.files.contains(&copy))
+        && group.files[0] != original
+        && group.files[0] != copy
+    {
+        // Byte-identical files are equally far from any original
+        assert_eq!(report.distances.get(&copy), report.distances.get(&original));
+    }
+
+    let file = DuplicatesFile::from_report(&report);
+    for (entry, group) in file.entries.iter().zip(&report.groups) {
+        assert_eq!(entry.distances.len(), group.files.len() - 1);
+    }
+}
+
+#[test]
+fn test_exact_only_skips_perceptual_matches() {
+    let files = vec![
+        fixture_path("exif/rotated.png"),
+        fixture_path("exif/tagged.png"),
+        fixture_path("images/image_a.png"),
+        fixture_path("images/image_a copy.png"),
+    ];
+    let options = DetectionOptions {
+        exact_only: true,
+        ..DetectionOptions::default()
+    };
+
+    let report = duplicate::find_duplicates_with_options(&files, None, &options).unwrap();
+
+    assert!(report.perceptual_skipped);
+    assert_eq!(report.groups.len(), 1, "Only the exact copies should be grouped");
+    assert_eq!(report.groups[0].duplicate_type, DuplicateType::Exact);
+}
+
+#[test]
+fn test_scan_groups_exif_rotated_copies() {
+    // rotated.png is tagged.png turned upright on disk, without an EXIF tag
+    let files = vec![fixture_path("exif/rotated.png"), fixture_path("exif/tagged.png")];
+
+    let report = duplicate::find_duplicates(&files).unwrap();
+
+    assert_eq!(report.groups.len(), 1, "The copies should be grouped together");
+    assert_eq!(report.groups[0].duplicate_type, DuplicateType::Perceptual);
+    assert_eq!(report.groups[0].files.len(), 2);
+}
+
+/// Returns the groups as sorted lists of sorted files, for order-independent comparison.
+fn sorted_groups<'a>(groups: impl IntoIterator<Item = &'a DuplicateGroup>) -> Vec<Vec<PathBuf>> {
+    let mut groups: Vec<Vec<PathBuf>> = groups
+        .into_iter()
+        .map(|g| {
+            let mut files = g.files.clone();
+            files.sort();
+            files
+        })
+        .collect();
+    groups.sort();
+    groups
+}
+
+#[test]
+fn test_find_duplicates_iter_yields_every_group() {
+    let mut files = get_image_files();
+    files.extend(
+        std::fs::read_dir(text_fixtures_dir())
+            .unwrap()
+            .map(|e| e.unwrap().path()),
+    );
+    let batch = duplicate::find_duplicates(&files).unwrap();
+
+    let mut stream = duplicate::find_duplicates_iter(files, None, DetectionOptions::default());
+    let streamed: Vec<DuplicateGroup> = stream.by_ref().collect();
+    let report = stream.finish().expect("Detection should succeed");
+
+    assert_eq!(sorted_groups(&streamed), sorted_groups(&batch.groups));
+    assert_eq!(sorted_groups(&report.groups), sorted_groups(&batch.groups));
+    assert_eq!(report.total_files, batch.total_files);
+}
+
+#[test]
+fn test_find_duplicates_iter_reports_invalid_options_on_finish() {
+    let options = DetectionOptions {
+        threshold: hasher::MAX_SIMILARITY_THRESHOLD + 1,
+        ..DetectionOptions::default()
+    };
+
+    let mut stream = duplicate::find_duplicates_iter(get_image_files(), None, options);
+
+    assert!(stream.next().is_none(), "Nothing should be yielded");
+    assert!(matches!(stream.finish(), Err(Error::InvalidThreshold(_))));
+}
+
+#[test]
+fn test_scan_limit_stops_after_n_files() {
+    let tmp = temp_dir();
+    for name in ["a.txt", "b.txt", "c.txt", "d.txt", "e.txt"] {
+        std::fs::write(tmp.path().join(name), "same content").unwrap();
+    }
+
+    let scan = |limit| {
+        Scanner::with_options(ScanOptions {
+            output: Some(tmp.path().join("duplicates.json")),
+            quiet: true,
+            use_cache: false,
+            limit: Some(limit),
+            ..ScanOptions::new(tmp.path().to_path_buf())
+        })
+        .execute()
+        .expect("Scan should succeed")
+    };
+
+    let report = scan(2);
+    assert_eq!(report.total_files, 2);
+    assert_eq!(report.duplicate_count(), 1);
+
+    // duplicates.json from the previous scan is the sixth file
+    let report = scan(10);
+    assert_eq!(report.total_files, 6);
+}
+
+#[test]
+fn test_scan_sorts_groups() {
+    let tmp = temp_dir();
+    // a: 100 bytes to reclaim, b: 20 bytes over 3 files, c: 500 bytes
+    for (name, copies, len) in [("a", 2, 100), ("b", 3, 10), ("c", 2, 500)] {
+        for copy in 0..copies {
+            let path = tmp.path().join(format!("{}{}.txt", name, copy));
+            std::fs::write(path, name.repeat(len)).unwrap();
+        }
+    }
+
+    let scan = |sort| {
+        Scanner::with_options(ScanOptions {
+            quiet: true,
+            use_cache: false,
+            sort,
+            ..ScanOptions::new(tmp.path().to_path_buf())
+        })
+        .execute()
+        .expect("Scan should succeed");
+        DuplicatesFile::load(&tmp.path().join("duplicates.json"))
+            .unwrap()
+            .entries
+            .iter()
+            .map(|e| e.original.file_name().unwrap().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(scan(Some(GroupOrder::Size)), ["c0.txt", "a0.txt", "b0.txt"]);
+    assert_eq!(scan(Some(GroupOrder::Count)), ["b0.txt", "a0.txt", "c0.txt"]);
+    assert_eq!(scan(Some(GroupOrder::Path)), ["a0.txt", "b0.txt", "c0.txt"]);
+}
+
+#[test]
+fn test_scan_finds_duplicates_across_roots() {
+    let photos = temp_dir();
+    let downloads = temp_dir();
+    let out = temp_dir();
+    std::fs::write(photos.path().join("a.txt"), "same content").unwrap();
+    std::fs::write(downloads.path().join("a copy.txt"), "same content").unwrap();
+    std::fs::write(downloads.path().join("other.txt"), "other content").unwrap();
+
+    let report = Scanner::with_options(ScanOptions {
+        output: Some(out.path().join("duplicates.json")),
+        quiet: true,
+        use_cache: false,
+        extra_paths: vec![downloads.path().to_path_buf()],
+        ..ScanOptions::new(photos.path().to_path_buf())
+    })
+    .execute()
+    .expect("Scan should succeed");
+
+    assert_eq!(report.total_files, 3);
+    let mut expected = vec![downloads.path().join("a copy.txt"), photos.path().join("a.txt")];
+    expected.sort();
+    assert_eq!(sorted_groups(&report.groups), vec![expected]);
+    assert_eq!(report.root, None, "Several roots have no single root");
+}
+
+#[test]
+fn test_scan_overlapping_roots_scan_files_once() {
+    let tmp = temp_dir();
+    let nested = tmp.path().join("nested");
+    std::fs::create_dir(&nested).unwrap();
+    std::fs::write(tmp.path().join("a.txt"), "same content").unwrap();
+    std::fs::write(nested.join("b.txt"), "same content").unwrap();
+    let out = temp_dir();
+
+    let report = Scanner::with_options(ScanOptions {
+        output: Some(out.path().join("duplicates.json")),
+        quiet: true,
+        use_cache: false,
+        extra_paths: vec![nested.clone(), tmp.path().to_path_buf()],
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    })
+    .execute()
+    .expect("Scan should succeed");
+
+    assert_eq!(report.total_files, 2);
+    assert_eq!(report.duplicate_count(), 1);
+}
+
+#[test]
+fn test_scan_several_roots_requires_output() {
+    let a = temp_dir();
+    let b = temp_dir();
+
+    let result = Scanner::with_options(ScanOptions {
+        quiet: true,
+        extra_paths: vec![b.path().to_path_buf()],
+        ..ScanOptions::new(a.path().to_path_buf())
+    })
+    .execute();
+
+    assert!(matches!(result, Err(Error::OutputRequired)));
+}
+
+#[test]
+fn test_scan_update_keeps_chosen_originals() {
+    let tmp = temp_dir();
+    let write = |name: &str, content: &str| {
+        let path = tmp.path().join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    };
+    let first = write("a1.txt", "aaaa");
+    let second = write("a2.txt", "aaaa");
+    let left = write("b1.txt", "bbbb");
+    let gone = write("b2.txt", "bbbb");
+    let manifest = tmp.path().join("duplicates.json");
+    let scan = |update| {
+        Scanner::with_options(ScanOptions {
+            quiet: true,
+            update,
+            ..ScanOptions::new(tmp.path().to_path_buf())
+        })
+        .execute()
+        .expect("Scan should succeed");
+    };
+    scan(false);
+
+    // Keep the file the scan did not pick, as erase --interactive would
+    let mut file = DuplicatesFile::load(&manifest).unwrap();
+    let entry = file
+        .entries
+        .iter_mut()
+        .find(|e| e.original == first || e.original == second)
+        .unwrap();
+    let chosen = entry.duplicates[0].clone();
+    let other = entry.original.clone();
+    entry.set_original(&chosen);
+    file.save(&manifest).unwrap();
+
+    let third = write("a3.txt", "aaaa");
+    write("c1.txt", "cccc");
+    write("c2.txt", "cccc");
+    std::fs::remove_file(&gone).unwrap();
+    scan(true);
+
+    let updated = DuplicatesFile::load(&manifest).unwrap();
+    assert_eq!(updated.duplicate_groups, 2);
+    assert_eq!(updated.total_files_scanned, 6);
+    let kept = updated
+        .entries
+        .iter()
+        .find(|e| e.original == chosen)
+        .expect("The chosen original should be kept");
+    let mut duplicates = kept.duplicates.clone();
+    duplicates.sort();
+    let mut expected = vec![other, third];
+    expected.sort();
+    assert_eq!(duplicates, expected);
+    assert!(updated.scanned_at > file.scanned_at);
+    assert!(
+        updated.entries.iter().all(|e| e.original != left && !e.duplicates.contains(&left)),
+        "The group whose duplicate was removed should be dropped"
+    );
+}
+
+#[test]
+fn test_canceled_detection_stops_between_files() {
+    let tmp = temp_dir();
+    let mut files = Vec::new();
+    for (index, contents) in ["a", "bb", "ccc"].iter().enumerate() {
+        for copy in ["one", "two"] {
+            let path = tmp.path().join(format!("{}_{}.txt", index, copy));
+            std::fs::write(&path, contents).unwrap();
+            files.push(path);
+        }
+    }
+    let options = DetectionOptions {
+        cancel: Some(CancelToken::new()),
+        ..DetectionOptions::from(MediaFilter::AnyFile)
+    };
+
+    let complete = duplicate::find_duplicates_with_options(&files, None, &options).unwrap();
+    assert_eq!(complete.groups.len(), 3);
+    assert!(!complete.canceled);
+
+    // Cancel as soon as the first file is hashed
+    let token = options.cancel.clone().unwrap();
+    let progress: ProgressCallback = Box::new(move |event| {
+        if matches!(event, ProgressEvent::Hashing { .. }) {
+            token.cancel();
+        }
+    });
+    let report = duplicate::find_duplicates_with_options(&files, Some(progress), &options).unwrap();
+    assert!(report.canceled);
+    assert!(
+        report.groups.is_empty(),
+        "No file should be read after the cancellation"
+    );
+}
+
+#[test]
+fn test_canceled_scan_is_reported_as_partial() {
+    let tmp = temp_dir();
+    std::fs::write(tmp.path().join("a.txt"), "same").unwrap();
+    std::fs::write(tmp.path().join("b.txt"), "same").unwrap();
+    let cancel = CancelToken::new();
+    cancel.cancel();
+
+    let report = Scanner::with_options(ScanOptions {
+        quiet: true,
+        detection: DetectionOptions {
+            cancel: Some(cancel),
+            ..DetectionOptions::from(MediaFilter::AnyFile)
+        },
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    })
+    .execute()
+    .unwrap();
+
+    assert!(report.canceled);
+    assert!(report.groups.is_empty());
+    assert!(!tmp.path().join("duplicates.json").exists());
+}
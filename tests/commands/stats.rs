@@ -0,0 +1,131 @@
+//! Integration tests for the stats command.
+
+use std::fs;
+
+use media_duplicate_eraser_rs::commands::scan::{ScanOptions, Scanner};
+use media_duplicate_eraser_rs::commands::stats::{Breakdown, Stats};
+use media_duplicate_eraser_rs::commands::Command;
+use media_duplicate_eraser_rs::services::duplicate::{DetectionOptions, DuplicatesFile};
+
+use crate::common::{fixture_path, temp_dir};
+
+#[test]
+fn test_stats_summarizes_duplicates_file() {
+    let tmp = temp_dir();
+    fs::write(tmp.path().join("a.txt"), "same content").unwrap();
+    fs::write(tmp.path().join("b.txt"), "same content").unwrap();
+    fs::write(tmp.path().join("c.txt"), "same content").unwrap();
+    fs::copy(fixture_path("images/image_a.png"), tmp.path().join("x.png")).unwrap();
+    fs::copy(fixture_path("images/image_a.png"), tmp.path().join("y.png")).unwrap();
+
+    let scanner = Scanner::with_options(ScanOptions {
+        quiet: true,
+        use_cache: false,
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    });
+    scanner.execute().expect("Scan should succeed");
+
+    // A duplicate deleted after the scan frees nothing
+    fs::remove_file(tmp.path().join("c.txt")).unwrap();
+
+    let stats = Stats::new(tmp.path().to_path_buf(), false, true)
+        .compute()
+        .unwrap()
+        .expect("duplicates.json should be found");
+    let png_size = fs::metadata(fixture_path("images/image_a.png")).unwrap().len();
+
+    assert_eq!(stats.groups, 2);
+    assert_eq!(stats.duplicates, 3);
+    assert_eq!(stats.missing, 1);
+    assert_eq!(stats.reclaimable_bytes, png_size + 12);
+    assert_eq!(stats.largest_group.unwrap().bytes, png_size);
+    assert_eq!(
+        stats.by_media_type["image"],
+        Breakdown {
+            files: 1,
+            bytes: png_size
+        }
+    );
+    assert_eq!(stats.by_media_type["other"], Breakdown { files: 1, bytes: 12 });
+    assert_eq!(stats.by_duplicate_type["exact"].files, 2);
+}
+
+#[test]
+fn test_stats_breaks_duplicates_down_by_directory() {
+    let tmp = temp_dir();
+    let backup = tmp.path().join("backup");
+    let old = tmp.path().join("old");
+    fs::create_dir_all(&backup).unwrap();
+    fs::create_dir_all(&old).unwrap();
+    fs::write(tmp.path().join("a.txt"), "small").unwrap();
+    fs::write(backup.join("a.txt"), "small").unwrap();
+    fs::write(tmp.path().join("b.txt"), "larger content").unwrap();
+    fs::write(backup.join("b.txt"), "larger content").unwrap();
+    fs::write(old.join("b.txt"), "larger content").unwrap();
+
+    let scanner = Scanner::with_options(ScanOptions {
+        quiet: true,
+        use_cache: false,
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    });
+    scanner.execute().expect("Scan should succeed");
+
+    let stats = Stats::new(tmp.path().to_path_buf(), false, true)
+        .with_by_directory(true)
+        .compute()
+        .unwrap()
+        .expect("duplicates.json should be found");
+
+    // The originals sort first, so the copies in backup/ and old/ are the duplicates
+    assert_eq!(
+        stats.directories_by_size(),
+        [
+            (backup.as_path(), Breakdown { files: 2, bytes: 19 }),
+            (old.as_path(), Breakdown { files: 1, bytes: 14 }),
+        ]
+    );
+}
+
+#[test]
+fn test_stats_counts_paired_live_photo_videos() {
+    let tmp = temp_dir();
+    let copy_dir = tmp.path().join("copy");
+    fs::create_dir(&copy_dir).unwrap();
+    for dir in [tmp.path(), copy_dir.as_path()] {
+        fs::write(dir.join("IMG_0001.HEIC"), "live photo still").unwrap();
+        fs::write(dir.join("IMG_0001.MOV"), "live photo motion").unwrap();
+    }
+
+    let scanner = Scanner::with_options(ScanOptions {
+        quiet: true,
+        use_cache: false,
+        detection: DetectionOptions {
+            live_photos: true,
+            ..DetectionOptions::default()
+        },
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    });
+    scanner.execute().expect("Scan should succeed");
+
+    let stats = Stats::new(tmp.path().to_path_buf(), false, true)
+        .compute()
+        .unwrap()
+        .expect("duplicates.json should be found");
+    let manifest = DuplicatesFile::load(&tmp.path().join("duplicates.json")).unwrap();
+
+    // The duplicate image's video is erased with it
+    assert_eq!(stats.groups, 1);
+    assert_eq!(stats.duplicates, 2);
+    assert_eq!(stats.reclaimable_bytes, 33);
+    assert_eq!(stats.reclaimable_bytes, manifest.reclaimable_bytes);
+    assert_eq!(stats.by_media_type["video"], Breakdown { files: 1, bytes: 17 });
+}
+
+#[test]
+fn test_stats_without_duplicates_file() {
+    let tmp = temp_dir();
+    let stats = Stats::new(tmp.path().to_path_buf(), true, true);
+
+    assert_eq!(stats.compute().unwrap(), None);
+    stats.execute().expect("A missing file is not an error");
+}
@@ -0,0 +1,100 @@
+//! Integration tests for soft deletion and the undo command.
+
+use std::fs;
+
+use chrono::{Duration, Utc};
+use media_duplicate_eraser_rs::commands::erase::{DeleteMode, Eraser};
+use media_duplicate_eraser_rs::commands::scan::{ScanOptions, Scanner};
+use media_duplicate_eraser_rs::commands::undo::Undoer;
+use media_duplicate_eraser_rs::commands::Command;
+use media_duplicate_eraser_rs::services::soft_delete::{self, SoftDeleteManifest};
+
+use crate::common::{assert_path_exists, assert_path_not_exists, temp_dir};
+
+/// Creates two identical files, scans them and soft-erases the duplicate.
+/// Returns (temp_dir, original_path, duplicate_path)
+fn soft_erase_duplicates() -> (tempfile::TempDir, std::path::PathBuf, std::path::PathBuf) {
+    let tmp = temp_dir();
+    let original = tmp.path().join("a.txt");
+    let duplicate = tmp.path().join("b.txt");
+    fs::write(&original, "soft deleted content").unwrap();
+    fs::write(&duplicate, "soft deleted content").unwrap();
+
+    let scanner = Scanner::with_options(ScanOptions {
+        recursive: false,
+        quiet: true,
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    });
+    scanner.execute().expect("Scan should succeed");
+
+    let summary = Eraser::new(tmp.path().to_path_buf(), true)
+        .with_delete_mode(DeleteMode::Soft)
+        .erase_duplicates()
+        .expect("Soft erase should succeed");
+    assert_eq!(summary.deleted, 1);
+
+    (tmp, original, duplicate)
+}
+
+#[test]
+fn test_undo_restores_soft_deleted_files() {
+    let (tmp, original, duplicate) = soft_erase_duplicates();
+    assert_path_exists(&original);
+    assert_path_not_exists(&duplicate);
+    assert_eq!(soft_delete::batches(tmp.path()).unwrap().len(), 1);
+
+    let summary = Undoer::new(tmp.path().to_path_buf(), true)
+        .undo_latest()
+        .expect("Undo should succeed");
+
+    assert_eq!(summary.restored, 1);
+    assert_eq!(summary.skipped, 0);
+    assert_path_exists(&duplicate);
+    assert_eq!(fs::read_to_string(&duplicate).unwrap(), "soft deleted content");
+    assert_path_not_exists(&soft_delete::soft_delete_dir(tmp.path()));
+}
+
+#[test]
+fn test_undo_does_not_overwrite_existing_files() {
+    let (tmp, _original, duplicate) = soft_erase_duplicates();
+    fs::write(&duplicate, "new file in the way").unwrap();
+
+    let summary = Undoer::new(tmp.path().to_path_buf(), true)
+        .undo_latest()
+        .expect("Undo should succeed");
+
+    assert_eq!(summary.restored, 0);
+    assert_eq!(summary.skipped, 1);
+    assert_eq!(fs::read_to_string(&duplicate).unwrap(), "new file in the way");
+    assert_eq!(
+        soft_delete::batches(tmp.path()).unwrap().len(),
+        1,
+        "The batch should be kept for a later undo"
+    );
+}
+
+#[test]
+fn test_undo_without_soft_erase_does_nothing() {
+    let tmp = temp_dir();
+    let undoer = Undoer::new(tmp.path().to_path_buf(), true);
+
+    assert!(undoer.execute().is_ok());
+    assert_eq!(undoer.undo_latest().unwrap().restored, 0);
+}
+
+#[test]
+fn test_prune_removes_old_batches() {
+    let (tmp, _original, _duplicate) = soft_erase_duplicates();
+    let batch = soft_delete::latest_batch(tmp.path()).unwrap().unwrap();
+
+    assert_eq!(soft_delete::prune(tmp.path(), 30).unwrap(), 0);
+
+    // Backdate the batch past the retention period
+    let mut manifest = SoftDeleteManifest::load(&batch).unwrap();
+    manifest.erased_at = Utc::now() - Duration::days(31);
+    manifest.save(&batch).unwrap();
+
+    assert_eq!(soft_delete::prune(tmp.path(), 30).unwrap(), 1);
+    assert_path_not_exists(&batch);
+    assert_path_not_exists(&soft_delete::soft_delete_dir(tmp.path()));
+}
@@ -0,0 +1,178 @@
+//! Integration tests for the verify command.
+
+use std::fs;
+
+use media_duplicate_eraser_rs::commands::scan::{ScanOptions, Scanner};
+use media_duplicate_eraser_rs::commands::verify::{MismatchReason, Verifier};
+use media_duplicate_eraser_rs::commands::Command;
+use media_duplicate_eraser_rs::error::Error;
+use media_duplicate_eraser_rs::services::duplicate::{DetectionOptions, MediaFilter};
+use media_duplicate_eraser_rs::services::hasher;
+
+use crate::common::{fixture_path, run_ffmpeg, temp_dir};
+
+fn scan(dir: &std::path::Path) {
+    let scanner = Scanner::with_options(ScanOptions {
+        quiet: true,
+        use_cache: false,
+        ..ScanOptions::new(dir.to_path_buf())
+    });
+    scanner.execute().expect("Scan should succeed");
+}
+
+#[test]
+fn test_verify_passes_when_nothing_changed() {
+    let tmp = temp_dir();
+    fs::write(tmp.path().join("a.txt"), "same content").unwrap();
+    fs::write(tmp.path().join("b.txt"), "same content").unwrap();
+    fs::copy(fixture_path("images/image_a.png"), tmp.path().join("x.png")).unwrap();
+    fs::copy(fixture_path("images/image_a.png"), tmp.path().join("y.png")).unwrap();
+    scan(tmp.path());
+
+    let verifier = Verifier::new(tmp.path().to_path_buf(), true);
+    let summary = verifier.verify().unwrap().expect("duplicates.json should be found");
+
+    assert_eq!(summary.groups, 2);
+    assert_eq!(summary.duplicates, 2);
+    assert!(summary.mismatches.is_empty());
+    assert!(verifier.execute().is_ok());
+}
+
+#[test]
+fn test_verify_reports_edited_and_missing_duplicates() {
+    let tmp = temp_dir();
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        fs::write(tmp.path().join(name), "same content").unwrap();
+    }
+    scan(tmp.path());
+
+    // Edit one duplicate and delete the other after the scan
+    fs::write(tmp.path().join("b.txt"), "edited content").unwrap();
+    fs::remove_file(tmp.path().join("c.txt")).unwrap();
+
+    let verifier = Verifier::new(tmp.path().to_path_buf(), true);
+    let summary = verifier.verify().unwrap().unwrap();
+
+    let reason_of = |name: &str| {
+        summary
+            .mismatches
+            .iter()
+            .find(|m| m.file == tmp.path().join(name))
+            .map(|m| m.reason.clone())
+    };
+    assert_eq!(summary.mismatches.len(), 2);
+    assert_eq!(reason_of("b.txt"), Some(MismatchReason::Changed));
+    assert_eq!(reason_of("c.txt"), Some(MismatchReason::Missing));
+
+    assert!(matches!(
+        verifier.execute(),
+        Err(Error::VerificationFailed(2))
+    ));
+}
+
+#[test]
+fn test_verify_without_duplicates_file() {
+    let tmp = temp_dir();
+    let verifier = Verifier::new(tmp.path().to_path_buf(), true);
+
+    assert!(verifier.verify().unwrap().is_none());
+    assert!(verifier.execute().is_ok());
+}
+
+#[test]
+fn test_verify_checks_videos_matched_by_scene() {
+    if !hasher::is_ffmpeg_available() {
+        eprintln!("Skipping: FFmpeg is not installed");
+        return;
+    }
+
+    let tmp = temp_dir();
+    let clip = tmp.path().join("scenes.mp4");
+    let clip_str = clip.to_string_lossy();
+    // Four 2-second scenes that look nothing alike
+    run_ffmpeg(&[
+        "-f",
+        "lavfi",
+        "-i",
+        "testsrc=duration=2:size=320x240:rate=25",
+        "-f",
+        "lavfi",
+        "-i",
+        "smptebars=duration=2:size=320x240:rate=25",
+        "-f",
+        "lavfi",
+        "-i",
+        "rgbtestsrc=duration=2:size=320x240:rate=25",
+        "-f",
+        "lavfi",
+        "-i",
+        "testsrc2=duration=2:size=320x240:rate=25",
+        "-filter_complex",
+        "concat=n=4:v=1",
+        &clip_str,
+    ]);
+    // Drop the first scene
+    let trimmed = tmp.path().join("scenes_trimmed.mp4");
+    run_ffmpeg(&["-ss", "2", "-i", &clip_str, &trimmed.to_string_lossy()]);
+
+    let scanner = Scanner::with_options(ScanOptions {
+        quiet: true,
+        use_cache: false,
+        detection: DetectionOptions {
+            video_scenes: true,
+            ..DetectionOptions::from(MediaFilter::VideosOnly)
+        },
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    });
+    scanner.execute().expect("Scan should succeed");
+
+    let verifier = Verifier::new(tmp.path().to_path_buf(), true);
+    let summary = verifier.verify().unwrap().unwrap();
+
+    assert_eq!(summary.duplicates, 1, "The trimmed copy should be found");
+    assert!(summary.mismatches.is_empty());
+    assert!(verifier.execute().is_ok());
+}
+
+#[test]
+fn test_verify_checks_videos_matched_by_sound() {
+    if !hasher::is_ffmpeg_available() {
+        eprintln!("Skipping: FFmpeg is not installed");
+        return;
+    }
+
+    let tmp = temp_dir();
+    // Pictures that look nothing alike over the same soundtrack
+    for (name, picture) in [("bars.mp4", "smptebars"), ("pattern.mp4", "testsrc")] {
+        run_ffmpeg(&[
+            "-f",
+            "lavfi",
+            "-i",
+            &format!("{}=duration=3:size=320x240:rate=25", picture),
+            "-f",
+            "lavfi",
+            "-i",
+            "anoisesrc=d=3:seed=7",
+            "-shortest",
+            &tmp.path().join(name).to_string_lossy(),
+        ]);
+    }
+
+    let scanner = Scanner::with_options(ScanOptions {
+        quiet: true,
+        use_cache: false,
+        detection: DetectionOptions {
+            video_audio_match: true,
+            ..DetectionOptions::from(MediaFilter::VideosOnly)
+        },
+        ..ScanOptions::new(tmp.path().to_path_buf())
+    });
+    scanner.execute().expect("Scan should succeed");
+
+    let verifier = Verifier::new(tmp.path().to_path_buf(), true);
+    let summary = verifier.verify().unwrap().unwrap();
+
+    assert_eq!(summary.duplicates, 1, "The videos should match by sound");
+    assert!(summary.mismatches.is_empty());
+    assert!(verifier.execute().is_ok());
+}
@@ -39,3 +39,13 @@ pub fn assert_path_exists(path: &Path) {
 pub fn assert_path_not_exists(path: &Path) {
     assert!(!path.exists(), "Path should not exist: {:?}", path);
 }
+
+/// Runs FFmpeg with `args`, panicking if it fails.
+pub fn run_ffmpeg(args: &[&str]) {
+    let status = std::process::Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "error"])
+        .args(args)
+        .status()
+        .expect("FFmpeg should run");
+    assert!(status.success(), "FFmpeg failed: {:?}", args);
+}
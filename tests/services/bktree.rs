@@ -0,0 +1,42 @@
+//! Tests for the BK-tree index.
+
+use image_hasher::ImageHash;
+use media_duplicate_eraser_rs::services::bktree::BkTree;
+
+fn hash(bytes: &[u8]) -> ImageHash {
+    ImageHash::from_bytes(bytes).unwrap()
+}
+
+#[test]
+fn test_bktree_finds_hashes_within_radius() {
+    let mut tree = BkTree::new();
+    tree.insert(hash(&[0b0000_0000, 0]), "zero");
+    tree.insert(hash(&[0b0000_0001, 0]), "one bit");
+    tree.insert(hash(&[0b0000_0111, 0]), "three bits");
+    tree.insert(hash(&[0b1111_1111, 0xFF]), "all bits");
+
+    assert_eq!(tree.len(), 4);
+
+    let mut found: Vec<_> = tree
+        .find_within(&hash(&[0, 0]), 1)
+        .into_iter()
+        .map(|(distance, value)| (distance, *value))
+        .collect();
+    found.sort();
+    assert_eq!(found, vec![(0, "zero"), (1, "one bit")]);
+
+    let found = tree.find_within(&hash(&[0, 0]), 3);
+    assert_eq!(found.len(), 3, "Radius 3 should include the three-bit hash");
+}
+
+#[test]
+fn test_bktree_handles_identical_hashes_and_empty_tree() {
+    let mut tree: BkTree<usize> = BkTree::new();
+    assert!(tree.is_empty());
+    assert!(tree.find_within(&hash(&[1, 2]), 64).is_empty());
+
+    tree.insert(hash(&[1, 2]), 0);
+    tree.insert(hash(&[1, 2]), 1);
+
+    assert_eq!(tree.find_within(&hash(&[1, 2]), 0).len(), 2);
+}
@@ -0,0 +1,120 @@
+//! Tests for the hash cache.
+
+use std::fs;
+
+use media_duplicate_eraser_rs::services::cache::HashCache;
+use media_duplicate_eraser_rs::services::hasher::{self, HashAlgorithm, PerceptualConfig};
+
+use crate::common::{fixture_path, temp_dir};
+
+#[test]
+fn test_cache_reuses_hashes_across_loads() {
+    let tmp = temp_dir();
+    let file = tmp.path().join("photo.png");
+    fs::copy(fixture_path("images/image_a.png"), &file).unwrap();
+    let cache_path = tmp.path().join("cache.json");
+
+    let mut cache = HashCache::load(&cache_path);
+    assert!(cache.is_empty());
+    let exact = cache.exact_hash(&file, HashAlgorithm::Sha256).unwrap();
+    let perceptual = cache.perceptual_hash(&file, PerceptualConfig::default()).unwrap();
+    assert_eq!(cache.misses(), 2);
+    cache.save().unwrap();
+
+    let mut cache = HashCache::load(&cache_path);
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.exact_hash(&file, HashAlgorithm::Sha256).unwrap(), exact);
+    assert_eq!(cache.perceptual_hash(&file, PerceptualConfig::default()).unwrap(), perceptual);
+    assert_eq!(cache.hits(), 2, "Both hashes should come from the cache");
+    assert_eq!(cache.misses(), 0);
+
+    // A different algorithm is computed separately
+    cache.exact_hash(&file, HashAlgorithm::Blake3).unwrap();
+    assert_eq!(cache.misses(), 1);
+}
+
+#[test]
+fn test_cache_invalidates_changed_files() {
+    let tmp = temp_dir();
+    let file = tmp.path().join("notes.txt");
+    fs::write(&file, "first version").unwrap();
+    let cache_path = tmp.path().join("cache.json");
+
+    let mut cache = HashCache::load(&cache_path);
+    cache.exact_hash(&file, HashAlgorithm::Sha256).unwrap();
+    cache.save().unwrap();
+
+    fs::write(&file, "second, longer version").unwrap();
+
+    let mut cache = HashCache::load(&cache_path);
+    let hash = cache.exact_hash(&file, HashAlgorithm::Sha256).unwrap();
+    assert_eq!(hash, hasher::sha256_hash(&file).unwrap());
+    assert_eq!(cache.hits(), 0, "A changed file must be rehashed");
+    assert_eq!(cache.misses(), 1);
+}
+
+#[test]
+fn test_cache_ignores_unreadable_file() {
+    let tmp = temp_dir();
+    let cache_path = tmp.path().join("cache.json");
+    fs::write(&cache_path, "not json").unwrap();
+
+    let cache = HashCache::load(&cache_path);
+    assert!(cache.is_empty());
+    assert_eq!(cache.path(), Some(cache_path.as_path()));
+}
+
+#[test]
+fn test_disabled_cache_is_never_saved() {
+    let tmp = temp_dir();
+    let file = tmp.path().join("notes.txt");
+    fs::write(&file, "content").unwrap();
+
+    let mut cache = HashCache::disabled();
+    cache.exact_hash(&file, HashAlgorithm::Sha256).unwrap();
+    cache.save().unwrap();
+
+    assert!(cache.is_empty());
+    assert_eq!(cache.path(), None);
+}
+
+#[test]
+fn test_cache_accepts_hashes_computed_elsewhere() {
+    let tmp = temp_dir();
+    let file = tmp.path().join("photo.png");
+    fs::copy(fixture_path("images/image_a.png"), &file).unwrap();
+    let mut cache = HashCache::load(&tmp.path().join("cache.json"));
+
+    let config = PerceptualConfig::default();
+    assert_eq!(cache.cached_perceptual_hash(&file, config).unwrap(), None);
+    let hash = hasher::media_perceptual_hash(&file).unwrap().unwrap();
+    cache.insert_perceptual_hash(&file, config, Some(&hash)).unwrap();
+    assert_eq!(cache.misses(), 1);
+
+    assert_eq!(cache.cached_perceptual_hash(&file, config).unwrap(), Some(hash));
+    assert_eq!(cache.hits(), 1);
+}
+
+#[test]
+fn test_cache_separates_perceptual_hash_settings() {
+    let tmp = temp_dir();
+    let file = tmp.path().join("photo.png");
+    fs::copy(fixture_path("images/image_a.png"), &file).unwrap();
+    let mut cache = HashCache::load(&tmp.path().join("cache.json"));
+
+    let small = PerceptualConfig {
+        hash_size: 8,
+        ..PerceptualConfig::default()
+    };
+    let hash = cache.perceptual_hash(&file, PerceptualConfig::default()).unwrap();
+    assert!(hash.is_some());
+    assert_eq!(
+        cache.cached_perceptual_hash(&file, small).unwrap(),
+        None,
+        "A hash computed with other settings should not be reused"
+    );
+
+    let small_hash = cache.perceptual_hash(&file, small).unwrap();
+    assert_eq!(small_hash, hasher::media_perceptual_hash_with(&file, small).unwrap());
+    assert_eq!(cache.misses(), 2);
+}
@@ -0,0 +1,593 @@
+//! Tests for the hashing service.
+
+use std::fs;
+use std::path::Path;
+
+use image_hasher::ImageHash;
+use media_duplicate_eraser_rs::error::{Error, FileAction};
+use media_duplicate_eraser_rs::services::hasher::{
+    self, Comparison, HashAlgorithm, MediaType, PerceptualAlgorithm, PerceptualConfig,
+    VideoSignature,
+};
+
+use crate::common::{fixture_path, run_ffmpeg, temp_dir};
+
+#[test]
+fn test_sniff_rejects_misnamed_non_media_files() {
+    let tmp = temp_dir();
+
+    let text = tmp.path().join("notes.mp4");
+    fs::write(&text, "just some text, not a video\n").unwrap();
+    assert!(!hasher::may_be_audio_or_video(&text), "Text should be rejected");
+
+    let image = tmp.path().join("picture.mp3");
+    fs::copy(fixture_path("images/image_a.png"), &image).unwrap();
+    assert!(!hasher::may_be_audio_or_video(&image), "PNG data should be rejected");
+
+    let empty = tmp.path().join("empty.mov");
+    fs::write(&empty, "").unwrap();
+    assert!(!hasher::may_be_audio_or_video(&empty), "Empty files should be rejected");
+
+    // A misnamed file never reaches FFmpeg
+    assert!(hasher::video_perceptual_hash(&text).unwrap().is_none());
+    assert!(hasher::audio_perceptual_hash(&image).unwrap().is_none());
+}
+
+#[test]
+fn test_sniff_accepts_container_headers() {
+    let tmp = temp_dir();
+
+    let mp4 = tmp.path().join("clip.mp4");
+    fs::write(&mp4, b"\x00\x00\x00\x18ftypmp42\x00\x00\x00\x00mp42isom").unwrap();
+    assert!(hasher::may_be_audio_or_video(&mp4));
+
+    let wav = tmp.path().join("sound.wav");
+    fs::write(&wav, b"RIFF\x24\x08\x00\x00WAVEfmt \x10\x00\x00\x00").unwrap();
+    assert!(hasher::may_be_audio_or_video(&wav));
+}
+
+#[test]
+fn test_exact_hash_algorithms() {
+    let tmp = temp_dir();
+    let path = tmp.path().join("abc.txt");
+    fs::write(&path, "abc").unwrap();
+
+    assert_eq!(
+        hasher::exact_hash(&path, HashAlgorithm::Sha256).unwrap(),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert_eq!(
+        hasher::exact_hash(&path, HashAlgorithm::Blake3).unwrap(),
+        "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+    );
+    assert_eq!(
+        hasher::sha256_hash(&path).unwrap(),
+        hasher::exact_hash(&path, HashAlgorithm::Sha256).unwrap()
+    );
+}
+
+#[test]
+fn test_mapped_and_buffered_hashes_match() {
+    let tmp = temp_dir();
+    let path = tmp.path().join("large.bin");
+    // Several buffers long, ending mid-buffer
+    let bytes: Vec<u8> = (0..100_003u32).map(|i| (i * 31 % 251) as u8).collect();
+    fs::write(&path, &bytes).unwrap();
+
+    for algorithm in [HashAlgorithm::Sha256, HashAlgorithm::Blake3] {
+        let buffered = hasher::exact_hash_with(&path, algorithm, None).unwrap();
+        assert_eq!(
+            hasher::exact_hash_with(&path, algorithm, Some(0)).unwrap(),
+            buffered
+        );
+        // Below the threshold the file is read as before
+        assert_eq!(
+            hasher::exact_hash_with(&path, algorithm, Some(bytes.len() as u64 + 1)).unwrap(),
+            buffered
+        );
+    }
+}
+
+#[test]
+fn test_io_errors_name_the_file() {
+    let tmp = temp_dir();
+    let missing = tmp.path().join("missing.txt");
+
+    for result in [
+        hasher::sha256_hash(&missing).map(|_| ()),
+        hasher::partial_hash(&missing, 10).map(|_| ()),
+        hasher::file_size(&missing).map(|_| ()),
+    ] {
+        let error = result.unwrap_err();
+        assert!(matches!(
+            &error,
+            Error::FileOperation { action: FileAction::Read, path, .. } if *path == missing
+        ));
+        let message = format!("Failed to read {}: ", missing.display());
+        assert!(error.to_string().starts_with(&message));
+    }
+}
+
+#[test]
+fn test_partial_hash_only_reads_prefix() {
+    let tmp = temp_dir();
+    let a = tmp.path().join("a.bin");
+    let b = tmp.path().join("b.bin");
+    fs::write(&a, b"same start, different end: 1").unwrap();
+    fs::write(&b, b"same start, different end: 2").unwrap();
+
+    assert_eq!(
+        hasher::partial_hash(&a, 10).unwrap(),
+        hasher::partial_hash(&b, 10).unwrap()
+    );
+    assert_ne!(
+        hasher::partial_hash(&a, 100).unwrap(),
+        hasher::partial_hash(&b, 100).unwrap()
+    );
+    // A prefix longer than the file covers the whole file
+    assert_eq!(
+        hasher::partial_hash(&a, 100).unwrap(),
+        hasher::exact_hash(&a, HashAlgorithm::Blake3).unwrap()
+    );
+}
+
+#[test]
+fn test_crc32_hash_tells_contents_apart() {
+    let tmp = temp_dir();
+    let a = tmp.path().join("a.bin");
+    let b = tmp.path().join("b.bin");
+    let c = tmp.path().join("c.bin");
+    fs::write(&a, b"thumbnail 1").unwrap();
+    fs::write(&b, b"thumbnail 1").unwrap();
+    fs::write(&c, b"thumbnail 2").unwrap();
+
+    assert_eq!(
+        hasher::crc32_hash(&a).unwrap(),
+        hasher::crc32_hash(&b).unwrap()
+    );
+    assert_ne!(
+        hasher::crc32_hash(&a).unwrap(),
+        hasher::crc32_hash(&c).unwrap()
+    );
+}
+
+#[test]
+fn test_head_tail_hash_skips_the_middle() {
+    let tmp = temp_dir();
+    let a = tmp.path().join("a.bin");
+    let b = tmp.path().join("b.bin");
+    let c = tmp.path().join("c.bin");
+    fs::write(&a, b"head, middle 1, tail").unwrap();
+    fs::write(&b, b"head, middle 2, tail").unwrap();
+    fs::write(&c, b"head, middle 1, tall").unwrap();
+
+    assert_eq!(
+        hasher::head_tail_hash(&a, 6).unwrap(),
+        hasher::head_tail_hash(&b, 6).unwrap()
+    );
+    assert_ne!(
+        hasher::head_tail_hash(&a, 6).unwrap(),
+        hasher::head_tail_hash(&c, 6).unwrap()
+    );
+    // Ends that meet cover the whole file
+    assert_eq!(
+        hasher::head_tail_hash(&a, 10).unwrap(),
+        hasher::exact_hash(&a, HashAlgorithm::Blake3).unwrap()
+    );
+}
+
+#[test]
+fn test_exif_orientation_is_applied_before_hashing() {
+    let tagged = fixture_path("exif/tagged.png");
+    let rotated = fixture_path("exif/rotated.png");
+    assert_eq!(hasher::exif_orientation(&tagged), Some(6));
+    assert_eq!(hasher::exif_orientation(&rotated), None);
+
+    let tagged = hasher::perceptual_hash(&tagged).unwrap().unwrap();
+    let rotated = hasher::perceptual_hash(&rotated).unwrap().unwrap();
+    assert_eq!(
+        hasher::hamming_distance(&tagged, &rotated),
+        0,
+        "The tagged image should be turned upright like the rotated copy"
+    );
+}
+
+#[test]
+fn test_animations_sharing_a_first_frame_do_not_match() {
+    let a = fixture_path("animated/animation_a.gif");
+    let b = fixture_path("animated/animation_b.gif");
+
+    // Both animations open on the same frame
+    let first_frame = |path: &Path| {
+        let hasher = image_hasher::HasherConfig::new().to_hasher();
+        hasher.hash_image(&image::open(path).unwrap())
+    };
+    assert_eq!(first_frame(&a), first_frame(&b));
+
+    let hash_a = hasher::perceptual_hash(&a).unwrap().unwrap();
+    let hash_b = hasher::perceptual_hash(&b).unwrap().unwrap();
+    assert!(
+        !hasher::are_similar(&hash_a, &hash_b, hasher::DEFAULT_SIMILARITY_THRESHOLD).unwrap(),
+        "Later frames should tell the animations apart"
+    );
+
+    let tmp = temp_dir();
+    let copy = tmp.path().join("copy.gif");
+    fs::copy(&a, &copy).unwrap();
+    assert_eq!(hasher::perceptual_hash(&copy).unwrap(), Some(hash_a));
+}
+
+#[test]
+fn test_raw_extensions_are_images() {
+    for name in ["IMG_0001.CR2", "DSC_0001.nef", "_DSC0001.arw", "photo.dng"] {
+        assert!(hasher::is_raw(Path::new(name)), "{}", name);
+        assert_eq!(hasher::get_media_type(Path::new(name)), MediaType::Image, "{}", name);
+    }
+    assert!(!hasher::is_raw(Path::new("photo.jpg")));
+}
+
+#[test]
+fn test_perceptual_hash_reads_format_from_content() {
+    let tmp = temp_dir();
+    let png = fixture_path("images/image_a.png");
+    // An extension the image decoder does not know, as registered by users
+    let renamed = tmp.path().join("image_a.custom");
+    fs::copy(&png, &renamed).unwrap();
+
+    assert_eq!(
+        hasher::perceptual_hash(&renamed).unwrap(),
+        hasher::perceptual_hash(&png).unwrap()
+    );
+}
+
+#[test]
+fn test_raw_without_preview_has_no_perceptual_hash() {
+    let tmp = temp_dir();
+    let raw = tmp.path().join("broken.cr2");
+    fs::write(&raw, "not a raw file").unwrap();
+
+    assert!(hasher::perceptual_hash(&raw).unwrap().is_none());
+}
+
+#[test]
+fn test_heif_extensions_are_images() {
+    for name in ["IMG_0001.HEIC", "photo.heif", "photo.avif"] {
+        assert!(hasher::is_heif(Path::new(name)), "{}", name);
+        assert_eq!(hasher::get_media_type(Path::new(name)), MediaType::Image, "{}", name);
+    }
+    assert!(!hasher::is_heif(Path::new("photo.jpg")));
+}
+
+#[test]
+fn test_heif_without_picture_has_no_perceptual_hash() {
+    let tmp = temp_dir();
+    let heic = tmp.path().join("broken.heic");
+    fs::write(&heic, "not a heic file").unwrap();
+
+    assert!(hasher::perceptual_hash(&heic).unwrap().is_none());
+}
+
+#[test]
+fn test_perceptual_config_sets_hash_size_and_algorithm() {
+    let image = fixture_path("images/image_a.png");
+    let config = |hash_size| PerceptualConfig {
+        hash_size,
+        algorithm: PerceptualAlgorithm::Mean,
+    };
+
+    let small = hasher::perceptual_hash_with(&image, config(8)).unwrap().unwrap();
+    let large = hasher::perceptual_hash_with(&image, config(32)).unwrap().unwrap();
+    assert_eq!(small.as_bytes().len(), 8, "A mean hash has one bit per cell");
+    assert_eq!(large.as_bytes().len(), 128);
+
+    let default = hasher::perceptual_hash(&image).unwrap().unwrap();
+    let same = hasher::perceptual_hash_with(&image, PerceptualConfig::default()).unwrap();
+    assert_eq!(Some(default), same);
+
+    // Thresholds keep the same share of differing bits at every size
+    assert_eq!(config(16).scale_threshold(10), 10);
+    assert_eq!(config(32).scale_threshold(10), 40);
+    assert_eq!(config(8).scale_threshold(10), 2);
+}
+
+#[test]
+fn test_hashes_of_different_sizes_are_not_compared() {
+    let image = fixture_path("images/image_a.png");
+    let config = |hash_size| PerceptualConfig {
+        hash_size,
+        algorithm: PerceptualAlgorithm::Mean,
+    };
+    let small = hasher::perceptual_hash_with(&image, config(8)).unwrap();
+    let large = hasher::perceptual_hash_with(&image, config(16)).unwrap();
+    let (small, large) = (small.unwrap(), large.unwrap());
+    assert_eq!(config(8).hash_len(), 8);
+    assert_eq!(config(16).hash_len(), 32);
+    let default = hasher::perceptual_hash(&image).unwrap().unwrap();
+    assert_eq!(PerceptualConfig::default().hash_len(), default.as_bytes().len());
+
+    // The 8x8 hash would otherwise be compared with the start of the 16x16 one
+    assert!(matches!(
+        hasher::are_similar(&small, &large, hasher::DEFAULT_SIMILARITY_THRESHOLD),
+        Err(Error::HashSizeMismatch(8, 32))
+    ));
+    assert!(hasher::try_hamming_distance(&large, &small).is_err());
+    assert_eq!(hasher::try_hamming_distance(&large, &large).unwrap(), 0);
+    assert!(std::panic::catch_unwind(|| hasher::hamming_distance(&small, &large)).is_err());
+}
+
+#[test]
+fn test_raw_is_hashed_through_ffmpeg_preview() {
+    if !hasher::is_ffmpeg_available() {
+        eprintln!("Skipping: FFmpeg is not installed");
+        return;
+    }
+
+    let raw = hasher::perceptual_hash(&fixture_path("raw/image_a.dng"))
+        .unwrap()
+        .expect("The DNG preview should be extracted");
+    let png = hasher::perceptual_hash(&fixture_path("images/image_a.png"))
+        .unwrap()
+        .unwrap();
+
+    assert!(
+        hasher::are_similar(&raw, &png, hasher::DEFAULT_SIMILARITY_THRESHOLD).unwrap(),
+        "The DNG holds a downscaled copy of image_a.png"
+    );
+}
+
+#[test]
+fn test_avif_is_hashed_like_its_jpeg_export() {
+    if !hasher::is_ffmpeg_available() {
+        eprintln!("Skipping: FFmpeg is not installed");
+        return;
+    }
+
+    let tmp = temp_dir();
+    let avif = tmp.path().join("photo.avif");
+    let png = fixture_path("converted/photo.png");
+    // Not every FFmpeg build has an AV1 encoder
+    let encoded = std::process::Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "error", "-i"])
+        .arg(&png)
+        .args(["-frames:v", "1"])
+        .arg(&avif)
+        .status()
+        .is_ok_and(|status| status.success());
+    if !encoded {
+        eprintln!("Skipping: FFmpeg cannot encode AVIF");
+        return;
+    }
+
+    let avif = hasher::perceptual_hash(&avif)
+        .unwrap()
+        .expect("The AVIF image should be decoded");
+    let jpeg = hasher::perceptual_hash(&fixture_path("converted/photo.jpg"))
+        .unwrap()
+        .unwrap();
+
+    assert!(
+        hasher::are_similar(&avif, &jpeg, hasher::DEFAULT_SIMILARITY_THRESHOLD).unwrap(),
+        "An AVIF and a JPEG export of the same photo should match (distance {})",
+        hasher::hamming_distance(&avif, &jpeg)
+    );
+}
+
+#[test]
+fn test_undecodable_video_is_not_an_ffmpeg_error() {
+    if !hasher::is_ffmpeg_available() {
+        eprintln!("Skipping: FFmpeg is not installed");
+        return;
+    }
+
+    let tmp = temp_dir();
+    let clip = tmp.path().join("broken.mp4");
+    // Binary content, so it is handed to FFmpeg, which cannot decode it
+    fs::write(&clip, (0..=255u8).cycle().take(4096).collect::<Vec<u8>>()).unwrap();
+
+    assert!(hasher::video_perceptual_hash(&clip).unwrap().is_none());
+    assert!(hasher::video_scene_signature(&clip).unwrap().is_none());
+    assert!(hasher::audio_perceptual_hash(&clip).unwrap().is_none());
+}
+
+#[test]
+fn test_video_reencode_matches_original() {
+    if !hasher::is_ffmpeg_available() {
+        eprintln!("Skipping: FFmpeg is not installed");
+        return;
+    }
+
+    let tmp = temp_dir();
+    let clip = tmp.path().join("clip.mp4");
+    let reencode = tmp.path().join("clip_small.mp4");
+    let clip_str = clip.to_string_lossy();
+    run_ffmpeg(&[
+        "-f",
+        "lavfi",
+        "-i",
+        "testsrc=duration=4:size=320x240:rate=30",
+        &clip_str,
+    ]);
+    // Half the resolution and a different frame rate
+    run_ffmpeg(&[
+        "-i",
+        &clip_str,
+        "-vf",
+        "scale=160:120",
+        "-r",
+        "24",
+        &reencode.to_string_lossy(),
+    ]);
+
+    let original = hasher::video_perceptual_hash(&clip)
+        .unwrap()
+        .expect("The clip should be hashed");
+    let small = hasher::video_perceptual_hash(&reencode)
+        .unwrap()
+        .expect("The re-encode should be hashed");
+
+    assert!(
+        hasher::are_similar(&original, &small, hasher::DEFAULT_SIMILARITY_THRESHOLD).unwrap(),
+        "A half-resolution re-encode should match its original (distance {})",
+        hasher::hamming_distance(&original, &small)
+    );
+}
+
+#[test]
+fn test_video_audio_hash_ignores_videos_without_sound() {
+    if !hasher::is_ffmpeg_available() {
+        eprintln!("Skipping: FFmpeg is not installed");
+        return;
+    }
+
+    let tmp = temp_dir();
+    let mute = tmp.path().join("mute.mp4");
+    let silent = tmp.path().join("silent.mp4");
+    let noisy = tmp.path().join("noisy.mp4");
+    let picture = "testsrc=duration=2:size=160x120:rate=25";
+    run_ffmpeg(&["-f", "lavfi", "-i", picture, &mute.to_string_lossy()]);
+    for (clip, sound) in [(&silent, "anullsrc=duration=2"), (&noisy, "anoisesrc=d=2:seed=7")] {
+        run_ffmpeg(&[
+            "-f",
+            "lavfi",
+            "-i",
+            picture,
+            "-f",
+            "lavfi",
+            "-i",
+            sound,
+            "-shortest",
+            &clip.to_string_lossy(),
+        ]);
+    }
+
+    let config = PerceptualConfig::default();
+    assert!(hasher::video_audio_hash_with(&mute, config).unwrap().is_none());
+    assert!(hasher::video_audio_hash_with(&silent, config).unwrap().is_none());
+    assert!(hasher::video_audio_hash_with(&noisy, config).unwrap().is_some());
+}
+
+fn signature(frames: &[[u8; 8]]) -> VideoSignature {
+    VideoSignature {
+        frames: frames
+            .iter()
+            .map(|bytes| ImageHash::from_bytes(bytes).unwrap())
+            .collect(),
+    }
+}
+
+#[test]
+fn test_video_signature_matches_by_shared_scenes() {
+    let full = signature(&[[0x00; 8], [0xFF; 8], [0x0F; 8], [0xF0; 8]]);
+    // A trimmed copy keeps the last two scenes, one of them slightly changed
+    let trimmed = signature(&[[0x0F; 8], [0xF1; 8]]);
+    let unrelated = signature(&[[0x33; 8], [0x55; 8]]);
+
+    assert_eq!(full.shared_scenes(&trimmed, 2), 1.0);
+    assert!(full.matches(&trimmed, 2));
+    assert!(trimmed.matches(&full, 2));
+    assert_eq!(full.shared_scenes(&unrelated, 2), 0.0);
+    assert!(!full.matches(&unrelated, 2));
+    assert!(!full.matches(&signature(&[]), 2));
+}
+
+#[test]
+fn test_differing_bits_locate_the_hamming_distance() {
+    let first: ImageHash = ImageHash::from_bytes(&[0x00; 8]).unwrap();
+    let second: ImageHash = ImageHash::from_bytes(&[0b0000_0101, 0, 0, 0, 0, 0, 0, 0x80]).unwrap();
+
+    let bits = hasher::differing_bits(&first, &second);
+    let differing: Vec<usize> = (0..bits.len()).filter(|&i| bits[i]).collect();
+
+    assert_eq!(bits.len(), 64);
+    assert_eq!(differing, vec![0, 2, 63]);
+    assert_eq!(
+        differing.len() as u32,
+        hasher::hamming_distance(&first, &second)
+    );
+    assert!(hasher::differing_bits(&first, &first).iter().all(|&b| !b));
+}
+
+#[test]
+fn test_video_scene_signature_matches_trimmed_copy() {
+    if !hasher::is_ffmpeg_available() {
+        eprintln!("Skipping: FFmpeg is not installed");
+        return;
+    }
+
+    let tmp = temp_dir();
+    let clip = tmp.path().join("scenes.mp4");
+    let trimmed = tmp.path().join("scenes_trimmed.mp4");
+    let clip_str = clip.to_string_lossy();
+    // Four 2-second scenes that look nothing alike
+    run_ffmpeg(&[
+        "-f",
+        "lavfi",
+        "-i",
+        "testsrc=duration=2:size=320x240:rate=25",
+        "-f",
+        "lavfi",
+        "-i",
+        "smptebars=duration=2:size=320x240:rate=25",
+        "-f",
+        "lavfi",
+        "-i",
+        "rgbtestsrc=duration=2:size=320x240:rate=25",
+        "-f",
+        "lavfi",
+        "-i",
+        "testsrc2=duration=2:size=320x240:rate=25",
+        "-filter_complex",
+        "concat=n=4:v=1",
+        &clip_str,
+    ]);
+    // Drop the first scene
+    run_ffmpeg(&["-ss", "2", "-i", &clip_str, &trimmed.to_string_lossy()]);
+
+    let full = hasher::video_scene_signature(&clip)
+        .unwrap()
+        .expect("The clip should have a signature");
+    let cut = hasher::video_scene_signature(&trimmed)
+        .unwrap()
+        .expect("The trimmed copy should have a signature");
+
+    assert!(full.frames.len() > cut.frames.len());
+    assert!(
+        full.matches(&cut, hasher::DEFAULT_SIMILARITY_THRESHOLD),
+        "A trimmed copy should share its scenes with the full video ({})",
+        full.shared_scenes(&cut, hasher::DEFAULT_SIMILARITY_THRESHOLD)
+    );
+}
+
+#[test]
+fn test_compare_reports_exact_and_perceptual_similarity() {
+    let image_a = fixture_path("images/image_a.png");
+
+    let copy = hasher::compare(&image_a, &fixture_path("images/image_a copy.png")).unwrap();
+    assert_eq!(
+        copy,
+        Comparison::Compared {
+            exact: true,
+            distance: Some(0),
+            similarity: 100.0
+        }
+    );
+
+    let Comparison::Compared {
+        exact,
+        distance,
+        similarity,
+    } = hasher::compare(&image_a, &fixture_path("images/unique.png")).unwrap()
+    else {
+        panic!("Two images should be comparable");
+    };
+    assert!(!exact);
+    let distance = distance.expect("Both images should be hashed");
+    assert!(distance > 0);
+    assert!((0.0..100.0).contains(&similarity));
+
+    let text = fixture_path("text/unique.txt");
+    assert_eq!(
+        hasher::compare(&image_a, &text).unwrap(),
+        Comparison::NotComparable(MediaType::Image, MediaType::Unknown)
+    );
+}
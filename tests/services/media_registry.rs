@@ -0,0 +1,67 @@
+//! Tests for the registry of media types by extension.
+
+use std::fs;
+use std::path::Path;
+
+use media_duplicate_eraser_rs::error::Error;
+use media_duplicate_eraser_rs::services::hasher::MediaType;
+use media_duplicate_eraser_rs::services::media_registry::MediaRegistry;
+
+use crate::common::temp_dir;
+
+#[test]
+fn test_builtin_registry_knows_supported_extensions() {
+    let registry = MediaRegistry::builtin();
+
+    for (name, expected) in [
+        ("photo.JPG", MediaType::Image),
+        ("IMG_0001.cr2", MediaType::Image),
+        ("clip.mp4", MediaType::Video),
+        ("song.flac", MediaType::Audio),
+        ("notes.txt", MediaType::Unknown),
+        ("README", MediaType::Unknown),
+    ] {
+        assert_eq!(registry.media_type(Path::new(name)), expected, "{}", name);
+    }
+}
+
+#[test]
+fn test_register_normalizes_and_removes_extensions() {
+    let mut registry = MediaRegistry::builtin();
+
+    registry.register(".MTS", MediaType::Video);
+    registry.register("gif", MediaType::Unknown);
+
+    let media_type = |name: &str| registry.media_type(Path::new(name));
+    assert_eq!(media_type("clip.mts"), MediaType::Video);
+    assert_eq!(media_type("loop.gif"), MediaType::Unknown);
+}
+
+#[test]
+fn test_media_types_file_extends_builtin_extensions() {
+    let tmp = temp_dir();
+    let config = tmp.path().join("media_types.json");
+    fs::write(&config, r#"{"image": ["jxl"], "video": ["m2ts", "mp3"]}"#).unwrap();
+
+    let registry = MediaRegistry::load(&config).unwrap();
+    let media_type = |name: &str| registry.media_type(Path::new(name));
+    assert_eq!(media_type("photo.jxl"), MediaType::Image);
+    assert_eq!(media_type("clip.M2TS"), MediaType::Video);
+    // Listed extensions take precedence over built-in ones
+    assert_eq!(media_type("song.mp3"), MediaType::Video);
+    assert_eq!(media_type("photo.png"), MediaType::Image);
+}
+
+#[test]
+fn test_invalid_media_types_file_is_rejected() {
+    let tmp = temp_dir();
+    let config = tmp.path().join("media_types.json");
+    fs::write(&config, r#"{"images": ["jxl"]}"#).unwrap();
+    assert!(matches!(MediaRegistry::load(&config), Err(Error::Json(_))));
+
+    let missing = tmp.path().join("missing.json");
+    assert!(matches!(
+        MediaRegistry::load(&missing),
+        Err(Error::FileOperation { path, .. }) if path == missing
+    ));
+}
@@ -0,0 +1,9 @@
+//! Integration tests for shared services.
+
+pub mod bktree;
+pub mod cache;
+pub mod hasher;
+pub mod media_registry;
+pub mod relocate;
+pub mod report;
+pub mod units;
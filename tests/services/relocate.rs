@@ -0,0 +1,75 @@
+//! Tests for moving files across filesystems.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use media_duplicate_eraser_rs::services::relocate;
+
+use crate::common::{assert_path_exists, assert_path_not_exists, temp_dir};
+
+/// A rename that behaves as if the paths were on different filesystems.
+fn cross_device_rename(_from: &Path, _to: &Path) -> io::Result<()> {
+    Err(io::Error::from(io::ErrorKind::CrossesDevices))
+}
+
+#[test]
+fn test_move_file_copies_across_filesystems() {
+    let tmp = temp_dir();
+    let source = tmp.path().join("photo.jpg");
+    let staged = tmp.path().join("staged");
+    fs::write(&source, "image bytes").unwrap();
+
+    relocate::move_file_with(&source, &staged, cross_device_rename).unwrap();
+    assert_path_not_exists(&source);
+    assert_eq!(fs::read_to_string(&staged).unwrap(), "image bytes");
+
+    // Rolling back takes the same fallback and restores the original
+    relocate::move_file_with(&staged, &source, cross_device_rename).unwrap();
+    assert_path_not_exists(&staged);
+    assert_eq!(fs::read_to_string(&source).unwrap(), "image bytes");
+}
+
+#[test]
+fn test_move_file_reports_other_rename_errors() {
+    let tmp = temp_dir();
+    let source = tmp.path().join("photo.jpg");
+    let staged = tmp.path().join("staged");
+    fs::write(&source, "image bytes").unwrap();
+
+    let result = relocate::move_file_with(&source, &staged, |_, _| {
+        Err(io::Error::from(io::ErrorKind::PermissionDenied))
+    });
+
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+    assert_path_exists(&source);
+    assert_path_not_exists(&staged);
+}
+
+#[test]
+fn test_move_file_fails_without_losing_source() {
+    let tmp = temp_dir();
+    let source = tmp.path().join("photo.jpg");
+    // The copy cannot be written into a directory that does not exist
+    let staged = tmp.path().join("missing").join("staged");
+    fs::write(&source, "image bytes").unwrap();
+
+    assert!(relocate::move_file_with(&source, &staged, cross_device_rename).is_err());
+    assert_path_exists(&source);
+}
+
+#[test]
+fn test_move_dir_moves_all_files() {
+    let tmp = temp_dir();
+    let from = tmp.path().join("staging");
+    let to = tmp.path().join("batch");
+    fs::create_dir(&from).unwrap();
+    fs::write(from.join("0"), "first").unwrap();
+    fs::write(from.join("1"), "second").unwrap();
+
+    relocate::move_dir(&from, &to).unwrap();
+
+    assert_path_not_exists(&from);
+    assert_eq!(fs::read_to_string(to.join("0")).unwrap(), "first");
+    assert_eq!(fs::read_to_string(to.join("1")).unwrap(), "second");
+}
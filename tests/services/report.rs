@@ -0,0 +1,173 @@
+//! Tests for duplicate report writers.
+
+use std::path::Path;
+
+use media_duplicate_eraser_rs::services::duplicate::{
+    self, DetectionOptions, DuplicatesFile, ManifestFormat,
+};
+use media_duplicate_eraser_rs::services::report::{NulListWriter, ReportFormat, ReportWriter};
+
+use crate::common::{temp_dir, text_fixtures_dir};
+
+fn text_report() -> duplicate::DuplicateReport {
+    let files: Vec<_> = std::fs::read_dir(text_fixtures_dir())
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+    duplicate::find_duplicates(&files).unwrap()
+}
+
+#[test]
+fn test_json_writer_produces_loadable_manifest() {
+    let report = text_report();
+    let mut out: Vec<u8> = Vec::new();
+
+    ReportFormat::Json.writer().write(&report, &mut out).unwrap();
+
+    let manifest: DuplicatesFile = serde_json::from_slice(&out).unwrap();
+    assert_eq!(manifest.duplicate_groups, report.groups.len());
+    assert_eq!(manifest.total_duplicates, report.duplicate_count());
+}
+
+#[test]
+fn test_manifest_round_trips_through_every_format() {
+    let tmp = temp_dir();
+    let files: Vec<_> = std::fs::read_dir(text_fixtures_dir())
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+    let options = DetectionOptions {
+        keep_hashes: true,
+        ..DetectionOptions::default()
+    };
+    let mut report = duplicate::find_duplicates_with_options(&files, None, &options).unwrap();
+    report.root = Some(text_fixtures_dir());
+    let manifest = DuplicatesFile::from_report(&report);
+    assert!(!manifest.entries.is_empty() && !manifest.hashes.is_empty());
+    let expected = serde_json::to_value(&manifest).unwrap();
+
+    for name in [
+        "duplicates.json",
+        "duplicates.yaml",
+        "duplicates.yml",
+        "duplicates.toml",
+        "duplicates.jsonl",
+    ] {
+        let path = tmp.path().join(name);
+        manifest.save(&path).unwrap();
+        let loaded = DuplicatesFile::load(&path).unwrap();
+        assert_eq!(serde_json::to_value(&loaded).unwrap(), expected, "{}", name);
+    }
+
+    let toml = std::fs::read_to_string(tmp.path().join("duplicates.toml")).unwrap();
+    assert!(toml.contains("[[entries]]"), "The manifest should be TOML: {}", toml);
+}
+
+#[test]
+fn test_jsonl_writer_puts_each_group_on_its_own_line() {
+    let report = text_report();
+    let mut out: Vec<u8> = Vec::new();
+
+    ReportFormat::Jsonl
+        .writer()
+        .write(&report, &mut out)
+        .unwrap();
+
+    let lines: Vec<serde_json::Value> = String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 1 + report.groups.len());
+    assert_eq!(lines[0]["duplicate_groups"], report.groups.len());
+    assert!(
+        lines[0].get("entries").is_none(),
+        "Groups belong on their own lines"
+    );
+    for entry in &lines[1..] {
+        assert!(entry["original"].is_string() && entry["duplicates"].is_array());
+    }
+}
+
+#[test]
+fn test_formats_follow_the_file_extension() {
+    let format = |name: &str| ManifestFormat::from_path(Path::new(name));
+    assert_eq!(format("duplicates.json"), ManifestFormat::Json);
+    assert_eq!(format("duplicates.YAML"), ManifestFormat::Yaml);
+    assert_eq!(format("duplicates.yml"), ManifestFormat::Yaml);
+    assert_eq!(format("duplicates.toml"), ManifestFormat::Toml);
+    assert_eq!(format("duplicates.jsonl"), ManifestFormat::JsonLines);
+    assert_eq!(format("duplicates.ndjson"), ManifestFormat::JsonLines);
+    assert_eq!(format("duplicates"), ManifestFormat::Json);
+
+    let format = |name: &str| ReportFormat::from_path(Path::new(name));
+    assert_eq!(format("report.csv"), ReportFormat::Csv);
+    assert_eq!(format("report.yaml"), ReportFormat::Yaml);
+    assert_eq!(format("report.jsonl"), ReportFormat::Jsonl);
+    assert_eq!(format("report.out"), ReportFormat::Json);
+    assert_eq!(ReportFormat::Csv.manifest_format(), None);
+}
+
+#[test]
+fn test_csv_writer_lists_each_duplicate() {
+    let report = text_report();
+    let mut out: Vec<u8> = Vec::new();
+
+    ReportFormat::Csv.writer().write(&report, &mut out).unwrap();
+
+    let csv = String::from_utf8(out).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("group_id,duplicate_type,original,duplicate,size_bytes")
+    );
+    assert_eq!(lines.count(), report.duplicate_count());
+}
+
+#[test]
+fn test_csv_writer_escapes_commas_and_quotes() {
+    let tmp = temp_dir();
+    let original = tmp.path().join("holiday, \"best\".txt");
+    let duplicate = tmp.path().join("plain.txt");
+    std::fs::write(&original, "same content").unwrap();
+    std::fs::write(&duplicate, "same content").unwrap();
+    let report = duplicate::find_duplicates(&[original.clone(), duplicate.clone()]).unwrap();
+
+    let mut out: Vec<u8> = Vec::new();
+    ReportFormat::Csv.writer().write(&report, &mut out).unwrap();
+    let csv = String::from_utf8(out).unwrap();
+
+    let row = csv.lines().nth(1).expect("One duplicate row");
+    let escaped = format!(
+        "\"{}\"",
+        original.to_string_lossy().replace('"', "\"\"")
+    );
+    assert!(row.starts_with("1,exact,"), "Unexpected row: {}", row);
+    assert!(row.contains(&escaped), "Path should be quoted: {}", row);
+    assert!(row.ends_with(",12"), "Size should be the last column: {}", row);
+}
+
+// Other platforms do not allow line breaks in file names
+#[cfg(unix)]
+#[test]
+fn test_nul_list_writer_separates_paths_with_nul_bytes() {
+    let tmp = temp_dir();
+    let original = tmp.path().join("a.txt");
+    let duplicate = tmp.path().join("line\nbreak.txt");
+    std::fs::write(&original, "same content").unwrap();
+    std::fs::write(&duplicate, "same content").unwrap();
+    let report = duplicate::find_duplicates(&[original, duplicate]).unwrap();
+    let kept = &report.groups[0].files[0];
+
+    let mut out: Vec<u8> = Vec::new();
+    NulListWriter.write(&report, &mut out).unwrap();
+
+    assert_eq!(out.last(), Some(&0), "Every path should end with a NUL byte");
+    let paths: Vec<&[u8]> = out[..out.len() - 1].split(|&b| b == 0).collect();
+    assert_eq!(paths.len(), 1, "Only the duplicate should be listed");
+    assert_ne!(paths[0], kept.as_os_str().as_encoded_bytes());
+}
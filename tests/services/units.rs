@@ -0,0 +1,92 @@
+//! Tests for human-readable size and time parsing and formatting.
+
+use chrono::{Duration, TimeZone, Utc};
+use media_duplicate_eraser_rs::services::units::{
+    format_size, parse_duration, parse_size, parse_time,
+};
+
+#[test]
+fn test_parse_size_bare_bytes() {
+    assert_eq!(parse_size("0").unwrap(), 0);
+    assert_eq!(parse_size("2048").unwrap(), 2048);
+}
+
+#[test]
+fn test_parse_size_units() {
+    assert_eq!(parse_size("4K").unwrap(), 4 * 1024);
+    assert_eq!(parse_size("10M").unwrap(), 10 * 1024 * 1024);
+    assert_eq!(parse_size("2G").unwrap(), 2 * 1024 * 1024 * 1024);
+    assert_eq!(parse_size("1T").unwrap(), 1024u64.pow(4));
+}
+
+#[test]
+fn test_parse_size_accepts_suffix_variants() {
+    assert_eq!(parse_size("50GB").unwrap(), parse_size("50G").unwrap());
+    assert_eq!(parse_size("50GiB").unwrap(), parse_size("50g").unwrap());
+    assert_eq!(parse_size("1.5K").unwrap(), 1536);
+    assert_eq!(parse_size(" 8 k ").unwrap(), 8192);
+}
+
+#[test]
+fn test_parse_size_rejects_invalid_input() {
+    assert!(parse_size("").is_err());
+    assert!(parse_size("abc").is_err());
+    assert!(parse_size("10X").is_err());
+    assert!(parse_size("1.2.3M").is_err());
+}
+
+#[test]
+fn test_format_size() {
+    assert_eq!(format_size(512), "512 B");
+    assert_eq!(format_size(1536), "1.5 KiB");
+    assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0 GiB");
+}
+
+#[test]
+fn test_parse_duration_units() {
+    assert_eq!(parse_duration("90s").unwrap(), Duration::seconds(90));
+    assert_eq!(parse_duration("45m").unwrap(), Duration::minutes(45));
+    assert_eq!(parse_duration("12h").unwrap(), Duration::hours(12));
+    assert_eq!(parse_duration("30D").unwrap(), Duration::days(30));
+    assert_eq!(parse_duration(" 2w ").unwrap(), Duration::weeks(2));
+}
+
+#[test]
+fn test_parse_duration_rejects_invalid_input() {
+    assert!(parse_duration("").is_err());
+    assert!(parse_duration("30").is_err());
+    assert!(parse_duration("d").is_err());
+    assert!(parse_duration("1.5d").is_err());
+    assert!(parse_duration("30y").is_err());
+    assert!(parse_duration("99999999999999w").is_err());
+}
+
+#[test]
+fn test_parse_time_dates() {
+    assert_eq!(
+        parse_time("2024-05-01").unwrap(),
+        Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap()
+    );
+    assert_eq!(
+        parse_time("2024-05-01T08:30:00+02:00").unwrap(),
+        Utc.with_ymd_and_hms(2024, 5, 1, 6, 30, 0).unwrap()
+    );
+}
+
+#[test]
+fn test_parse_time_durations_count_back_from_now() {
+    let before = Utc::now();
+    let time = parse_time("30d").unwrap();
+    let after = Utc::now();
+
+    assert!(time >= before - Duration::days(30));
+    assert!(time <= after - Duration::days(30));
+}
+
+#[test]
+fn test_parse_time_rejects_invalid_input() {
+    assert!(parse_time("").is_err());
+    assert!(parse_time("yesterday").is_err());
+    assert!(parse_time("2024-13-01").is_err());
+    assert!(parse_time("01/05/2024").is_err());
+}